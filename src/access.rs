@@ -1,23 +1,101 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
 use colored::Colorize;
 use comfy_table::{presets::UTF8_FULL, Table};
+use serde::{Deserialize, Serialize};
 
 use crate::client::{
-    AccessPolicy, CloudflareClient, CreateAccessApp, PolicyEmail, PolicyEmailDomain, PolicyRule,
+    AccessApp, AccessPolicy, CloudflareClient, CorsHeaders, CreateAccessApp, IdentityProvider,
+    PolicyAccessGroup, PolicyEmail, PolicyEmailDomain, PolicyGSuite, PolicyRule, UpdateAccessApp,
 };
 use crate::error::Result;
-use crate::i18n::lang;
-use crate::prompt;
+use crate::i18n::{lang, Lang};
 use crate::t;
+use crate::{dns, prompt, tunnel, util};
 
 fn short_id(id: Option<&str>) -> String {
     id.unwrap_or("-").chars().take(8).collect()
 }
 
+impl prompt::TableRow for AccessPolicy {
+    fn header() -> Vec<String> {
+        let l = lang();
+        vec![
+            t!(l, "Policy", "策略").to_string(),
+            t!(l, "Decision", "决策").to_string(),
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.name.clone(), self.decision.clone()]
+    }
+}
+
 // ---------------------------------------------------------------------------
 // List Access applications
 // ---------------------------------------------------------------------------
 
-pub async fn list_apps(client: &CloudflareClient) -> Result<()> {
+/// Names of the identity providers in `allowed_idps`, falling back to the
+/// short ID for unknown ones; "any" when no provider is required.
+fn idp_names(l: Lang, allowed_idps: &[String], providers: &[IdentityProvider]) -> String {
+    if allowed_idps.is_empty() {
+        return t!(l, "any", "任意").to_string();
+    }
+    allowed_idps
+        .iter()
+        .map(|id| {
+            providers
+                .iter()
+                .find(|p| &p.id == id)
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| short_id(Some(id)))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// CORS column of the verbose app list.
+fn cors_status(cors: Option<&CorsHeaders>) -> String {
+    let l = lang();
+    match cors {
+        Some(c) if c.allow_all_origins => {
+            format!(
+                "✅ {}",
+                t!(l, "configured (all origins)", "已配置 (所有来源)")
+            )
+        }
+        Some(c) if !c.allowed_origins.is_empty() => format!(
+            "✅ {} ({} {})",
+            t!(l, "configured", "已配置"),
+            c.allowed_origins.len(),
+            t!(l, "origins", "个来源")
+        ),
+        _ => format!("➖ {}", t!(l, "none", "无")),
+    }
+}
+
+/// Whether `domain` looks like it serves a web frontend or API that browsers
+/// call cross-origin: a label (or `-`-separated part of one) is `app`, `web`,
+/// `api` or `frontend`, optionally followed by `s` or digits (`apps`, `api2`).
+fn looks_like_web_frontend(domain: &str) -> bool {
+    const KEYWORDS: [&str; 4] = ["app", "web", "api", "frontend"];
+    domain.to_ascii_lowercase().split(['.', '-']).any(|part| {
+        KEYWORDS.iter().any(|kw| {
+            part.strip_prefix(kw).is_some_and(|rest| {
+                rest.is_empty() || rest == "s" || rest.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+    })
+}
+
+/// Application detail lookups issued at once for `access list --verbose`.
+const DETAIL_FETCH_CONCURRENCY: usize = 5;
+
+/// With `verbose`, also fetch each application's details to show which
+/// identity providers it requires and its CORS settings.
+pub async fn list_apps(client: &CloudflareClient, verbose: bool) -> Result<()> {
     let l = lang();
     println!(
         "{}",
@@ -29,7 +107,7 @@ pub async fn list_apps(client: &CloudflareClient) -> Result<()> {
         .bold()
     );
 
-    let apps = client.list_access_apps().await?;
+    let apps = client.list_access_apps(None, None).await?;
 
     if apps.is_empty() {
         println!(
@@ -39,23 +117,76 @@ pub async fn list_apps(client: &CloudflareClient) -> Result<()> {
         return Ok(());
     }
 
+    let providers = if verbose {
+        client.list_identity_providers().await.unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
-    table.set_header(vec![
+    let mut header = vec![
         t!(l, "Name", "名称"),
         t!(l, "Domain", "域名"),
         t!(l, "Type", "类型"),
-        "ID",
-    ]);
+        t!(l, "Session", "会话"),
+    ];
+    if verbose {
+        header.push("IdPs");
+        header.push("CORS");
+    }
+    header.push("ID");
+    table.set_header(header);
 
-    for app in &apps {
+    let mut details = Vec::new();
+    if verbose {
+        let ids: Vec<Option<String>> = apps.iter().map(|a| a.id.clone()).collect();
+        let handles = util::spawn_bounded(ids, DETAIL_FETCH_CONCURRENCY, |id| {
+            let client = client.clone();
+            async move {
+                match id {
+                    Some(id) => client.get_access_app_detail(&id).await.ok(),
+                    None => None,
+                }
+            }
+        });
+        for handle in handles {
+            details.push(handle.await.ok().flatten());
+        }
+    }
+
+    let mut cors_suggestions = Vec::new();
+
+    for (i, app) in apps.iter().enumerate() {
         let id_display = short_id(app.id.as_deref());
-        table.add_row(vec![
-            &app.name,
-            &app.domain,
-            app.app_type.as_deref().unwrap_or("-"),
-            &id_display,
-        ]);
+        let session = app
+            .session_duration
+            .as_deref()
+            .map(format_session_duration)
+            .unwrap_or_else(|| "-".to_string());
+        let mut row = vec![
+            app.name.clone(),
+            app.domain.clone(),
+            app.app_type.clone().unwrap_or_else(|| "-".to_string()),
+            session,
+        ];
+        if verbose {
+            match details.get(i).and_then(Option::as_ref) {
+                Some(detail) => {
+                    row.push(idp_names(l, &detail.allowed_idps, &providers));
+                    row.push(cors_status(detail.cors_headers.as_ref()));
+                    if detail.cors_headers.is_none() && looks_like_web_frontend(&app.domain) {
+                        cors_suggestions.push(app.domain.as_str());
+                    }
+                }
+                None => {
+                    row.push("-".to_string());
+                    row.push("-".to_string());
+                }
+            }
+        }
+        row.push(id_display);
+        table.add_row(row);
     }
 
     println!("{table}");
@@ -64,6 +195,178 @@ pub async fn list_apps(client: &CloudflareClient) -> Result<()> {
         t!(l, "Total:", "共:"),
         apps.len().to_string().cyan()
     );
+
+    if !cors_suggestions.is_empty() {
+        println!("\n{}", t!(l, "Suggestions:", "建议:").bold());
+        for domain in cors_suggestions {
+            println!(
+                "  💡 {domain}: {}",
+                t!(
+                    l,
+                    "looks like a web frontend or API; consider configuring CORS",
+                    "看起来是 Web 前端或 API，建议配置 CORS"
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Application details
+// ---------------------------------------------------------------------------
+
+pub async fn show_app_detail(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let app_id = match id {
+        Some(id) => id,
+        None => {
+            let apps = client.list_access_apps(None, None).await?;
+            if apps.is_empty() {
+                println!("{}", t!(l, "No applications found.", "未找到应用。"));
+                return Ok(());
+            }
+            let items: Vec<String> = apps
+                .iter()
+                .map(|a| format!("{} ({})", a.name, a.domain))
+                .collect();
+
+            let sel = prompt::paged_select(
+                t!(l, "Select application", "选择应用"),
+                &items,
+                prompt::DEFAULT_PAGE_SIZE,
+                None,
+            );
+
+            match sel.and_then(|i| apps.get(i)).and_then(|a| a.id.clone()) {
+                Some(app_id) => app_id,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let detail = client.get_access_app_detail(&app_id).await?;
+    let providers = client.list_identity_providers().await.unwrap_or_default();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.add_row(vec![t!(l, "Name", "名称"), &detail.name]);
+    table.add_row(vec![t!(l, "Domain", "域名"), &detail.domain]);
+    table.add_row(vec![
+        t!(l, "Type", "类型"),
+        detail.app_type.as_deref().unwrap_or("-"),
+    ]);
+    table.add_row(vec![
+        t!(l, "Session", "会话"),
+        &detail
+            .session_duration
+            .as_deref()
+            .map(format_session_duration)
+            .unwrap_or_else(|| "-".to_string()),
+    ]);
+    table.add_row(vec![
+        t!(l, "Identity providers", "身份提供商"),
+        &idp_names(l, &detail.allowed_idps, &providers),
+    ]);
+    table.add_row(vec![
+        t!(l, "Auto-redirect to IdP", "自动跳转到身份提供商"),
+        if detail.auto_redirect_to_identity {
+            t!(l, "yes", "是")
+        } else {
+            t!(l, "no", "否")
+        },
+    ]);
+    table.add_row(vec!["ID", &app_id]);
+    println!("{table}");
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Audit logs
+// ---------------------------------------------------------------------------
+
+/// Default number of audit log entries shown.
+pub const DEFAULT_LOG_LIMIT: u32 = 50;
+
+/// Show recent Access authentication events, optionally only those newer
+/// than `since` (e.g. `1h`, `2d`).
+pub async fn show_audit_logs(
+    client: &CloudflareClient,
+    limit: Option<u32>,
+    since: Option<String>,
+) -> Result<()> {
+    let l = lang();
+
+    let since = match since {
+        Some(s) => match util::parse_duration(&s) {
+            Some(secs) => {
+                let start = chrono::Utc::now() - chrono::Duration::seconds(secs as i64);
+                Some(start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            }
+            None => anyhow::bail!(
+                "{} '{s}' (e.g. 30m, 1h, 2d)",
+                t!(l, "Invalid duration", "无效的时长")
+            ),
+        },
+        None => None,
+    };
+
+    println!(
+        "{}",
+        t!(
+            l,
+            "Fetching Access audit logs...",
+            "获取 Access 审计日志..."
+        )
+        .bold()
+    );
+    let logs = client
+        .list_access_audit_logs(limit.unwrap_or(DEFAULT_LOG_LIMIT), since.as_deref())
+        .await?;
+
+    if logs.is_empty() {
+        println!("{}", t!(l, "No audit log entries.", "没有审计日志。"));
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        t!(l, "Time", "时间"),
+        t!(l, "User", "用户"),
+        "IP",
+        t!(l, "App", "应用"),
+        t!(l, "Action", "操作"),
+        t!(l, "Result", "结果"),
+    ]);
+
+    for log in &logs {
+        let time = log
+            .created_at
+            .get(..16)
+            .map(|s| s.replacen('T', " ", 1))
+            .unwrap_or_else(|| log.created_at.clone());
+        table.add_row(vec![
+            time,
+            log.user_email.clone(),
+            log.ip_address.clone(),
+            short_id(Some(&log.app_uid)),
+            log.action.clone(),
+            if log.allowed { "✅" } else { "❌" }.to_string(),
+        ]);
+    }
+
+    println!("{table}");
+    let denied = logs.iter().filter(|log| !log.allowed).count();
+    println!(
+        "\n{} {}  {} {}",
+        t!(l, "Total:", "共:"),
+        logs.len().to_string().cyan(),
+        t!(l, "Denied:", "拒绝:"),
+        denied.to_string().red()
+    );
     Ok(())
 }
 
@@ -71,13 +374,230 @@ pub async fn list_apps(client: &CloudflareClient) -> Result<()> {
 // Create Access application
 // ---------------------------------------------------------------------------
 
+/// Shortest session duration Access accepts.
+const MIN_SESSION_SECS: u64 = 15 * 60;
+/// Longest session duration Access accepts.
+const MAX_SESSION_SECS: u64 = 30 * 86400;
+
+/// Parse an Access session duration into seconds. Accepts Go-style
+/// durations (`24h`, `1440m`, `1h30m`, `7776000s`, plus `d`/`w`) and ISO 8601
+/// durations (`P7D`, `PT24H`, `P1DT12H`).
+fn parse_session_duration(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if let Some(iso) = s.strip_prefix('P').or_else(|| s.strip_prefix('p')) {
+        let (date, time) = iso.split_once(['T', 't']).unwrap_or((iso, ""));
+        let date_secs = sum_duration_parts(date, |u| match u {
+            'W' | 'w' => Some(7 * 86400),
+            'D' | 'd' => Some(86400),
+            _ => None,
+        })?;
+        let time_secs = sum_duration_parts(time, |u| match u {
+            'H' | 'h' => Some(3600),
+            'M' | 'm' => Some(60),
+            'S' | 's' => Some(1),
+            _ => None,
+        })?;
+        return Some(date_secs + time_secs).filter(|secs| *secs > 0);
+    }
+    sum_duration_parts(s, |u| match u {
+        'w' => Some(7 * 86400),
+        'd' => Some(86400),
+        'h' => Some(3600),
+        'm' => Some(60),
+        's' => Some(1),
+        _ => None,
+    })
+    .filter(|secs| *secs > 0)
+}
+
+/// Sum `<number><unit>` pairs such as `1h30m`, using `unit_secs` for each unit.
+fn sum_duration_parts(s: &str, unit_secs: impl Fn(char) -> Option<u64>) -> Option<u64> {
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+        } else {
+            let value: u64 = std::mem::take(&mut digits).parse().ok()?;
+            total = total.checked_add(value.checked_mul(unit_secs(c)?)?)?;
+        }
+    }
+    digits.is_empty().then_some(total)
+}
+
+/// Human-readable session duration: `24h` → "24 hours", `7776000s` → "90 days".
+/// Durations over a day are broken into days and the remaining smaller units;
+/// unparseable values are shown as-is.
+pub fn format_session_duration(s: &str) -> String {
+    let l = lang();
+    let Some(mut secs) = parse_session_duration(s) else {
+        return s.to_string();
+    };
+    let units: [(u64, &str, &str, &str); 4] = [
+        (86400, "day", "days", "天"),
+        (3600, "hour", "hours", "小时"),
+        (60, "minute", "minutes", "分钟"),
+        (1, "second", "seconds", "秒"),
+    ];
+    let mut parts = Vec::new();
+    for (unit, one, many, zh) in units {
+        // A single day reads better as "24 hours".
+        if unit == 86400 && secs <= 86400 {
+            continue;
+        }
+        let value = secs / unit;
+        secs %= unit;
+        if value > 0 {
+            parts.push(format!(
+                "{value} {}",
+                t!(l, if value == 1 { one } else { many }, zh)
+            ));
+        }
+    }
+    parts.join(" ")
+}
+
+/// `secs` as a Go duration, the only form the API takes for
+/// `session_duration`: whole hours as `24h`, else minutes or seconds.
+fn api_session_duration(secs: u64) -> String {
+    if secs.is_multiple_of(3600) {
+        format!("{}h", secs / 3600)
+    } else if secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Session duration to send for an update: `None` when left empty, the
+/// current value when unchanged, otherwise the validated input normalised
+/// by [`api_session_duration`].
+fn updated_session_duration(current: Option<&str>, input: &str) -> Result<Option<String>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    if current == Some(input) {
+        return Ok(Some(input.to_string()));
+    }
+    Ok(Some(api_session_duration(validate_session_duration(
+        input,
+    )?)))
+}
+
+/// Reject session durations Access would not accept (under 15 minutes or
+/// over 30 days) before calling the API.
+pub fn validate_session_duration(s: &str) -> Result<u64> {
+    let l = lang();
+    let Some(secs) = parse_session_duration(s) else {
+        anyhow::bail!(
+            "{} '{}' ({})",
+            t!(l, "Invalid session duration", "无效的会话时长"),
+            s,
+            t!(l, "e.g. 30m, 24h, 7d", "如 30m, 24h, 7d")
+        );
+    };
+    if !(MIN_SESSION_SECS..=MAX_SESSION_SECS).contains(&secs) {
+        anyhow::bail!(
+            "{} {} ({}) {}",
+            t!(l, "Session duration", "会话时长"),
+            s,
+            format_session_duration(s),
+            t!(
+                l,
+                "must be between 15 minutes and 30 days",
+                "必须在 15 分钟到 30 天之间"
+            )
+        );
+    }
+    Ok(secs)
+}
+
+/// Session caps of common identity providers: (`--idp-type`, name, max hours).
+const IDP_SESSION_LIMITS: &[(&str, &str, u32)] = &[
+    ("github", "GitHub", 24),
+    ("google", "Google", 24),
+    ("google-apps", "Google Workspace", 24),
+];
+
+/// Checks a session duration against the session cap of an identity provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDurationValidator {
+    pub max_hours: u32,
+    pub provider_name: String,
+}
+
+impl SessionDurationValidator {
+    /// Validator for a known IdP type (case-insensitive), if any.
+    pub fn for_idp(idp_type: &str) -> Option<Self> {
+        IDP_SESSION_LIMITS
+            .iter()
+            .find(|(key, _, _)| key.eq_ignore_ascii_case(idp_type.trim()))
+            .map(|(_, name, max_hours)| Self {
+                max_hours: *max_hours,
+                provider_name: name.to_string(),
+            })
+    }
+
+    /// Whether `duration` (e.g. `24h`, `30m`) is longer than the provider allows.
+    /// Unparseable durations are left for the API to reject.
+    pub fn exceeds(&self, duration: &str) -> bool {
+        parse_session_duration(duration).is_some_and(|secs| secs > u64::from(self.max_hours) * 3600)
+    }
+
+    fn warning(&self, duration: &str) -> String {
+        let l = lang();
+        format!(
+            "⚠️ {} {} {}h; {} {} {}",
+            self.provider_name,
+            t!(
+                l,
+                "identity provider caps sessions at",
+                "身份提供商的会话上限为"
+            ),
+            self.max_hours,
+            t!(l, "selecting", "选择"),
+            duration,
+            t!(
+                l,
+                "may cause frequent reauthentication",
+                "可能导致频繁重新认证"
+            )
+        )
+    }
+}
+
 pub async fn create_app(
     client: &CloudflareClient,
     name: Option<String>,
     domain: Option<String>,
+    idp_type: Option<String>,
 ) -> Result<()> {
     let l = lang();
 
+    let validator = match idp_type.as_deref() {
+        Some(kind) => {
+            let found = SessionDurationValidator::for_idp(kind);
+            if found.is_none() {
+                let known: Vec<&str> = IDP_SESSION_LIMITS.iter().map(|(k, _, _)| *k).collect();
+                println!(
+                    "{} {} '{}' ({}: {})",
+                    "⚠️".yellow(),
+                    t!(
+                        l,
+                        "No session limit known for IdP type",
+                        "未知的身份提供商类型"
+                    ),
+                    kind,
+                    t!(l, "known", "已知"),
+                    known.join(", ")
+                );
+            }
+            found
+        }
+        None => None,
+    };
+
     let name = match name {
         Some(n) => n,
         None => match prompt::input_opt(t!(l, "Application name", "应用名称"), false, None) {
@@ -102,19 +622,58 @@ pub async fn create_app(
         },
     };
 
-    let session_options = vec!["24h", "12h", "6h", "1h", "30m"];
-    let sel = prompt::select_opt(
-        t!(l, "Session duration", "会话时长"),
-        &session_options,
-        Some(0),
-    )
-    .unwrap_or(0);
+    let session_options = [
+        "24h",
+        "12h",
+        "6h",
+        "1h",
+        "30m",
+        "168h",
+        "720h",
+        t!(l, "Custom...", "自定义..."),
+    ];
+    let labels: Vec<String> = session_options
+        .iter()
+        .enumerate()
+        .map(|(i, o)| {
+            if i + 1 == session_options.len() {
+                o.to_string()
+            } else {
+                format!("{o} ({})", format_session_duration(o))
+            }
+        })
+        .collect();
+    let sel =
+        prompt::select_opt(t!(l, "Session duration", "会话时长"), &labels, Some(0)).unwrap_or(0);
+    let session_duration = if sel + 1 == session_options.len() {
+        match prompt::input_opt(
+            t!(
+                l,
+                "Session duration (15m - 30d, e.g. 36h)",
+                "会话时长 (15m - 30d, 如 36h)"
+            ),
+            false,
+            None,
+        ) {
+            Some(v) => v.trim().to_string(),
+            None => return Ok(()),
+        }
+    } else {
+        session_options.get(sel).unwrap_or(&"24h").to_string()
+    };
+    let session_duration = api_session_duration(validate_session_duration(&session_duration)?);
+
+    if let Some(v) = &validator {
+        if v.exceeds(&session_duration) {
+            println!("{}", v.warning(&session_duration).yellow());
+        }
+    }
 
     let app = CreateAccessApp {
         name: name.clone(),
         domain: domain.clone(),
         app_type: "self_hosted".to_string(),
-        session_duration: session_options.get(sel).unwrap_or(&"24h").to_string(),
+        session_duration,
     };
 
     println!(
@@ -153,21 +712,18 @@ pub async fn create_app(
 }
 
 // ---------------------------------------------------------------------------
-// Delete Access application
+// Update Access application
 // ---------------------------------------------------------------------------
 
-pub async fn delete_app(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+pub async fn update_app(client: &CloudflareClient, id: Option<String>) -> Result<()> {
     let l = lang();
 
     let app_id = match id {
         Some(id) => id,
         None => {
-            let apps = client.list_access_apps().await?;
+            let apps = client.list_access_apps(None, None).await?;
             if apps.is_empty() {
-                println!(
-                    "{}",
-                    t!(l, "No applications to delete.", "没有可删除的应用。")
-                );
+                println!("{}", t!(l, "No applications found.", "未找到应用。"));
                 return Ok(());
             }
             let items: Vec<String> = apps
@@ -175,80 +731,306 @@ pub async fn delete_app(client: &CloudflareClient, id: Option<String>) -> Result
                 .map(|a| format!("{} ({})", a.name, a.domain))
                 .collect();
 
-            let sel = prompt::select_opt(
-                t!(l, "Select application to delete", "选择要删除的应用"),
+            let sel = prompt::paged_select(
+                t!(l, "Select application to edit", "选择要编辑的应用"),
                 &items,
+                prompt::DEFAULT_PAGE_SIZE,
                 None,
             );
 
-            match sel {
-                Some(i) => match apps.get(i).and_then(|a| a.id.clone()) {
-                    Some(app_id) => app_id,
-                    None => {
-                        println!(
-                            "{} {}",
-                            "❌".red(),
-                            t!(
-                                l,
-                                "Selected application has no valid ID.",
-                                "所选应用缺少有效 ID。"
-                            )
-                        );
-                        return Ok(());
-                    }
-                },
+            match sel.and_then(|i| apps.get(i)).and_then(|a| a.id.clone()) {
+                Some(app_id) => app_id,
                 None => return Ok(()),
             }
         }
     };
 
-    let confirmed = prompt::confirm_opt(
+    let current = client.get_access_app(&app_id).await?;
+
+    let name = match prompt::input_opt(
+        t!(l, "Application name", "应用名称"),
+        false,
+        Some(&current.name),
+    ) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let domain = match prompt::input_opt(
+        t!(l, "Application domain", "应用域名"),
+        false,
+        Some(&current.domain),
+    ) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    // Left empty when the application has no duration of its own, so an
+    // untouched prompt does not pin it to a value
+    let session_duration = match prompt::input_opt(
         t!(
             l,
-            "Are you sure? This will remove all associated policies.",
-            "确认删除? 这将移除所有关联的策略。"
+            "Session duration (e.g. 24h, 30m)",
+            "会话时长 (如 24h, 30m)"
         ),
-        false,
-    )
-    .unwrap_or(false);
+        current.session_duration.is_none(),
+        current.session_duration.as_deref(),
+    ) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let session_duration =
+        updated_session_duration(current.session_duration.as_deref(), &session_duration)?;
 
-    if !confirmed {
+    let update = UpdateAccessApp::changes(&current, &name, &domain, session_duration.as_deref());
+    if update.is_empty() {
+        println!("{}", t!(l, "No changes.", "没有变更。"));
         return Ok(());
     }
 
-    client.delete_access_app(&app_id).await?;
+    let updated = client.update_access_app(&app_id, &update).await?;
     println!(
-        "{} {}",
+        "{} {} '{}' @ {}",
         "✅".green(),
-        t!(l, "Application deleted.", "应用已删除。")
+        t!(l, "Application updated:", "应用已更新:"),
+        updated.name,
+        updated.domain.cyan()
     );
     Ok(())
 }
 
 // ---------------------------------------------------------------------------
-// Manage policies
+// Delete Access application
 // ---------------------------------------------------------------------------
 
-pub async fn manage_policies(client: &CloudflareClient, app_id: Option<String>) -> Result<()> {
-    let l = lang();
+/// Selections larger than this must be confirmed by typing [`DELETE_ALL_PHRASE`].
+const BATCH_CONFIRM_THRESHOLD: usize = 3;
 
-    let app_id = match app_id {
-        Some(id) => id,
-        None => {
-            let apps = client.list_access_apps().await?;
-            if apps.is_empty() {
-                println!("{}", t!(l, "No applications found.", "未找到应用。"));
-                return Ok(());
-            }
-            let items: Vec<String> = apps
-                .iter()
-                .map(|a| format!("{} ({})", a.name, a.domain))
-                .collect();
+const DELETE_ALL_PHRASE: &str = "DELETE ALL";
 
-            let sel = prompt::select_opt(t!(l, "Select application", "选择应用"), &items, None);
+/// Deletions issued at once during batch deletion.
+const DELETE_CONCURRENCY: usize = 3;
 
-            match sel {
-                Some(i) => match apps.get(i).and_then(|a| a.id.clone()) {
+/// Whether deleting `count` applications requires the typed-phrase guard.
+fn needs_typed_confirmation(count: usize) -> bool {
+    count > BATCH_CONFIRM_THRESHOLD
+}
+
+/// Delete one application by ID, or pick several interactively when `id` is `None`.
+pub async fn delete_app(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let Some(app_id) = id else {
+        return delete_apps_batch(client).await;
+    };
+
+    let policies = client
+        .list_access_policies(&app_id)
+        .await
+        .unwrap_or_default();
+    let confirmed = prompt::confirm_with_table(
+        t!(
+            l,
+            "Are you sure? This will remove all associated policies.",
+            "确认删除? 这将移除所有关联的策略。"
+        ),
+        &policies,
+        false,
+    )
+    .unwrap_or(false);
+
+    if !confirmed {
+        return Ok(());
+    }
+
+    client.delete_access_app(&app_id).await?;
+    println!(
+        "{} {}",
+        "✅".green(),
+        t!(l, "Application deleted.", "应用已删除。")
+    );
+    Ok(())
+}
+
+/// Multi-select applications and delete them, at most three at a time.
+/// Failures are reported and do not stop the remaining deletions.
+async fn delete_apps_batch(client: &CloudflareClient) -> Result<()> {
+    let l = lang();
+
+    let apps = client.list_access_apps(None, None).await?;
+    if apps.is_empty() {
+        println!(
+            "{}",
+            t!(l, "No applications to delete.", "没有可删除的应用。")
+        );
+        return Ok(());
+    }
+    let items: Vec<String> = apps
+        .iter()
+        .map(|a| format!("{} ({})", a.name, a.domain))
+        .collect();
+
+    let selected: Vec<(String, String)> = match prompt::multi_select_opt(
+        t!(
+            l,
+            "Select applications to delete (space to toggle)",
+            "选择要删除的应用 (空格选择)"
+        ),
+        &items,
+    ) {
+        Some(sel) => sel
+            .into_iter()
+            .filter_map(|i| Some((apps.get(i)?.id.clone()?, items[i].clone())))
+            .collect(),
+        None => return Ok(()),
+    };
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    let warning = format!(
+        "{} {} {}",
+        t!(l, "Delete", "删除"),
+        selected.len(),
+        t!(
+            l,
+            "application(s) and all associated policies?",
+            "个应用及其所有关联策略?"
+        )
+    );
+    let confirmed = if needs_typed_confirmation(selected.len()) {
+        prompt::confirm_by_typing(&warning, DELETE_ALL_PHRASE)
+    } else {
+        prompt::confirm_opt(&warning, false).unwrap_or(false)
+    };
+    if !confirmed {
+        return Ok(());
+    }
+
+    let total = selected.len();
+    let handles = util::spawn_bounded(selected, DELETE_CONCURRENCY, |(id, label)| {
+        let client = client.clone();
+        async move {
+            match client.delete_access_app(&id).await {
+                Ok(_) => {
+                    println!("  {} {label}", "✅".green());
+                    true
+                }
+                Err(e) => {
+                    println!("  {} {label}: {e:#}", "❌".red());
+                    false
+                }
+            }
+        }
+    });
+
+    let mut deleted = 0;
+    for handle in handles {
+        if let Ok(true) = handle.await {
+            deleted += 1;
+        }
+    }
+    println!(
+        "\n{} {deleted}/{total} {}",
+        t!(l, "Deleted", "已删除"),
+        t!(l, "applications", "个应用")
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Reorder Access applications
+// ---------------------------------------------------------------------------
+
+/// IDs of `apps` arranged in `order` (indices into `apps`), skipping apps
+/// without an ID.
+fn ordered_app_ids(apps: &[AccessApp], order: &[usize]) -> Vec<String> {
+    order
+        .iter()
+        .filter_map(|&i| apps.get(i).and_then(|a| a.id.clone()))
+        .collect()
+}
+
+/// Change the order in which Access applications are evaluated when several
+/// match the same domain.
+pub async fn reorder_apps(client: &CloudflareClient) -> Result<()> {
+    let l = lang();
+
+    let apps = client.list_access_apps_ordered().await?;
+    if apps.len() < 2 {
+        println!(
+            "{}",
+            t!(
+                l,
+                "At least two applications are needed to reorder.",
+                "至少需要两个应用才能调整顺序。"
+            )
+        );
+        return Ok(());
+    }
+
+    let items: Vec<String> = apps
+        .iter()
+        .map(|a| format!("{} ({})", a.name, a.domain))
+        .collect();
+    let Some(order) = prompt::sort_opt(
+        t!(
+            l,
+            "Reorder applications (space to pick, ↑/↓ to move, enter to save)",
+            "调整应用顺序 (空格选中, ↑/↓ 移动, 回车保存)"
+        ),
+        &items,
+    ) else {
+        return Ok(());
+    };
+
+    if order.iter().copied().eq(0..apps.len()) {
+        println!("{}", t!(l, "Order unchanged.", "顺序未改变。"));
+        return Ok(());
+    }
+
+    let ids = ordered_app_ids(&apps, &order);
+    client.reorder_access_apps(&ids).await?;
+    println!(
+        "{} {}",
+        "✅".green(),
+        t!(l, "Application order updated:", "应用顺序已更新:")
+    );
+    for (n, &i) in order.iter().enumerate() {
+        println!("  {}. {}", n + 1, items[i]);
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Manage policies
+// ---------------------------------------------------------------------------
+
+pub async fn manage_policies(client: &CloudflareClient, app_id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let app_id = match app_id {
+        Some(id) => id,
+        None => {
+            let apps = client.list_access_apps(None, None).await?;
+            if apps.is_empty() {
+                println!("{}", t!(l, "No applications found.", "未找到应用。"));
+                return Ok(());
+            }
+            let items: Vec<String> = apps
+                .iter()
+                .map(|a| format!("{} ({})", a.name, a.domain))
+                .collect();
+
+            let sel = prompt::paged_select(
+                t!(l, "Select application", "选择应用"),
+                &items,
+                prompt::DEFAULT_PAGE_SIZE,
+                None,
+            );
+
+            match sel {
+                Some(i) => match apps.get(i).and_then(|a| a.id.clone()) {
                     Some(app_id) => app_id,
                     None => {
                         println!(
@@ -268,8 +1050,17 @@ pub async fn manage_policies(client: &CloudflareClient, app_id: Option<String>)
         }
     };
 
+    if let Ok(detail) = client.get_access_app_detail(&app_id).await {
+        let providers = client.list_identity_providers().await.unwrap_or_default();
+        println!(
+            "{} {}",
+            t!(l, "Identity providers:", "身份提供商:"),
+            idp_names(l, &detail.allowed_idps, &providers).cyan()
+        );
+    }
+
     // List existing policies
-    let policies = client.list_access_policies(&app_id).await?;
+    let (policies, total) = client.list_access_policies_counted(&app_id).await?;
 
     if policies.is_empty() {
         println!(
@@ -285,14 +1076,49 @@ pub async fn manage_policies(client: &CloudflareClient, app_id: Option<String>)
 
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
-    table.set_header(vec![t!(l, "Name", "名称"), t!(l, "Decision", "决策"), "ID"]);
+    table.set_header(vec![
+        t!(l, "Name", "名称"),
+        t!(l, "Decision", "决策"),
+        t!(l, "Groups", "组"),
+        t!(l, "Require", "必需"),
+        t!(l, "Exclude", "排除"),
+        "ID",
+    ]);
 
     for p in &policies {
         let id_display = short_id(p.id.as_deref());
-        table.add_row(vec![&p.name, &p.decision, &id_display]);
+        let groups: Vec<String> = p
+            .include
+            .iter()
+            .filter_map(|r| r.access_group.as_ref())
+            .map(|g| short_id(Some(&g.id)))
+            .collect();
+        let groups_display = if groups.is_empty() {
+            "-".to_string()
+        } else {
+            groups.join(", ")
+        };
+        table.add_row(vec![
+            p.name.clone(),
+            p.decision.clone(),
+            groups_display,
+            p.require.len().to_string(),
+            p.exclude.len().to_string(),
+            id_display,
+        ]);
     }
 
     println!("{table}");
+    let shown = policies.len();
+    println!(
+        "{}",
+        t!(
+            l,
+            format!("Showing {shown} of {total} policies"),
+            format!("显示 {shown} / {total} 条策略")
+        )
+        .dimmed()
+    );
 
     let add_more =
         prompt::confirm_opt(t!(l, "Add another policy?", "添加新策略?"), false).unwrap_or(false);
@@ -304,18 +1130,120 @@ pub async fn manage_policies(client: &CloudflareClient, app_id: Option<String>)
     Ok(())
 }
 
+/// Pick a Google Workspace identity provider, or ask for its ID when none
+/// can be listed.
+async fn select_google_idp(client: &CloudflareClient) -> Option<String> {
+    let l = lang();
+    let providers: Vec<_> = client
+        .list_identity_providers()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.idp_type.as_deref() == Some("google-apps"))
+        .collect();
+
+    if providers.is_empty() {
+        return prompt::input_opt(t!(l, "Identity provider ID", "身份提供商 ID"), false, None);
+    }
+    let items: Vec<String> = providers
+        .iter()
+        .map(|p| format!("{} ({})", p.name, short_id(Some(&p.id))))
+        .collect();
+    prompt::select_opt(
+        t!(l, "Select identity provider", "选择身份提供商"),
+        &items,
+        None,
+    )
+    .and_then(|i| providers.get(i))
+    .map(|p| p.id.clone())
+}
+
 /// Interactive policy creation wizard.
 async fn create_policy_interactive(client: &CloudflareClient, app_id: &str) -> Result<()> {
     let l = lang();
+    let Some(policy) = prompt_policy(client).await? else {
+        return Ok(());
+    };
+
+    client.create_access_policy(app_id, &policy).await?;
+    println!(
+        "{} {}",
+        "✅".green(),
+        t!(l, "Policy created.", "策略已创建。")
+    );
+    Ok(())
+}
+
+/// Ask for a policy name, decision, include rule and optional require and
+/// exclude rules. `None` when cancelled.
+async fn prompt_policy(client: &CloudflareClient) -> Result<Option<AccessPolicy>> {
+    let l = lang();
 
     let name = match prompt::input_opt(t!(l, "Policy name", "策略名称"), false, Some("Allow")) {
         Some(v) => v,
-        None => return Ok(()),
+        None => return Ok(None),
     };
 
     let decisions = vec!["allow", "deny", "bypass"];
     let dec_sel = prompt::select_opt(t!(l, "Decision", "决策"), &decisions, Some(0)).unwrap_or(0);
 
+    let Some(include) = prompt_rule(client, t!(l, "Include rule", "包含规则")).await? else {
+        return Ok(None);
+    };
+    let require = prompt_extra_rules(
+        client,
+        t!(
+            l,
+            "Add require rules (AND condition)?",
+            "添加必需规则 (AND 条件)?"
+        ),
+        t!(l, "Require rule", "必需规则"),
+    )
+    .await?;
+    let exclude = prompt_extra_rules(
+        client,
+        t!(l, "Add exclude rules?", "添加排除规则?"),
+        t!(l, "Exclude rule", "排除规则"),
+    )
+    .await?;
+
+    let policy = AccessPolicy {
+        id: None,
+        name,
+        decision: decisions.get(dec_sel).unwrap_or(&"allow").to_string(),
+        include: vec![include],
+        exclude,
+        require,
+    };
+
+    Ok(Some(policy))
+}
+
+/// Ask whether to add rules of one kind (`question`), then pick rules until
+/// the user stops or cancels.
+async fn prompt_extra_rules(
+    client: &CloudflareClient,
+    question: &str,
+    title: &str,
+) -> Result<Vec<PolicyRule>> {
+    let l = lang();
+    let mut rules = Vec::new();
+    if !prompt::confirm_opt(question, false).unwrap_or(false) {
+        return Ok(rules);
+    }
+    while let Some(rule) = prompt_rule(client, title).await? {
+        rules.push(rule);
+        if !prompt::confirm_opt(t!(l, "Add another?", "继续添加?"), false).unwrap_or(false) {
+            break;
+        }
+    }
+    Ok(rules)
+}
+
+/// Pick a rule type and fill in its value. `None` when cancelled.
+async fn prompt_rule(client: &CloudflareClient, title: &str) -> Result<Option<PolicyRule>> {
+    let l = lang();
+
     let rule_types = vec![
         t!(
             l,
@@ -328,23 +1256,28 @@ async fn create_policy_interactive(client: &CloudflareClient, app_id: &str) -> R
             "邮箱域名 (如 example.com)"
         ),
         t!(l, "Everyone", "所有人"),
+        t!(l, "Existing Access group", "已有 Access 组"),
+        t!(l, "OTP (email magic link)", "一次性验证码 (邮件链接)"),
+        t!(l, "Google Workspace domain", "Google Workspace 域"),
     ];
 
-    let rule_sel =
-        prompt::select_opt(t!(l, "Include rule", "包含规则"), &rule_types, Some(0)).unwrap_or(0);
+    let rule_sel = prompt::select_opt(title, &rule_types, Some(0)).unwrap_or(0);
 
-    let include = match rule_sel {
+    let rule = match rule_sel {
         0 => {
             let email = match prompt::input_opt(t!(l, "Email address", "邮箱地址"), false, None)
             {
                 Some(v) => v,
-                None => return Ok(()),
+                None => return Ok(None),
             };
-            vec![PolicyRule {
+            PolicyRule {
                 email: Some(PolicyEmail { email }),
                 email_domain: None,
                 everyone: None,
-            }]
+                access_group: None,
+                service_token: None,
+                gsuite: None,
+            }
         }
         1 => {
             let mut domain = match prompt::input_opt(
@@ -353,39 +1286,744 @@ async fn create_policy_interactive(client: &CloudflareClient, app_id: &str) -> R
                 Some("example.com"),
             ) {
                 Some(v) => v,
-                None => return Ok(()),
+                None => return Ok(None),
             };
             // Strip leading @ or extract domain from full email
             if let Some(at_pos) = domain.find('@') {
                 domain = domain[at_pos + 1..].to_string();
             }
-            vec![PolicyRule {
+            PolicyRule {
                 email: None,
                 email_domain: Some(PolicyEmailDomain { domain }),
                 everyone: None,
-            }]
+                access_group: None,
+                service_token: None,
+                gsuite: None,
+            }
+        }
+        3 => {
+            let groups = client.list_access_groups().await?;
+            if groups.is_empty() {
+                println!("{}", t!(l, "No Access groups found.", "未找到 Access 组。"));
+                return Ok(None);
+            }
+            let items: Vec<String> = groups
+                .iter()
+                .map(|g| format!("{} ({})", g.name, short_id(Some(&g.id))))
+                .collect();
+            let id = match prompt::select_opt(t!(l, "Select group", "选择组"), &items, None)
+                .and_then(|i| groups.get(i))
+            {
+                Some(g) => g.id.clone(),
+                None => return Ok(None),
+            };
+            PolicyRule {
+                email: None,
+                email_domain: None,
+                everyone: None,
+                access_group: Some(PolicyAccessGroup { id }),
+                service_token: None,
+                gsuite: None,
+            }
+        }
+        4 => PolicyRule {
+            email: None,
+            email_domain: None,
+            everyone: None,
+            access_group: None,
+            service_token: Some(serde_json::json!({})),
+            gsuite: None,
+        },
+        5 => {
+            let email = match prompt::input_opt(
+                t!(
+                    l,
+                    "Google Workspace group or domain email",
+                    "Google Workspace 组或域邮箱"
+                ),
+                false,
+                None,
+            ) {
+                Some(v) => v,
+                None => return Ok(None),
+            };
+            let identity_provider_id = match select_google_idp(client).await {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            PolicyRule {
+                email: None,
+                email_domain: None,
+                everyone: None,
+                access_group: None,
+                service_token: None,
+                gsuite: Some(PolicyGSuite {
+                    email,
+                    identity_provider_id,
+                }),
+            }
         }
-        _ => vec![PolicyRule {
+        _ => PolicyRule {
             email: None,
             email_domain: None,
             everyone: Some(serde_json::json!({})),
-        }],
+            access_group: None,
+            service_token: None,
+            gsuite: None,
+        },
     };
 
-    let policy = AccessPolicy {
-        id: None,
-        name,
-        decision: decisions.get(dec_sel).unwrap_or(&"allow").to_string(),
-        include,
-        exclude: vec![],
-        require: vec![],
+    Ok(Some(rule))
+}
+
+// ---------------------------------------------------------------------------
+// Protect a tunnel mapping
+// ---------------------------------------------------------------------------
+
+/// Session length of applications created by `protect_mapping`.
+const PROTECT_SESSION_DURATION: &str = "24h";
+
+/// Create the Access application and policy for `hostname`, then the tunnel
+/// CNAME when `create_dns` is set. Returns the created application.
+async fn create_protection(
+    client: &CloudflareClient,
+    hostname: &str,
+    tunnel_id: &str,
+    policy: &AccessPolicy,
+    create_dns: bool,
+) -> Result<AccessApp> {
+    let l = lang();
+    let app = CreateAccessApp {
+        name: hostname.to_string(),
+        domain: hostname.to_string(),
+        app_type: "self_hosted".to_string(),
+        session_duration: PROTECT_SESSION_DURATION.to_string(),
     };
+    let created = client.create_access_app(&app).await?;
+    println!(
+        "{} {} '{}'",
+        "✅".green(),
+        t!(l, "Application created:", "应用已创建:"),
+        hostname
+    );
 
-    client.create_access_policy(app_id, &policy).await?;
+    let app_id = created
+        .id
+        .as_deref()
+        .context("Access application created without an ID")?;
+    client.create_access_policy(app_id, policy).await?;
     println!(
-        "{} {}",
+        "{} {} '{}'",
         "✅".green(),
-        t!(l, "Policy created.", "策略已创建。")
+        t!(l, "Policy created:", "策略已创建:"),
+        policy.name
+    );
+
+    if create_dns {
+        dns::ensure_dns_for_hostname(client, tunnel_id, hostname).await?;
+    }
+    Ok(created)
+}
+
+/// Put a tunnel hostname behind Cloudflare Access in one go: an application
+/// with a 24h session, a policy from the policy wizard, and the DNS record
+/// if it is missing.
+pub async fn protect_mapping(
+    client: &CloudflareClient,
+    hostname: Option<String>,
+    tunnel_id: Option<String>,
+) -> Result<()> {
+    let l = lang();
+
+    let tunnel_id = match tunnel_id {
+        Some(id) => id,
+        None => match tunnel::select_tunnel(client).await? {
+            Some(t) => t.id,
+            None => return Ok(()),
+        },
+    };
+
+    let hostname = match hostname {
+        Some(h) => h,
+        None => {
+            let hostnames: Vec<String> = client
+                .get_tunnel_config(&tunnel_id)
+                .await?
+                .map(|c| {
+                    c.config
+                        .ingress
+                        .into_iter()
+                        .filter_map(|r| r.hostname)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if hostnames.is_empty() {
+                println!(
+                    "{}",
+                    t!(l, "This tunnel has no mappings.", "该隧道没有映射。")
+                );
+                return Ok(());
+            }
+            match prompt::select_opt(
+                t!(l, "Select mapping to protect", "选择要保护的映射"),
+                &hostnames,
+                None,
+            )
+            .and_then(|i| hostnames.get(i))
+            {
+                Some(h) => h.clone(),
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let existing = client.list_access_apps(None, Some(&hostname)).await?;
+    if let Some(app) = existing
+        .iter()
+        .find(|a| a.domain.eq_ignore_ascii_case(&hostname))
+    {
+        println!(
+            "{} {} '{}' ({})",
+            "ℹ️".cyan(),
+            t!(
+                l,
+                "Already protected by Access application",
+                "已受 Access 应用保护:"
+            ),
+            app.name,
+            short_id(app.id.as_deref())
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\n🔐 {} {} ({} {PROTECT_SESSION_DURATION})",
+        t!(l, "Protecting", "正在保护"),
+        hostname.cyan(),
+        t!(l, "session", "会话")
+    );
+    let Some(policy) = prompt_policy(client).await? else {
+        return Ok(());
+    };
+
+    let create_dns = !dns::hostname_has_record(client, &hostname).await
+        && prompt::confirm_opt(
+            t!(
+                l,
+                "No DNS record for this hostname yet. Create it?",
+                "该域名还没有 DNS 记录，是否创建？"
+            ),
+            true,
+        )
+        .unwrap_or(false);
+
+    create_protection(client, &hostname, &tunnel_id, &policy, create_dns).await?;
+    println!(
+        "\n{} {} {}",
+        "✅".green(),
+        hostname.cyan(),
+        t!(
+            l,
+            "now requires Cloudflare Access login.",
+            "现在需要通过 Cloudflare Access 登录。"
+        )
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Import / export
+// ---------------------------------------------------------------------------
+
+/// One application in an import/export file, with its policies.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AppExport {
+    #[serde(flatten)]
+    pub app: CreateAccessApp,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<AccessPolicy>,
+}
+
+impl AppExport {
+    /// Portable form of an existing application; IDs are dropped so the
+    /// entry can be created in another account.
+    fn from_app(app: &AccessApp, policies: Vec<AccessPolicy>) -> Self {
+        Self {
+            app: CreateAccessApp {
+                name: app.name.clone(),
+                domain: app.domain.clone(),
+                app_type: app
+                    .app_type
+                    .clone()
+                    .unwrap_or_else(|| "self_hosted".to_string()),
+                session_duration: app
+                    .session_duration
+                    .clone()
+                    .unwrap_or_else(|| "24h".to_string()),
+            },
+            policies: policies
+                .into_iter()
+                .map(|p| AccessPolicy { id: None, ..p })
+                .collect(),
+        }
+    }
+}
+
+fn parse_app_exports(content: &str) -> Result<Vec<AppExport>> {
+    Ok(serde_json::from_str(content)?)
+}
+
+/// Write every Access application and its policies to `path` as JSON.
+pub async fn export_apps(client: &CloudflareClient, path: &Path) -> Result<()> {
+    let l = lang();
+    let apps = client.list_access_apps(None, None).await?;
+    let mut exports = Vec::with_capacity(apps.len());
+    for app in &apps {
+        let policies = match app.id.as_deref() {
+            Some(id) => client.list_access_policies(id).await?,
+            None => Vec::new(),
+        };
+        exports.push(AppExport::from_app(app, policies));
+    }
+
+    let json = serde_json::to_string_pretty(&exports)?;
+    fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))?;
+    println!(
+        "{} {} {} → {}",
+        "✅".green(),
+        exports.len(),
+        t!(l, "application(s) exported", "个应用已导出"),
+        path.display()
     );
     Ok(())
 }
+
+/// Create the applications (and their policies) listed in a JSON file,
+/// skipping any whose domain is already configured.
+pub async fn import_apps(client: &CloudflareClient, path: &Path) -> Result<()> {
+    let l = lang();
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let entries = parse_app_exports(&content)?;
+    let existing = client.list_access_apps(None, None).await?;
+
+    for entry in &entries {
+        let app = &entry.app;
+        if existing.iter().any(|e| e.domain == app.domain) {
+            println!(
+                "  {} {} ({}) — {}",
+                "⏭".yellow(),
+                app.name,
+                app.domain,
+                t!(l, "domain already configured", "域名已配置")
+            );
+            continue;
+        }
+        let created = match client.create_access_app(app).await {
+            Ok(created) => created,
+            Err(e) => {
+                println!("  {} {} ({}) — {e}", "❌".red(), app.name, app.domain);
+                continue;
+            }
+        };
+        println!("  {} {} ({})", "✅".green(), app.name, app.domain);
+
+        let Some(app_id) = created.id.as_deref() else {
+            continue;
+        };
+        for policy in &entry.policies {
+            match client.create_access_policy(app_id, policy).await {
+                Ok(_) => println!(
+                    "     {} {} {}",
+                    "✅".green(),
+                    t!(l, "policy", "策略"),
+                    policy.name
+                ),
+                Err(e) => println!(
+                    "     {} {} {} — {e}",
+                    "❌".red(),
+                    t!(l, "policy", "策略"),
+                    policy.name
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::MockApi;
+
+    #[test]
+    fn policy_with_require_and_exclude_serializes_all_rule_lists() {
+        let rule = |email: &str| PolicyRule {
+            email: Some(PolicyEmail {
+                email: email.to_string(),
+            }),
+            email_domain: None,
+            everyone: None,
+            access_group: None,
+            service_token: None,
+            gsuite: None,
+        };
+        let policy = AccessPolicy {
+            id: None,
+            name: "Staff".to_string(),
+            decision: "allow".to_string(),
+            include: vec![PolicyRule {
+                email: None,
+                email_domain: Some(PolicyEmailDomain {
+                    domain: "example.com".to_string(),
+                }),
+                everyone: None,
+                access_group: None,
+                service_token: None,
+                gsuite: None,
+            }],
+            exclude: vec![rule("intern@example.com")],
+            require: vec![PolicyRule {
+                email: None,
+                email_domain: None,
+                everyone: None,
+                access_group: Some(PolicyAccessGroup {
+                    id: "grp1".to_string(),
+                }),
+                service_token: None,
+                gsuite: None,
+            }],
+        };
+
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(
+            json["include"],
+            serde_json::json!([{"email_domain": {"domain": "example.com"}}])
+        );
+        assert_eq!(
+            json["require"],
+            serde_json::json!([{"group": {"id": "grp1"}}])
+        );
+        assert_eq!(
+            json["exclude"],
+            serde_json::json!([{"email": {"email": "intern@example.com"}}])
+        );
+    }
+
+    #[test]
+    fn web_frontend_domains_get_cors_suggestions() {
+        for domain in [
+            "app.example.com",
+            "api.example.com",
+            "web.example.com",
+            "frontend.example.com",
+            "my-app.example.com",
+            "apps.example.com",
+            "api2.example.com",
+            "API.Example.com",
+        ] {
+            assert!(looks_like_web_frontend(domain), "{domain}");
+        }
+        for domain in [
+            "grafana.example.com",
+            "apple.example.com",
+            "rapid.example.com",
+            "webhook.example.com",
+            "example.com",
+        ] {
+            assert!(!looks_like_web_frontend(domain), "{domain}");
+        }
+    }
+
+    #[test]
+    fn cors_status_counts_origins() {
+        let none = cors_status(None);
+        assert!(none.starts_with("➖"));
+        let cors = CorsHeaders {
+            allowed_origins: vec![
+                "https://a.example.com".into(),
+                "https://b.example.com".into(),
+            ],
+            ..Default::default()
+        };
+        assert!(cors_status(Some(&cors)).contains('2'));
+        assert!(cors_status(Some(&CorsHeaders::default())).starts_with("➖"));
+    }
+
+    #[test]
+    fn idp_names_map_ids_to_provider_names() {
+        let providers = vec![IdentityProvider {
+            id: "idp-google".to_string(),
+            name: "Google Workspace".to_string(),
+            idp_type: Some("google-apps".to_string()),
+        }];
+        assert_eq!(idp_names(Lang::En, &[], &providers), "any");
+        assert_eq!(idp_names(Lang::Zh, &[], &providers), "任意");
+        assert_eq!(
+            idp_names(
+                Lang::En,
+                &["idp-google".to_string(), "0123456789abcdef".to_string()],
+                &providers
+            ),
+            "Google Workspace, 01234567"
+        );
+    }
+
+    fn app(id: &str) -> AccessApp {
+        AccessApp {
+            id: Some(id.to_string()),
+            name: id.to_string(),
+            domain: format!("{id}.example.com"),
+            app_type: None,
+            session_duration: None,
+            precedence: None,
+            cors_headers: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn protect_mapping_creates_app_policy_and_dns() {
+        let api = MockApi::routes(vec![
+                (
+                    "POST",
+                    "/accounts/acc123/access/apps/app-1/policies",
+                    r#"{"success":true,"result":{"id":"pol-1","name":"Team","decision":"allow","include":[{"email_domain":{"domain":"example.com"}}]}}"#,
+                ),
+                (
+                    "POST",
+                    "/accounts/acc123/access/apps",
+                    r#"{"success":true,"result":{"id":"app-1","name":"app.example.com","domain":"app.example.com","session_duration":"24h"}}"#,
+                ),
+                (
+                    "GET",
+                    "/zones?",
+                    r#"{"success":true,"result":[{"id":"zone-1","name":"example.com"}]}"#,
+                ),
+                (
+                    "GET",
+                    "/zones/zone-1/dns_records",
+                    r#"{"success":true,"result":[]}"#,
+                ),
+                (
+                    "POST",
+                    "/zones/zone-1/dns_records",
+                    r#"{"success":true,"result":{"id":"rec-1","name":"app.example.com","type":"CNAME","content":"tid.cfargotunnel.com"}}"#,
+                ),
+        ])
+        .await;
+
+        let cfg = crate::config::ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::from_config(&cfg)
+            .unwrap()
+            .with_base_url(&api.base);
+        let policy = AccessPolicy {
+            id: None,
+            name: "Team".to_string(),
+            decision: "allow".to_string(),
+            include: vec![PolicyRule {
+                email: None,
+                email_domain: Some(PolicyEmailDomain {
+                    domain: "example.com".to_string(),
+                }),
+                everyone: None,
+                access_group: None,
+                service_token: None,
+                gsuite: None,
+            }],
+            exclude: vec![],
+            require: vec![],
+        };
+
+        let app = create_protection(&client, "app.example.com", "tid", &policy, true)
+            .await
+            .unwrap();
+        assert_eq!(app.id.as_deref(), Some("app-1"));
+        assert_eq!(app.session_duration.as_deref(), Some("24h"));
+
+        assert_eq!(
+            api.requests(),
+            vec![
+                "POST /accounts/acc123/access/apps",
+                "POST /accounts/acc123/access/apps/app-1/policies",
+                "GET /zones?per_page=50&page=1",
+                "GET /zones/zone-1/dns_records?per_page=100",
+                "POST /zones/zone-1/dns_records",
+            ]
+        );
+    }
+
+    #[test]
+    fn session_duration_formats() {
+        let cases = [
+            ("24h", ["24 hours", "24 小时"]),
+            ("1440m", ["24 hours", "24 小时"]),
+            ("7d", ["7 days", "7 天"]),
+            ("168h", ["7 days", "7 天"]),
+            ("7776000s", ["90 days", "90 天"]),
+            ("30m", ["30 minutes", "30 分钟"]),
+            ("1h30m", ["1 hour 30 minutes", "1 小时 30 分钟"]),
+            ("36h", ["1 day 12 hours", "1 天 12 小时"]),
+            ("PT24H", ["24 hours", "24 小时"]),
+            ("P1DT12H", ["1 day 12 hours", "1 天 12 小时"]),
+            ("P30D", ["30 days", "30 天"]),
+            ("PT15M", ["15 minutes", "15 分钟"]),
+        ];
+        for (input, expected) in cases {
+            let got = format_session_duration(input);
+            assert!(expected.contains(&got.as_str()), "{input} -> {got}");
+        }
+        assert_eq!(format_session_duration("forever"), "forever");
+    }
+
+    #[test]
+    fn session_duration_bounds() {
+        assert_eq!(validate_session_duration("15m").unwrap(), 900);
+        assert_eq!(validate_session_duration("720h").unwrap(), 30 * 86400);
+        assert_eq!(validate_session_duration("P30D").unwrap(), 30 * 86400);
+        assert!(validate_session_duration("14m").is_err());
+        assert!(validate_session_duration("31d").is_err());
+        assert!(validate_session_duration("abc").is_err());
+        assert!(validate_session_duration("24").is_err());
+    }
+
+    #[test]
+    fn app_export_round_trip() {
+        let policy = AccessPolicy {
+            id: Some("pol-1".to_string()),
+            name: "Team".to_string(),
+            decision: "allow".to_string(),
+            include: vec![PolicyRule {
+                email: None,
+                email_domain: Some(PolicyEmailDomain {
+                    domain: "example.com".to_string(),
+                }),
+                everyone: None,
+                access_group: None,
+                service_token: None,
+                gsuite: None,
+            }],
+            exclude: vec![],
+            require: vec![],
+        };
+        let exports = vec![
+            AppExport::from_app(&app("grafana"), vec![policy]),
+            AppExport::from_app(&app("wiki"), vec![]),
+        ];
+
+        let json = serde_json::to_string_pretty(&exports).unwrap();
+        let parsed = parse_app_exports(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].app.domain, "grafana.example.com");
+        assert_eq!(parsed[0].app.app_type, "self_hosted");
+        assert_eq!(parsed[0].policies.len(), 1);
+        assert_eq!(parsed[0].policies[0].name, "Team");
+        assert!(parsed[0].policies[0].id.is_none());
+        assert_eq!(parsed[1].app.name, "wiki");
+        assert!(parsed[1].policies.is_empty());
+        assert!(!json.contains("pol-1"));
+    }
+
+    #[test]
+    fn typed_confirmation_only_for_large_selections() {
+        assert!(!needs_typed_confirmation(1));
+        assert!(!needs_typed_confirmation(3));
+        assert!(needs_typed_confirmation(4));
+        assert!(needs_typed_confirmation(20));
+    }
+
+    #[test]
+    fn reorder_uses_user_specified_order() {
+        let apps = vec![app("a"), app("b"), app("c")];
+        assert_eq!(ordered_app_ids(&apps, &[2, 0, 1]), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn session_validator_lookup() {
+        let github = SessionDurationValidator::for_idp("GitHub").unwrap();
+        assert_eq!(github.max_hours, 24);
+        assert_eq!(github.provider_name, "GitHub");
+        assert!(SessionDurationValidator::for_idp(" google-apps ").is_some());
+        assert!(SessionDurationValidator::for_idp("saml").is_none());
+    }
+
+    #[test]
+    fn session_validator_boundaries() {
+        let v = SessionDurationValidator {
+            max_hours: 24,
+            provider_name: "GitHub".to_string(),
+        };
+        assert!(!v.exceeds("30m"));
+        assert!(!v.exceeds("23h"));
+        assert!(!v.exceeds("24h"));
+        assert!(!v.exceeds("1440m"));
+        assert!(!v.exceeds("86400s"));
+        assert!(v.exceeds("86401s"));
+        assert!(v.exceeds("25h"));
+        assert!(v.exceeds("1441m"));
+        assert!(v.exceeds("720h"));
+        assert!(v.exceeds("24h30m"));
+        assert!(v.exceeds("P2D"));
+        assert!(!v.exceeds("PT24H"));
+        assert!(!v.exceeds("forever"));
+        assert!(v.warning("720h").contains("24h"));
+    }
+
+    #[tokio::test]
+    async fn session_durations_are_sent_in_go_units() {
+        assert_eq!(api_session_duration(7 * 86400), "168h");
+        assert_eq!(api_session_duration(5400), "90m");
+        assert_eq!(api_session_duration(901), "901s");
+        assert_eq!(updated_session_duration(None, " ").unwrap(), None);
+        assert_eq!(
+            updated_session_duration(Some("1h30m"), "1h30m").unwrap(),
+            Some("1h30m".to_string())
+        );
+        assert!(updated_session_duration(None, "5m").is_err());
+
+        let api = MockApi::json(
+            r#"{"success":true,"result":{"id":"app-1","name":"App","domain":"app.example.com"}}"#,
+        )
+        .await;
+        let cfg = crate::config::ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::from_config(&cfg)
+            .unwrap()
+            .with_base_url(&api.base);
+
+        let app = CreateAccessApp {
+            name: "App".to_string(),
+            domain: "app.example.com".to_string(),
+            app_type: "self_hosted".to_string(),
+            session_duration: api_session_duration(validate_session_duration("P7D").unwrap()),
+        };
+        client.create_access_app(&app).await.unwrap();
+
+        let current: AccessApp = serde_json::from_str(
+            r#"{"id":"app-1","name":"App","domain":"app.example.com","session_duration":"24h"}"#,
+        )
+        .unwrap();
+        let duration = updated_session_duration(Some("24h"), "1w").unwrap();
+        let update =
+            UpdateAccessApp::changes(&current, "App", "app.example.com", duration.as_deref());
+        client.update_access_app("app-1", &update).await.unwrap();
+
+        let sent = api.raw_requests();
+        assert!(
+            sent[0].ends_with(r#""session_duration":"168h"}"#),
+            "{}",
+            sent[0]
+        );
+        assert!(
+            sent[1].contains(r#""session_duration":"168h""#),
+            "{}",
+            sent[1]
+        );
+    }
+}
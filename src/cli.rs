@@ -1,6 +1,13 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
 
+use crate::config::CliOverrides;
+use crate::monitor::DEFAULT_EXPORT_INTERVAL_SECS;
+use crate::tools::ConfigFormat;
+use crate::tunnel::{MappingsFormat, TunnelSort};
+
 #[derive(Parser)]
 #[command(
     name = "tunnel",
@@ -17,6 +24,33 @@ pub struct Cli {
     /// Language: en / zh
     #[arg(long, global = true)]
     pub lang: Option<String>,
+
+    /// Use this account ID instead of the saved one
+    #[arg(long, global = true, value_name = "ID")]
+    pub account_id: Option<String>,
+
+    /// Use this zone ID instead of the saved one
+    #[arg(long, global = true, value_name = "ID")]
+    pub zone_id: Option<String>,
+
+    /// Use this API token instead of the saved one
+    #[arg(long, global = true, value_name = "TOKEN")]
+    pub token: Option<String>,
+
+    /// Exit the interactive menu when a prompt waits this many seconds for input (off by default)
+    #[arg(long, alias = "timeout", value_name = "SECS")]
+    pub session_timeout: Option<u64>,
+}
+
+impl Cli {
+    /// Credential overrides given on the command line.
+    pub fn overrides(&self) -> CliOverrides {
+        CliOverrides {
+            account_id: self.account_id.clone(),
+            zone_id: self.zone_id.clone(),
+            token: self.token.clone(),
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -26,11 +60,31 @@ pub enum Commands {
 
     // === Tunnel management ===
     /// List tunnels / 查看隧道列表
-    List,
+    List {
+        /// Also show active connector counts and last activity
+        #[arg(long, short)]
+        verbose: bool,
+        /// Sort order (last-active implies --verbose)
+        #[arg(long, value_enum)]
+        sort: Option<TunnelSort>,
+        /// Only show tunnels with no connections in the last N days
+        #[arg(long, value_name = "N")]
+        inactive_days: Option<u64>,
+        /// Also show how many hostnames each tunnel's ingress routes
+        #[arg(long)]
+        count_hostnames: bool,
+    },
     /// Create a new tunnel / 创建新隧道
     Create {
         /// Tunnel name
         name: Option<String>,
+        /// Tunnel secret (base64, 32 bytes) instead of a random one; with a
+        /// name, creates without any prompts
+        #[arg(long, value_name = "BASE64", conflicts_with = "secret_env")]
+        secret: Option<String>,
+        /// Read the tunnel secret from this environment variable
+        #[arg(long, value_name = "VAR_NAME")]
+        secret_env: Option<String>,
     },
     /// Delete a tunnel / 删除隧道
     Delete,
@@ -39,6 +93,16 @@ pub enum Commands {
         /// Tunnel ID (interactive if omitted)
         id: Option<String>,
     },
+    /// Force-disconnect all tunnel connections / 强制断开隧道连接
+    Disconnect {
+        /// Tunnel ID (interactive if omitted)
+        id: Option<String>,
+    },
+    /// Rotate the tunnel secret / 轮换隧道密钥
+    RotateSecret {
+        /// Tunnel ID (interactive if omitted)
+        id: Option<String>,
+    },
 
     // === Mapping management (remotely-managed) ===
     /// Add a domain mapping / 添加域名映射
@@ -50,6 +114,9 @@ pub enum Commands {
         hostname: Option<String>,
         /// Local service, e.g. http://localhost:3000
         service: Option<String>,
+        /// Don't warn when another hostname already points to the same service
+        #[arg(long)]
+        allow_duplicate_service: bool,
     },
     /// Remove a domain mapping / 移除域名映射
     Unmap {
@@ -61,6 +128,14 @@ pub enum Commands {
     },
     /// Show current mappings / 查看当前映射
     Show {
+        /// Tunnel ID (interactive if omitted; required with `--format json`)
+        id: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: MappingsFormat,
+    },
+    /// Compare mappings with the saved snapshot / 与快照对比映射
+    Diff {
         /// Tunnel ID (interactive if omitted)
         id: Option<String>,
     },
@@ -95,6 +170,27 @@ pub enum Commands {
         /// Timeout in ms per port
         #[arg(long, default_value = "500")]
         timeout: u64,
+        /// Maximum number of ports probed at once
+        #[arg(long, default_value = "100")]
+        concurrency: usize,
+        /// Offer to map found services onto a tunnel via the API
+        #[arg(long)]
+        api: bool,
+        /// Only show services that appeared or disappeared since the baseline
+        #[arg(long)]
+        summary: bool,
+        /// Save this scan as the baseline for later comparisons
+        #[arg(long)]
+        save_baseline: bool,
+        /// Scan the TCP ports of an IANA service registry CSV instead of the defaults
+        #[arg(long, value_name = "PATH")]
+        iana_db: Option<PathBuf>,
+    },
+    /// Suggest tunnel mappings for local services / 为本地服务建议隧道映射
+    Suggest {
+        /// Tunnel ID (select interactively if omitted)
+        #[arg(long)]
+        tunnel: Option<String>,
     },
     /// Manage cloudflared service / 管理 cloudflared 服务
     Service {
@@ -102,9 +198,73 @@ pub enum Commands {
         action: ServiceAction,
     },
 
+    /// Show tunnel statistics / 查看隧道统计
+    Stats {
+        /// Exit with 2 when inactive, 3 when the error rate exceeds 5%
+        #[arg(long)]
+        exit_code: bool,
+    },
+
+    /// Write cloudflared metrics to a Prometheus text file / 导出 Prometheus 指标文件
+    ExportMetrics {
+        /// Output .prom file (e.g. for the node_exporter textfile collector)
+        output: PathBuf,
+        /// Keep re-exporting until interrupted
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between exports with --watch
+        #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_EXPORT_INTERVAL_SECS, requires = "watch")]
+        interval: u64,
+    },
+
     /// Interactive TUI dashboard / 交互式 TUI 仪表盘
     Dashboard,
 
+    /// Export tunnels and DNS as Terraform / 导出为 Terraform 配置
+    Terraform {
+        /// Output .tf file
+        #[arg(long, short, default_value = "opentunnel.tf")]
+        output: PathBuf,
+    },
+
+    /// Send a raw Cloudflare API request (advanced/expert use only) / 原始 API 请求（仅限高级用户）
+    #[command(
+        long_about = "Send a request to any Cloudflare API v4 endpoint and print the raw response.\n\
+                      Advanced/expert use only: no validation or confirmation is performed.\n\n\
+                      Example: tunnel raw-api GET /accounts/<id>/cfd_tunnel"
+    )]
+    RawApi {
+        /// HTTP method: GET, POST, PUT, PATCH, DELETE
+        method: String,
+        /// Path relative to https://api.cloudflare.com/client/v4
+        path: String,
+        /// JSON request body
+        body: Option<String>,
+    },
+
+    /// Show the mapping change log / 查看映射变更记录
+    Events {
+        /// Number of most recent events to show (default 20)
+        #[arg(long)]
+        limit: Option<u32>,
+        /// Delete the event log
+        #[arg(long, conflicts_with = "limit")]
+        clear: bool,
+    },
+
+    /// Time common API calls to spot slow regions or rate limiting / API 性能测试
+    Benchmark,
+
+    /// Print debug information for bug reports / 输出调试信息
+    Debug {
+        /// Show unmasked IDs and paths (for local debugging only)
+        #[arg(long)]
+        full_config: bool,
+        /// Write the report to a file (a temp file if no path is given)
+        #[arg(long, value_name = "PATH")]
+        output: Option<Option<PathBuf>>,
+    },
+
     /// Generate shell completions / 生成 Shell 补全脚本
     Completions {
         /// Shell: bash, zsh, fish, elvish, powershell
@@ -116,7 +276,11 @@ pub enum Commands {
 #[derive(Subcommand)]
 pub enum DnsAction {
     /// List DNS records / 列出 DNS 记录
-    List,
+    List {
+        /// Only show records of this type (e.g. CNAME)
+        #[arg(long = "type", value_name = "TYPE")]
+        record_type: Option<String>,
+    },
     /// Add a DNS record / 添加 DNS 记录
     Add {
         /// Record name (e.g. app)
@@ -128,27 +292,69 @@ pub enum DnsAction {
         /// Record content / target
         #[arg(long)]
         content: Option<String>,
-        /// Proxy through Cloudflare
-        #[arg(long, default_value = "true")]
-        proxied: bool,
+        /// Proxy through Cloudflare: true / false (prompted if omitted)
+        #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+        proxied: Option<bool>,
+        /// TTL in seconds (1 = auto; prompted if omitted for unproxied records)
+        #[arg(long)]
+        ttl: Option<u32>,
+        /// Write TXT content in $EDITOR (lines are joined with spaces)
+        #[arg(long)]
+        editor: bool,
+    },
+    /// Update a DNS record / 更新 DNS 记录
+    Update {
+        /// Record ID to update
+        id: Option<String>,
     },
     /// Delete a DNS record / 删除 DNS 记录
     Delete {
         /// Record ID to delete
         id: Option<String>,
+        /// Warn if a tunnel mapping routes this hostname (extra API calls)
+        #[arg(long)]
+        check_tunnel: bool,
     },
     /// Sync tunnel routes to DNS / 同步隧道路由到 DNS
     Sync {
         /// Tunnel ID (interactive if omitted)
         #[arg(long)]
         tunnel: Option<String>,
+        /// Also suggest missing DMARC / DKIM TXT records
+        #[arg(long)]
+        suggest_email: bool,
+        /// Create the missing records without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Import records from `dig axfr` output / 从 dig AXFR 输出导入记录
+    ImportAxfr {
+        /// File containing the dig output
+        path: PathBuf,
+    },
+    /// Create CNAMEs for mappings saved by `tunnel show --format json` / 从映射文件创建 DNS 记录
+    SyncFromFile {
+        /// File containing the JSON mappings
+        path: PathBuf,
+        /// Tunnel ID the records point to (interactive if omitted)
+        #[arg(long)]
+        tunnel: Option<String>,
     },
 }
 
 #[derive(Subcommand)]
 pub enum AccessAction {
     /// List Access applications / 查看 Access 应用
-    List,
+    List {
+        /// Also show the identity providers each application requires
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Show an application's full settings / 查看应用详情
+    Detail {
+        /// Application ID
+        id: Option<String>,
+    },
     /// Create a new Access application / 创建新应用
     Create {
         /// Application name
@@ -156,6 +362,15 @@ pub enum AccessAction {
         /// Application domain
         #[arg(long)]
         domain: Option<String>,
+        /// Identity provider type (github, google, google-apps); warns when the
+        /// session duration exceeds its cap
+        #[arg(long, value_name = "TYPE")]
+        idp_type: Option<String>,
+    },
+    /// Edit an Access application / 编辑应用
+    Update {
+        /// Application ID
+        id: Option<String>,
     },
     /// Delete an Access application / 删除应用
     Delete {
@@ -167,6 +382,35 @@ pub enum AccessAction {
         /// Application ID
         app_id: Option<String>,
     },
+    /// Protect a tunnel mapping with an app and policy / 一键保护隧道映射
+    Protect {
+        /// Hostname to protect (select from the tunnel's mappings if omitted)
+        hostname: Option<String>,
+        /// Tunnel ID (interactive if omitted)
+        #[arg(long)]
+        tunnel: Option<String>,
+    },
+    /// Change application evaluation order / 调整应用优先级
+    Reorder,
+    /// Show authentication audit logs / 查看审计日志
+    Logs {
+        /// Maximum number of entries (default 50)
+        #[arg(long)]
+        limit: Option<u32>,
+        /// Only show events newer than this, e.g. 30m, 1h, 2d
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Create applications and policies from a JSON file / 从 JSON 导入应用
+    Import {
+        /// JSON file written by `access export`
+        path: PathBuf,
+    },
+    /// Save all applications and policies to a JSON file / 导出应用到 JSON
+    Export {
+        /// Output file
+        path: PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -178,12 +422,44 @@ pub enum ConfigAction {
         #[command(subcommand)]
         action: AccountAction,
     },
+    /// Labelled API tokens / 多 Token 管理
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+    /// Replace the API token, keeping account and zone / 轮换 API Token
+    Rotate,
     /// Show current configuration / 查看当前配置
     Show,
     /// Test API connection / 测试 API 连接
     Test,
     /// Clear saved configuration / 清除配置
     Clear,
+    /// Export configuration (token omitted) / 导出配置 (不含 Token)
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: ConfigFormat,
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Include the API token in plain text (keep the file secret)
+        #[arg(long)]
+        include_token: bool,
+    },
+    /// Import configuration from an export file / 从导出文件导入配置
+    Import {
+        /// Path to the exported file
+        path: PathBuf,
+        /// Input format
+        #[arg(long, value_enum, default_value = "json")]
+        format: ConfigFormat,
+    },
+    /// Extra ports for `tunnel scan` / 自定义扫描端口
+    ScanPorts {
+        #[command(subcommand)]
+        action: ScanPortsAction,
+    },
     /// Set preferred language / 设置语言
     Lang {
         /// Language code: en / zh
@@ -202,6 +478,48 @@ pub enum AccountAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum TokenAction {
+    /// List stored tokens / 列出已保存的 Token
+    List,
+    /// Add a labelled token / 添加带标签的 Token
+    Add {
+        /// Label, e.g. personal, work-ci
+        label: String,
+        /// Permission scope note (repeatable)
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+    },
+    /// Remove a labelled token / 移除 Token
+    Remove {
+        /// Label of the token to remove
+        label: String,
+    },
+    /// Set the active token / 设置当前使用的 Token
+    Use {
+        /// Label of the token to use
+        label: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScanPortsAction {
+    /// List custom scan ports / 列出自定义端口
+    List,
+    /// Add or relabel a port / 添加端口
+    Add {
+        /// Port number
+        port: u16,
+        /// Service description shown in scan results
+        description: String,
+    },
+    /// Remove a custom port / 移除端口
+    Remove {
+        /// Port number
+        port: u16,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum ServiceAction {
     /// Show service status / 查看服务状态
@@ -211,17 +529,41 @@ pub enum ServiceAction {
         /// Tunnel ID (interactive if omitted)
         #[arg(long)]
         tunnel: Option<String>,
+        /// Run cloudflared with this config file (`--config <PATH>`)
+        #[arg(long, value_name = "PATH")]
+        config_path: Option<PathBuf>,
     },
     /// Start service / 启动服务
     Start,
     /// Stop service / 停止服务
     Stop,
     /// Restart service / 重启服务
-    Restart,
+    Restart {
+        /// Do not wait for the metrics endpoint; for services run without metrics
+        #[arg(long)]
+        no_wait_metrics: bool,
+    },
+    /// Upgrade cloudflared / 升级 cloudflared
+    Upgrade,
     /// Show recent logs / 查看最近日志
     Logs {
         /// Number of lines
         #[arg(long, default_value = "100")]
         lines: usize,
+        /// Only show lines containing this pattern
+        #[arg(long, value_name = "PATTERN")]
+        filter: Option<String>,
+        /// Read a cloudflared JSON log file instead of the system journal
+        #[arg(long, value_name = "PATH", conflicts_with = "filter")]
+        structured_log: Option<PathBuf>,
+    },
+    /// Show or set memory/CPU limits (Linux systemd) / 查看或设置资源限制
+    Limits {
+        /// Maximum memory, e.g. 512M or 2G
+        #[arg(long)]
+        memory: Option<String>,
+        /// CPU quota in percent (1-100)
+        #[arg(long)]
+        cpu: Option<String>,
     },
 }
@@ -1,13 +1,34 @@
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use anyhow::{bail, Context};
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::config::ApiConfig;
+use crate::config::{self, ApiConfig};
 use crate::error::{CftError, Result};
 
 const BASE_URL: &str = "https://api.cloudflare.com/client/v4";
 
+/// Response body size limit used when `response_size_limit_mb` is not set.
+pub const DEFAULT_RESPONSE_LIMIT_MB: u32 = 10;
+
+/// Page size used when listing zones (the API maximum is 50).
+const ZONES_PER_PAGE: u32 = 50;
+
+/// Default page size when listing Access applications.
+const ACCESS_APPS_PER_PAGE: u32 = 100;
+
+/// Page size when listing the policies of an Access application.
+const ACCESS_POLICIES_PER_PAGE: u32 = 50;
+
+/// How long `get_account_details` reuses a fetched account.
+const ACCOUNT_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
 // ---------------------------------------------------------------------------
 // Generic Cloudflare API response types
 // ---------------------------------------------------------------------------
@@ -49,6 +70,9 @@ pub struct Tunnel {
     pub name: String,
     pub created_at: Option<String>,
     pub status: Option<String>,
+    /// Set once the tunnel is deleted; deleted tunnels stay listable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
 }
 
 /// Remotely-managed tunnel configuration (ingress rules).
@@ -57,6 +81,66 @@ pub struct TunnelConfiguration {
     pub config: TunnelConfigInner,
 }
 
+impl TunnelConfiguration {
+    /// Number of ingress rules that route a hostname (everything but the
+    /// catch-all).
+    pub fn hostname_count(&self) -> usize {
+        self.config
+            .ingress
+            .iter()
+            .filter(|r| !r.is_catch_all())
+            .count()
+    }
+
+    /// Check the ingress rules the way cloudflared does: exactly one
+    /// catch-all rule (no hostname, or `*`), placed last; unique hostnames;
+    /// and a service on every rule. Returns one message per problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let ingress = &self.config.ingress;
+        let mut problems = Vec::new();
+
+        let catch_all: Vec<usize> = ingress
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.is_catch_all())
+            .map(|(i, _)| i)
+            .collect();
+        match catch_all.len() {
+            0 => problems.push("no catch-all rule (a rule without hostname)".to_string()),
+            1 => {}
+            n => problems.push(format!("{n} catch-all rules; exactly one is allowed")),
+        }
+        if catch_all.iter().any(|&i| i + 1 != ingress.len()) {
+            problems.push("the catch-all rule must be the last rule".to_string());
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for rule in ingress.iter().filter(|r| !r.is_catch_all()) {
+            let host = rule.hostname.as_deref().unwrap_or_default();
+            if !seen.insert(host.to_ascii_lowercase()) {
+                problems.push(format!("duplicate hostname {host}"));
+            }
+        }
+
+        for rule in ingress.iter().filter(|r| r.service.trim().is_empty()) {
+            problems.push(format!(
+                "empty service for {}",
+                rule.hostname.as_deref().unwrap_or("the catch-all rule")
+            ));
+        }
+
+        problems
+    }
+}
+
+/// Configuration as returned by the API; `config` is absent or null for a
+/// tunnel that has never been configured.
+#[derive(Debug, Deserialize)]
+struct StoredTunnelConfiguration {
+    #[serde(default)]
+    config: Option<TunnelConfigInner>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TunnelConfigInner {
     pub ingress: Vec<IngressRule>,
@@ -72,6 +156,15 @@ pub struct IngressRule {
     pub origin_request: Option<serde_json::Value>,
 }
 
+impl IngressRule {
+    /// Whether the rule matches every hostname: no hostname, or `*`.
+    pub fn is_catch_all(&self) -> bool {
+        self.hostname
+            .as_deref()
+            .is_none_or(|h| matches!(h.trim(), "" | "*"))
+    }
+}
+
 /// An active tunnel connector (a running cloudflared instance).
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TunnelConnector {
@@ -101,6 +194,9 @@ pub struct DnsRecord {
     pub content: String,
     pub proxied: Option<bool>,
     pub ttl: Option<u32>,
+    /// Set for MX records.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -111,6 +207,9 @@ pub struct CreateDnsRecord {
     pub content: String,
     pub proxied: bool,
     pub ttl: Option<u32>,
+    /// Required for MX records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u16>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -121,9 +220,66 @@ pub struct AccessApp {
     #[serde(rename = "type")]
     pub app_type: Option<String>,
     pub session_duration: Option<String>,
+    /// Evaluation order when several applications match a request (1 = first).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precedence: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors_headers: Option<CorsHeaders>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// CORS settings Access applies to preflight requests for an application.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CorsHeaders {
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    #[serde(default)]
+    pub allow_all_origins: bool,
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+/// Full Access application settings, including its login requirements.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessAppDetail {
+    pub name: String,
+    pub domain: String,
+    #[serde(rename = "type")]
+    pub app_type: Option<String>,
+    pub session_duration: Option<String>,
+    /// Identity provider IDs users may log in with; empty means any.
+    #[serde(default)]
+    pub allowed_idps: Vec<String>,
+    /// Skip the login page when exactly one identity provider is allowed.
+    #[serde(default)]
+    pub auto_redirect_to_identity: bool,
+    #[serde(default)]
+    pub cors_headers: Option<CorsHeaders>,
+}
+
+/// One entry of an Access application reorder request.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AccessAppPrecedence {
+    pub id: String,
+    pub precedence: u32,
+}
+
+impl AccessAppPrecedence {
+    /// Assign precedence 1, 2, ... to `ordered_ids` in the given order.
+    pub fn from_order(ordered_ids: &[String]) -> Vec<Self> {
+        ordered_ids
+            .iter()
+            .zip(1..)
+            .map(|(id, precedence)| Self {
+                id: id.clone(),
+                precedence,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CreateAccessApp {
     pub name: String,
     pub domain: String,
@@ -132,6 +288,40 @@ pub struct CreateAccessApp {
     pub session_duration: String,
 }
 
+/// Partial update for an Access application; `None` fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct UpdateAccessApp {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_duration: Option<String>,
+}
+
+impl UpdateAccessApp {
+    /// Build an update containing only the values that differ from `current`.
+    /// A `None` session duration leaves the application's setting untouched.
+    pub fn changes(
+        current: &AccessApp,
+        name: &str,
+        domain: &str,
+        session_duration: Option<&str>,
+    ) -> Self {
+        Self {
+            name: (current.name != name).then(|| name.to_string()),
+            domain: (current.domain != domain).then(|| domain.to_string()),
+            session_duration: session_duration
+                .filter(|d| current.session_duration.as_deref() != Some(*d))
+                .map(str::to_string),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.domain.is_none() && self.session_duration.is_none()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AccessPolicy {
     pub id: Option<String>,
@@ -152,6 +342,13 @@ pub struct PolicyRule {
     pub email_domain: Option<PolicyEmailDomain>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub everyone: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "group")]
+    pub access_group: Option<PolicyAccessGroup>,
+    /// One-time PIN: serialized as `{"service_token": {}}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_token: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gsuite: Option<PolicyGSuite>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -164,6 +361,52 @@ pub struct PolicyEmailDomain {
     pub domain: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PolicyAccessGroup {
+    pub id: String,
+}
+
+/// Google Workspace rule: members of `email` (a group or domain address)
+/// authenticated through the given identity provider.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PolicyGSuite {
+    pub email: String,
+    pub identity_provider_id: String,
+}
+
+/// A login method configured for Access (Google Workspace, GitHub, OTP, ...).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdentityProvider {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub idp_type: Option<String>,
+}
+
+/// A reusable Access group (set of rules shared across policies).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessGroup {
+    pub id: String,
+    pub name: String,
+}
+
+/// One Access authentication event from the audit log.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessAuditLog {
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub user_email: String,
+    #[serde(default)]
+    pub ip_address: String,
+    #[serde(default)]
+    pub app_uid: String,
+    #[serde(default)]
+    pub action: String,
+    #[serde(default)]
+    pub allowed: bool,
+}
+
 /// A Cloudflare zone setting (e.g. always_use_https).
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ZoneSetting {
@@ -192,6 +435,13 @@ pub struct Account {
     pub name: String,
 }
 
+/// Whether the Cloudflare API answered an unauthenticated request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkReachability {
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+}
+
 /// Token verification outcome.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenVerifyStatus {
@@ -201,65 +451,214 @@ pub enum TokenVerifyStatus {
     Unknown,
 }
 
+// ---------------------------------------------------------------------------
+// Request audit log
+// ---------------------------------------------------------------------------
+
+/// Appends one JSON line when each API request starts and one when it ends.
+#[derive(Debug, Clone)]
+pub struct RequestLogger {
+    pub log_path: PathBuf,
+}
+
+impl RequestLogger {
+    pub fn new(log_path: PathBuf) -> Self {
+        Self { log_path }
+    }
+
+    /// Append `entry` as a single write so concurrent writers never interleave.
+    fn append(&self, entry: &serde_json::Value) {
+        let line = format!("{entry}\n");
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)
+            .and_then(|mut f| f.write_all(line.as_bytes()));
+        if let Err(e) = written {
+            eprintln!("audit log {}: {e}", self.log_path.display());
+        }
+    }
+
+    /// Log a request about to be sent; returns its timestamp.
+    fn request_started(&self, method: &reqwest::Method, url: &str) -> String {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.append(&serde_json::json!({
+            "timestamp": timestamp,
+            "method": method.as_str(),
+            "url": url,
+            "success": null,
+        }));
+        timestamp
+    }
+
+    /// Log the outcome of the request started at `timestamp`.
+    fn request_finished(
+        &self,
+        timestamp: &str,
+        method: &reqwest::Method,
+        url: &str,
+        status: Option<reqwest::StatusCode>,
+        duration: Duration,
+    ) {
+        self.append(&serde_json::json!({
+            "timestamp": timestamp,
+            "method": method.as_str(),
+            "url": url,
+            "success": status.is_some_and(|s| s.is_success()),
+            "status": status.map(|s| s.as_u16()),
+            "duration_ms": duration.as_millis() as u64,
+        }));
+    }
+}
+
 // ---------------------------------------------------------------------------
 // CloudflareClient
 // ---------------------------------------------------------------------------
 
 /// Unified HTTP client for all Cloudflare API interactions.
+///
+/// Cloning is cheap: clones share the underlying HTTP connection pool and
+/// account cache, so pass `client.clone()` into spawned tasks.
 #[derive(Clone)]
 pub struct CloudflareClient {
     http: reqwest::Client,
+    /// API root, `BASE_URL` outside of tests.
+    base_url: String,
     pub account_id: String,
     pub zone_id: Option<String>,
+    /// Maximum accepted body size in bytes, applied to each response (page).
+    /// `None` accepts any size.
+    response_limit: Option<usize>,
+    /// Account details and when they were fetched, shared between clones.
+    account_cache: Arc<Mutex<Option<(Instant, Account)>>>,
+    /// Audit log of every request, when enabled.
+    logger: Option<RequestLogger>,
+}
+
+/// Never prints the API token, which lives in the HTTP client's default
+/// `Authorization` header.
+impl fmt::Debug for CloudflareClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudflareClient")
+            .field("account_id", &self.account_id)
+            .field("zone_id", &self.zone_id)
+            .field("token", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl fmt::Display for CloudflareClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.account_id)
+    }
 }
 
 #[allow(dead_code)]
 impl CloudflareClient {
     /// Build a client from a saved `ApiConfig`.
     pub fn from_config(config: &ApiConfig) -> Result<Self> {
-        let token = config
-            .api_token
-            .as_ref()
-            .ok_or(CftError::ApiNotConfigured)?;
+        let token = config.effective_token().ok_or(CftError::ApiNotConfigured)?;
         let account_id = config
             .account_id
             .as_ref()
             .ok_or(CftError::ApiNotConfigured)?
             .clone();
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", token))
-                .context("invalid token characters")?,
-        );
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-
-        let http = reqwest::Client::builder()
-            .default_headers(headers)
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
-
         Ok(Self {
-            http,
+            http: http_client(Some(token))?,
+            base_url: BASE_URL.to_string(),
             account_id,
             zone_id: config.zone_id.clone(),
+            response_limit: response_limit(Some(config)),
+            account_cache: Arc::new(Mutex::new(None)),
+            logger: config::audit_log_path_for(Some(config)).map(RequestLogger::new),
+        })
+    }
+
+    /// Build a client from the saved config with this invocation's
+    /// `--token` / `--account-id` / `--zone-id` overrides applied.
+    pub fn from_effective_config() -> Result<Self> {
+        Self::from_config(&config::require_api_config()?)
+    }
+
+    /// A client that is not bound to an account, for the checks made while
+    /// one is being chosen. Without a token no `Authorization` header is sent.
+    /// The size limit and audit log come from `saved`, usually the saved
+    /// config from [`saved_config`].
+    fn unscoped(token: Option<&str>, saved: Option<&ApiConfig>) -> Result<Self> {
+        Ok(Self {
+            http: http_client(token)?,
+            base_url: BASE_URL.to_string(),
+            account_id: String::new(),
+            zone_id: None,
+            response_limit: response_limit(saved),
+            account_cache: Arc::new(Mutex::new(None)),
+            logger: config::audit_log_path_for(saved).map(RequestLogger::new),
         })
     }
 
+    /// Send requests to `base_url` instead of the Cloudflare API.
+    #[cfg(test)]
+    pub(crate) fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Log every request made by this client to `logger`.
+    pub fn with_request_logger(mut self, logger: RequestLogger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
     // -- helpers ------------------------------------------------------------
 
+    /// Send a request, recording it in the audit log when one is configured.
+    async fn send(&self, req: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let request = req.build()?;
+        let Some(logger) = &self.logger else {
+            return self.http.execute(request).await;
+        };
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let timestamp = logger.request_started(&method, &url);
+        let started = Instant::now();
+        let result = self.http.execute(request).await;
+        let status = result.as_ref().ok().map(|r| r.status());
+        logger.request_finished(&timestamp, &method, &url, status, started.elapsed());
+        result
+    }
+
+    /// Read a response body, giving up as soon as it exceeds the size limit:
+    /// an oversized `Content-Length` is rejected before anything is read.
+    async fn read_body(&self, mut resp: reqwest::Response) -> Result<String> {
+        let Some(limit) = self.response_limit else {
+            return resp.text().await.context("failed to read response body");
+        };
+        if let Some(len) = resp.content_length().filter(|&len| len as usize > limit) {
+            return Err(CftError::ResponseTooLarge {
+                bytes: len as usize,
+            }
+            .into());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await.context("failed to read response body")? {
+            body.extend_from_slice(&chunk);
+            check_body_size(body.len(), limit)?;
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
     async fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
-        let resp = self.http.get(url).send().await.context("HTTP GET failed")?;
+        let resp = self
+            .send(self.http.get(url))
+            .await
+            .context("HTTP GET failed")?;
         self.parse_response(resp).await
     }
 
     async fn post<T: DeserializeOwned, B: Serialize>(&self, url: &str, body: &B) -> Result<T> {
         let resp = self
-            .http
-            .post(url)
-            .json(body)
-            .send()
+            .send(self.http.post(url).json(body))
             .await
             .context("HTTP POST failed")?;
         self.parse_response(resp).await
@@ -267,10 +666,7 @@ impl CloudflareClient {
 
     async fn put<T: DeserializeOwned, B: Serialize>(&self, url: &str, body: &B) -> Result<T> {
         let resp = self
-            .http
-            .put(url)
-            .json(body)
-            .send()
+            .send(self.http.put(url).json(body))
             .await
             .context("HTTP PUT failed")?;
         self.parse_response(resp).await
@@ -278,49 +674,120 @@ impl CloudflareClient {
 
     async fn patch<T: DeserializeOwned, B: Serialize>(&self, url: &str, body: &B) -> Result<T> {
         let resp = self
-            .http
-            .patch(url)
-            .json(body)
-            .send()
+            .send(self.http.patch(url).json(body))
             .await
             .context("HTTP PATCH failed")?;
         self.parse_response(resp).await
     }
 
-    async fn delete_req<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+    /// `DELETE` a resource. Many delete endpoints answer with a `null` result.
+    async fn delete_req<T: DeserializeOwned>(&self, url: &str) -> Result<Option<T>> {
         let resp = self
-            .http
-            .delete(url)
-            .send()
+            .send(self.http.delete(url))
             .await
             .context("HTTP DELETE failed")?;
-        self.parse_response(resp).await
+        self.parse_response_opt(resp).await
     }
 
+    /// Parse a response whose `result` must be present (creation/read endpoints).
     async fn parse_response<T: DeserializeOwned>(&self, resp: reqwest::Response) -> Result<T> {
         let status = resp.status();
-        let body = resp.text().await.context("failed to read response body")?;
+        self.parse_response_opt(resp)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("empty result from Cloudflare API (HTTP {status})"))
+    }
 
-        let cf: CfResponse<T> =
-            serde_json::from_str(&body).context("failed to parse Cloudflare response")?;
+    /// GET `url` page by page (adding `page=N`) until `total_pages` is reached
+    /// or a page comes back empty, and collect every item.
+    async fn get_all_pages<T: DeserializeOwned>(&self, url: reqwest::Url) -> Result<Vec<T>> {
+        Ok(self.get_all_pages_counted(url).await?.0)
+    }
 
-        if !cf.success {
-            let msg = cf
-                .errors
-                .first()
-                .map(|e| format!("{} (code {})", e.message, e.code))
-                .unwrap_or_else(|| format!("HTTP {status}"));
-            bail!("Cloudflare API error: {msg}");
+    /// Like `get_all_pages`, also returning the `total_count` the API reports
+    /// (the number of items collected when it reports none).
+    async fn get_all_pages_counted<T: DeserializeOwned>(
+        &self,
+        url: reqwest::Url,
+    ) -> Result<(Vec<T>, usize)> {
+        let mut items = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let mut page_url = url.clone();
+            page_url
+                .query_pairs_mut()
+                .append_pair("page", &page.to_string());
+            let resp = self
+                .send(self.http.get(page_url))
+                .await
+                .context("HTTP GET failed")?;
+            let status = resp.status();
+            let body = self.read_body(resp).await?;
+            let (batch, info) = decode_page::<T>(status, &body)?;
+            let total_pages = info.as_ref().and_then(|i| i.total_pages).unwrap_or(1);
+            let done = batch.is_empty() || page >= total_pages;
+            items.extend(batch);
+            if done {
+                let total = info
+                    .and_then(|i| i.total_count)
+                    .map_or(items.len(), |n| n as usize);
+                return Ok((items, total));
+            }
+            page += 1;
         }
+    }
 
-        cf.result
-            .ok_or_else(|| anyhow::anyhow!("empty result from Cloudflare API (HTTP {status})"))
+    /// Parse a response whose `result` may be `null` on success (e.g. deletions).
+    async fn parse_response_opt<T: DeserializeOwned>(
+        &self,
+        resp: reqwest::Response,
+    ) -> Result<Option<T>> {
+        let status = resp.status();
+        let body = self.read_body(resp).await?;
+        decode_response(status, &body)
     }
 
     /// Fetch raw JSON response (for debugging API responses).
     pub async fn get_raw(&self, url: &str) -> Result<String> {
-        let resp = self.http.get(url).send().await.context("HTTP GET failed")?;
-        resp.text().await.context("failed to read response body")
+        let resp = self
+            .send(self.http.get(url))
+            .await
+            .context("HTTP GET failed")?;
+        self.read_body(resp).await
+    }
+
+    /// Full API URL for a path such as `/zones` or `accounts/{id}/rules`.
+    fn raw_url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    /// Send a request to an API path (relative to the v4 base URL) and return
+    /// the raw response body. For endpoints not wrapped by this client.
+    pub async fn raw_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<String> {
+        let mut req = self.http.request(method.clone(), self.raw_url(path));
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let resp = self
+            .send(req)
+            .await
+            .with_context(|| format!("HTTP {method} failed"))?;
+        self.read_body(resp).await
+    }
+
+    /// `GET` an API path and return the raw response body.
+    pub async fn raw_get(&self, path: &str) -> Result<String> {
+        self.raw_request(reqwest::Method::GET, path, None).await
+    }
+
+    /// `POST` a JSON body to an API path and return the raw response body.
+    pub async fn raw_post(&self, path: &str, body: serde_json::Value) -> Result<String> {
+        self.raw_request(reqwest::Method::POST, path, Some(body))
+            .await
     }
 
     fn require_zone_id(&self) -> Result<&str> {
@@ -334,12 +801,13 @@ impl CloudflareClient {
     /// Check basic network connectivity to the Cloudflare API.
     /// Returns Ok(()) if reachable, Err with a human-readable description if not.
     pub async fn check_network() -> Result<()> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .build()?;
-        client
+        let client = Self::unscoped(None, saved_config().as_ref())?;
+        let req = client
+            .http
             .get("https://cloudflare.com")
-            .send()
+            .timeout(Duration::from_secs(5));
+        client
+            .send(req)
             .await
             .context("Cannot reach Cloudflare (network unreachable)")?;
         Ok(())
@@ -347,19 +815,21 @@ impl CloudflareClient {
 
     /// Verify the current API token is valid.
     pub async fn verify_token(token: &str, _account_id: Option<&str>) -> Result<TokenVerifyStatus> {
-        let client = reqwest::Client::new();
-        let url = format!("{BASE_URL}/user/tokens/verify");
-        let resp = match client
-            .get(url)
-            .bearer_auth(token)
-            .send()
+        Self::unscoped(Some(token), saved_config().as_ref())?
+            .token_status()
             .await
-        {
+    }
+
+    /// Classify `/user/tokens/verify` for this client's token. Network and
+    /// permission problems are `Unknown`, not `Invalid`.
+    async fn token_status(&self) -> Result<TokenVerifyStatus> {
+        let url = format!("{}/user/tokens/verify", self.base_url);
+        let resp = match self.send(self.http.get(url)).await {
             Ok(r) => r,
             Err(_) => return Ok(TokenVerifyStatus::Unknown),
         };
 
-        let body = resp.text().await.unwrap_or_default();
+        let body = self.read_body(resp).await.unwrap_or_default();
 
         #[derive(Deserialize)]
         struct VerifyResult {
@@ -402,14 +872,17 @@ impl CloudflareClient {
 
     /// Fetch all accounts accessible by the token.
     pub async fn fetch_accounts(token: &str) -> Result<Vec<Account>> {
-        let client = reqwest::Client::new();
-        let resp = client
-            .get(format!("{BASE_URL}/accounts"))
-            .bearer_auth(token)
-            .send()
+        Self::unscoped(Some(token), saved_config().as_ref())?
+            .accounts()
+            .await
+    }
+
+    async fn accounts(&self) -> Result<Vec<Account>> {
+        let resp = self
+            .send(self.http.get(format!("{}/accounts", self.base_url)))
             .await
             .context("failed to fetch accounts")?;
-        let body = resp.text().await?;
+        let body = self.read_body(resp).await?;
         let cf: CfResponse<Vec<Account>> = serde_json::from_str(&body)?;
         if !cf.success {
             let msg = cf
@@ -419,42 +892,175 @@ impl CloudflareClient {
                 .unwrap_or_else(|| "unknown error".to_string());
             bail!("Cloudflare API error: {msg}");
         }
-        Ok(cf.result.unwrap_or_default())
+        let accounts = cf.result.unwrap_or_default();
+        if accounts.is_empty() {
+            self.explain_empty_result(CftError::EmptyResult {
+                resource: "accounts",
+                hint: "Token is valid but grants no account access. Ensure 'Account - Account: Read' permission is included.",
+            })
+            .await?;
+        }
+        Ok(accounts)
+    }
+
+    /// An empty list is ambiguous: check the token, and turn "valid token,
+    /// nothing visible" into `empty` so callers can show the permission hint.
+    /// When the token cannot be checked the empty list is accepted as-is.
+    async fn explain_empty_result(&self, empty: CftError) -> Result<()> {
+        match self.token_status().await? {
+            TokenVerifyStatus::Valid => Err(empty.into()),
+            TokenVerifyStatus::Invalid(reason) => bail!("Invalid API token: {reason}"),
+            TokenVerifyStatus::Unknown => Ok(()),
+        }
+    }
+
+    /// Verify the client's own token (`/user/tokens/verify`).
+    pub async fn verify_current_token(&self) -> Result<serde_json::Value> {
+        let url = format!("{}/user/tokens/verify", self.base_url);
+        self.get(&url).await
     }
 
-    /// Fetch all zones accessible by the token.
+    /// Check that the Cloudflare API can be reached at all, without a token.
+    pub async fn check_reachability() -> NetworkReachability {
+        match Self::unscoped(None, saved_config().as_ref()) {
+            Ok(client) => client.reachability().await,
+            Err(_) => NetworkReachability {
+                reachable: false,
+                latency_ms: None,
+            },
+        }
+    }
+
+    /// Any HTTP response from the API root counts as reachable, whatever its
+    /// status.
+    async fn reachability(&self) -> NetworkReachability {
+        let url = format!("{}/", self.base_url);
+        let started = std::time::Instant::now();
+        let req = self.http.get(url).timeout(Duration::from_secs(10));
+        match self.send(req).await {
+            Ok(_) => NetworkReachability {
+                reachable: true,
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+            },
+            Err(_) => NetworkReachability {
+                reachable: false,
+                latency_ms: None,
+            },
+        }
+    }
+
+    /// Fetch all zones accessible by the token, following pagination.
     pub async fn fetch_zones(token: &str) -> Result<Vec<Zone>> {
-        let client = reqwest::Client::new();
-        let resp = client
-            .get(format!("{BASE_URL}/zones"))
-            .bearer_auth(token)
-            .send()
+        Self::unscoped(Some(token), saved_config().as_ref())?
+            .zones_for(None)
             .await
-            .context("failed to fetch zones")?;
-        let body = resp.text().await?;
-        let cf: CfResponse<Vec<Zone>> = serde_json::from_str(&body)?;
-        if !cf.success {
-            let msg = cf
-                .errors
-                .first()
-                .map(|e| format!("{} (code {})", e.message, e.code))
-                .unwrap_or_else(|| "unknown error".to_string());
-            bail!("Cloudflare API error: {msg}");
+    }
+
+    /// Fetch all zones accessible by the token that belong to `account_id`.
+    pub async fn fetch_zones_for_account(token: &str, account_id: &str) -> Result<Vec<Zone>> {
+        Self::unscoped(Some(token), saved_config().as_ref())?
+            .zones_for(Some(account_id))
+            .await
+    }
+
+    /// Every zone visible to the token, optionally only `account_id`'s.
+    async fn zones_for(&self, account_id: Option<&str>) -> Result<Vec<Zone>> {
+        let mut zones: Vec<Zone> = self.get_all_pages(self.zones_url(account_id)?).await?;
+
+        // The account filter is advisory on some plans; enforce it here.
+        if let Some(id) = account_id {
+            zones.retain(|z| z.account.as_ref().is_none_or(|a| a.id == id));
+        }
+        if zones.is_empty() {
+            self.explain_empty_result(CftError::EmptyResult {
+                resource: "zones",
+                hint: "Token is valid but grants no zone access. Ensure 'Zone - Zone: Read' permission is included.",
+            })
+            .await?;
+        }
+        Ok(zones)
+    }
+
+    /// List the zones visible to this client's token, following pagination.
+    pub async fn list_zones(&self) -> Result<Vec<Zone>> {
+        self.get_all_pages(self.zones_url(None)?).await
+    }
+
+    fn zones_url(&self, account_id: Option<&str>) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!("{}/zones", self.base_url))
+            .context("invalid zones URL")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("per_page", &ZONES_PER_PAGE.to_string());
+            if let Some(id) = account_id {
+                query.append_pair("account.id", id);
+            }
+        }
+        Ok(url)
+    }
+
+    /// Details (name) of the configured account, cached for five minutes.
+    pub async fn get_account_details(&self) -> Result<Account> {
+        // A poisoned lock only means the cache is skipped
+        let cached = self.account_cache.lock().ok().and_then(|cache| {
+            cache
+                .as_ref()
+                .filter(|(fetched, _)| fetched.elapsed() < ACCOUNT_CACHE_TTL)
+                .map(|(_, account)| account.clone())
+        });
+        if let Some(account) = cached {
+            return Ok(account);
+        }
+        let url = format!("{}/accounts/{}", self.base_url, self.account_id);
+        let account: Account = self.get(&url).await?;
+        if let Ok(mut cache) = self.account_cache.lock() {
+            *cache = Some((Instant::now(), account.clone()));
         }
-        Ok(cf.result.unwrap_or_default())
+        Ok(account)
     }
 
     // -- Tunnel operations --------------------------------------------------
 
-    /// List all tunnels in the account.
+    /// List the account's tunnels, leaving out deleted ones.
     pub async fn list_tunnels(&self) -> Result<Vec<Tunnel>> {
-        let url = format!("{BASE_URL}/accounts/{}/cfd_tunnel", self.account_id);
-        self.get(&url).await
+        self.list_tunnels_filtered(false, None).await
+    }
+
+    /// List tunnels, optionally including deleted ones and restricted to a
+    /// `status` such as `"healthy"`, `"inactive"` or `"degraded"`.
+    pub async fn list_tunnels_filtered(
+        &self,
+        include_deleted: bool,
+        status: Option<&str>,
+    ) -> Result<Vec<Tunnel>> {
+        let url = self.tunnels_url(include_deleted, status)?;
+        self.get(url.as_str()).await
+    }
+
+    fn tunnels_url(&self, include_deleted: bool, status: Option<&str>) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/accounts/{}/cfd_tunnel",
+            self.base_url, self.account_id
+        ))
+        .context("invalid tunnel list URL")?;
+        {
+            let mut query = url.query_pairs_mut();
+            if !include_deleted {
+                query.append_pair("is_deleted", "false");
+            }
+            if let Some(status) = status {
+                query.append_pair("status", status);
+            }
+        }
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        Ok(url)
     }
 
     /// Create a new tunnel.
     pub async fn create_tunnel(&self, name: &str, secret: &str) -> Result<Tunnel> {
-        let url = format!("{BASE_URL}/accounts/{}/cfd_tunnel", self.account_id);
+        let url = format!("{}/accounts/{}/cfd_tunnel", self.base_url, self.account_id);
         let body = serde_json::json!({
             "name": name,
             "tunnel_secret": secret,
@@ -462,11 +1068,21 @@ impl CloudflareClient {
         self.post(&url, &body).await
     }
 
+    /// Replace the secret of an existing tunnel.
+    pub async fn rotate_tunnel_secret(&self, tunnel_id: &str, secret: &str) -> Result<Tunnel> {
+        let url = format!(
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}",
+            self.base_url, self.account_id
+        );
+        let body = serde_json::json!({ "tunnel_secret": secret });
+        self.patch(&url, &body).await
+    }
+
     /// Delete a tunnel by ID.
-    pub async fn delete_tunnel(&self, tunnel_id: &str) -> Result<serde_json::Value> {
+    pub async fn delete_tunnel(&self, tunnel_id: &str) -> Result<Option<serde_json::Value>> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}",
-            self.account_id
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}",
+            self.base_url, self.account_id
         );
         self.delete_req(&url).await
     }
@@ -474,8 +1090,8 @@ impl CloudflareClient {
     /// Get tunnel details.
     pub async fn get_tunnel(&self, tunnel_id: &str) -> Result<Tunnel> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}",
-            self.account_id
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}",
+            self.base_url, self.account_id
         );
         self.get(&url).await
     }
@@ -483,58 +1099,90 @@ impl CloudflareClient {
     /// Get the tunnel token (used to run `cloudflared tunnel run --token <TOKEN>`).
     pub async fn get_tunnel_token(&self, tunnel_id: &str) -> Result<String> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}/token",
-            self.account_id
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}/token",
+            self.base_url, self.account_id
         );
         self.get(&url).await
     }
 
+    fn tunnel_connections_url(&self, tunnel_id: &str) -> String {
+        format!(
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}/connections",
+            self.base_url, self.account_id
+        )
+    }
+
     /// List active connectors for a tunnel.
     pub async fn list_tunnel_connections(&self, tunnel_id: &str) -> Result<Vec<TunnelConnector>> {
-        let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}/connections",
-            self.account_id
-        );
+        let url = self.tunnel_connections_url(tunnel_id);
         self.get(&url).await
     }
 
+    /// Drop all active connections for a tunnel (e.g. stale connectors from
+    /// crashed cloudflared instances). Running connectors must reconnect.
+    pub async fn force_disconnect_tunnel(&self, tunnel_id: &str) -> Result<()> {
+        let url = self.tunnel_connections_url(tunnel_id);
+        self.delete_req::<serde_json::Value>(&url).await?;
+        Ok(())
+    }
+
     // -- Tunnel configuration (remotely-managed) ----------------------------
 
     /// Get the ingress configuration for a remotely-managed tunnel.
-    pub async fn get_tunnel_config(&self, tunnel_id: &str) -> Result<TunnelConfiguration> {
+    /// Returns `None` when no configuration has been set for the tunnel yet.
+    pub async fn get_tunnel_config(&self, tunnel_id: &str) -> Result<Option<TunnelConfiguration>> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}/configurations",
-            self.account_id
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}/configurations",
+            self.base_url, self.account_id
         );
-        self.get(&url).await
+        let resp = self
+            .send(self.http.get(&url))
+            .await
+            .context("HTTP GET failed")?;
+        let status = resp.status();
+        let body = self.read_body(resp).await?;
+        parse_tunnel_config(status, &body)
     }
 
-    /// Set the ingress configuration for a remotely-managed tunnel.
+    /// Set the ingress configuration for a remotely-managed tunnel. The
+    /// rules are validated locally first, so mistakes are reported clearly
+    /// instead of as a generic API rejection.
     pub async fn put_tunnel_config(
         &self,
         tunnel_id: &str,
         config: &TunnelConfiguration,
     ) -> Result<TunnelConfiguration> {
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(CftError::InvalidInput(problems.join("; ")).into());
+        }
         let url = format!(
-            "{BASE_URL}/accounts/{}/cfd_tunnel/{tunnel_id}/configurations",
-            self.account_id
+            "{}/accounts/{}/cfd_tunnel/{tunnel_id}/configurations",
+            self.base_url, self.account_id
         );
         self.put(&url, config).await
     }
 
     // -- DNS operations -----------------------------------------------------
 
+    /// Details of the configured zone.
+    pub async fn get_zone(&self) -> Result<Zone> {
+        let zone_id = self.require_zone_id()?;
+        let url = format!("{}/zones/{zone_id}", self.base_url);
+        self.get(&url).await
+    }
+
     /// List DNS records for the configured zone.
     pub async fn list_dns_records(&self) -> Result<Vec<DnsRecord>> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/dns_records?per_page=100");
+        let url = format!("{}/zones/{zone_id}/dns_records?per_page=100", self.base_url);
         self.get(&url).await
     }
 
     /// Add a DNS record.
     pub async fn create_dns_record(&self, record: &CreateDnsRecord) -> Result<DnsRecord> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/dns_records");
+        let url = format!("{}/zones/{zone_id}/dns_records", self.base_url);
         self.post(&url, record).await
     }
 
@@ -545,49 +1193,152 @@ impl CloudflareClient {
         record: &CreateDnsRecord,
     ) -> Result<DnsRecord> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/dns_records/{record_id}");
+        let url = format!("{}/zones/{zone_id}/dns_records/{record_id}", self.base_url);
         self.put(&url, record).await
     }
 
     /// Delete a DNS record by ID.
-    pub async fn delete_dns_record(&self, record_id: &str) -> Result<serde_json::Value> {
+    pub async fn delete_dns_record(&self, record_id: &str) -> Result<Option<serde_json::Value>> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/dns_records/{record_id}");
+        let url = format!("{}/zones/{zone_id}/dns_records/{record_id}", self.base_url);
         self.delete_req(&url).await
     }
 
     // -- Access operations --------------------------------------------------
 
-    /// List Access applications.
-    pub async fn list_access_apps(&self) -> Result<Vec<AccessApp>> {
-        let url = format!("{BASE_URL}/accounts/{}/access/apps", self.account_id);
-        self.get(&url).await
+    /// List Access applications, following pagination. `per_page` defaults to
+    /// 100; `filter_by_domain` asks the API for apps on that domain only.
+    pub async fn list_access_apps(
+        &self,
+        per_page: Option<u32>,
+        filter_by_domain: Option<&str>,
+    ) -> Result<Vec<AccessApp>> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/accounts/{}/access/apps",
+            self.base_url, self.account_id
+        ))
+        .context("invalid Access apps URL")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair(
+                "per_page",
+                &per_page.unwrap_or(ACCESS_APPS_PER_PAGE).to_string(),
+            );
+            if let Some(domain) = filter_by_domain {
+                query.append_pair("domain", domain);
+            }
+        }
+        self.get_all_pages(url).await
+    }
+
+    /// List Access applications in evaluation order. Applications without a
+    /// precedence keep their API order after the ranked ones.
+    pub async fn list_access_apps_ordered(&self) -> Result<Vec<AccessApp>> {
+        let mut apps = self.list_access_apps(None, None).await?;
+        apps.sort_by_key(|a| a.precedence.unwrap_or(u32::MAX));
+        Ok(apps)
+    }
+
+    /// Set the evaluation order of Access applications; the first ID is
+    /// evaluated first.
+    pub async fn reorder_access_apps(&self, ordered_ids: &[String]) -> Result<()> {
+        let url = format!(
+            "{}/accounts/{}/access/apps/order",
+            self.base_url, self.account_id
+        );
+        let body = serde_json::json!({ "apps": AccessAppPrecedence::from_order(ordered_ids) });
+        let _: serde_json::Value = self.put(&url, &body).await?;
+        Ok(())
     }
 
     /// Create an Access application.
     pub async fn create_access_app(&self, app: &CreateAccessApp) -> Result<AccessApp> {
-        let url = format!("{BASE_URL}/accounts/{}/access/apps", self.account_id);
+        let url = format!("{}/accounts/{}/access/apps", self.base_url, self.account_id);
         self.post(&url, app).await
     }
 
-    /// Delete an Access application.
-    pub async fn delete_access_app(&self, app_id: &str) -> Result<serde_json::Value> {
+    /// Get a single Access application.
+    pub async fn get_access_app(&self, app_id: &str) -> Result<AccessApp> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/access/apps/{app_id}",
-            self.account_id
+            "{}/accounts/{}/access/apps/{app_id}",
+            self.base_url, self.account_id
         );
-        self.delete_req(&url).await
+        self.get(&url).await
     }
 
-    /// List policies for an Access application.
-    pub async fn list_access_policies(&self, app_id: &str) -> Result<Vec<AccessPolicy>> {
+    /// Get an Access application with its identity provider settings.
+    pub async fn get_access_app_detail(&self, app_id: &str) -> Result<AccessAppDetail> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/access/apps/{app_id}/policies",
-            self.account_id
+            "{}/accounts/{}/access/apps/{app_id}",
+            self.base_url, self.account_id
         );
         self.get(&url).await
     }
 
+    /// Update an Access application.
+    pub async fn update_access_app(
+        &self,
+        app_id: &str,
+        update: &UpdateAccessApp,
+    ) -> Result<AccessApp> {
+        let url = format!(
+            "{}/accounts/{}/access/apps/{app_id}",
+            self.base_url, self.account_id
+        );
+        self.put(&url, update).await
+    }
+
+    /// Delete an Access application.
+    pub async fn delete_access_app(&self, app_id: &str) -> Result<Option<serde_json::Value>> {
+        let url = format!(
+            "{}/accounts/{}/access/apps/{app_id}",
+            self.base_url, self.account_id
+        );
+        self.delete_req(&url).await
+    }
+
+    /// List policies for an Access application, following pagination.
+    pub async fn list_access_policies(&self, app_id: &str) -> Result<Vec<AccessPolicy>> {
+        self.list_access_policies_filtered(app_id, None).await
+    }
+
+    /// List policies for an Access application, optionally only those with
+    /// the given `decision` (`allow`, `deny`, `bypass`, `non_identity`).
+    pub async fn list_access_policies_filtered(
+        &self,
+        app_id: &str,
+        decision: Option<&str>,
+    ) -> Result<Vec<AccessPolicy>> {
+        let url = self.access_policies_url(app_id, decision)?;
+        self.get_all_pages(url).await
+    }
+
+    /// List every policy of an Access application together with the total
+    /// the API reports for it.
+    pub async fn list_access_policies_counted(
+        &self,
+        app_id: &str,
+    ) -> Result<(Vec<AccessPolicy>, usize)> {
+        let url = self.access_policies_url(app_id, None)?;
+        self.get_all_pages_counted(url).await
+    }
+
+    fn access_policies_url(&self, app_id: &str, decision: Option<&str>) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/accounts/{}/access/apps/{app_id}/policies",
+            self.base_url, self.account_id
+        ))
+        .context("invalid Access policies URL")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("per_page", &ACCESS_POLICIES_PER_PAGE.to_string());
+            if let Some(decision) = decision {
+                query.append_pair("decision", decision);
+            }
+        }
+        Ok(url)
+    }
+
     /// Create a policy for an Access application.
     pub async fn create_access_policy(
         &self,
@@ -595,24 +1346,894 @@ impl CloudflareClient {
         policy: &AccessPolicy,
     ) -> Result<AccessPolicy> {
         let url = format!(
-            "{BASE_URL}/accounts/{}/access/apps/{app_id}/policies",
-            self.account_id
+            "{}/accounts/{}/access/apps/{app_id}/policies",
+            self.base_url, self.account_id
         );
         self.post(&url, policy).await
     }
 
+    /// List Access groups in the account.
+    pub async fn list_access_groups(&self) -> Result<Vec<AccessGroup>> {
+        let url = format!(
+            "{}/accounts/{}/access/groups",
+            self.base_url, self.account_id
+        );
+        self.get(&url).await
+    }
+
+    /// List identity providers configured for Access.
+    pub async fn list_identity_providers(&self) -> Result<Vec<IdentityProvider>> {
+        let url = format!(
+            "{}/accounts/{}/access/identity_providers",
+            self.base_url, self.account_id
+        );
+        self.get(&url).await
+    }
+
+    /// List recent Access authentication events, newest first.
+    /// `since` is an RFC 3339 timestamp limiting how far back to look.
+    pub async fn list_access_audit_logs(
+        &self,
+        limit: u32,
+        since: Option<&str>,
+    ) -> Result<Vec<AccessAuditLog>> {
+        let mut url = format!(
+            "{}/accounts/{}/access/logs/access-requests?limit={limit}&direction=desc",
+            self.base_url, self.account_id
+        );
+        if let Some(since) = since {
+            url.push_str(&format!("&since={since}"));
+        }
+        self.get(&url).await
+    }
+
     /// Get a zone setting by name (e.g. "always_use_https").
     pub async fn get_zone_setting(&self, setting: &str) -> Result<ZoneSetting> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/settings/{setting}");
+        let url = format!("{}/zones/{zone_id}/settings/{setting}", self.base_url);
         self.get(&url).await
     }
 
     /// Patch a zone setting. `value` should be `"on"` or `"off"` for boolean settings.
-    pub async fn patch_zone_setting(&self, setting: &str, value: serde_json::Value) -> Result<ZoneSetting> {
+    pub async fn patch_zone_setting(
+        &self,
+        setting: &str,
+        value: serde_json::Value,
+    ) -> Result<ZoneSetting> {
         let zone_id = self.require_zone_id()?;
-        let url = format!("{BASE_URL}/zones/{zone_id}/settings/{setting}");
+        let url = format!("{}/zones/{zone_id}/settings/{setting}", self.base_url);
         let body = serde_json::json!({ "value": value });
         self.patch(&url, &body).await
     }
 }
+
+/// HTTP client sending JSON, and `token` as a bearer token when given.
+fn http_client(token: Option<&str>) -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    if let Some(token) = token {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}"))
+                .context("invalid token characters")?,
+        );
+    }
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+/// First API error as "message (code N)", or the HTTP status when there is none.
+fn api_error_message(errors: &[CfApiError], status: reqwest::StatusCode) -> String {
+    errors
+        .first()
+        .map(|e| format!("{} (code {})", e.message, e.code))
+        .unwrap_or_else(|| format!("HTTP {status}"))
+}
+
+/// The saved API config, if any, for clients built without one.
+fn saved_config() -> Option<ApiConfig> {
+    config::load_api_config().ok().flatten()
+}
+
+/// The body size limit in bytes for `config`: `response_size_limit_mb`, or
+/// the default when unset. A limit of `0` turns the check off.
+fn response_limit(config: Option<&ApiConfig>) -> Option<usize> {
+    let mb = config
+        .and_then(|c| c.response_size_limit_mb)
+        .unwrap_or(DEFAULT_RESPONSE_LIMIT_MB);
+    (mb > 0).then(|| mb as usize * 1024 * 1024)
+}
+
+/// Reject a body once `bytes` exceeds `limit`.
+fn check_body_size(bytes: usize, limit: usize) -> Result<()> {
+    if bytes > limit {
+        return Err(CftError::ResponseTooLarge { bytes }.into());
+    }
+    Ok(())
+}
+
+/// Decode a Cloudflare response envelope. A successful response with a
+/// `null` or missing `result` yields `Ok(None)`.
+fn decode_response<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: &str,
+) -> Result<Option<T>> {
+    let cf: CfResponse<T> =
+        serde_json::from_str(body).context("failed to parse Cloudflare response")?;
+    if !cf.success {
+        bail!(
+            "Cloudflare API error: {}",
+            api_error_message(&cf.errors, status)
+        );
+    }
+    Ok(cf.result)
+}
+
+/// Decode one page of a list response into its items and pagination info.
+fn decode_page<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: &str,
+) -> Result<(Vec<T>, Option<ResultInfo>)> {
+    let cf: CfResponse<Vec<T>> =
+        serde_json::from_str(body).context("failed to parse Cloudflare response")?;
+    if !cf.success {
+        bail!(
+            "Cloudflare API error: {}",
+            api_error_message(&cf.errors, status)
+        );
+    }
+    Ok((cf.result.unwrap_or_default(), cf.result_info))
+}
+
+/// Decode a tunnel configuration response. A tunnel without configuration
+/// comes back as a "configuration not found" error or an empty result; both
+/// map to `Ok(None)`. Any other error, 400s included, is returned.
+fn parse_tunnel_config(
+    status: reqwest::StatusCode,
+    body: &str,
+) -> Result<Option<TunnelConfiguration>> {
+    let cf: CfResponse<StoredTunnelConfiguration> =
+        serde_json::from_str(body).context("failed to parse Cloudflare response")?;
+    if !cf.success {
+        if cf.errors.iter().any(is_config_not_found) {
+            return Ok(None);
+        }
+        bail!(
+            "Cloudflare API error: {}",
+            api_error_message(&cf.errors, status)
+        );
+    }
+
+    Ok(cf
+        .result
+        .and_then(|r| r.config)
+        .map(|config| TunnelConfiguration { config }))
+}
+
+/// The error Cloudflare returns for a tunnel whose configuration was never set.
+fn is_config_not_found(error: &CfApiError) -> bool {
+    let message = error.message.to_lowercase();
+    message.contains("configuration") && message.contains("not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::MockApi;
+
+    fn test_client() -> CloudflareClient {
+        let cfg = ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            ..Default::default()
+        };
+        CloudflareClient::from_config(&cfg).unwrap()
+    }
+
+    #[test]
+    fn null_result_on_success_is_none() {
+        use reqwest::StatusCode;
+
+        let body = r#"{"success": true, "errors": [], "result": null}"#;
+        let parsed: Option<serde_json::Value> = decode_response(StatusCode::OK, body).unwrap();
+        assert_eq!(parsed, None);
+
+        let body = r#"{"success": true, "result": {"id": "tid-1"}}"#;
+        let parsed: Option<serde_json::Value> = decode_response(StatusCode::OK, body).unwrap();
+        assert_eq!(parsed, Some(serde_json::json!({"id": "tid-1"})));
+
+        let body = r#"{"success": false, "errors": [{"code": 1003, "message": "not found"}], "result": null}"#;
+        let err = decode_response::<serde_json::Value>(StatusCode::NOT_FOUND, body).unwrap_err();
+        assert!(err.to_string().contains("not found (code 1003)"));
+    }
+
+    #[test]
+    fn tunnel_config_missing_is_none() {
+        use reqwest::StatusCode;
+
+        let not_found = r#"{"success":false,"errors":[{"code":1055,"message":"Tunnel configuration not found"}],"result":null}"#;
+        assert!(parse_tunnel_config(StatusCode::BAD_REQUEST, not_found)
+            .unwrap()
+            .is_none());
+
+        // Other 400s, such as a malformed tunnel ID, are real failures.
+        let bad_id = r#"{"success":false,"errors":[{"code":1003,"message":"Invalid tunnel id"}],"result":null}"#;
+        let err = parse_tunnel_config(StatusCode::BAD_REQUEST, bad_id).unwrap_err();
+        assert!(err.to_string().contains("Invalid tunnel id (code 1003)"));
+
+        let empty =
+            r#"{"success":true,"errors":[],"result":{"tunnel_id":"t","version":0,"config":null}}"#;
+        assert!(parse_tunnel_config(StatusCode::OK, empty)
+            .unwrap()
+            .is_none());
+
+        let configured = r#"{"success":true,"errors":[],"result":{"config":{"ingress":[{"service":"http_status:404"}]}}}"#;
+        let cfg = parse_tunnel_config(StatusCode::OK, configured)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cfg.config.ingress.len(), 1);
+
+        let failure = r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}],"result":null}"#;
+        let err = parse_tunnel_config(StatusCode::FORBIDDEN, failure).unwrap_err();
+        assert!(err.to_string().contains("Authentication error"));
+    }
+
+    #[test]
+    fn access_audit_log_deserializes() {
+        let body = r#"{
+            "success": true,
+            "errors": [],
+            "result": [
+                {
+                    "user_email": "alice@example.com",
+                    "ip_address": "198.51.100.7",
+                    "app_uid": "df7e2w5f-02b7-4d9d-af26-8d1988fca630",
+                    "app_domain": "grafana.example.com",
+                    "action": "login",
+                    "allowed": true,
+                    "connection": "onetimepin",
+                    "country": "US",
+                    "ray_id": "187d944c61940c77",
+                    "created_at": "2026-10-16T08:30:00Z"
+                },
+                {
+                    "user_email": "mallory@example.com",
+                    "action": "login",
+                    "allowed": false,
+                    "created_at": "2026-10-16T08:31:00Z"
+                }
+            ]
+        }"#;
+        let cf: CfResponse<Vec<AccessAuditLog>> = serde_json::from_str(body).unwrap();
+        let logs = cf.result.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].user_email, "alice@example.com");
+        assert_eq!(logs[0].ip_address, "198.51.100.7");
+        assert!(logs[0].allowed);
+        assert_eq!(logs[1].ip_address, "");
+        assert!(!logs[1].allowed);
+    }
+
+    fn ingress(rules: &[(Option<&str>, &str)]) -> TunnelConfiguration {
+        TunnelConfiguration {
+            config: TunnelConfigInner {
+                ingress: rules
+                    .iter()
+                    .map(|(hostname, service)| IngressRule {
+                        hostname: hostname.map(str::to_string),
+                        service: service.to_string(),
+                        origin_request: None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn valid_tunnel_config_has_no_problems() {
+        let cfg = ingress(&[
+            (Some("app.example.com"), "http://localhost:3000"),
+            (Some("api.example.com"), "http://localhost:8080"),
+            (None, "http_status:404"),
+        ]);
+        assert!(cfg.validate().is_empty());
+    }
+
+    #[test]
+    fn tunnel_config_requires_one_catch_all() {
+        let none = ingress(&[(Some("app.example.com"), "http://localhost:3000")]);
+        assert_eq!(
+            none.validate(),
+            vec!["no catch-all rule (a rule without hostname)"]
+        );
+
+        let two = ingress(&[
+            (Some("app.example.com"), "http://localhost:3000"),
+            (None, "http_status:404"),
+            (Some(""), "http_status:503"),
+        ]);
+        let problems = two.validate();
+        assert!(problems.iter().any(|p| p.contains("2 catch-all rules")));
+
+        let star = ingress(&[
+            (Some("app.example.com"), "http://localhost:3000"),
+            (Some("*"), "http_status:404"),
+        ]);
+        assert!(star.validate().is_empty());
+    }
+
+    #[test]
+    fn tunnel_config_catch_all_must_be_last() {
+        let cfg = ingress(&[
+            (None, "http_status:404"),
+            (Some("app.example.com"), "http://localhost:3000"),
+        ]);
+        assert_eq!(
+            cfg.validate(),
+            vec!["the catch-all rule must be the last rule"]
+        );
+    }
+
+    #[test]
+    fn tunnel_config_rejects_duplicate_hostnames() {
+        let cfg = ingress(&[
+            (Some("app.example.com"), "http://localhost:3000"),
+            (Some("App.Example.com"), "http://localhost:3001"),
+            (None, "http_status:404"),
+        ]);
+        assert_eq!(cfg.validate(), vec!["duplicate hostname App.Example.com"]);
+    }
+
+    #[test]
+    fn tunnel_config_rejects_empty_service() {
+        let cfg = ingress(&[(Some("app.example.com"), " "), (None, "http_status:404")]);
+        assert_eq!(cfg.validate(), vec!["empty service for app.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn invalid_tunnel_config_is_not_sent() {
+        let cfg = ingress(&[(Some("app.example.com"), "http://localhost:3000")]);
+        // Nothing listens on this address, so reaching the network would
+        // surface as an HTTP error instead of InvalidInput.
+        let client = test_client().with_base_url("http://127.0.0.1:9");
+        let err = client.put_tunnel_config("tid", &cfg).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CftError>(),
+            Some(CftError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn debug_output_redacts_token() {
+        let client = test_client();
+        let debug = format!("{client:?}");
+        assert!(!debug.contains("Bearer"));
+        assert!(!debug.contains("test-token"));
+        assert_eq!(
+            debug,
+            r#"CloudflareClient { account_id: "acc123", zone_id: None, token: "[REDACTED]" }"#
+        );
+        assert_eq!(client.to_string(), "acc123");
+    }
+
+    #[test]
+    fn access_app_detail_deserializes() {
+        let body = r#"{
+            "success": true,
+            "errors": [],
+            "result": {
+                "id": "f174e90a-fafe-4643-bbbc-4a0ed4fc8415",
+                "name": "Grafana",
+                "domain": "grafana.example.com",
+                "type": "self_hosted",
+                "allowed_idps": ["idp-google", "idp-github"],
+                "auto_redirect_to_identity": true,
+                "cors_headers": {
+                    "allowed_origins": ["https://app.example.com"],
+                    "allowed_methods": ["GET", "POST"],
+                    "allow_credentials": true
+                }
+            }
+        }"#;
+        let cf: CfResponse<AccessAppDetail> = serde_json::from_str(body).unwrap();
+        let app = cf.result.unwrap();
+        assert_eq!(app.name, "Grafana");
+        assert_eq!(app.domain, "grafana.example.com");
+        assert_eq!(app.allowed_idps, vec!["idp-google", "idp-github"]);
+        assert!(app.auto_redirect_to_identity);
+        assert_eq!(
+            app.cors_headers.unwrap().allowed_origins,
+            vec!["https://app.example.com"]
+        );
+
+        let minimal: AccessAppDetail =
+            serde_json::from_str(r#"{"name": "Docs", "domain": "docs.example.com"}"#).unwrap();
+        assert!(minimal.allowed_idps.is_empty());
+        assert!(!minimal.auto_redirect_to_identity);
+        assert!(minimal.cors_headers.is_none());
+    }
+
+    #[test]
+    fn raw_url_prepends_base() {
+        let client = test_client();
+        assert_eq!(
+            client.raw_url("/zones"),
+            "https://api.cloudflare.com/client/v4/zones"
+        );
+        assert_eq!(
+            client.raw_url("accounts/acc123/rules"),
+            "https://api.cloudflare.com/client/v4/accounts/acc123/rules"
+        );
+    }
+
+    #[tokio::test]
+    async fn raw_post_uses_auth_header() {
+        let api = MockApi::json(r#"{"success":true}"#).await;
+
+        let body = test_client()
+            .with_base_url(&api.base)
+            .raw_post("/zones", serde_json::json!({"name": "example.com"}))
+            .await
+            .unwrap();
+        assert_eq!(body, r#"{"success":true}"#);
+
+        let request = api.raw_requests()[0].to_lowercase();
+        assert!(request.starts_with("post /zones http/1.1"));
+        assert!(request.contains("authorization: bearer test-token"));
+        assert!(request.contains(r#"{"name":"example.com"}"#));
+    }
+
+    #[tokio::test]
+    async fn oversized_response_is_rejected() {
+        let api = MockApi::start(|_, _| {
+            let padding = "x".repeat(1024 * 1024);
+            (200, format!(r#"{{"success":true,"result":"{padding}"}}"#))
+        })
+        .await;
+
+        let cfg = ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            response_size_limit_mb: Some(1),
+            ..Default::default()
+        };
+        let client = CloudflareClient::from_config(&cfg).unwrap();
+        let err = client
+            .get::<String>(&format!("{}/client/v4/zones", api.base))
+            .await
+            .unwrap_err();
+
+        match err.downcast_ref::<CftError>() {
+            Some(CftError::ResponseTooLarge { bytes }) => assert!(*bytes > 1024 * 1024),
+            other => panic!("unexpected error: {other:?} ({err})"),
+        }
+    }
+
+    #[test]
+    fn body_size_limit_boundary() {
+        assert!(check_body_size(4, 4).is_ok());
+        let err = check_body_size(5, 4).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CftError>(),
+            Some(CftError::ResponseTooLarge { bytes: 5 })
+        ));
+        assert_eq!(test_client().response_limit, Some(10 * 1024 * 1024));
+
+        let unlimited = ApiConfig {
+            response_size_limit_mb: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(response_limit(Some(&unlimited)), None);
+        assert_eq!(response_limit(None), Some(10 * 1024 * 1024));
+    }
+
+    #[tokio::test]
+    async fn fetch_zones_follows_pages() {
+        let api = MockApi::start(|_, path| {
+            let page: u32 = if path.contains("page=2") { 2 } else { 1 };
+            let zones: Vec<String> = (1..=3)
+                .map(|i| {
+                    let n = (page - 1) * 3 + i;
+                    format!(r#"{{"id":"z{n}","name":"zone{n}.com","account":{{"id":"acc","name":"A"}}}}"#)
+                })
+                .collect();
+            let body = format!(
+                r#"{{"success":true,"result":[{}],"result_info":{{"page":{page},"per_page":3,"total_pages":2}}}}"#,
+                zones.join(",")
+            );
+            (200, body)
+        })
+        .await;
+
+        let zones = CloudflareClient::unscoped(Some("t"), None)
+            .unwrap()
+            .with_base_url(&api.base)
+            .zones_for(Some("acc"))
+            .await
+            .unwrap();
+        let ids: Vec<&str> = zones.iter().map(|z| z.id.as_str()).collect();
+        assert_eq!(ids, vec!["z1", "z2", "z3", "z4", "z5", "z6"]);
+
+        let requests = api.requests();
+        assert!(requests[0].contains("per_page=50&account.id=acc&page=1"));
+        assert!(requests[1].contains("page=2"));
+    }
+
+    #[test]
+    fn tunnel_list_query() {
+        let client = test_client();
+        let url = |deleted, status| client.tunnels_url(deleted, status).unwrap().to_string();
+        assert_eq!(
+            url(false, None),
+            format!("{BASE_URL}/accounts/acc123/cfd_tunnel?is_deleted=false")
+        );
+        assert_eq!(
+            url(false, Some("degraded")),
+            format!("{BASE_URL}/accounts/acc123/cfd_tunnel?is_deleted=false&status=degraded")
+        );
+        assert_eq!(
+            url(true, Some("inactive")),
+            format!("{BASE_URL}/accounts/acc123/cfd_tunnel?status=inactive")
+        );
+        assert_eq!(
+            url(true, None),
+            format!("{BASE_URL}/accounts/acc123/cfd_tunnel")
+        );
+    }
+
+    #[tokio::test]
+    async fn list_access_apps_follows_pages() {
+        let api = MockApi::start(|_, path| {
+            let page: u32 = path
+                .rsplit("page=")
+                .next()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(1);
+            let apps: Vec<String> = (1..=10)
+                .map(|i| {
+                    let n = (page - 1) * 10 + i;
+                    format!(r#"{{"id":"app{n}","name":"App {n}","domain":"app{n}.example.com"}}"#)
+                })
+                .collect();
+            let body = format!(
+                r#"{{"success":true,"result":[{}],"result_info":{{"page":{page},"per_page":10,"total_pages":3}}}}"#,
+                apps.join(",")
+            );
+            (200, body)
+        })
+        .await;
+
+        let apps = test_client()
+            .with_base_url(&api.base)
+            .list_access_apps(Some(10), Some("example.com"))
+            .await
+            .unwrap();
+        assert_eq!(apps.len(), 30);
+        assert_eq!(apps[0].id.as_deref(), Some("app1"));
+        assert_eq!(apps[29].id.as_deref(), Some("app30"));
+
+        let requests = api.requests();
+        assert!(requests[0]
+            .starts_with("GET /accounts/acc123/access/apps?per_page=10&domain=example.com&page=1"));
+        assert!(requests[2].ends_with("page=3"));
+    }
+
+    #[tokio::test]
+    async fn list_access_policies_collects_both_pages() {
+        let api = MockApi::start(|_, path| {
+            let body = if path.contains("page=2") {
+                r#"{"success":true,"result":[{"id":"p3","name":"Third","decision":"allow","include":[]}],"result_info":{"page":2,"per_page":2,"total_pages":2,"total_count":3}}"#
+            } else {
+                r#"{"success":true,"result":[{"id":"p1","name":"First","decision":"allow","include":[]},{"id":"p2","name":"Second","decision":"allow","include":[]}],"result_info":{"page":1,"per_page":2,"total_pages":2,"total_count":3}}"#
+            };
+            (200, body.to_string())
+        })
+        .await;
+        let client = test_client().with_base_url(&api.base);
+
+        let policies = client
+            .list_access_policies_filtered("app1", Some("allow"))
+            .await
+            .unwrap();
+        let names: Vec<&str> = policies.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["First", "Second", "Third"]);
+
+        let requests = api.requests();
+        assert_eq!(
+            requests[0],
+            "GET /accounts/acc123/access/apps/app1/policies?per_page=50&decision=allow&page=1"
+        );
+        assert!(requests[1].ends_with("page=2"));
+
+        let (policies, total) = client.list_access_policies_counted("app1").await.unwrap();
+        assert_eq!((policies.len(), total), (3, 3));
+    }
+
+    /// Serve `/accounts` and `/zones` with an empty list and
+    /// `/user/tokens/verify` with `verify_body`.
+    async fn serve_empty_then_verify(verify_body: &'static str) -> MockApi {
+        MockApi::routes(vec![
+            ("GET", "/user/tokens/verify", verify_body),
+            ("GET", "/", r#"{"success":true,"result":[]}"#),
+        ])
+        .await
+    }
+
+    #[tokio::test]
+    async fn empty_accounts_with_valid_token_explain_permissions() {
+        let api = serve_empty_then_verify(r#"{"success":true,"result":{"status":"active"}}"#).await;
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("audit.jsonl");
+        let err = CloudflareClient::unscoped(Some("t"), None)
+            .unwrap()
+            .with_base_url(&api.base)
+            .with_request_logger(RequestLogger::new(log_path.clone()))
+            .accounts()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CftError>(),
+            Some(CftError::EmptyResult {
+                resource: "accounts",
+                ..
+            })
+        ));
+        assert!(err.to_string().contains("Account - Account: Read"));
+        assert_eq!(
+            api.requests(),
+            vec!["GET /accounts", "GET /user/tokens/verify"]
+        );
+        // Both requests are audited, start and outcome each.
+        let audited = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(audited.lines().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn empty_zones_with_invalid_token_report_the_token() {
+        let api = serve_empty_then_verify(
+            r#"{"success":false,"errors":[{"code":1000,"message":"Invalid API Token"}]}"#,
+        )
+        .await;
+        let err = CloudflareClient::unscoped(Some("t"), None)
+            .unwrap()
+            .with_base_url(&api.base)
+            .zones_for(None)
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<CftError>().is_none());
+        assert!(err.to_string().contains("Invalid API Token"));
+    }
+
+    #[tokio::test]
+    async fn reachability_distinguishes_network_failure() {
+        // Any status, even 400 without a token, means the API is reachable.
+        let api = MockApi::start(|_, _| (400, String::new())).await;
+        let unscoped = CloudflareClient::unscoped(None, None).unwrap();
+        let ok = unscoped
+            .clone()
+            .with_base_url(&api.base)
+            .reachability()
+            .await;
+        assert!(ok.reachable);
+        assert!(ok.latency_ms.is_some());
+        assert!(!api.raw_requests()[0]
+            .to_lowercase()
+            .contains("authorization:"));
+
+        // Nothing listening: connection refused.
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = closed.local_addr().unwrap();
+        drop(closed);
+        let down = unscoped
+            .with_base_url(&format!("http://{addr}"))
+            .reachability()
+            .await;
+        assert_eq!(
+            down,
+            NetworkReachability {
+                reachable: false,
+                latency_ms: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn account_details_are_cached() {
+        let api =
+            MockApi::json(r#"{"success":true,"result":{"id":"acc123","name":"Home Lab"}}"#).await;
+
+        let client = test_client().with_base_url(&api.base);
+        let first = client.get_account_details().await.unwrap();
+        let second = client.clone().get_account_details().await.unwrap();
+        assert_eq!(first.name, "Home Lab");
+        assert_eq!(second.id, "acc123");
+        assert_eq!(api.requests().len(), 1);
+
+        // An expired entry is fetched again.
+        let stale = Instant::now().checked_sub(ACCOUNT_CACHE_TTL + Duration::from_secs(1));
+        if let Some(stale) = stale {
+            client.account_cache.lock().unwrap().as_mut().unwrap().0 = stale;
+            client.get_account_details().await.unwrap();
+            assert_eq!(api.requests().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn request_logger_records_start_and_outcome() {
+        let api = MockApi::routes(vec![
+            (
+                "GET",
+                "/accounts/acc123/cfd_tunnel/tid-1/configurations",
+                r#"{"success":true,"result":{"config":null}}"#,
+            ),
+            ("GET", "/", r#"{"success":true,"result":[]}"#),
+        ])
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("audit.jsonl");
+        let client = test_client()
+            .with_base_url(&api.base)
+            .with_request_logger(RequestLogger::new(log_path.clone()));
+
+        let url = format!("{}/accounts/acc123/cfd_tunnel", api.base);
+        client.get::<Vec<Tunnel>>(&url).await.unwrap();
+        assert!(client.get_tunnel_config("tid-1").await.unwrap().is_none());
+
+        let content = std::fs::read_to_string(&log_path).unwrap();
+        let entries: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0]["method"], "GET");
+        assert_eq!(entries[0]["url"], url.as_str());
+        assert!(entries[0]["success"].is_null());
+        assert_eq!(entries[1]["timestamp"], entries[0]["timestamp"]);
+        assert_eq!(entries[1]["success"], true);
+        assert_eq!(entries[1]["status"], 200);
+        assert!(entries[1]["duration_ms"].is_u64());
+        assert!(entries[3]["url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/cfd_tunnel/tid-1/configurations"));
+        assert!(!content.contains("test-token"));
+    }
+
+    #[tokio::test]
+    async fn clones_work_across_tasks() {
+        let api =
+            MockApi::json(r#"{"success":true,"result":[{"id":"tid-1","name":"home"}]}"#).await;
+
+        let client = test_client();
+        let url = format!("{}/client/v4/accounts/acc123/cfd_tunnel", api.base);
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let client = client.clone();
+                let url = url.clone();
+                tokio::spawn(async move { client.get::<Vec<Tunnel>>(&url).await })
+            })
+            .collect();
+        for task in tasks {
+            let tunnels = task.await.unwrap().unwrap();
+            assert_eq!(tunnels[0].id, "tid-1");
+        }
+    }
+
+    #[test]
+    fn force_disconnect_url() {
+        let client = test_client();
+        assert_eq!(
+            client.tunnel_connections_url("tid-1"),
+            "https://api.cloudflare.com/client/v4/accounts/acc123/cfd_tunnel/tid-1/connections"
+        );
+    }
+
+    #[test]
+    fn access_app_precedence_follows_given_order() {
+        let ids = vec!["b".to_string(), "a".to_string(), "c".to_string()];
+        assert_eq!(
+            serde_json::to_value(AccessAppPrecedence::from_order(&ids)).unwrap(),
+            serde_json::json!([
+                {"id": "b", "precedence": 1},
+                {"id": "a", "precedence": 2},
+                {"id": "c", "precedence": 3},
+            ])
+        );
+    }
+
+    #[test]
+    fn update_access_app_serializes_only_changed_fields() {
+        let current = AccessApp {
+            id: Some("app1".to_string()),
+            name: "Grafana".to_string(),
+            domain: "grafana.example.com".to_string(),
+            app_type: Some("self_hosted".to_string()),
+            session_duration: Some("24h".to_string()),
+            precedence: None,
+            cors_headers: None,
+        };
+
+        let unchanged =
+            UpdateAccessApp::changes(&current, "Grafana", "grafana.example.com", Some("24h"));
+        assert!(unchanged.is_empty());
+        assert_eq!(
+            serde_json::to_value(&unchanged).unwrap(),
+            serde_json::json!({})
+        );
+
+        let update =
+            UpdateAccessApp::changes(&current, "Grafana", "metrics.example.com", Some("6h"));
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            serde_json::json!({
+                "domain": "metrics.example.com",
+                "session_duration": "6h"
+            })
+        );
+
+        // No duration on the app and none entered: nothing is sent for it
+        let no_duration = AccessApp {
+            session_duration: None,
+            ..current
+        };
+        let update = UpdateAccessApp::changes(&no_duration, "Grafana", "grafana.example.com", None);
+        assert!(update.is_empty());
+    }
+
+    #[test]
+    fn policy_rule_with_group_serializes_to_api_format() {
+        let rule = PolicyRule {
+            email: None,
+            email_domain: None,
+            everyone: None,
+            access_group: Some(PolicyAccessGroup {
+                id: "grp-123".to_string(),
+            }),
+            service_token: None,
+            gsuite: None,
+        };
+        let json = serde_json::to_value(&rule).unwrap();
+        assert_eq!(json, serde_json::json!({ "group": { "id": "grp-123" } }));
+    }
+
+    #[test]
+    fn policy_rule_otp_and_gsuite_serialize_to_api_format() {
+        let otp = PolicyRule {
+            email: None,
+            email_domain: None,
+            everyone: None,
+            access_group: None,
+            service_token: Some(serde_json::json!({})),
+            gsuite: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&otp).unwrap(),
+            serde_json::json!({ "service_token": {} })
+        );
+
+        let gsuite = PolicyRule {
+            email: None,
+            email_domain: None,
+            everyone: None,
+            access_group: None,
+            service_token: None,
+            gsuite: Some(PolicyGSuite {
+                email: "team@example.com".to_string(),
+                identity_provider_id: "idp-1".to_string(),
+            }),
+        };
+        assert_eq!(
+            serde_json::to_value(&gsuite).unwrap(),
+            serde_json::json!({
+                "gsuite": {
+                    "email": "team@example.com",
+                    "identity_provider_id": "idp-1"
+                }
+            })
+        );
+    }
+}
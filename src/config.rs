@@ -1,92 +1,492 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Once, OnceLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 
+use crate::client::{CloudflareClient, TokenVerifyStatus};
 use crate::error::Result;
+use crate::i18n::{lang, Lang};
+use crate::t;
 
 // ---------------------------------------------------------------------------
-// API config (~/.opentunnel/config.json)
+// API config (<config dir>/config.json)
 // ---------------------------------------------------------------------------
 
 /// Stored credentials and user preferences.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct ApiConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_token: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub account_id: Option<String>,
+    /// Display name of `account_id`, saved alongside it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zone_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zone_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
+    /// Additional labelled tokens (e.g. a read-only monitoring token).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<NamedToken>,
+    /// Label of the token in `tokens` to use instead of `api_token`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_token: Option<String>,
+    /// Maximum size of a single API response body in MB (default 10); `0`
+    /// removes the limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_size_limit_mb: Option<u32>,
+    /// Append a JSON line for every API request to this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<PathBuf>,
+}
+
+/// An API token stored under a user-chosen label.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamedToken {
+    pub label: String,
+    pub token: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+/// Kind of credential stored as the API token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    /// A scoped API token (`cf_` prefix or 40 URL-safe characters).
+    ScopedToken,
+    /// A legacy Global API Key (hex), which has full account access.
+    GlobalApiKey,
+    Unknown,
+}
+
+impl TokenType {
+    /// Classify a raw credential by its format.
+    pub fn detect(token: &str) -> Self {
+        let len = token.len();
+        if (len == 37 || len == 40) && token.chars().all(|c| c.is_ascii_hexdigit()) {
+            Self::GlobalApiKey
+        } else if token.starts_with("cf_")
+            || (len == 40
+                && token
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        {
+            Self::ScopedToken
+        } else {
+            Self::Unknown
+        }
+    }
 }
 
 impl ApiConfig {
-    /// Mask token for display, e.g. `abcd***...***mnop`.
+    /// Mask token for display, e.g. `abcd***...***mnop`. Global API Keys are
+    /// fully hidden as `Global API Key ****`.
     pub fn masked_token(&self) -> String {
-        match &self.api_token {
-            Some(t) if t.chars().count() > 8 => {
-                let prefix: String = t.chars().take(4).collect();
-                let suffix: String = t
-                    .chars()
-                    .rev()
-                    .take(4)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect();
-                format!("{prefix}***...***{suffix}")
+        match self.effective_token() {
+            Some(_) if self.token_type() == TokenType::GlobalApiKey => {
+                "Global API Key ****".to_string()
             }
-            Some(_) => "****".to_string(),
+            Some(t) => mask_token(t),
             None => "not set".to_string(),
         }
     }
+
+    /// Kind of the effective token; `Unknown` when none is set.
+    pub fn token_type(&self) -> TokenType {
+        self.effective_token()
+            .map_or(TokenType::Unknown, TokenType::detect)
+    }
+
+    /// The token requests should use: the active labelled token if one is
+    /// selected, otherwise `api_token`.
+    pub fn effective_token(&self) -> Option<&str> {
+        match &self.active_token {
+            Some(label) => self
+                .tokens
+                .iter()
+                .find(|t| &t.label == label)
+                .map(|t| t.token.as_str()),
+            None => self.api_token.as_deref(),
+        }
+    }
+
+    /// Add a labelled token, replacing any existing token with the same label.
+    pub fn add_token(&mut self, token: NamedToken) {
+        self.tokens.retain(|t| t.label != token.label);
+        self.tokens.push(token);
+    }
+
+    /// Remove a labelled token. Returns `false` if no token had that label.
+    /// Removing the active token clears the selection so a new one must be chosen.
+    pub fn remove_token(&mut self, label: &str) -> bool {
+        let before = self.tokens.len();
+        self.tokens.retain(|t| t.label != label);
+        if self.active_token.as_deref() == Some(label) {
+            self.active_token = None;
+        }
+        self.tokens.len() != before
+    }
+
+    /// Replace the token requests use (the active labelled token, or
+    /// `api_token`), leaving accounts, zones and preferences untouched.
+    pub fn replace_token(&mut self, new_token: &str) {
+        let active = self
+            .active_token
+            .as_deref()
+            .and_then(|label| self.tokens.iter_mut().find(|t| t.label == label));
+        match active {
+            Some(named) => named.token = new_token.to_string(),
+            None => self.api_token = Some(new_token.to_string()),
+        }
+    }
+
+    /// Make the labelled token the active one.
+    pub fn use_token(&mut self, label: &str) -> Result<()> {
+        if !self.tokens.iter().any(|t| t.label == label) {
+            bail!(crate::error::CftError::InvalidInput(format!(
+                "no token labelled '{label}'"
+            )));
+        }
+        self.active_token = Some(label.to_string());
+        Ok(())
+    }
+}
+
+/// Mask a raw token for display, e.g. `abcd***...***mnop`.
+pub fn mask_token(token: &str) -> String {
+    if token.chars().count() > 8 {
+        let prefix: String = token.chars().take(4).collect();
+        let suffix: String = token
+            .chars()
+            .rev()
+            .take(4)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        format!("{prefix}***...***{suffix}")
+    } else {
+        "****".to_string()
+    }
 }
 
-/// Return the path to the opentunnel config directory (`~/.opentunnel`).
+/// Config directories used by earlier releases, newest first.
+const LEGACY_CONFIG_DIRS: &[&str] = &[".opentunnel", ".cft"];
+
+static LEGACY_MIGRATION: Once = Once::new();
+
+/// Return the opentunnel config directory for this platform, migrating a
+/// legacy `~/.opentunnel` or `~/.cft` directory into it on first use:
+///
+/// - Linux: `$XDG_CONFIG_HOME/opentunnel` (default `~/.config/opentunnel`)
+/// - macOS: `~/Library/Application Support/openTunnel`
+/// - Windows: `%APPDATA%\openTunnel`
 pub fn config_dir() -> Result<PathBuf> {
     let home = dirs::home_dir().context("cannot determine home directory")?;
-    Ok(home.join(".opentunnel"))
+    let dir = platform_config_dir(std::env::consts::OS, &home, |key| std::env::var(key).ok());
+    LEGACY_MIGRATION.call_once(|| migrate_legacy_config(&home, &dir));
+    Ok(dir)
 }
 
-/// Return the path to `~/.opentunnel/config.json`.
+/// Config directory for `os`, reading environment variables through `env`.
+fn platform_config_dir(os: &str, home: &Path, env: impl Fn(&str) -> Option<String>) -> PathBuf {
+    // Relative values are invalid per the XDG spec and ignored.
+    let env_dir = |key: &str| env(key).map(PathBuf::from).filter(|p| p.is_absolute());
+    match os {
+        "macos" => home.join("Library/Application Support/openTunnel"),
+        "windows" => env_dir("APPDATA")
+            .unwrap_or_else(|| home.join("AppData").join("Roaming"))
+            .join("openTunnel"),
+        _ => env_dir("XDG_CONFIG_HOME")
+            .unwrap_or_else(|| home.join(".config"))
+            .join("opentunnel"),
+    }
+}
+
+/// Legacy directory to migrate from: one holding a `config.json` while
+/// `new_dir` has none yet.
+fn legacy_migration_source(home: &Path, new_dir: &Path) -> Option<PathBuf> {
+    if new_dir.join("config.json").exists() {
+        return None;
+    }
+    LEGACY_CONFIG_DIRS
+        .iter()
+        .map(|name| home.join(name))
+        .find(|dir| dir.join("config.json").is_file())
+}
+
+/// Copy the files of `from` into `to`. The legacy directory is left in place.
+fn copy_config_files(from: &Path, to: &Path) -> Result<usize> {
+    fs::create_dir_all(to).with_context(|| format!("failed to create {}", to.display()))?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from).with_context(|| format!("failed to read {}", from.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), to.join(entry.file_name()))
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+fn migrate_legacy_config(home: &Path, new_dir: &Path) {
+    let Some(legacy) = legacy_migration_source(home, new_dir) else {
+        return;
+    };
+    eprintln!(
+        "{} {} → {}",
+        migration_notice(std::env::consts::OS, lang()),
+        legacy.display(),
+        new_dir.display()
+    );
+    if let Err(e) = copy_config_files(&legacy, new_dir) {
+        eprintln!("⚠️ {e:#}");
+    }
+}
+
+/// Message shown while migrating, naming the config location used on `os`.
+fn migration_notice(os: &str, l: Lang) -> &'static str {
+    match os {
+        "macos" => t!(
+            l,
+            "Migrating config to Application Support...",
+            "正在将配置迁移到 Application Support 目录..."
+        ),
+        "windows" => t!(
+            l,
+            "Migrating config to AppData...",
+            "正在将配置迁移到 AppData 目录..."
+        ),
+        _ => t!(
+            l,
+            "Migrating config to XDG path...",
+            "正在将配置迁移到 XDG 路径..."
+        ),
+    }
+}
+
+/// Return the path to `<config dir>/config.json`.
 pub fn api_config_path() -> Result<PathBuf> {
     Ok(config_dir()?.join("config.json"))
 }
 
 /// Load the API config from disk. Returns `None` if the file does not exist.
 pub fn load_api_config() -> Result<Option<ApiConfig>> {
-    let path = api_config_path()?;
+    load_api_config_from(&config_dir()?)
+}
+
+fn load_api_config_from(dir: &Path) -> Result<Option<ApiConfig>> {
+    let _lock = lock_config_shared(dir, CONFIG_LOCK_TIMEOUT)?;
+    read_api_config(&dir.join("config.json"))
+}
+
+/// Parse `config.json` at `path`; the caller holds the lock.
+fn read_api_config(path: &Path) -> Result<Option<ApiConfig>> {
     if !path.exists() {
         return Ok(None);
     }
     let content =
-        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
     let cfg: ApiConfig = serde_json::from_str(&content)
         .with_context(|| format!("failed to parse {}", path.display()))?;
     Ok(Some(cfg))
 }
 
-/// Save the API config to disk with secure file permissions (0600).
-pub fn save_api_config(config: &ApiConfig) -> Result<()> {
-    let dir = config_dir()?;
-    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+/// How long to wait for another `tunnel` process to release the config lock.
+const CONFIG_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
 
-    let path = api_config_path()?;
+/// Take an exclusive advisory lock on `<dir>/config.lock`, creating it if
+/// needed. The lock is released when the returned file is dropped.
+fn lock_config(dir: &Path, timeout: Duration) -> Result<fs::File> {
+    let path = dir.join("config.lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    wait_for_lock(&file, &path, true, timeout)?;
+    Ok(file)
+}
+
+/// Take a shared lock for reading. Readers never create the lock file: with
+/// none on disk no save has ever run, and there is nothing to wait for.
+fn lock_config_shared(dir: &Path, timeout: Duration) -> Result<Option<fs::File>> {
+    let path = dir.join("config.lock");
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to open {}", path.display())),
+    };
+    wait_for_lock(&file, &path, false, timeout)?;
+    Ok(Some(file))
+}
+
+fn wait_for_lock(file: &fs::File, path: &Path, exclusive: bool, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let attempt = if exclusive {
+            file.try_lock()
+        } else {
+            file.try_lock_shared()
+        };
+        match attempt {
+            Ok(()) => return Ok(()),
+            Err(fs::TryLockError::WouldBlock) if Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                let l = lang();
+                bail!(t!(
+                    l,
+                    "Config file is locked by another process",
+                    "配置文件已被其他进程锁定"
+                ));
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("failed to lock {}", path.display()));
+            }
+        }
+    }
+}
+
+/// Swap in a new API token without re-running the setup wizard. The token
+/// must verify and still see the configured account before it is saved.
+pub async fn rotate_token(new_token: &str) -> Result<()> {
+    let l = lang();
+    let previous = load_api_config()?.filter(|c| c.effective_token().is_some());
+    let mut cfg = previous
+        .clone()
+        .ok_or(crate::error::CftError::ApiNotConfigured)?;
+
+    match CloudflareClient::verify_token(new_token, cfg.account_id.as_deref()).await? {
+        TokenVerifyStatus::Valid => {}
+        TokenVerifyStatus::Invalid(reason) => bail!(
+            "{} {reason}",
+            t!(l, "New token is invalid:", "新 Token 无效:")
+        ),
+        TokenVerifyStatus::Unknown => eprintln!(
+            "⚠️ {}",
+            t!(
+                l,
+                "Could not verify the new token; checking account access instead.",
+                "无法验证新 Token，改为检查账户访问权限。"
+            )
+        ),
+    }
+    if let Some(account_id) = &cfg.account_id {
+        let accounts = CloudflareClient::fetch_accounts(new_token).await?;
+        if !accounts.iter().any(|a| &a.id == account_id) {
+            bail!(
+                "{} {account_id}",
+                t!(
+                    l,
+                    "New token lacks access to account",
+                    "新 Token 无权访问账户"
+                )
+            );
+        }
+    }
+
+    cfg.replace_token(new_token);
+    save_api_config_if_changed(&cfg, previous.as_ref())?;
+    Ok(())
+}
+
+/// Save the API config to disk with secure file permissions (0600), skipping
+/// the write when `new` equals `old`, the config it was derived from.
+/// Returns whether the file was written.
+///
+/// The save is refused when the file no longer holds `old`: another process
+/// saved in between, and writing `new` would silently drop its change.
+pub fn save_api_config_if_changed(new: &ApiConfig, old: Option<&ApiConfig>) -> Result<bool> {
+    save_api_config_in_if_changed(&config_dir()?, new, old)
+}
+
+fn save_api_config_in_if_changed(
+    dir: &Path,
+    new: &ApiConfig,
+    old: Option<&ApiConfig>,
+) -> Result<bool> {
+    if old == Some(new) {
+        return Ok(false);
+    }
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+    // Compare and write under one lock, so no save can slip in between.
+    let path = dir.join("config.json");
+    let _lock = lock_config(dir, CONFIG_LOCK_TIMEOUT)?;
+    if read_api_config(&path)?.as_ref() != old {
+        let l = lang();
+        bail!(t!(
+            l,
+            "Config file was changed by another process; run the command again",
+            "配置文件已被其他进程修改，请重新运行命令"
+        ));
+    }
+    write_api_config(&path, new)?;
+    Ok(true)
+}
+
+/// Write `config` to `path`; the caller holds the lock.
+fn write_api_config(path: &Path, config: &ApiConfig) -> Result<()> {
     let json = serde_json::to_string_pretty(config)?;
-    fs::write(&path, &json).with_context(|| format!("failed to write {}", path.display()))?;
+    write_atomic(path, json.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
 
-    set_config_permissions(&path)?;
+/// Write `contents` to `path` atomically: write a sibling `.tmp` file, fsync it,
+/// then rename over the target. A crash at any point leaves either the old or
+/// the new file, never a truncated one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = write_temp(path, contents)?;
+    commit_temp(&tmp, path)
+}
 
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// First half of `write_atomic`: write and fsync the temp file next to `path`.
+fn write_temp(path: &Path, contents: &[u8]) -> Result<PathBuf> {
+    let tmp = temp_path(path);
+    // Leftover from a previous interrupted save
+    if tmp.exists() {
+        fs::remove_file(&tmp).with_context(|| format!("failed to remove {}", tmp.display()))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)
+        .with_context(|| format!("failed to create {}", tmp.display()))?;
+    set_config_permissions(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(tmp)
+}
+
+/// Second half of `write_atomic`: move the temp file over the target.
+/// `fs::rename` is atomic on POSIX and uses `MoveFileExW` with
+/// `MOVEFILE_REPLACE_EXISTING` on Windows.
+fn commit_temp(tmp: &Path, path: &Path) -> Result<()> {
+    fs::rename(tmp, path)
+        .with_context(|| format!("failed to move {} to {}", tmp.display(), path.display()))?;
     Ok(())
 }
 
 #[cfg(unix)]
-fn set_config_permissions(path: &std::path::Path) -> Result<()> {
+fn set_config_permissions(path: &Path) -> Result<()> {
     use std::os::unix::fs::PermissionsExt;
     let perms = fs::Permissions::from_mode(0o600);
     fs::set_permissions(path, perms)?;
@@ -94,7 +494,7 @@ fn set_config_permissions(path: &std::path::Path) -> Result<()> {
 }
 
 #[cfg(not(unix))]
-fn set_config_permissions(_path: &std::path::Path) -> Result<()> {
+fn set_config_permissions(_path: &Path) -> Result<()> {
     Ok(())
 }
 
@@ -102,33 +502,250 @@ fn set_config_permissions(_path: &std::path::Path) -> Result<()> {
 pub fn clear_api_config() -> Result<()> {
     let path = api_config_path()?;
     if path.exists() {
+        let _lock = lock_config(&config_dir()?, CONFIG_LOCK_TIMEOUT)?;
         fs::remove_file(&path)?;
     }
     Ok(())
 }
 
-/// Quick check: is API token configured?
-pub fn is_api_configured() -> bool {
-    load_api_config()
-        .ok()
-        .flatten()
-        .map(|c| c.api_token.is_some())
-        .unwrap_or(false)
+/// How much of the API configuration is in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiConfigStatus {
+    /// No usable token.
+    NotConfigured,
+    /// Token saved, but no account selected.
+    TokenOnly,
+    /// Token and account; no zone, so DNS features are unavailable.
+    TokenAndAccount,
+    /// Token, account and zone ID. `has_zone` is false when the zone name
+    /// was not saved alongside the ID.
+    FullyConfigured { has_zone: bool },
 }
 
-/// Quick check: is account_id configured?
-pub fn is_account_configured() -> bool {
-    load_api_config()
-        .ok()
-        .flatten()
-        .map(|c| c.account_id.is_some())
-        .unwrap_or(false)
+impl ApiConfigStatus {
+    pub fn has_token(self) -> bool {
+        self != Self::NotConfigured
+    }
+
+    pub fn has_account(self) -> bool {
+        matches!(self, Self::TokenAndAccount | Self::FullyConfigured { .. })
+    }
+
+    pub fn has_zone_id(self) -> bool {
+        matches!(self, Self::FullyConfigured { .. })
+    }
+}
+
+/// Classify a (possibly missing) config.
+pub fn api_config_status(cfg: Option<&ApiConfig>) -> ApiConfigStatus {
+    let Some(cfg) = cfg.filter(|c| c.effective_token().is_some()) else {
+        return ApiConfigStatus::NotConfigured;
+    };
+    match (&cfg.account_id, &cfg.zone_id) {
+        (None, _) => ApiConfigStatus::TokenOnly,
+        (Some(_), None) => ApiConfigStatus::TokenAndAccount,
+        (Some(_), Some(_)) => ApiConfigStatus::FullyConfigured {
+            has_zone: cfg.zone_name.is_some(),
+        },
+    }
+}
+
+/// Check how much of the saved API configuration is in place.
+pub fn check_api_config_status() -> ApiConfigStatus {
+    api_config_status(load_api_config().ok().flatten().as_ref())
+}
+
+/// API audit log file: `CFT_AUDIT_LOG` if set, else `audit_log` of `cfg`.
+pub fn audit_log_path_for(cfg: Option<&ApiConfig>) -> Option<PathBuf> {
+    match std::env::var_os("CFT_AUDIT_LOG") {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => cfg.and_then(|c| c.audit_log.clone()),
+    }
+}
+
+/// API audit log file for the saved configuration.
+pub fn audit_log_path() -> Option<PathBuf> {
+    audit_log_path_for(load_api_config().ok().flatten().as_ref())
+}
+
+// ---------------------------------------------------------------------------
+// Local cloudflared config (~/.cloudflared/config.yml)
+// ---------------------------------------------------------------------------
+
+/// A locally-managed cloudflared config file.
+#[derive(Debug, Clone)]
+pub struct LocalTunnelConfig {
+    pub path: PathBuf,
+    pub yaml: serde_yaml::Value,
+}
+
+impl LocalTunnelConfig {
+    /// Hostnames of the ingress rules, in file order.
+    pub fn ingress_hostnames(&self) -> Vec<String> {
+        self.ingress_rules()
+            .iter()
+            .filter_map(|rule| rule.get("hostname").and_then(|h| h.as_str()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Number of ingress rules, including the catch-all.
+    pub fn ingress_count(&self) -> usize {
+        self.ingress_rules().len()
+    }
+
+    /// The `tunnel` key (tunnel ID or name).
+    pub fn tunnel(&self) -> Option<&str> {
+        self.yaml.get("tunnel").and_then(|t| t.as_str())
+    }
+
+    /// The `credentials-file` key.
+    pub fn credentials_file(&self) -> Option<PathBuf> {
+        self.yaml
+            .get("credentials-file")
+            .and_then(|c| c.as_str())
+            .map(PathBuf::from)
+    }
+
+    /// `credentials-file` of this config when its `tunnel` key is `id` or `name`.
+    pub fn credentials_file_for(&self, id: &str, name: &str) -> Option<PathBuf> {
+        let tunnel = self.tunnel()?;
+        if tunnel != id && tunnel != name {
+            return None;
+        }
+        self.credentials_file()
+    }
+
+    fn ingress_rules(&self) -> &[serde_yaml::Value] {
+        self.yaml
+            .get("ingress")
+            .and_then(|i| i.as_sequence())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Places cloudflared looks for its config, in priority order.
+fn tunnel_config_candidates() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(".cloudflared/config.yml"));
+        paths.push(home.join(".cloudflared/config.yaml"));
+    }
+    paths.push(PathBuf::from("/etc/cloudflared/config.yml"));
+    paths.push(PathBuf::from("/usr/local/etc/cloudflared/config.yml"));
+    paths
+}
+
+/// Whether the local cloudflared config exists and parses.
+#[derive(Debug)]
+pub enum TunnelConfigStatus {
+    Missing,
+    Valid(PathBuf),
+    /// The file exists but is not valid YAML (or cannot be read).
+    Invalid {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+/// Check the first cloudflared config file found, telling a missing file
+/// apart from one that fails to parse.
+pub fn tunnel_config_status() -> TunnelConfigStatus {
+    let Some(path) = tunnel_config_candidates().into_iter().find(|p| p.exists()) else {
+        return TunnelConfigStatus::Missing;
+    };
+    let parsed = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|c| {
+            serde_yaml::from_str::<serde_yaml::Value>(&c)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+    match parsed {
+        Ok(()) => TunnelConfigStatus::Valid(path),
+        Err(error) => TunnelConfigStatus::Invalid { path, error },
+    }
+}
+
+/// Load the first cloudflared config file found. Returns `None` when there is none.
+pub fn load_tunnel_config() -> Result<Option<LocalTunnelConfig>> {
+    let Some(path) = tunnel_config_candidates().into_iter().find(|p| p.exists()) else {
+        return Ok(None);
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let yaml = serde_yaml::from_str(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(LocalTunnelConfig { path, yaml }))
+}
+
+// ---------------------------------------------------------------------------
+// Per-invocation overrides (--account-id / --zone-id / --token)
+// ---------------------------------------------------------------------------
+
+/// Credentials given on the command line for a single invocation.
+/// They are applied on top of the saved config and never written to disk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliOverrides {
+    pub account_id: Option<String>,
+    pub zone_id: Option<String>,
+    pub token: Option<String>,
+}
+
+static FLAG_OVERRIDES: OnceLock<CliOverrides> = OnceLock::new();
+
+/// Install the overrides used by [`require_api_config`] for this process.
+/// Only the first call takes effect.
+pub fn init_flag_overrides(overrides: CliOverrides) {
+    let _ = FLAG_OVERRIDES.set(overrides);
+}
+
+/// Apply command-line overrides to `cfg`. A `--token` replaces whichever
+/// token is active; a `--zone-id` drops the saved zone name.
+pub fn apply_flag_overrides(cfg: &mut ApiConfig, cli_overrides: &CliOverrides) {
+    if let Some(token) = &cli_overrides.token {
+        cfg.api_token = Some(token.clone());
+        cfg.active_token = None;
+    }
+    if let Some(account_id) = &cli_overrides.account_id {
+        if cfg.account_id.as_ref() != Some(account_id) {
+            cfg.account_name = None;
+        }
+        cfg.account_id = Some(account_id.clone());
+    }
+    if let Some(zone_id) = &cli_overrides.zone_id {
+        if cfg.zone_id.as_ref() != Some(zone_id) {
+            cfg.zone_name = None;
+        }
+        cfg.zone_id = Some(zone_id.clone());
+    }
+}
+
+/// The saved config with this invocation's command-line overrides applied,
+/// for commands that read it. Code that saves the config back must start
+/// from [`load_api_config`] instead, so overrides never reach the disk.
+pub fn load_effective_api_config() -> Result<Option<ApiConfig>> {
+    let mut cfg = load_api_config()?;
+    if let Some(overrides) = FLAG_OVERRIDES
+        .get()
+        .filter(|o| **o != CliOverrides::default())
+    {
+        let c = cfg.get_or_insert_with(ApiConfig::default);
+        apply_flag_overrides(c, overrides);
+    }
+    Ok(cfg)
 }
 
 /// Load and return ApiConfig, or bail with a helpful message.
+/// `api_token` in the returned config is the effective (active) token.
+/// Command-line overrides are applied before validation.
 pub fn require_api_config() -> Result<ApiConfig> {
-    match load_api_config()? {
-        Some(ref c) if c.api_token.is_some() && c.account_id.is_some() => Ok(c.clone()),
+    match load_effective_api_config()? {
+        Some(mut c) if c.effective_token().is_some() && c.account_id.is_some() => {
+            c.api_token = c.effective_token().map(str::to_string);
+            Ok(c)
+        }
         _ => bail!(crate::error::CftError::ApiNotConfigured),
     }
 }
@@ -146,6 +763,126 @@ pub fn require_zone_config() -> Result<ApiConfig> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn replace_token_preserves_other_fields() {
+        let cfg = ApiConfig {
+            api_token: Some("old".to_string()),
+            account_id: Some("acc".to_string()),
+            account_name: Some("Acme".to_string()),
+            zone_id: Some("zone".to_string()),
+            zone_name: Some("example.com".to_string()),
+            language: Some("zh".to_string()),
+            response_size_limit_mb: Some(8),
+            audit_log: Some(PathBuf::from("/tmp/audit.log")),
+            ..Default::default()
+        };
+        let mut rotated = cfg.clone();
+        rotated.replace_token("new");
+        assert_eq!(rotated.api_token.as_deref(), Some("new"));
+        rotated.api_token = cfg.api_token.clone();
+        assert_eq!(
+            serde_json::to_value(&rotated).unwrap(),
+            serde_json::to_value(&cfg).unwrap()
+        );
+
+        // With a labelled token active, that token is the one replaced.
+        let mut labelled = cfg.clone();
+        labelled.add_token(NamedToken {
+            label: "ci".to_string(),
+            token: "old-ci".to_string(),
+            scopes: vec!["dns".to_string()],
+        });
+        labelled.use_token("ci").unwrap();
+        labelled.replace_token("new-ci");
+        assert_eq!(labelled.effective_token(), Some("new-ci"));
+        assert_eq!(labelled.api_token.as_deref(), Some("old"));
+        assert_eq!(labelled.tokens[0].scopes, vec!["dns".to_string()]);
+    }
+
+    #[test]
+    fn api_config_status_variants() {
+        assert_eq!(api_config_status(None), ApiConfigStatus::NotConfigured);
+
+        let mut cfg = ApiConfig {
+            account_id: Some("acc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            api_config_status(Some(&cfg)),
+            ApiConfigStatus::NotConfigured
+        );
+
+        cfg.account_id = None;
+        cfg.api_token = Some("token".to_string());
+        assert_eq!(api_config_status(Some(&cfg)), ApiConfigStatus::TokenOnly);
+
+        cfg.account_id = Some("acc".to_string());
+        assert_eq!(
+            api_config_status(Some(&cfg)),
+            ApiConfigStatus::TokenAndAccount
+        );
+
+        cfg.zone_id = Some("zone".to_string());
+        assert_eq!(
+            api_config_status(Some(&cfg)),
+            ApiConfigStatus::FullyConfigured { has_zone: false }
+        );
+
+        cfg.zone_name = Some("example.com".to_string());
+        let status = api_config_status(Some(&cfg));
+        assert_eq!(status, ApiConfigStatus::FullyConfigured { has_zone: true });
+        assert!(status.has_token() && status.has_account() && status.has_zone_id());
+    }
+
+    #[test]
+    fn api_config_status_uses_active_named_token() {
+        let cfg = ApiConfig {
+            account_id: Some("acc".to_string()),
+            tokens: vec![NamedToken {
+                label: "ro".to_string(),
+                token: "named".to_string(),
+                scopes: Vec::new(),
+            }],
+            active_token: Some("ro".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            api_config_status(Some(&cfg)),
+            ApiConfigStatus::TokenAndAccount
+        );
+    }
+
+    #[test]
+    fn token_type_detection() {
+        let token = |t: &str| ApiConfig {
+            api_token: Some(t.to_string()),
+            ..Default::default()
+        };
+
+        let global = token("0123456789abcdef0123456789abcdef01234");
+        assert_eq!(global.token_type(), TokenType::GlobalApiKey);
+        assert_eq!(global.masked_token(), "Global API Key ****");
+        assert_eq!(
+            token("0123456789ABCDEF0123456789abcdef01234567").token_type(),
+            TokenType::GlobalApiKey
+        );
+
+        assert_eq!(
+            token("cf_Zm9vYmFyYmF6cXV4").token_type(),
+            TokenType::ScopedToken
+        );
+        let scoped = token("Ab3_dEfGhIjKlMnOpQrStUvWxYz-0123456789xy");
+        assert_eq!(scoped.token_type(), TokenType::ScopedToken);
+        assert_eq!(scoped.masked_token(), "Ab3_***...***89xy");
+
+        assert_eq!(token("short").token_type(), TokenType::Unknown);
+        assert_eq!(
+            token("not a token at all!").token_type(),
+            TokenType::Unknown
+        );
+        assert_eq!(ApiConfig::default().token_type(), TokenType::Unknown);
+    }
+
     #[test]
     fn masked_token_display() {
         let cfg = ApiConfig {
@@ -212,6 +949,7 @@ mod tests {
             zone_id: Some("zone".to_string()),
             zone_name: Some("example.com".to_string()),
             language: Some("en".to_string()),
+            ..Default::default()
         };
         let json = serde_json::to_string(&cfg).unwrap();
         let parsed: ApiConfig = serde_json::from_str(&json).unwrap();
@@ -221,4 +959,323 @@ mod tests {
         assert_eq!(parsed.zone_name, cfg.zone_name);
         assert_eq!(parsed.language, cfg.language);
     }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("opentunnel-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn concurrent_saves_do_not_corrupt_config() {
+        let dir = scratch_dir("config-lock");
+        let configs: Vec<ApiConfig> = ["token-a", "token-b"]
+            .iter()
+            .map(|token| ApiConfig {
+                api_token: Some(token.to_string()),
+                account_id: Some(format!("account-{token}")),
+                ..Default::default()
+            })
+            .collect();
+
+        let handles: Vec<_> = configs
+            .iter()
+            .cloned()
+            .map(|cfg| {
+                let dir = dir.clone();
+                std::thread::spawn(move || save_api_config_in_if_changed(&dir, &cfg, None))
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // Both started from "no config": the first save wins and the second
+        // is refused instead of overwriting it.
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        let refused = results.iter().find_map(|r| r.as_ref().err()).unwrap();
+        assert!(
+            refused.to_string().contains("changed by another process")
+                || refused.to_string().contains("其他进程修改")
+        );
+
+        let saved = load_api_config_from(&dir).unwrap().unwrap();
+        let matches: Vec<_> = configs
+            .iter()
+            .filter(|c| c.api_token == saved.api_token && c.account_id == saved.account_id)
+            .collect();
+        assert_eq!(matches.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unchanged_config_is_not_rewritten() {
+        let dir = scratch_dir("config-unchanged");
+        let cfg = ApiConfig {
+            api_token: Some("token".into()),
+            language: Some("en".into()),
+            ..Default::default()
+        };
+        assert!(save_api_config_in_if_changed(&dir, &cfg, None).unwrap());
+        let path = dir.join("config.json");
+        let before = fs::metadata(&path).unwrap().modified().unwrap();
+
+        assert!(!save_api_config_in_if_changed(&dir, &cfg.clone(), Some(&cfg)).unwrap());
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), before);
+
+        let changed = ApiConfig {
+            language: Some("zh".into()),
+            ..cfg.clone()
+        };
+        assert!(save_api_config_in_if_changed(&dir, &changed, Some(&cfg)).unwrap());
+        let saved = load_api_config_from(&dir).unwrap().unwrap();
+        assert_eq!(saved.language.as_deref(), Some("zh"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn held_config_lock_times_out() {
+        let dir = scratch_dir("config-lock-timeout");
+        let _held = lock_config(&dir, Duration::ZERO).unwrap();
+        let err = lock_config_shared(&dir, Duration::from_millis(100)).unwrap_err();
+        assert!(
+            err.to_string().contains("locked by another process")
+                || err.to_string().contains("其他进程锁定")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reading_config_leaves_no_lock_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_api_config_from(dir.path()).unwrap().is_none());
+        assert!(!dir.path().join("config.lock").exists());
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents() {
+        let dir = scratch_dir("atomic-replace");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!temp_path(&path).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interrupted_write_leaves_original_intact() {
+        let dir = scratch_dir("atomic-interrupt");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"original").unwrap();
+
+        // Simulate a crash after the temp file is written but before rename
+        let tmp = write_temp(&path, b"half-finished").unwrap();
+        assert!(tmp.exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+
+        // The next save copes with the stale temp file
+        write_atomic(&path, b"recovered").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "recovered");
+        assert!(!tmp.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_sets_private_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = scratch_dir("atomic-perms");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"{}").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn config_with_tokens() -> ApiConfig {
+        let mut cfg = ApiConfig {
+            api_token: Some("default-token".to_string()),
+            account_id: Some("acc".to_string()),
+            ..Default::default()
+        };
+        cfg.add_token(NamedToken {
+            label: "personal".to_string(),
+            token: "personal-token".to_string(),
+            scopes: vec![],
+        });
+        cfg.add_token(NamedToken {
+            label: "work-ci".to_string(),
+            token: "work-ci-token".to_string(),
+            scopes: vec!["tunnel:read".to_string()],
+        });
+        cfg
+    }
+
+    #[test]
+    fn flag_overrides_apply_independently() {
+        let base = ApiConfig {
+            zone_name: Some("example.com".to_string()),
+            zone_id: Some("zone".to_string()),
+            account_name: Some("Home".to_string()),
+            ..config_with_tokens()
+        };
+
+        let mut cfg = base.clone();
+        apply_flag_overrides(
+            &mut cfg,
+            &CliOverrides {
+                token: Some("flag-token".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(cfg.effective_token(), Some("flag-token"));
+        assert_eq!(cfg.account_id, base.account_id);
+        assert_eq!(cfg.zone_id, base.zone_id);
+
+        let mut cfg = base.clone();
+        apply_flag_overrides(
+            &mut cfg,
+            &CliOverrides {
+                account_id: Some("other-acc".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(cfg.account_id.as_deref(), Some("other-acc"));
+        assert_eq!(cfg.account_name, None);
+        assert_eq!(cfg.effective_token(), base.effective_token());
+        assert_eq!(cfg.zone_id, base.zone_id);
+
+        let mut cfg = base.clone();
+        apply_flag_overrides(
+            &mut cfg,
+            &CliOverrides {
+                zone_id: Some("abc123".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(cfg.zone_id.as_deref(), Some("abc123"));
+        assert_eq!(cfg.zone_name, None);
+        assert_eq!(cfg.account_id, base.account_id);
+        assert_eq!(cfg.effective_token(), base.effective_token());
+    }
+
+    #[test]
+    fn effective_token_defaults_to_api_token() {
+        let cfg = config_with_tokens();
+        assert_eq!(cfg.effective_token(), Some("default-token"));
+    }
+
+    #[test]
+    fn use_token_switches_effective_token() {
+        let mut cfg = config_with_tokens();
+        cfg.use_token("work-ci").unwrap();
+        assert_eq!(cfg.effective_token(), Some("work-ci-token"));
+        assert!(crate::client::CloudflareClient::from_config(&cfg).is_ok());
+        cfg.use_token("personal").unwrap();
+        assert_eq!(cfg.effective_token(), Some("personal-token"));
+        assert!(cfg.use_token("missing").is_err());
+    }
+
+    #[test]
+    fn removing_active_token_clears_selection() {
+        let mut cfg = config_with_tokens();
+        cfg.use_token("work-ci").unwrap();
+        assert!(cfg.remove_token("work-ci"));
+        assert_eq!(cfg.active_token, None);
+        assert_eq!(cfg.tokens.len(), 1);
+        assert!(!cfg.remove_token("work-ci"));
+    }
+
+    #[test]
+    fn add_token_replaces_same_label() {
+        let mut cfg = config_with_tokens();
+        cfg.add_token(NamedToken {
+            label: "personal".to_string(),
+            token: "rotated".to_string(),
+            scopes: vec![],
+        });
+        assert_eq!(cfg.tokens.len(), 2);
+        cfg.use_token("personal").unwrap();
+        assert_eq!(cfg.effective_token(), Some("rotated"));
+    }
+
+    #[test]
+    fn platform_config_dirs() {
+        let home = Path::new("/home/ana");
+        let no_env = |_: &str| None;
+        assert_eq!(
+            platform_config_dir("linux", home, no_env),
+            PathBuf::from("/home/ana/.config/opentunnel")
+        );
+        assert_eq!(
+            platform_config_dir("linux", home, |k| (k == "XDG_CONFIG_HOME")
+                .then(|| "/srv/conf".to_string())),
+            PathBuf::from("/srv/conf/opentunnel")
+        );
+        assert_eq!(
+            platform_config_dir("linux", home, |_| Some("relative/conf".to_string())),
+            PathBuf::from("/home/ana/.config/opentunnel")
+        );
+        assert_eq!(
+            platform_config_dir("macos", home, no_env),
+            PathBuf::from("/home/ana/Library/Application Support/openTunnel")
+        );
+        assert_eq!(
+            platform_config_dir("windows", home, no_env),
+            home.join("AppData").join("Roaming").join("openTunnel")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_config_dir_uses_appdata() {
+        let home = Path::new(r"C:\Users\ana");
+        assert_eq!(
+            platform_config_dir("windows", home, |k| (k == "APPDATA")
+                .then(|| r"C:\Users\ana\AppData\Roaming".to_string())),
+            PathBuf::from(r"C:\Users\ana\AppData\Roaming\openTunnel")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn legacy_config_migration() {
+        let home = std::env::temp_dir().join(format!("opentunnel-xdg-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let new_dir = home.join(".config/opentunnel");
+
+        // Nothing to migrate yet.
+        assert_eq!(legacy_migration_source(&home, &new_dir), None);
+
+        let legacy = home.join(".cft");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("config.json"), "{}").unwrap();
+        fs::write(legacy.join("events.jsonl"), "").unwrap();
+        assert_eq!(
+            legacy_migration_source(&home, &new_dir),
+            Some(legacy.clone())
+        );
+
+        // `~/.opentunnel` wins over the older `~/.cft`.
+        let newer = home.join(".opentunnel");
+        fs::create_dir_all(&newer).unwrap();
+        fs::write(newer.join("config.json"), "{}").unwrap();
+        assert_eq!(legacy_migration_source(&home, &new_dir), Some(newer));
+
+        assert_eq!(copy_config_files(&legacy, &new_dir).unwrap(), 2);
+        assert!(new_dir.join("events.jsonl").exists());
+        assert!(legacy.join("config.json").exists());
+
+        // Once the new path has a config, no further migration.
+        assert_eq!(legacy_migration_source(&home, &new_dir), None);
+        let _ = fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn migration_notice_names_the_platform_location() {
+        assert!(migration_notice("linux", Lang::En).contains("XDG"));
+        assert!(migration_notice("macos", Lang::En).contains("Application Support"));
+        assert!(migration_notice("windows", Lang::En).contains("AppData"));
+        assert!(!migration_notice("windows", Lang::Zh).contains("XDG"));
+    }
 }
@@ -12,8 +12,8 @@ use ratatui::{
 };
 use std::io::{self, stdout};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 use tokio::time::interval;
 
 use crate::i18n::lang;
@@ -97,7 +97,9 @@ impl App {
 pub async fn run_dashboard() -> Result<()> {
     // Setup terminal
     enable_raw_mode().context("failed to enable raw mode")?;
-    stdout().execute(EnterAlternateScreen).context("failed to enter alternate screen")?;
+    stdout()
+        .execute(EnterAlternateScreen)
+        .context("failed to enter alternate screen")?;
     let mut terminal = Terminal::new(ratatui::backend::CrosstermBackend::new(stdout()))?;
 
     let app = Arc::new(Mutex::new(App::new()));
@@ -127,7 +129,9 @@ pub async fn run_dashboard() -> Result<()> {
 
     // Cleanup
     disable_raw_mode().context("failed to disable raw mode")?;
-    stdout().execute(LeaveAlternateScreen).context("failed to leave alternate screen")?;
+    stdout()
+        .execute(LeaveAlternateScreen)
+        .context("failed to leave alternate screen")?;
 
     result
 }
@@ -201,7 +205,10 @@ fn draw_ui(f: &mut Frame, app: &App) {
 
     // Draw blocks
     let block = Block::default()
-        .title(format!(" {} ", t!(l, "openTunnel Dashboard", "openTunnel 仪表盘")))
+        .title(format!(
+            " {} ",
+            t!(l, "openTunnel Dashboard", "openTunnel 仪表盘")
+        ))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
     f.render_widget(block, content_area);
@@ -233,7 +240,11 @@ fn draw_header(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         t!(l, "○ Disconnected", "○ 未连接").to_string()
     };
 
-    let status_color = if app.connected { Color::Green } else { Color::Red };
+    let status_color = if app.connected {
+        Color::Green
+    } else {
+        Color::Red
+    };
 
     let header_text = format!(
         "{} | {}: {} | {}: {}",
@@ -245,7 +256,11 @@ fn draw_header(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     );
 
     let header = Paragraph::new(header_text)
-        .style(Style::default().fg(status_color).add_modifier(Modifier::BOLD))
+        .style(
+            Style::default()
+                .fg(status_color)
+                .add_modifier(Modifier::BOLD),
+        )
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::BOTTOM));
 
@@ -270,7 +285,7 @@ fn draw_metrics(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     // Requests card
     let requests_val = metrics
         .and_then(|m| m.total_requests)
-        .map(|v| format_num(v))
+        .map(format_num)
         .unwrap_or_else(|| "-".to_string());
     draw_metric_card(
         f,
@@ -283,7 +298,7 @@ fn draw_metrics(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     // Streams card
     let streams_val = metrics
         .and_then(|m| m.active_streams)
-        .map(|v| format_num(v))
+        .map(format_num)
         .unwrap_or_else(|| "-".to_string());
     draw_metric_card(
         f,
@@ -296,7 +311,7 @@ fn draw_metrics(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     // Errors card
     let errors_val = metrics
         .and_then(|m| m.request_errors)
-        .map(|v| format_num(v))
+        .map(format_num)
         .unwrap_or_else(|| "-".to_string());
     draw_metric_card(
         f,
@@ -438,8 +453,16 @@ fn draw_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
         t!(l, "Force refresh", "强制刷新"),
         t!(l, "Notes", "说明"),
         t!(l, "Data updates every 2 seconds", "数据每 2 秒更新"),
-        t!(l, "Requires cloudflared metrics enabled", "需要开启 cloudflared 指标"),
-        t!(l, "Default metrics endpoint: 127.0.0.1:20241", "默认指标端点: 127.0.0.1:20241")
+        t!(
+            l,
+            "Requires cloudflared metrics enabled",
+            "需要开启 cloudflared 指标"
+        ),
+        t!(
+            l,
+            "Default metrics endpoint: 127.0.0.1:20241",
+            "默认指标端点: 127.0.0.1:20241"
+        )
     );
 
     let help_paragraph = Paragraph::new(help_content)
@@ -450,7 +473,11 @@ fn draw_help_popup(f: &mut Frame, area: ratatui::layout::Rect) {
     f.render_widget(help_paragraph, inner);
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
+fn centered_rect(
+    percent_x: u16,
+    percent_y: u16,
+    r: ratatui::layout::Rect,
+) -> ratatui::layout::Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -1,12 +1,68 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Context;
 use colored::Colorize;
 use comfy_table::{presets::UTF8_FULL, Table};
 
-use crate::client::{CloudflareClient, CreateDnsRecord, ZoneSetting};
+use crate::client::{
+    CloudflareClient, CreateDnsRecord, DnsRecord, Tunnel, TunnelConfiguration, Zone, ZoneSetting,
+};
 use crate::error::Result;
 use crate::i18n::lang;
-use crate::prompt;
 use crate::t;
-use crate::tunnel;
+use crate::{config, prompt, tunnel};
+
+/// Find the most specific zone that `hostname` belongs to (longest suffix match).
+pub fn find_matching_zone<'a>(hostname: &str, available_zones: &'a [Zone]) -> Option<&'a Zone> {
+    let host = hostname.trim_end_matches('.').to_ascii_lowercase();
+    available_zones
+        .iter()
+        .filter(|z| {
+            let zone = z.name.trim_end_matches('.').to_ascii_lowercase();
+            !zone.is_empty()
+                && (host == zone
+                    || host
+                        .strip_suffix(zone.as_str())
+                        .is_some_and(|rest| rest.ends_with('.')))
+        })
+        .max_by_key(|z| z.name.len())
+}
+
+/// Zones visible to `client`, listed once so a batch of hostnames can each be
+/// matched to their zone. Empty when the zones cannot be listed.
+async fn visible_zones(client: &CloudflareClient) -> Vec<Zone> {
+    client.list_zones().await.unwrap_or_default()
+}
+
+/// Return a client scoped to the zone `hostname` belongs to. Falls back to
+/// `client` when the zones cannot be listed or none of them match.
+async fn client_for_hostname(client: &CloudflareClient, hostname: &str) -> CloudflareClient {
+    if !hostname.contains('.') {
+        return client.clone();
+    }
+    client_in_zone(client, hostname, &visible_zones(client).await)
+}
+
+/// Like `client_for_hostname`, matching against already listed `zones`.
+fn client_in_zone(client: &CloudflareClient, hostname: &str, zones: &[Zone]) -> CloudflareClient {
+    let l = lang();
+    match find_matching_zone(hostname, zones) {
+        Some(zone) if client.zone_id.as_deref() != Some(zone.id.as_str()) => {
+            println!(
+                "  ℹ️ {} {} {} {}",
+                t!(l, "Using zone", "使用 Zone"),
+                zone.name.cyan(),
+                t!(l, "for hostname", "处理域名"),
+                hostname
+            );
+            let mut scoped = client.clone();
+            scoped.zone_id = Some(zone.id.clone());
+            scoped
+        }
+        _ => client.clone(),
+    }
+}
 
 /// Create a CNAME record for a single hostname pointing to a tunnel.
 /// Skips silently if the record already exists.
@@ -14,9 +70,22 @@ pub async fn ensure_dns_for_hostname(
     client: &CloudflareClient,
     tunnel_id: &str,
     hostname: &str,
+) -> Result<()> {
+    let zones = visible_zones(client).await;
+    ensure_dns_in_zones(client, tunnel_id, hostname, &zones).await
+}
+
+/// `ensure_dns_for_hostname` for one hostname of a batch, with the zones
+/// listed once up front.
+async fn ensure_dns_in_zones(
+    client: &CloudflareClient,
+    tunnel_id: &str,
+    hostname: &str,
+    zones: &[Zone],
 ) -> Result<()> {
     let l = lang();
     let tunnel_cname = format!("{tunnel_id}.cfargotunnel.com");
+    let client = &client_in_zone(client, hostname, zones);
 
     let existing = client.list_dns_records().await.unwrap_or_default();
     let exists = existing
@@ -39,6 +108,7 @@ pub async fn ensure_dns_for_hostname(
         content: tunnel_cname.clone(),
         proxied: true,
         ttl: None,
+        priority: None,
     };
 
     client.create_dns_record(&record).await?;
@@ -46,10 +116,32 @@ pub async fn ensure_dns_for_hostname(
     Ok(())
 }
 
+/// Whether the zone `hostname` belongs to already has a record named
+/// `hostname`. `false` when the records cannot be listed.
+pub async fn hostname_has_record(client: &CloudflareClient, hostname: &str) -> bool {
+    let client = &client_for_hostname(client, hostname).await;
+    client
+        .list_dns_records()
+        .await
+        .is_ok_and(|records| records.iter().any(|r| hostnames_match(&r.name, hostname)))
+}
+
 fn short_id(id: &str) -> String {
     id.chars().take(8).collect()
 }
 
+/// Format a TTL for display: `auto`, `2m`, `1h`, `1d`, or `custom <N>s`.
+fn format_ttl(ttl: u32) -> String {
+    match ttl {
+        1 => "auto".to_string(),
+        0 => format!("custom {ttl}s"),
+        t if t % 86400 == 0 => format!("{}d", t / 86400),
+        t if t % 3600 == 0 => format!("{}h", t / 3600),
+        t if t % 60 == 0 => format!("{}m", t / 60),
+        t => format!("custom {t}s"),
+    }
+}
+
 fn truncate(s: &str, max: usize) -> String {
     if s.chars().count() <= max {
         s.to_string()
@@ -62,15 +154,35 @@ fn truncate(s: &str, max: usize) -> String {
 // List DNS records
 // ---------------------------------------------------------------------------
 
-/// Display all DNS records for the configured zone.
-pub async fn list_records(client: &CloudflareClient) -> Result<()> {
+/// Number of records of each type.
+pub fn count_by_type(records: &[DnsRecord]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for r in records {
+        *counts.entry(r.record_type.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Type counts sorted by count (descending), then type name.
+fn sorted_type_counts(counts: &HashMap<String, usize>) -> Vec<(&str, usize)> {
+    let mut sorted: Vec<(&str, usize)> = counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    sorted
+}
+
+/// Display all DNS records for the configured zone, optionally only those
+/// of `record_type` (e.g. `CNAME`).
+pub async fn list_records(client: &CloudflareClient, record_type: Option<&str>) -> Result<()> {
     let l = lang();
     println!(
         "{}",
         t!(l, "Fetching DNS records...", "获取 DNS 记录...").bold()
     );
 
-    let records = client.list_dns_records().await?;
+    let mut records = client.list_dns_records().await?;
+    if let Some(record_type) = record_type {
+        records.retain(|r| r.record_type.eq_ignore_ascii_case(record_type));
+    }
 
     if records.is_empty() {
         println!("{}", t!(l, "No DNS records found.", "未找到 DNS 记录。"));
@@ -84,6 +196,7 @@ pub async fn list_records(client: &CloudflareClient) -> Result<()> {
         t!(l, "Type", "类型"),
         t!(l, "Content", "内容"),
         t!(l, "Proxy", "代理"),
+        "TTL",
     ]);
 
     for r in &records {
@@ -93,7 +206,8 @@ pub async fn list_records(client: &CloudflareClient) -> Result<()> {
             None => "-",
         };
         let content = truncate(&r.content, 30);
-        table.add_row(vec![&r.name, &r.record_type, &content, proxied_str]);
+        let ttl = r.ttl.map(format_ttl).unwrap_or_else(|| "-".to_string());
+        table.add_row(vec![&r.name, &r.record_type, &content, proxied_str, &ttl]);
     }
 
     println!("{table}");
@@ -102,6 +216,21 @@ pub async fn list_records(client: &CloudflareClient) -> Result<()> {
         t!(l, "Total:", "共:"),
         records.len().to_string().cyan()
     );
+
+    let counts = count_by_type(&records);
+    let summary: Vec<String> = sorted_type_counts(&counts)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (record_type, count))| {
+            let entry = format!("{record_type}: {count}");
+            if i == 0 {
+                entry.cyan().to_string()
+            } else {
+                entry
+            }
+        })
+        .collect();
+    println!("{}", summary.join(", "));
     Ok(())
 }
 
@@ -109,13 +238,51 @@ pub async fn list_records(client: &CloudflareClient) -> Result<()> {
 // Add DNS record
 // ---------------------------------------------------------------------------
 
+/// Record types Cloudflare can proxy.
+fn is_proxiable(record_type: &str) -> bool {
+    matches!(record_type, "A" | "AAAA" | "CNAME")
+}
+
+/// Proxy default for a new record: on for A/AAAA/CNAME, off for everything
+/// else (MX, TXT, SRV, ...), which Cloudflare cannot proxy. Only proxiable
+/// types are prompted for.
+fn default_proxied(record_type: &str) -> bool {
+    is_proxiable(record_type)
+}
+
+/// Proxy setting when `--proxied` was not given. Proxiable types are asked
+/// about on a terminal; otherwise, or when the prompt is dismissed, the
+/// type's default applies so scripted runs still create the record.
+fn choose_proxied(record_type: &str, interactive: bool) -> bool {
+    let l = lang();
+    if !interactive || !is_proxiable(record_type) {
+        return default_proxied(record_type);
+    }
+    println!(
+        "{}",
+        t!(
+            l,
+            "Proxied records hide your origin IP and enable Cloudflare's CDN and DDoS protection. Non-proxied records expose the raw IP/CNAME.",
+            "代理记录会隐藏源站 IP，并启用 Cloudflare 的 CDN 与 DDoS 防护；非代理记录会直接暴露 IP/CNAME。"
+        )
+        .dimmed()
+    );
+    prompt::confirm_opt(
+        t!(l, "Proxy through Cloudflare?", "是否通过 Cloudflare 代理？"),
+        default_proxied(record_type),
+    )
+    .unwrap_or_else(|| default_proxied(record_type))
+}
+
 /// Add a new DNS record, with optional interactive prompts.
 pub async fn add_record(
     client: &CloudflareClient,
     name: Option<String>,
     record_type: Option<String>,
     content: Option<String>,
-    proxied: bool,
+    proxied: Option<bool>,
+    ttl: Option<u32>,
+    editor: bool,
 ) -> Result<()> {
     let l = lang();
 
@@ -143,6 +310,12 @@ pub async fn add_record(
 
     let content = match content {
         Some(c) => c,
+        None if editor && record_type == "TXT" => {
+            match prompt::multiline_input(t!(l, "TXT record content", "TXT 记录内容"), None) {
+                Some(v) => join_txt_lines(&v),
+                None => return Ok(()),
+            }
+        }
         None => match prompt::input_opt(t!(l, "Record content / target", "记录内容"), false, None)
         {
             Some(v) => v,
@@ -150,14 +323,32 @@ pub async fn add_record(
         },
     };
 
+    let proxied = match proxied {
+        Some(p) => p,
+        None => choose_proxied(&record_type, prompt::is_interactive()),
+    };
+
+    // Proxied records always use automatic TTL, so only ask for unproxied
+    // ones, and only when someone can answer.
+    let ttl = match ttl {
+        Some(t) => t,
+        None if proxied || !prompt::is_interactive() => 1,
+        None => match prompt::select_ttl(t!(l, "TTL", "TTL"), Some(1)) {
+            Some(t) => t,
+            None => return Ok(()),
+        },
+    };
+
     let record = CreateDnsRecord {
         record_type: record_type.clone(),
         name: name.clone(),
         content: content.clone(),
         proxied,
-        ttl: None,
+        ttl: Some(ttl),
+        priority: None,
     };
 
+    let client = &client_for_hostname(client, &name).await;
     println!(
         "{}",
         t!(l, "Creating DNS record...", "正在创建 DNS 记录...").bold()
@@ -175,16 +366,237 @@ pub async fn add_record(
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Update DNS record
+// ---------------------------------------------------------------------------
+
+/// Update the content and TTL (and priority of MX records) of a DNS record.
+/// If `id` is None, show interactive picker.
+pub async fn update_record(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let records = client.list_dns_records().await?;
+    let record = match id {
+        Some(id) => match records.into_iter().find(|r| r.id == id) {
+            Some(r) => r,
+            None => {
+                println!(
+                    "{} {}",
+                    "❌".red(),
+                    t!(l, "DNS record not found.", "未找到该 DNS 记录。")
+                );
+                return Ok(());
+            }
+        },
+        None => {
+            if records.is_empty() {
+                println!(
+                    "{}",
+                    t!(l, "No DNS records to update.", "没有可更新的 DNS 记录。")
+                );
+                return Ok(());
+            }
+            let items: Vec<String> = records
+                .iter()
+                .map(|r| format!("{} {} → {}", r.record_type, r.name, r.content))
+                .collect();
+
+            let sel = prompt::select_opt(
+                t!(l, "Select record to update", "选择要更新的记录"),
+                &items,
+                None,
+            );
+
+            match sel.and_then(|i| records.into_iter().nth(i)) {
+                Some(r) => r,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let content = match prompt::input_opt(
+        t!(l, "Record content / target", "记录内容"),
+        false,
+        Some(&record.content),
+    ) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let proxied = record.proxied.unwrap_or(false);
+    let ttl = if proxied {
+        1
+    } else {
+        match prompt::select_ttl(t!(l, "TTL", "TTL"), record.ttl) {
+            Some(t) => t,
+            None => return Ok(()),
+        }
+    };
+
+    // MX records are rejected without a priority, so it is kept and editable
+    let priority = if record.record_type == "MX" {
+        let initial = record.priority.map(|p| p.to_string());
+        match prompt::input_validated(
+            t!(l, "Priority (0-65535)", "优先级 (0-65535)"),
+            initial.as_deref(),
+            validate_mx_priority,
+        ) {
+            Some(v) => v.trim().parse::<u16>().ok(),
+            None => return Ok(()),
+        }
+    } else {
+        record.priority
+    };
+
+    let update = CreateDnsRecord {
+        record_type: record.record_type.clone(),
+        name: record.name.clone(),
+        content: content.clone(),
+        proxied,
+        ttl: Some(ttl),
+        priority,
+    };
+
+    let updated = client.update_dns_record(&record.id, &update).await?;
+    println!(
+        "{} {} {} → {} (TTL: {})",
+        "✅".green(),
+        updated.record_type,
+        updated.name.cyan(),
+        content,
+        format_ttl(ttl)
+    );
+    Ok(())
+}
+
+/// Check an MX priority entered at the prompt.
+fn validate_mx_priority(value: &str) -> std::result::Result<(), String> {
+    let l = lang();
+    value.trim().parse::<u16>().map(|_| ()).map_err(|_| {
+        t!(
+            l,
+            "enter a whole number from 0 to 65535",
+            "请输入 0 到 65535 之间的整数"
+        )
+        .to_string()
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Delete DNS record
 // ---------------------------------------------------------------------------
 
-/// Delete a DNS record. If `id` is None, show interactive picker.
-pub async fn delete_record(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+/// A tunnel ingress rule that routes a hostname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TunnelReference {
+    tunnel_id: String,
+    tunnel_name: String,
+    /// Hostname as written in the ingress rule.
+    hostname: String,
+}
+
+/// DNS names compare case-insensitively and ignore a trailing dot.
+fn hostnames_match(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+/// Ingress rules among `configs` that route `hostname`.
+fn find_tunnel_references(
+    hostname: &str,
+    configs: &[(Tunnel, TunnelConfiguration)],
+) -> Vec<TunnelReference> {
+    configs
+        .iter()
+        .flat_map(|(tunnel, config)| {
+            config
+                .config
+                .ingress
+                .iter()
+                .filter_map(|r| r.hostname.as_deref())
+                .filter(|h| hostnames_match(h, hostname))
+                .map(|h| TunnelReference {
+                    tunnel_id: tunnel.id.clone(),
+                    tunnel_name: tunnel.name.clone(),
+                    hostname: h.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Fetch every tunnel's remote config and find the rules routing `hostname`.
+/// Tunnels whose config cannot be read are skipped.
+async fn tunnel_references(
+    client: &CloudflareClient,
+    hostname: &str,
+) -> Result<Vec<TunnelReference>> {
+    let mut configs = Vec::new();
+    for tunnel in client.list_tunnels().await? {
+        if let Ok(Some(config)) = client.get_tunnel_config(&tunnel.id).await {
+            configs.push((tunnel, config));
+        }
+    }
+    Ok(find_tunnel_references(hostname, &configs))
+}
+
+/// Warn about tunnel mappings that route `hostname` and return the ones the
+/// user wants removed along with the record.
+async fn confirm_tunnel_references(
+    client: &CloudflareClient,
+    hostname: &str,
+) -> Result<Vec<TunnelReference>> {
     let l = lang();
+    let mut to_remove = Vec::new();
+    for reference in tunnel_references(client, hostname).await? {
+        println!(
+            "{} {} '{}' {} '{}' — {}",
+            "⚠️".yellow(),
+            t!(l, "Hostname", "主机名"),
+            reference.hostname.cyan(),
+            t!(l, "is referenced by tunnel", "被隧道引用:"),
+            reference.tunnel_name,
+            t!(
+                l,
+                "deleting this DNS record will break that mapping.",
+                "删除该 DNS 记录会导致该映射失效。"
+            )
+        );
+        let remove = prompt::confirm_opt(
+            t!(l, "Also remove the tunnel mapping?", "同时移除该隧道映射?"),
+            false,
+        )
+        .unwrap_or(false);
+        if remove {
+            to_remove.push(reference);
+        }
+    }
+    Ok(to_remove)
+}
 
-    let record_id = match id {
-        Some(id) => id,
+/// Delete a DNS record. If `id` is None, show interactive picker. With
+/// `check_tunnel`, first look for tunnel mappings that route the record's
+/// hostname and offer to remove them too.
+pub async fn delete_record(
+    client: &CloudflareClient,
+    id: Option<String>,
+    check_tunnel: bool,
+) -> Result<()> {
+    let l = lang();
+
+    let (record_id, record_name) = match id {
+        Some(id) => {
+            let name = if check_tunnel {
+                client
+                    .list_dns_records()
+                    .await?
+                    .into_iter()
+                    .find(|r| r.id == id)
+                    .map(|r| r.name)
+            } else {
+                None
+            };
+            (id, name)
+        }
         None => {
             let records = client.list_dns_records().await?;
             if records.is_empty() {
@@ -199,15 +611,16 @@ pub async fn delete_record(client: &CloudflareClient, id: Option<String>) -> Res
                 .map(|r| format!("{} {} → {}", r.record_type, r.name, r.content))
                 .collect();
 
-            let sel = prompt::select_opt(
+            let sel = prompt::paged_select(
                 t!(l, "Select record to delete", "选择要删除的记录"),
                 &items,
+                prompt::DEFAULT_PAGE_SIZE,
                 None,
             );
 
             match sel {
                 Some(i) => match records.get(i) {
-                    Some(record) => record.id.clone(),
+                    Some(record) => (record.id.clone(), Some(record.name.clone())),
                     None => return Ok(()),
                 },
                 None => return Ok(()),
@@ -215,6 +628,11 @@ pub async fn delete_record(client: &CloudflareClient, id: Option<String>) -> Res
         }
     };
 
+    let mappings_to_remove = match (&record_name, check_tunnel) {
+        (Some(name), true) => confirm_tunnel_references(client, name).await?,
+        _ => Vec::new(),
+    };
+
     let confirmed = prompt::confirm_opt(
         t!(
             l,
@@ -235,6 +653,10 @@ pub async fn delete_record(client: &CloudflareClient, id: Option<String>) -> Res
         "✅".green(),
         t!(l, "DNS record deleted.", "DNS 记录已删除。")
     );
+
+    for reference in mappings_to_remove {
+        tunnel::remove_mapping(client, Some(reference.tunnel_id), Some(reference.hostname)).await?;
+    }
     Ok(())
 }
 
@@ -243,10 +665,12 @@ pub async fn delete_record(client: &CloudflareClient, id: Option<String>) -> Res
 // ---------------------------------------------------------------------------
 
 /// For each hostname in the tunnel's remote config, ensure a CNAME record
-/// pointing to the tunnel exists.
+/// pointing to the tunnel exists. The records to create are confirmed first
+/// unless `confirmed` is set or no terminal is attached.
 pub async fn sync_tunnel_routes(
     client: &CloudflareClient,
     tunnel_id: Option<String>,
+    confirmed: bool,
 ) -> Result<()> {
     let l = lang();
 
@@ -258,13 +682,15 @@ pub async fn sync_tunnel_routes(
         },
     };
 
-    let config = client.get_tunnel_config(&tunnel_id).await?;
-    let hostnames: Vec<String> = config
-        .config
-        .ingress
-        .iter()
-        .filter_map(|r| r.hostname.clone())
-        .collect();
+    let hostnames: Vec<String> = match client.get_tunnel_config(&tunnel_id).await? {
+        Some(config) => config
+            .config
+            .ingress
+            .iter()
+            .filter_map(|r| r.hostname.clone())
+            .collect(),
+        None => Vec::new(),
+    };
 
     if hostnames.is_empty() {
         println!(
@@ -280,6 +706,60 @@ pub async fn sync_tunnel_routes(
 
     let tunnel_cname = format!("{}.cfargotunnel.com", tunnel_id);
 
+    let existing = client.list_dns_records().await.unwrap_or_default();
+    let has_cname = |hostname: &str| {
+        existing
+            .iter()
+            .any(|r| r.record_type == "CNAME" && hostnames_match(&r.name, hostname))
+    };
+
+    // Hostnames a `*.<zone>` record already answers for. They are skipped
+    // unless the user asks for individual records anyway.
+    let covered: Vec<&String> = hostnames
+        .iter()
+        .filter(|h| !has_cname(h) && wildcard_covers(&existing, h))
+        .collect();
+    let mut skip_covered = false;
+    if !covered.is_empty() && check_wildcard_dns(client).await.unwrap_or(false) {
+        println!(
+            "{} {}",
+            "ℹ️".cyan(),
+            t!(
+                l,
+                "Wildcard DNS detected. Hostname-specific CNAME records are optional.",
+                "检测到通配符 DNS 记录，无需为每个域名单独创建 CNAME。"
+            )
+        );
+        skip_covered = confirmed
+            || !prompt::is_interactive()
+            || prompt::confirm_with_details(
+                t!(l, "Skip creating individual records?", "跳过创建单独记录?"),
+                &covered,
+                true,
+            ) == Some(true);
+    }
+    let is_skipped = |hostname: &str| {
+        has_cname(hostname) || (skip_covered && wildcard_covers(&existing, hostname))
+    };
+
+    let to_create: Vec<&String> = hostnames.iter().filter(|h| !is_skipped(h)).collect();
+    // Callers that already confirmed, and scripts without a terminal, go ahead
+    if !to_create.is_empty() && !confirmed && prompt::is_interactive() {
+        let answer = prompt::confirm_with_details(
+            &format!(
+                "{} {} {}",
+                t!(l, "Create", "创建"),
+                to_create.len(),
+                t!(l, "CNAME record(s)?", "条 CNAME 记录?")
+            ),
+            &to_create,
+            true,
+        );
+        if answer == Some(false) {
+            return Ok(());
+        }
+    }
+
     println!(
         "{} {} {} ...",
         "🔄".cyan(),
@@ -287,22 +767,17 @@ pub async fn sync_tunnel_routes(
         hostnames.len()
     );
 
-    let existing = client.list_dns_records().await.unwrap_or_default();
-
     let mut created = 0u32;
     let mut skipped = 0u32;
 
     for hostname in &hostnames {
-        let exists = existing
-            .iter()
-            .any(|r| r.name == *hostname && r.record_type == "CNAME");
-
-        if exists {
-            println!(
-                "  ⏭️ {} {}",
-                hostname,
+        if is_skipped(hostname) {
+            let reason = if has_cname(hostname) {
                 t!(l, "(already exists)", "(已存在)")
-            );
+            } else {
+                t!(l, "(covered by wildcard)", "(由通配符记录覆盖)")
+            };
+            println!("  ⏭️ {} {}", hostname, reason);
             skipped += 1;
             continue;
         }
@@ -313,6 +788,7 @@ pub async fn sync_tunnel_routes(
             content: tunnel_cname.clone(),
             proxied: true,
             ttl: None,
+            priority: None,
         };
 
         match client.create_dns_record(&record).await {
@@ -336,6 +812,504 @@ pub async fn sync_tunnel_routes(
     Ok(())
 }
 
+/// Whether the configured zone has a `*.<zone>` CNAME or A record, which
+/// answers for every hostname directly below the zone.
+pub async fn check_wildcard_dns(client: &CloudflareClient) -> Result<bool> {
+    let zone = client.get_zone().await?;
+    let records = client.list_dns_records().await?;
+    Ok(has_wildcard_record(&records, &zone.name))
+}
+
+/// Whether `records` hold a wildcard CNAME or A record for `*.<parent>`.
+fn has_wildcard_record(records: &[DnsRecord], parent: &str) -> bool {
+    let wildcard = format!("*.{}", parent.trim_end_matches('.'));
+    records.iter().any(|r| {
+        matches!(r.record_type.as_str(), "CNAME" | "A") && hostnames_match(&r.name, &wildcard)
+    })
+}
+
+/// Whether a wildcard record in `records` answers for `hostname`. A
+/// `*.<parent>` record only answers for names exactly one label below
+/// `<parent>`, never for `<parent>` itself.
+fn wildcard_covers(records: &[DnsRecord], hostname: &str) -> bool {
+    hostname
+        .trim_end_matches('.')
+        .split_once('.')
+        .is_some_and(|(_, parent)| has_wildcard_record(records, parent))
+}
+
+// ---------------------------------------------------------------------------
+// Email authentication records (DMARC / DKIM)
+// ---------------------------------------------------------------------------
+
+const DMARC_PLACEHOLDER: &str = "v=DMARC1; p=none";
+const DKIM_PLACEHOLDER: &str = "v=DKIM1; k=rsa; p=";
+
+/// Return the `(name, placeholder content)` of email TXT records missing from `records`.
+fn missing_email_records(records: &[DnsRecord], zone_name: &str) -> Vec<(String, &'static str)> {
+    let wanted = [
+        (format!("_dmarc.{zone_name}"), DMARC_PLACEHOLDER),
+        (format!("default._domainkey.{zone_name}"), DKIM_PLACEHOLDER),
+    ];
+    wanted
+        .into_iter()
+        .filter(|(name, _)| {
+            !records
+                .iter()
+                .any(|r| r.record_type == "TXT" && r.name.eq_ignore_ascii_case(name))
+        })
+        .collect()
+}
+
+/// Offer to create placeholder DMARC and DKIM TXT records when they are missing.
+pub async fn suggest_email_records(client: &CloudflareClient, zone_name: &str) -> Result<()> {
+    let l = lang();
+    let records = client.list_dns_records().await?;
+    let missing = missing_email_records(&records, zone_name);
+
+    if missing.is_empty() {
+        println!(
+            "{} {}",
+            "✅".green(),
+            t!(
+                l,
+                "DMARC and DKIM records already exist.",
+                "DMARC 与 DKIM 记录已存在。"
+            )
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\n📧 {}",
+        t!(
+            l,
+            "Missing email authentication records:",
+            "缺少邮件认证记录:"
+        )
+        .bold()
+    );
+    for (name, content) in &missing {
+        println!("  • TXT {} → \"{}\"", name, content);
+    }
+    println!(
+        "{}",
+        t!(
+            l,
+            "⚠️  These are placeholders: update the DMARC policy and replace the DKIM key with the one from your mail provider.",
+            "⚠️  以下仅为占位记录：请调整 DMARC 策略，并将 DKIM 公钥替换为邮件服务商提供的值。"
+        )
+        .yellow()
+    );
+
+    let create = prompt::confirm_opt(
+        t!(l, "Create placeholder records?", "是否创建占位记录？"),
+        false,
+    )
+    .unwrap_or(false);
+    if !create {
+        return Ok(());
+    }
+
+    for (name, content) in missing {
+        let record = CreateDnsRecord {
+            record_type: "TXT".to_string(),
+            name: name.clone(),
+            content: content.to_string(),
+            proxied: false,
+            ttl: None,
+            priority: None,
+        };
+        match client.create_dns_record(&record).await {
+            Ok(_) => println!("  {} TXT {}", "✅".green(), name),
+            Err(e) => println!("  {} {} — {}", "❌".red(), name, e),
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Bulk import (dig AXFR output)
+// ---------------------------------------------------------------------------
+
+/// Records parsed from an import file, plus the `(type, name)` of skipped ones.
+#[derive(Debug, Default)]
+struct ParsedImport {
+    records: Vec<CreateDnsRecord>,
+    skipped: Vec<(String, String)>,
+}
+
+/// Split a line into whitespace-separated fields, keeping quoted strings
+/// (with their quotes) together and dropping `;` comments.
+fn tokenize_record_line(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        if in_quotes {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_quotes = false;
+            }
+            continue;
+        }
+        match c {
+            ';' => break,
+            '"' => {
+                in_quotes = true;
+                current.push(c);
+            }
+            '(' | ')' => {}
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Join physical lines into logical records: a record continues while a
+/// quoted string or a `( ... )` group is still open.
+fn logical_record_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+    let mut depth = 0i32;
+    let mut in_quotes = false;
+
+    for line in text.lines() {
+        let mut escaped = false;
+        for c in line.chars() {
+            if in_quotes {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_quotes = false;
+                }
+                continue;
+            }
+            match c {
+                ';' => break,
+                '"' => in_quotes = true,
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        if !pending.is_empty() {
+            pending.push(if in_quotes { '\n' } else { ' ' });
+        }
+        pending.push_str(line);
+        if depth <= 0 && !in_quotes {
+            lines.push(std::mem::take(&mut pending));
+            depth = 0;
+        }
+    }
+    if !pending.trim().is_empty() {
+        lines.push(pending);
+    }
+    lines
+}
+
+/// Resolve an owner name against the zone: `@` is the apex, names ending in
+/// `.` are absolute, anything else is relative to the zone.
+fn absolute_name(name: &str, zone_name: &str) -> String {
+    if name == "@" {
+        zone_name.to_string()
+    } else if let Some(abs) = name.strip_suffix('.') {
+        abs.to_string()
+    } else {
+        format!("{name}.{zone_name}")
+    }
+}
+
+/// Join TXT content edited over several lines into one value.
+fn join_txt_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Concatenate the character-strings of TXT rdata, without quotes.
+fn txt_content(rdata: &[String]) -> String {
+    rdata
+        .iter()
+        .map(|s| {
+            s.strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(s)
+                .replace("\\\"", "\"")
+        })
+        .collect()
+}
+
+/// Parse `dig axfr` output (`name TTL class type rdata` per record).
+/// SOA and NS records are skipped, as are AAAA and unsupported types.
+fn parse_axfr(text: &str, zone_name: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+
+    for line in logical_record_lines(text) {
+        let tokens = tokenize_record_line(&line);
+        if tokens.len() < 5 {
+            continue;
+        }
+        let Ok(ttl) = tokens[1].parse::<u32>() else {
+            continue;
+        };
+        let name = absolute_name(&tokens[0], zone_name);
+        let record_type = tokens[3].to_uppercase();
+        let rdata = &tokens[4..];
+
+        let (content, priority) = match record_type.as_str() {
+            "A" => (rdata[0].clone(), None),
+            "CNAME" => (rdata[0].trim_end_matches('.').to_string(), None),
+            "MX" if rdata.len() >= 2 => (
+                rdata[1].trim_end_matches('.').to_string(),
+                rdata[0].parse::<u16>().ok(),
+            ),
+            "TXT" => (txt_content(rdata), None),
+            _ => {
+                parsed.skipped.push((record_type, name));
+                continue;
+            }
+        };
+
+        parsed.records.push(CreateDnsRecord {
+            record_type,
+            name,
+            content,
+            proxied: false,
+            ttl: Some(ttl.clamp(60, 86400)),
+            priority,
+        });
+    }
+    parsed
+}
+
+/// Create `records`, skipping any that already exist with the same name, type
+/// and content, then print a summary.
+async fn import_records(client: &CloudflareClient, records: Vec<CreateDnsRecord>) -> Result<()> {
+    let l = lang();
+    let existing = client.list_dns_records().await?;
+
+    let mut created = 0u32;
+    let mut skipped = 0u32;
+    let mut failed = 0u32;
+    let mut seen = std::collections::HashSet::new();
+
+    for record in &records {
+        let key = (
+            record.name.to_lowercase(),
+            record.record_type.clone(),
+            record.content.clone(),
+        );
+        let exists = existing.iter().any(|r| {
+            r.name.eq_ignore_ascii_case(&record.name)
+                && r.record_type == record.record_type
+                && r.content == record.content
+        });
+        if exists || !seen.insert(key) {
+            println!(
+                "  ⏭️ {} {} {}",
+                record.record_type,
+                record.name,
+                t!(l, "(already exists)", "(已存在)")
+            );
+            skipped += 1;
+            continue;
+        }
+
+        match client.create_dns_record(record).await {
+            Ok(_) => {
+                println!(
+                    "  {} {} {} → {}",
+                    "✅".green(),
+                    record.record_type,
+                    record.name,
+                    record.content
+                );
+                created += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} {} — {}",
+                    "❌".red(),
+                    record.record_type,
+                    record.name,
+                    e
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "\n📊 {} {}, {} {}, {} {}",
+        created,
+        t!(l, "created", "已创建"),
+        skipped,
+        t!(l, "skipped", "已跳过"),
+        failed,
+        t!(l, "failed", "失败")
+    );
+    Ok(())
+}
+
+/// Name of `client`'s zone. The name saved with the config is used when it
+/// belongs to that zone; after a `--zone-id` override it is looked up.
+pub async fn zone_name(client: &CloudflareClient) -> Result<String> {
+    let saved = config::load_effective_api_config()?
+        .filter(|c| c.zone_id.is_some() && c.zone_id == client.zone_id)
+        .and_then(|c| c.zone_name);
+    match saved {
+        Some(name) => Ok(name),
+        None => Ok(client.get_zone().await?.name),
+    }
+}
+
+/// Import records from `dig axfr` output into the configured zone.
+pub async fn import_dig_output(client: &CloudflareClient, path: &Path) -> Result<()> {
+    let l = lang();
+
+    let zone_name = zone_name(client).await?;
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    let parsed = parse_axfr(&text, &zone_name);
+
+    let ipv6 = parsed.skipped.iter().filter(|(t, _)| t == "AAAA").count();
+    if ipv6 > 0 {
+        println!(
+            "{}",
+            format!(
+                "⚠️  {} {ipv6} {}",
+                t!(l, "Skipping", "跳过"),
+                t!(l, "AAAA (IPv6) records.", "条 AAAA (IPv6) 记录。")
+            )
+            .yellow()
+        );
+    }
+    let unsupported: Vec<String> = parsed
+        .skipped
+        .iter()
+        .filter(|(t, _)| !matches!(t.as_str(), "SOA" | "NS" | "AAAA"))
+        .map(|(t, name)| format!("{t} {name}"))
+        .collect();
+    if !unsupported.is_empty() {
+        println!(
+            "{} {}",
+            t!(
+                l,
+                "⚠️  Unsupported record types skipped:",
+                "⚠️  已跳过不支持的记录类型:"
+            )
+            .yellow(),
+            unsupported.join(", ")
+        );
+    }
+
+    if parsed.records.is_empty() {
+        println!("{}", t!(l, "No records to import.", "没有可导入的记录。"));
+        return Ok(());
+    }
+
+    println!(
+        "{} {} {} {}",
+        "📥".cyan(),
+        t!(l, "Importing", "正在导入"),
+        parsed.records.len(),
+        t!(l, "records...", "条记录...")
+    );
+    import_records(client, parsed.records).await
+}
+
+// ---------------------------------------------------------------------------
+// Sync from a mappings file (`tunnel show --format json`)
+// ---------------------------------------------------------------------------
+
+/// Hostnames of the ingress rules in a `tunnel show --format json` file,
+/// in file order. The catch-all rule has no hostname and is skipped.
+fn read_mapping_hostnames(path: &Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let rules: Vec<crate::client::IngressRule> = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a JSON array of mappings", path.display()))?;
+    Ok(rules
+        .into_iter()
+        .filter_map(|r| r.hostname)
+        .filter(|h| !h.trim().is_empty())
+        .collect())
+}
+
+/// Create a tunnel CNAME for every hostname in a mappings file written by
+/// `tunnel show --format json`. Existing records are left alone and a failed
+/// hostname does not stop the rest.
+pub async fn create_records_from_mappings_json(
+    client: &CloudflareClient,
+    path: &Path,
+    tunnel_id: Option<String>,
+) -> Result<()> {
+    let l = lang();
+
+    let hostnames = read_mapping_hostnames(path)?;
+    if hostnames.is_empty() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "No hostnames in the mappings file.",
+                "映射文件中没有域名。"
+            )
+        );
+        return Ok(());
+    }
+
+    let tunnel_id = match tunnel_id {
+        Some(id) => id,
+        None => match tunnel::select_tunnel(client).await? {
+            Some(t) => t.id,
+            None => return Ok(()),
+        },
+    };
+
+    let zones = visible_zones(client).await;
+    let total = hostnames.len();
+    let mut failed = 0;
+    for (i, hostname) in hostnames.iter().enumerate() {
+        println!("[{}/{total}] {hostname}", i + 1);
+        if let Err(e) = ensure_dns_in_zones(client, &tunnel_id, hostname, &zones).await {
+            failed += 1;
+            println!("  {} {hostname}: {e}", "❌".red());
+        }
+    }
+
+    println!(
+        "\n{} {}/{total}",
+        t!(l, "DNS records in place:", "DNS 记录就绪:"),
+        total - failed
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} {}", t!(l, "hostname(s) failed", "个域名失败"));
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Zone settings (Always Use HTTPS, etc.)
 // ---------------------------------------------------------------------------
@@ -348,7 +1322,12 @@ pub async fn zone_settings_menu(client: &CloudflareClient) -> Result<()> {
     let setting = match client.get_zone_setting("always_use_https").await {
         Ok(s) => s,
         Err(e) => {
-            println!("{} {}: {}", "❌".red(), t!(l, "Failed to fetch zone settings", "获取域名设置失败"), e);
+            println!(
+                "{} {}: {}",
+                "❌".red(),
+                t!(l, "Failed to fetch zone settings", "获取域名设置失败"),
+                e
+            );
             return Ok(());
         }
     };
@@ -360,10 +1339,7 @@ pub async fn zone_settings_menu(client: &CloudflareClient) -> Result<()> {
         t!(l, "OFF", "已关闭").red().to_string()
     };
 
-    println!(
-        "\n{}",
-        t!(l, "🔒 Zone Settings", "🔒 域名设置").bold()
-    );
+    println!("\n{}", t!(l, "🔒 Zone Settings", "🔒 域名设置").bold());
     println!(
         "  ├─ {}: {}",
         t!(l, "Always Use HTTPS", "强制 HTTPS"),
@@ -374,13 +1350,14 @@ pub async fn zone_settings_menu(client: &CloudflareClient) -> Result<()> {
     let toggle_label = if current_on {
         t!(l, "Turn OFF (allow HTTP)", "关闭强制 HTTPS (允许 HTTP)")
     } else {
-        t!(l, "Turn ON (redirect HTTP → HTTPS)", "开启强制 HTTPS (HTTP 自动跳转 HTTPS)")
+        t!(
+            l,
+            "Turn ON (redirect HTTP → HTTPS)",
+            "开启强制 HTTPS (HTTP 自动跳转 HTTPS)"
+        )
     };
 
-    let options = vec![
-        toggle_label,
-        t!(l, "◀️  Back", "◀️  返回"),
-    ];
+    let options = vec![toggle_label, t!(l, "◀️  Back", "◀️  返回")];
 
     let sel = prompt::select_opt(t!(l, "Zone Settings", "域名设置"), &options, None);
 
@@ -408,9 +1385,443 @@ pub async fn zone_settings_menu(client: &CloudflareClient) -> Result<()> {
             );
         }
         Err(e) => {
-            println!("{} {}: {}", "❌".red(), t!(l, "Failed to update setting", "更新设置失败"), e);
+            println!(
+                "{} {}: {}",
+                "❌".red(),
+                t!(l, "Failed to update setting", "更新设置失败"),
+                e
+            );
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::MockApi;
+
+    #[test]
+    fn mappings_file_hostnames_skip_catch_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mappings.json");
+        std::fs::write(
+            &path,
+            r#"[
+  { "hostname": "app.example.com", "service": "http://localhost:3000" },
+  { "hostname": "api.example.com", "service": "http://localhost:8080" },
+  { "hostname": "ssh.example.com", "service": "ssh://localhost:22" },
+  { "service": "http_status:404" }
+]"#,
+        )
+        .unwrap();
+
+        let hostnames = read_mapping_hostnames(&path).unwrap();
+        assert_eq!(
+            hostnames,
+            ["app.example.com", "api.example.com", "ssh.example.com"]
+        );
+
+        std::fs::write(&path, r#"{"hostname": "app.example.com"}"#).unwrap();
+        assert!(read_mapping_hostnames(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn mappings_file_lists_zones_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mappings.json");
+        std::fs::write(
+            &path,
+            r#"[
+  { "hostname": "app.example.com", "service": "http://localhost:3000" },
+  { "hostname": "api.example.com", "service": "http://localhost:8080" }
+]"#,
+        )
+        .unwrap();
+        let api = MockApi::routes(vec![
+            (
+                "GET",
+                "/zones?",
+                r#"{"success":true,"result":[{"id":"zone-1","name":"example.com"}]}"#,
+            ),
+            (
+                "GET",
+                "/zones/zone-1/dns_records",
+                r#"{"success":true,"result":[]}"#,
+            ),
+            (
+                "POST",
+                "/zones/zone-1/dns_records",
+                r#"{"success":true,"result":{"id":"rec","name":"app.example.com","type":"CNAME","content":"tid.cfargotunnel.com"}}"#,
+            ),
+        ])
+        .await;
+        let cfg = crate::config::ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::from_config(&cfg)
+            .unwrap()
+            .with_base_url(&api.base);
+
+        create_records_from_mappings_json(&client, &path, Some("tid".to_string()))
+            .await
+            .unwrap();
+
+        let requests = api.requests();
+        let zone_lists = requests.iter().filter(|r| r.starts_with("GET /zones?"));
+        assert_eq!(zone_lists.count(), 1);
+        let created = requests.iter().filter(|r| r.starts_with("POST "));
+        assert_eq!(created.count(), 2);
+    }
+
+    fn tunnel_with_hosts(id: &str, name: &str, hosts: &[&str]) -> (Tunnel, TunnelConfiguration) {
+        let mut ingress: Vec<crate::client::IngressRule> = hosts
+            .iter()
+            .map(|h| crate::client::IngressRule {
+                hostname: Some(h.to_string()),
+                service: "http://localhost:8080".to_string(),
+                origin_request: None,
+            })
+            .collect();
+        ingress.push(crate::client::IngressRule {
+            hostname: None,
+            service: "http_status:404".to_string(),
+            origin_request: None,
+        });
+        (
+            Tunnel {
+                id: id.to_string(),
+                name: name.to_string(),
+                created_at: None,
+                status: None,
+                deleted_at: None,
+            },
+            TunnelConfiguration {
+                config: crate::client::TunnelConfigInner { ingress },
+            },
+        )
+    }
+
+    #[test]
+    fn tunnel_references_match_hostnames() {
+        let configs = vec![
+            tunnel_with_hosts("t1", "home", &["app.example.com", "wiki.example.com"]),
+            tunnel_with_hosts("t2", "office", &["App.Example.com."]),
+            tunnel_with_hosts("t3", "lab", &["api.example.com"]),
+        ];
+        let refs = find_tunnel_references("app.example.com", &configs);
+        assert_eq!(
+            refs,
+            vec![
+                TunnelReference {
+                    tunnel_id: "t1".to_string(),
+                    tunnel_name: "home".to_string(),
+                    hostname: "app.example.com".to_string(),
+                },
+                TunnelReference {
+                    tunnel_id: "t2".to_string(),
+                    tunnel_name: "office".to_string(),
+                    hostname: "App.Example.com.".to_string(),
+                },
+            ]
+        );
+        assert!(find_tunnel_references("example.com", &configs).is_empty());
+        assert!(find_tunnel_references("app.example.com", &[]).is_empty());
+    }
+
+    #[test]
+    fn txt_lines_are_joined() {
+        assert_eq!(
+            join_txt_lines("v=spf1 include:_spf.google.com\n  include:mailgun.org\n\n-all\n"),
+            "v=spf1 include:_spf.google.com include:mailgun.org -all"
+        );
+    }
+
+    const AXFR_FIXTURE: &str = r#"
+; <<>> DiG 9.18.24 <<>> axfr example.com @ns1.example.com
+;; global options: +cmd
+example.com.		3600	IN	SOA	ns1.example.com. hostmaster.example.com. 2024010101 7200 3600 1209600 3600
+example.com.		3600	IN	NS	ns1.example.com.
+example.com.		3600	IN	NS	ns2.example.com.
+example.com.		300	IN	A	203.0.113.10
+example.com.		300	IN	AAAA	2001:db8::10
+example.com.		3600	IN	MX	10 mail.example.com.
+example.com.		3600	IN	TXT	"v=spf1 include:_spf.example.com ~all"
+default._domainkey.example.com.	3600 IN	TXT	"v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAq" "8sBxLmZl0kXz2mW3Zn7y"
+long.example.com.	3600	IN	TXT	( "first part "
+				"second part" )
+www.example.com.	300	IN	CNAME	example.com.
+mail.example.com.	300	IN	A	203.0.113.20
+_sip._tcp.example.com.	3600	IN	SRV	10 60 5060 sip.example.com.
+example.com.		3600	IN	SOA	ns1.example.com. hostmaster.example.com. 2024010101 7200 3600 1209600 3600
+;; Query time: 12 msec
+;; XFR size: 14 records (messages 1, bytes 512)
+"#;
+
+    #[test]
+    fn parse_axfr_fixture() {
+        let parsed = parse_axfr(AXFR_FIXTURE, "example.com");
+        let summary: Vec<(&str, &str, &str)> = parsed
+            .records
+            .iter()
+            .map(|r| (r.record_type.as_str(), r.name.as_str(), r.content.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("A", "example.com", "203.0.113.10"),
+                ("MX", "example.com", "mail.example.com"),
+                ("TXT", "example.com", "v=spf1 include:_spf.example.com ~all"),
+                (
+                    "TXT",
+                    "default._domainkey.example.com",
+                    "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAq8sBxLmZl0kXz2mW3Zn7y"
+                ),
+                ("TXT", "long.example.com", "first part second part"),
+                ("CNAME", "www.example.com", "example.com"),
+                ("A", "mail.example.com", "203.0.113.20"),
+            ]
+        );
+        assert_eq!(parsed.records[1].priority, Some(10));
+        assert_eq!(parsed.records[0].ttl, Some(300));
+
+        let skipped: Vec<&str> = parsed.skipped.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(skipped, vec!["SOA", "NS", "NS", "AAAA", "SRV", "SOA"]);
+    }
+
+    #[test]
+    fn axfr_names_resolve_against_zone() {
+        let parsed = parse_axfr(
+            "@ 300 IN A 192.0.2.1\napi 300 IN CNAME example.com.\n",
+            "example.com",
+        );
+        assert_eq!(parsed.records[0].name, "example.com");
+        assert_eq!(parsed.records[1].name, "api.example.com");
+    }
+
+    fn zone(name: &str) -> Zone {
+        Zone {
+            id: format!("id-{name}"),
+            name: name.to_string(),
+            status: None,
+            account: None,
+        }
+    }
+
+    #[test]
+    fn matching_zone_prefers_longest_suffix() {
+        let zones = vec![
+            zone("example.com"),
+            zone("dev.example.com"),
+            zone("other.com"),
+        ];
+        let found = |h: &str| find_matching_zone(h, &zones).map(|z| z.name.as_str());
+
+        assert_eq!(found("app.example.com"), Some("example.com"));
+        assert_eq!(found("api.dev.example.com"), Some("dev.example.com"));
+        assert_eq!(found("App.Other.com."), Some("other.com"));
+    }
+
+    #[test]
+    fn matching_zone_root_and_misses() {
+        let zones = vec![zone("example.com"), zone("dev.example.com")];
+        let found = |h: &str| find_matching_zone(h, &zones).map(|z| z.name.as_str());
+
+        assert_eq!(found("example.com"), Some("example.com"));
+        assert_eq!(found("dev.example.com"), Some("dev.example.com"));
+        assert_eq!(found("badexample.com"), None);
+        assert_eq!(found("example.org"), None);
+        assert_eq!(found("app"), None);
+        assert!(find_matching_zone("app.example.com", &[]).is_none());
+    }
+
+    fn txt(name: &str) -> DnsRecord {
+        DnsRecord {
+            id: name.to_string(),
+            name: name.to_string(),
+            record_type: "TXT".to_string(),
+            content: "v=...".to_string(),
+            proxied: None,
+            ttl: None,
+            priority: None,
+        }
+    }
+
+    fn record(name: &str, record_type: &str) -> DnsRecord {
+        DnsRecord {
+            record_type: record_type.to_string(),
+            ..txt(name)
+        }
+    }
+
+    #[test]
+    fn mx_priority_validation() {
+        assert!(validate_mx_priority("10").is_ok());
+        assert!(validate_mx_priority(" 0 ").is_ok());
+        assert!(validate_mx_priority("65536").is_err());
+        assert!(validate_mx_priority("-1").is_err());
+        assert!(validate_mx_priority("").is_err());
+
+        let mx: DnsRecord = serde_json::from_str(
+            r#"{"id":"r1","name":"example.com","type":"MX","content":"mx.example.com","priority":10}"#,
+        )
+        .unwrap();
+        assert_eq!(mx.priority, Some(10));
+        assert_eq!(txt("example.com").priority, None);
+    }
+
+    #[test]
+    fn record_counts_by_type() {
+        assert!(count_by_type(&[]).is_empty());
+
+        let single = vec![record("a.example.com", "A"), record("b.example.com", "A")];
+        assert_eq!(
+            count_by_type(&single),
+            HashMap::from([("A".to_string(), 2)])
+        );
+
+        let mixed = vec![
+            record("a.example.com", "A"),
+            record("app.example.com", "CNAME"),
+            record("api.example.com", "CNAME"),
+            record("example.com", "MX"),
+            record("example.com", "TXT"),
+            record("docs.example.com", "CNAME"),
+            record("b.example.com", "A"),
+        ];
+        let counts = count_by_type(&mixed);
+        assert_eq!(counts.len(), 4);
+        assert_eq!(
+            sorted_type_counts(&counts),
+            vec![("CNAME", 3), ("A", 2), ("MX", 1), ("TXT", 1)]
+        );
+    }
+
+    #[test]
+    fn wildcard_detection() {
+        let wildcard = |name: &str, record_type: &str, content: &str| DnsRecord {
+            content: content.to_string(),
+            ..record(name, record_type)
+        };
+        let records = vec![
+            record("example.com", "A"),
+            wildcard("*.Example.com", "CNAME", "tid-1.cfargotunnel.com."),
+        ];
+        assert!(has_wildcard_record(&records, "example.com"));
+        assert!(has_wildcard_record(&records, "example.com."));
+        assert!(wildcard_covers(&records, "app.example.com"));
+
+        // The apex and deeper names are not matched by `*.example.com`.
+        assert!(!wildcard_covers(&records, "example.com"));
+        assert!(!wildcard_covers(&records, "a.dev.example.com"));
+        assert!(!has_wildcard_record(&records, "dev.example.com"));
+
+        // An A record counts too, whatever it points at; other types do not.
+        let a_record = [wildcard("*.example.com", "A", "192.0.2.1")];
+        assert!(has_wildcard_record(&a_record, "example.com"));
+        assert!(wildcard_covers(&a_record, "app.example.com"));
+        let txt_record = [wildcard("*.example.com", "TXT", "v=spf1 -all")];
+        assert!(!has_wildcard_record(&txt_record, "example.com"));
+        assert!(!has_wildcard_record(&[], "example.com"));
+    }
+
+    #[tokio::test]
+    async fn wildcard_check_uses_the_zone_name() {
+        let api = MockApi::routes(vec![
+            (
+                "GET",
+                "/zones/zone-1/dns_records",
+                r#"{"success":true,"result":[{"id":"r1","name":"*.example.com","type":"A","content":"192.0.2.1"}]}"#,
+            ),
+            (
+                "GET",
+                "/zones/zone-1",
+                r#"{"success":true,"result":{"id":"zone-1","name":"example.com"}}"#,
+            ),
+        ])
+        .await;
+        let cfg = crate::config::ApiConfig {
+            api_token: Some("test-token".to_string()),
+            account_id: Some("acc123".to_string()),
+            zone_id: Some("zone-1".to_string()),
+            ..Default::default()
+        };
+        let client = CloudflareClient::from_config(&cfg)
+            .unwrap()
+            .with_base_url(&api.base);
+        assert!(check_wildcard_dns(&client).await.unwrap());
+    }
+
+    #[test]
+    fn missing_email_records_when_none_exist() {
+        let missing = missing_email_records(&[], "example.com");
+        let names: Vec<&str> = missing.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["_dmarc.example.com", "default._domainkey.example.com"]
+        );
+    }
+
+    #[test]
+    fn missing_email_records_skips_existing() {
+        let records = vec![txt("_DMARC.example.com")];
+        let missing = missing_email_records(&records, "example.com");
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].0, "default._domainkey.example.com");
+
+        let records = vec![
+            txt("_dmarc.example.com"),
+            txt("default._domainkey.example.com"),
+        ];
+        assert!(missing_email_records(&records, "example.com").is_empty());
+    }
+
+    #[test]
+    fn missing_email_records_ignores_non_txt() {
+        let mut cname = txt("_dmarc.example.com");
+        cname.record_type = "CNAME".to_string();
+        assert_eq!(missing_email_records(&[cname], "example.com").len(), 2);
+    }
+
+    #[test]
+    fn proxied_default_per_record_type() {
+        for t in ["A", "AAAA", "CNAME"] {
+            assert!(default_proxied(t), "{t} should default to proxied");
+        }
+        for t in ["MX", "TXT", "SRV", "NS", "CAA"] {
+            assert!(!default_proxied(t), "{t} should default to DNS only");
+        }
+    }
+
+    #[test]
+    fn non_interactive_add_uses_proxy_default() {
+        for t in ["A", "AAAA", "CNAME"] {
+            assert!(choose_proxied(t, false), "{t} should be proxied");
+        }
+        for t in ["MX", "TXT"] {
+            assert!(!choose_proxied(t, false), "{t} should be DNS only");
+        }
+    }
+
+    #[test]
+    fn format_ttl_presets() {
+        assert_eq!(format_ttl(1), "auto");
+        assert_eq!(format_ttl(120), "2m");
+        assert_eq!(format_ttl(300), "5m");
+        assert_eq!(format_ttl(600), "10m");
+        assert_eq!(format_ttl(1800), "30m");
+        assert_eq!(format_ttl(3600), "1h");
+        assert_eq!(format_ttl(86400), "1d");
+    }
+
+    #[test]
+    fn format_ttl_edge_cases() {
+        assert_eq!(format_ttl(0), "custom 0s");
+        assert_eq!(format_ttl(90), "custom 90s");
+        assert_eq!(format_ttl(7200), "2h");
+        assert_eq!(format_ttl(172800), "2d");
+    }
+}
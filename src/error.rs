@@ -18,6 +18,16 @@ pub enum CftError {
 
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+
+    #[error("Cloudflare API response too large ({bytes} bytes)")]
+    ResponseTooLarge { bytes: usize },
+
+    /// A valid token that lists nothing, with a hint about the missing permission.
+    #[error("No {resource} returned. {hint}")]
+    EmptyResult {
+        resource: &'static str,
+        hint: &'static str,
+    },
 }
 
 /// Convenience alias used throughout the application.
@@ -70,4 +80,21 @@ mod tests {
             "Invalid input: bad value"
         );
     }
+
+    #[test]
+    fn empty_result_message() {
+        let e = CftError::EmptyResult {
+            resource: "zones",
+            hint: "Check permissions.",
+        };
+        assert_eq!(e.to_string(), "No zones returned. Check permissions.");
+    }
+
+    #[test]
+    fn response_too_large_message() {
+        assert_eq!(
+            CftError::ResponseTooLarge { bytes: 12_000_000 }.to_string(),
+            "Cloudflare API response too large (12000000 bytes)"
+        );
+    }
 }
@@ -0,0 +1,209 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use colored::Colorize;
+use comfy_table::{presets::UTF8_FULL, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::Result;
+use crate::i18n::lang;
+use crate::t;
+
+// ---------------------------------------------------------------------------
+// Mapping event log (<config dir>/events.jsonl)
+// ---------------------------------------------------------------------------
+
+/// Number of events shown by `tunnel events` when no limit is given.
+pub const DEFAULT_EVENT_LIMIT: u32 = 20;
+
+/// What changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventAction {
+    MappingAdded,
+    MappingRemoved,
+}
+
+impl EventAction {
+    fn label(self) -> &'static str {
+        match self {
+            Self::MappingAdded => "mapping_added",
+            Self::MappingRemoved => "mapping_removed",
+        }
+    }
+}
+
+/// One line of the event log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub timestamp: String,
+    pub action: EventAction,
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+impl Event {
+    fn detail(&self, key: &str) -> &str {
+        self.details
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("-")
+    }
+}
+
+/// Return the path to `<config dir>/events.jsonl`.
+pub fn events_path() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("events.jsonl"))
+}
+
+/// Append `event` to `path` as a single JSON line.
+fn append_to(path: &Path, event: &Event) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let line = serde_json::to_string(event)?;
+    writeln!(file, "{line}").with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// The last `limit` events in `path`, oldest first. Malformed lines are skipped.
+fn read_last(path: &Path, limit: usize) -> Result<Vec<Event>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let events: Vec<Event> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = events.len().saturating_sub(limit);
+    Ok(events.into_iter().skip(skip).collect())
+}
+
+/// Record an event in the log.
+pub fn log(action: EventAction, details: serde_json::Value) -> Result<()> {
+    let event = Event {
+        timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        action,
+        details,
+    };
+    append_to(&events_path()?, &event)
+}
+
+/// Show the most recent events.
+pub fn list(limit: Option<u32>) -> Result<()> {
+    let l = lang();
+    let limit = limit.unwrap_or(DEFAULT_EVENT_LIMIT) as usize;
+    let events = read_last(&events_path()?, limit)?;
+
+    if events.is_empty() {
+        println!("{}", t!(l, "No events recorded.", "暂无事件记录。"));
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        t!(l, "Time", "时间"),
+        t!(l, "Action", "操作"),
+        t!(l, "Hostname", "域名"),
+        t!(l, "Service", "服务"),
+    ]);
+    for event in &events {
+        table.add_row(vec![
+            event.timestamp.as_str(),
+            event.action.label(),
+            event.detail("hostname"),
+            event.detail("service"),
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
+
+/// Delete the event log.
+pub fn clear() -> Result<()> {
+    let l = lang();
+    let path = events_path()?;
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    println!(
+        "{} {}",
+        "✅".green(),
+        t!(l, "Event log cleared.", "事件日志已清除。")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An events file path in a fresh directory, removed with the guard.
+    fn scratch_file() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        (dir, path)
+    }
+
+    fn event(hostname: &str) -> Event {
+        Event {
+            timestamp: "2026-03-01T10:00:00Z".to_string(),
+            action: EventAction::MappingAdded,
+            details: serde_json::json!({ "hostname": hostname, "service": "http://localhost:3000" }),
+        }
+    }
+
+    #[test]
+    fn append_keeps_earlier_lines() {
+        let (_dir, path) = scratch_file();
+        append_to(&path, &event("a.example.com")).unwrap();
+        append_to(&path, &event("b.example.com")).unwrap();
+        append_to(&path, &event("c.example.com")).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(content.ends_with('\n'));
+
+        let last = read_last(&path, 2).unwrap();
+        assert_eq!(last.len(), 2);
+        assert_eq!(last[0].detail("hostname"), "b.example.com");
+        assert_eq!(last[1].detail("hostname"), "c.example.com");
+        assert_eq!(read_last(&path, 10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn json_line_format() {
+        let line = r#"{"timestamp":"2026-03-01T10:00:00Z","action":"mapping_removed","details":{"hostname":"app.example.com","tunnel_id":"tid"}}"#;
+        let parsed: Event = serde_json::from_str(line).unwrap();
+        assert_eq!(parsed.action, EventAction::MappingRemoved);
+        assert_eq!(parsed.detail("hostname"), "app.example.com");
+        assert_eq!(parsed.detail("service"), "-");
+        assert_eq!(
+            serde_json::to_value(event("x")).unwrap()["action"],
+            "mapping_added"
+        );
+
+        let (_dir, path) = scratch_file();
+        append_to(&path, &event("ok.example.com")).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        assert_eq!(read_last(&path, 10).unwrap().len(), 1);
+        let (_missing_dir, missing) = scratch_file();
+        assert!(read_last(&missing, 10).unwrap().is_empty());
+    }
+}
@@ -9,6 +9,10 @@ pub enum Lang {
 
 static CURRENT_LANG: AtomicU8 = AtomicU8::new(0); // default En
 
+/// Held by tests that switch the global language, so they do not race.
+#[cfg(test)]
+pub static LANG_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 /// Initialise the global language.
 /// Priority: CLI flag > `CFT_LANG` env > config file > system locale > default `En`.
 pub fn init_lang(cli_flag: Option<&str>, config_lang: Option<&str>) {
@@ -59,10 +63,36 @@ fn resolve_lang(cli_flag: Option<&str>, config_lang: Option<&str>) -> Lang {
         }
     }
 
-    // 5. Default
+    // 5. Windows UI language, else the default
+    system_ui_lang()
+}
+
+/// Windows does not set `LANG`; ask for the user's UI language instead.
+#[cfg(target_os = "windows")]
+fn system_ui_lang() -> Lang {
+    // SAFETY: takes no arguments and only reads the user's settings.
+    let lcid = unsafe { windows_sys::Win32::Globalization::GetUserDefaultUILanguage() };
+    lang_from_lcid(lcid)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_ui_lang() -> Lang {
     Lang::En
 }
 
+/// Map a Windows language ID to a supported language. Any Chinese variant
+/// (primary language 0x04, e.g. 0x0404 zh-TW, 0x0804 zh-CN) selects `Zh`;
+/// everything else, including Japanese (0x0411), falls back to `En`.
+#[cfg(any(target_os = "windows", test))]
+fn lang_from_lcid(lcid: u16) -> Lang {
+    const LANG_CHINESE: u16 = 0x04;
+    if lcid & 0x3ff == LANG_CHINESE {
+        Lang::Zh
+    } else {
+        Lang::En
+    }
+}
+
 pub fn parse_lang(s: &str) -> Option<Lang> {
     match s.to_lowercase().as_str() {
         "en" | "english" => Some(Lang::En),
@@ -102,6 +132,16 @@ mod tests {
         assert_eq!(parse_lang("fr"), None);
     }
 
+    #[test]
+    fn windows_lcid_mapping() {
+        assert_eq!(lang_from_lcid(0x0804), Lang::Zh);
+        assert_eq!(lang_from_lcid(0x0404), Lang::Zh);
+        assert_eq!(lang_from_lcid(0x0c04), Lang::Zh);
+        assert_eq!(lang_from_lcid(0x0409), Lang::En);
+        assert_eq!(lang_from_lcid(0x0411), Lang::En);
+        assert_eq!(lang_from_lcid(0x0407), Lang::En);
+    }
+
     #[test]
     fn t_macro_selects_correctly() {
         assert_eq!(t!(Lang::En, "Hello", "你好"), "Hello");
@@ -110,6 +150,7 @@ mod tests {
 
     #[test]
     fn set_lang_at_runtime() {
+        let _lang = LANG_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         set_lang(Lang::Zh);
         assert_eq!(lang(), Lang::Zh);
         set_lang(Lang::En);
@@ -133,6 +174,7 @@ mod tests {
 
     #[test]
     fn init_lang_cli_flag_overrides_config() {
+        let _lang = LANG_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         init_lang(Some("zh"), Some("en"));
         assert_eq!(lang(), Lang::Zh);
         set_lang(Lang::En); // reset global state
@@ -140,6 +182,7 @@ mod tests {
 
     #[test]
     fn init_lang_falls_back_to_config() {
+        let _lang = LANG_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         init_lang(None, Some("zh"));
         assert_eq!(lang(), Lang::Zh);
         set_lang(Lang::En); // reset global state
@@ -147,6 +190,7 @@ mod tests {
 
     #[test]
     fn init_lang_invalid_flag_uses_config() {
+        let _lang = LANG_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         init_lang(Some("invalid"), Some("zh"));
         assert_eq!(lang(), Lang::Zh);
         set_lang(Lang::En); // reset global state
@@ -5,19 +5,26 @@ mod config;
 mod dashboard;
 mod dns;
 mod error;
+mod event;
 mod i18n;
 mod menu;
+#[cfg(test)]
+mod mock_api;
 mod monitor;
 mod prompt;
 mod scan;
 mod service;
 mod tools;
 mod tunnel;
+mod util;
 
 use clap::Parser;
 use colored::Colorize;
 
-use cli::{AccessAction, AccountAction, Cli, Commands, ConfigAction, DnsAction, ServiceAction};
+use cli::{
+    AccessAction, AccountAction, Cli, Commands, ConfigAction, DnsAction, ScanPortsAction,
+    ServiceAction, TokenAction,
+};
 use error::Result;
 use i18n::lang;
 
@@ -32,6 +39,21 @@ async fn main() {
         .and_then(|c| c.language.clone());
     i18n::init_lang(cli.lang.as_deref(), config_lang.as_deref());
 
+    let overrides = cli.overrides();
+    if overrides.token.is_some() && overrides.account_id.is_none() {
+        let l = lang();
+        eprintln!(
+            "{} {}",
+            "warning:".yellow().bold(),
+            t!(
+                l,
+                "--token given without --account-id; using the saved account ID.",
+                "指定了 --token 但未指定 --account-id，将使用已保存的账户 ID。"
+            )
+        );
+    }
+    config::init_flag_overrides(overrides);
+
     if let Err(e) = run(cli).await {
         eprintln!("{} {:#}", "error:".red().bold(), e);
         std::process::exit(1);
@@ -40,16 +62,32 @@ async fn main() {
 
 async fn run(cli: Cli) -> Result<()> {
     match cli.command {
-        None | Some(Commands::Menu) => menu::interactive_menu().await,
+        None | Some(Commands::Menu) => {
+            let limit = cli
+                .session_timeout
+                .filter(|&secs| secs > 0)
+                .map(std::time::Duration::from_secs);
+            menu::interactive_menu(limit).await
+        }
 
         // Tunnel management
-        Some(Commands::List) => {
+        Some(Commands::List {
+            verbose,
+            sort,
+            inactive_days,
+            count_hostnames,
+        }) => {
             let client = require_client()?;
-            tunnel::list_tunnels(&client).await
+            tunnel::list_tunnels(&client, verbose, sort, inactive_days, count_hostnames).await
         }
-        Some(Commands::Create { name }) => {
+        Some(Commands::Create {
+            name,
+            secret,
+            secret_env,
+        }) => {
+            let secret = tunnel::provided_secret(secret, secret_env)?;
             let client = require_client()?;
-            tunnel::create_tunnel(&client, name).await
+            tunnel::create_tunnel(&client, name, secret).await
         }
         Some(Commands::Delete) => {
             let client = require_client()?;
@@ -59,15 +97,24 @@ async fn run(cli: Cli) -> Result<()> {
             let client = require_client()?;
             tunnel::get_token(&client, id).await
         }
+        Some(Commands::Disconnect { id }) => {
+            let client = require_client()?;
+            tunnel::force_disconnect(&client, id).await
+        }
+        Some(Commands::RotateSecret { id }) => {
+            let client = require_client()?;
+            tunnel::rotate_secret(&client, id).await
+        }
 
         // Mapping management (remotely-managed via API)
         Some(Commands::Map {
             tunnel: tid,
             hostname,
             service,
+            allow_duplicate_service,
         }) => {
             let client = require_client()?;
-            tunnel::add_mapping(&client, tid, hostname, service).await
+            tunnel::add_mapping(&client, tid, hostname, service, allow_duplicate_service).await
         }
         Some(Commands::Unmap {
             tunnel: tid,
@@ -76,24 +123,52 @@ async fn run(cli: Cli) -> Result<()> {
             let client = require_client()?;
             tunnel::remove_mapping(&client, tid, hostname).await
         }
-        Some(Commands::Show { id }) => {
+        Some(Commands::Show { id, format }) => {
+            let client = require_client()?;
+            tunnel::show_mappings(&client, id, format).await
+        }
+        Some(Commands::Diff { id }) => {
             let client = require_client()?;
-            tunnel::show_mappings(&client, id).await
+            tunnel::diff_mappings(&client, id).await
         }
 
         // DNS
         Some(Commands::Dns { action }) => {
             let client = require_client_with_zone()?;
             match action {
-                DnsAction::List => dns::list_records(&client).await,
+                DnsAction::List { record_type } => {
+                    dns::list_records(&client, record_type.as_deref()).await
+                }
                 DnsAction::Add {
                     name,
                     record_type,
                     content,
                     proxied,
-                } => dns::add_record(&client, name, record_type, content, proxied).await,
-                DnsAction::Delete { id } => dns::delete_record(&client, id).await,
-                DnsAction::Sync { tunnel: tid } => dns::sync_tunnel_routes(&client, tid).await,
+                    ttl,
+                    editor,
+                } => {
+                    dns::add_record(&client, name, record_type, content, proxied, ttl, editor).await
+                }
+                DnsAction::Update { id } => dns::update_record(&client, id).await,
+                DnsAction::Delete { id, check_tunnel } => {
+                    dns::delete_record(&client, id, check_tunnel).await
+                }
+                DnsAction::Sync {
+                    tunnel: tid,
+                    suggest_email,
+                    yes,
+                } => {
+                    dns::sync_tunnel_routes(&client, tid, yes).await?;
+                    if !suggest_email {
+                        return Ok(());
+                    }
+                    let zone_name = dns::zone_name(&client).await?;
+                    dns::suggest_email_records(&client, &zone_name).await
+                }
+                DnsAction::ImportAxfr { path } => dns::import_dig_output(&client, &path).await,
+                DnsAction::SyncFromFile { path, tunnel: tid } => {
+                    dns::create_records_from_mappings_json(&client, &path, tid).await
+                }
             }
         }
 
@@ -101,12 +176,25 @@ async fn run(cli: Cli) -> Result<()> {
         Some(Commands::Access { action }) => {
             let client = require_client()?;
             match action {
-                AccessAction::List => access::list_apps(&client).await,
-                AccessAction::Create { name, domain } => {
-                    access::create_app(&client, name, domain).await
-                }
+                AccessAction::List { verbose } => access::list_apps(&client, verbose).await,
+                AccessAction::Detail { id } => access::show_app_detail(&client, id).await,
+                AccessAction::Create {
+                    name,
+                    domain,
+                    idp_type,
+                } => access::create_app(&client, name, domain, idp_type).await,
+                AccessAction::Update { id } => access::update_app(&client, id).await,
                 AccessAction::Delete { id } => access::delete_app(&client, id).await,
                 AccessAction::Policy { app_id } => access::manage_policies(&client, app_id).await,
+                AccessAction::Protect { hostname, tunnel } => {
+                    access::protect_mapping(&client, hostname, tunnel).await
+                }
+                AccessAction::Reorder => access::reorder_apps(&client).await,
+                AccessAction::Logs { limit, since } => {
+                    access::show_audit_logs(&client, limit, since).await
+                }
+                AccessAction::Import { path } => access::import_apps(&client, &path).await,
+                AccessAction::Export { path } => access::export_apps(&client, &path).await,
             }
         }
 
@@ -117,14 +205,34 @@ async fn run(cli: Cli) -> Result<()> {
                 AccountAction::List => menu::list_accounts().await,
                 AccountAction::Set { id } => menu::set_account(id).await,
             },
+            ConfigAction::Token { action } => match action {
+                TokenAction::List => menu::list_tokens(),
+                TokenAction::Add { label, scopes } => menu::add_token(label, scopes),
+                TokenAction::Remove { label } => menu::remove_token(label),
+                TokenAction::Use { label } => menu::use_token(label),
+            },
+            ConfigAction::Rotate => {
+                let l = lang();
+                let token = match prompt::password_opt(t!(l, "New API Token", "新 API Token")) {
+                    Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+                    _ => return Ok(()),
+                };
+                config::rotate_token(&token).await?;
+                println!(
+                    "{} {}",
+                    "✅".green(),
+                    t!(l, "Token rotated successfully", "Token 轮换成功")
+                );
+                Ok(())
+            }
             ConfigAction::Show => {
                 print_api_config();
                 Ok(())
             }
             ConfigAction::Test => {
                 let l = lang();
-                let cfg = match config::load_api_config()? {
-                    Some(c) if c.api_token.is_some() => c,
+                let cfg = match config::load_effective_api_config()? {
+                    Some(c) if c.effective_token().is_some() => c,
                     _ => {
                         println!(
                             "{} {}",
@@ -139,8 +247,7 @@ async fn run(cli: Cli) -> Result<()> {
                     }
                 };
                 let token = cfg
-                    .api_token
-                    .as_deref()
+                    .effective_token()
                     .ok_or_else(|| anyhow::anyhow!("missing api token in config"))?;
                 match client::CloudflareClient::verify_token(token, cfg.account_id.as_deref())
                     .await?
@@ -184,10 +291,24 @@ async fn run(cli: Cli) -> Result<()> {
                 );
                 Ok(())
             }
+            ConfigAction::Export {
+                format,
+                output,
+                include_token,
+            } => tools::export_config(format, output, include_token).await,
+            ConfigAction::Import { path, format } => tools::import_config(path, format).await,
+            ConfigAction::ScanPorts { action } => match action {
+                ScanPortsAction::List => scan::list_custom_ports(),
+                ScanPortsAction::Add { port, description } => {
+                    scan::add_custom_port(port, description)
+                }
+                ScanPortsAction::Remove { port } => scan::remove_custom_port(port),
+            },
             ConfigAction::Lang { code } => {
-                let mut cfg = config::load_api_config()?.unwrap_or_default();
+                let previous = config::load_api_config()?;
+                let mut cfg = previous.clone().unwrap_or_default();
                 cfg.language = Some(code.clone());
-                config::save_api_config(&cfg)?;
+                config::save_api_config_if_changed(&cfg, previous.as_ref())?;
                 let l = lang();
                 println!(
                     "{} {} {}",
@@ -200,19 +321,84 @@ async fn run(cli: Cli) -> Result<()> {
         },
 
         // Smart features
-        Some(Commands::Scan { ports, timeout }) => scan::scan_local_services(ports, timeout).await,
+        Some(Commands::Scan {
+            ports,
+            timeout,
+            concurrency,
+            api,
+            summary,
+            save_baseline,
+            iana_db,
+        }) => {
+            scan::scan_local_services(
+                ports,
+                timeout,
+                concurrency,
+                api,
+                summary,
+                save_baseline,
+                iana_db,
+            )
+            .await
+        }
+        Some(Commands::Suggest { tunnel }) => {
+            let client = require_client_with_zone()?;
+            tools::suggest_config(&client, tunnel).await
+        }
         Some(Commands::Service { action }) => match action {
             ServiceAction::Status => service::status().await,
-            ServiceAction::Install { tunnel } => {
+            ServiceAction::Install {
+                tunnel,
+                config_path,
+            } => {
                 let client = require_client()?;
-                service::install(&client, tunnel).await
+                service::install(&client, tunnel, config_path).await
             }
             ServiceAction::Start => service::start(),
             ServiceAction::Stop => service::stop(),
-            ServiceAction::Restart => service::restart(),
-            ServiceAction::Logs { lines } => service::logs(lines),
+            ServiceAction::Restart { no_wait_metrics } => service::restart(!no_wait_metrics).await,
+            ServiceAction::Upgrade => service::upgrade_cloudflared(),
+            ServiceAction::Logs {
+                lines,
+                filter,
+                structured_log,
+            } => match (structured_log, filter) {
+                (Some(path), _) => service::show_structured_logs(&path, lines),
+                (None, Some(pattern)) => service::logs_filtered(lines, &pattern),
+                (None, None) => service::logs(lines),
+            },
+            ServiceAction::Limits { memory, cpu } => service::configure_limits(memory, cpu),
         },
 
+        Some(Commands::Terraform { output }) => {
+            let client = require_client()?;
+            tools::export_terraform(&client, &output).await
+        }
+
+        // Raw API access
+        Some(Commands::RawApi { method, path, body }) => {
+            let client = require_client()?;
+            tools::raw_api(&client, &method, &path, body).await
+        }
+
+        Some(Commands::Benchmark) => {
+            let client = require_client()?;
+            tools::benchmark(&client).await
+        }
+
+        Some(Commands::Debug {
+            full_config,
+            output,
+        }) => tools::debug_mode(full_config, output),
+
+        Some(Commands::Events { limit, clear }) => {
+            if clear {
+                event::clear()
+            } else {
+                event::list(limit)
+            }
+        }
+
         // Shell completions
         Some(Commands::Completions { shell }) => {
             use clap::CommandFactory;
@@ -223,14 +409,27 @@ async fn run(cli: Cli) -> Result<()> {
             Ok(())
         }
 
+        Some(Commands::ExportMetrics {
+            output,
+            watch,
+            interval,
+        }) => monitor::export_metrics(&output, watch, interval).await,
+
+        Some(Commands::Stats { exit_code }) => {
+            let health = monitor::show_stats().await?;
+            if exit_code {
+                std::process::exit(health.exit_code());
+            }
+            Ok(())
+        }
+
         // TUI Dashboard
         Some(Commands::Dashboard) => dashboard::run_dashboard().await,
     }
 }
 
 fn require_client() -> Result<client::CloudflareClient> {
-    let cfg = config::require_api_config()?;
-    client::CloudflareClient::from_config(&cfg)
+    client::CloudflareClient::from_effective_config()
 }
 
 fn require_client_with_zone() -> Result<client::CloudflareClient> {
@@ -247,22 +446,33 @@ fn print_api_config() {
                 t!(l, "Current API Configuration:", "当前 API 配置:").bold()
             );
             println!("├─ API Token: {}", cfg.masked_token());
+            menu::print_token_type_warning(&cfg);
+            menu::print_token_list(&cfg);
             println!(
                 "├─ Account ID: {}",
                 cfg.account_id
                     .as_deref()
                     .unwrap_or(t!(l, "not set", "未设置"))
             );
+            if let Some(name) = &cfg.account_name {
+                println!("├─ Account Name: {name}");
+            }
             println!(
                 "├─ Zone ID: {}",
                 cfg.zone_id.as_deref().unwrap_or(t!(l, "not set", "未设置"))
             );
             println!(
-                "└─ Zone Name: {}",
+                "├─ Zone Name: {}",
                 cfg.zone_name
                     .as_deref()
                     .unwrap_or(t!(l, "not set", "未设置"))
             );
+            println!(
+                "└─ Audit Log: {}",
+                config::audit_log_path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| t!(l, "off", "关闭").to_string())
+            );
         }
         _ => {
             println!(
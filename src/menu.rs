@@ -1,9 +1,11 @@
+use std::time::Duration;
+
 use colored::Colorize;
 
 use crate::client::{CloudflareClient, TokenVerifyStatus};
 use crate::config;
 use crate::error::Result;
-use crate::i18n::lang;
+use crate::i18n::{lang, Lang};
 use crate::{access, dns, monitor, prompt, scan, service, t, tools, tunnel};
 
 // ---------------------------------------------------------------------------
@@ -11,7 +13,12 @@ use crate::{access, dns, monitor, prompt, scan, service, t, tools, tunnel};
 // ---------------------------------------------------------------------------
 
 /// Entry point for the interactive TUI menu.
-pub async fn interactive_menu() -> Result<()> {
+/// With `session_timeout`, the menu exits when any prompt is left waiting that long.
+pub async fn interactive_menu(session_timeout: Option<Duration>) -> Result<()> {
+    if let Some(limit) = session_timeout {
+        prompt::enable_idle_timeout(limit);
+    }
+
     let mut asked_config = false;
     loop {
         let l = lang();
@@ -39,22 +46,10 @@ pub async fn interactive_menu() -> Result<()> {
             }
         }
 
-        let options = vec![
-            t!(l, "➕ Add Domain Mapping", "➕ 添加域名映射"),
-            t!(l, "🌩️  Tunnel Management", "🌩️  隧道管理"),
-            t!(l, "⚙️  cloudflared Service", "⚙️  cloudflared 服务"),
-            t!(l, "🌐 DNS Management", "🌐 DNS 管理"),
-            t!(l, "🔐 Zero Trust / Access", "🔐 Zero Trust / Access"),
-            t!(l, "📊 Monitoring & Scan", "📊 监控与扫描"),
-            t!(l, "🔧 Settings", "🔧 设置"),
-            t!(l, "❌ Exit", "❌ 退出"),
-        ];
-
-        let sel = match prompt::select_opt_result(
-            t!(l, "Select module", "选择功能模块"),
-            &options,
-            Some(0),
-        ) {
+        let options = main_menu_options(l);
+
+        let title = t!(l, "Select module", "选择功能模块");
+        let sel = match prompt::select_opt_result(title, &options, Some(0)) {
             Ok(v) => v,
             Err(e) => {
                 println!("\n{} {:#}\n", "❌".red(), e);
@@ -66,18 +61,19 @@ pub async fn interactive_menu() -> Result<()> {
             Some(0) => {
                 // Quick Map — the killer feature
                 if let Some(client) = try_build_client() {
-                    tunnel::add_mapping(&client, None, None, None).await
+                    tunnel::add_mapping(&client, None, None, None, false).await
                 } else {
                     Ok(())
                 }
             }
-            Some(1) => tunnel_menu().await,
-            Some(2) => tunnel_service_menu().await,
-            Some(3) => dns_menu().await,
-            Some(4) => access_menu().await,
-            Some(5) => monitoring_scan_menu().await,
-            Some(6) => settings_menu().await,
-            Some(7) | None => {
+            Some(1) => quick_actions_menu().await,
+            Some(2) => tunnel_menu().await,
+            Some(3) => tunnel_service_menu().await,
+            Some(4) => dns_menu().await,
+            Some(5) => access_menu().await,
+            Some(6) => monitoring_scan_menu().await,
+            Some(7) => settings_menu().await,
+            Some(8) | None => {
                 println!("{}", t!(l, "👋 Goodbye!", "👋 再见！").cyan());
                 break;
             }
@@ -95,6 +91,22 @@ pub async fn interactive_menu() -> Result<()> {
     Ok(())
 }
 
+/// Top-level menu entries. Built on every loop iteration so a language switch
+/// in Settings shows up on the next render.
+fn main_menu_options(l: Lang) -> Vec<&'static str> {
+    vec![
+        t!(l, "➕ Add Domain Mapping", "➕ 添加域名映射"),
+        t!(l, "⚡ Quick Actions", "⚡ 快捷操作"),
+        t!(l, "🌩️  Tunnel Management", "🌩️  隧道管理"),
+        t!(l, "⚙️  cloudflared Service", "⚙️  cloudflared 服务"),
+        t!(l, "🌐 DNS Management", "🌐 DNS 管理"),
+        t!(l, "🔐 Zero Trust / Access", "🔐 Zero Trust / Access"),
+        t!(l, "📊 Monitoring & Scan", "📊 监控与扫描"),
+        t!(l, "🔧 Settings", "🔧 设置"),
+        t!(l, "❌ Exit", "❌ 退出"),
+    ]
+}
+
 /// Run only the API token configuration wizard.
 pub async fn run_config_set_wizard() -> Result<()> {
     set_api_token().await
@@ -167,6 +179,144 @@ fn try_build_client_with_zone() -> Option<CloudflareClient> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Quick actions
+// ---------------------------------------------------------------------------
+
+/// Number of recently used quick actions listed first.
+const RECENT_QUICK_ACTIONS: usize = 3;
+
+/// Common operations reachable without sub-menu navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuickAction {
+    AddMapping,
+    ShowMappings,
+    SyncDns,
+    RestartService,
+    ShowStats,
+}
+
+impl QuickAction {
+    const ALL: [Self; 5] = [
+        Self::AddMapping,
+        Self::ShowMappings,
+        Self::SyncDns,
+        Self::RestartService,
+        Self::ShowStats,
+    ];
+
+    /// Stable name stored in `recent.json`.
+    fn key(self) -> &'static str {
+        match self {
+            Self::AddMapping => "add_mapping",
+            Self::ShowMappings => "show_mappings",
+            Self::SyncDns => "sync_dns",
+            Self::RestartService => "restart_service",
+            Self::ShowStats => "show_stats",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.key() == key)
+    }
+
+    fn label(self) -> &'static str {
+        let l = lang();
+        match self {
+            Self::AddMapping => t!(l, "➕ Add mapping", "➕ 添加映射"),
+            Self::ShowMappings => t!(l, "📋 Show mappings", "📋 查看映射"),
+            Self::SyncDns => t!(l, "🔄 Sync DNS", "🔄 同步 DNS"),
+            Self::RestartService => t!(l, "🔁 Restart service", "🔁 重启服务"),
+            Self::ShowStats => t!(l, "📊 Show stats", "📊 查看统计"),
+        }
+    }
+
+    async fn run(self) -> Result<()> {
+        match self {
+            Self::AddMapping => {
+                if let Some(client) = try_build_client() {
+                    tunnel::add_mapping(&client, None, None, None, false).await?;
+                }
+            }
+            Self::ShowMappings => {
+                if let Some(client) = try_build_client() {
+                    tunnel::show_mappings(&client, None, tunnel::MappingsFormat::Table).await?;
+                }
+            }
+            Self::SyncDns => {
+                if let Some(client) = try_build_client_with_zone() {
+                    dns::sync_tunnel_routes(&client, None, false).await?;
+                }
+            }
+            Self::RestartService => service::restart(true).await?,
+            Self::ShowStats => {
+                monitor::show_stats().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Move `action` to the front of `recent`, keeping at most
+/// `RECENT_QUICK_ACTIONS` distinct entries.
+fn record_recent_action(recent: &mut Vec<QuickAction>, action: QuickAction) {
+    recent.retain(|a| *a != action);
+    recent.insert(0, action);
+    recent.truncate(RECENT_QUICK_ACTIONS);
+}
+
+/// All quick actions: recently used first, then the rest in default order.
+fn ordered_quick_actions(recent: &[QuickAction]) -> Vec<QuickAction> {
+    let mut ordered: Vec<QuickAction> = recent.to_vec();
+    ordered.extend(QuickAction::ALL.into_iter().filter(|a| !recent.contains(a)));
+    ordered
+}
+
+fn recent_actions_path() -> Result<std::path::PathBuf> {
+    Ok(config::config_dir()?.join("recent.json"))
+}
+
+/// Recently used quick actions; unknown or unreadable entries are ignored.
+fn load_recent_actions() -> Vec<QuickAction> {
+    let keys: Vec<String> = recent_actions_path()
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    keys.iter()
+        .filter_map(|k| QuickAction::from_key(k))
+        .take(RECENT_QUICK_ACTIONS)
+        .collect()
+}
+
+fn save_recent_actions(recent: &[QuickAction]) -> Result<()> {
+    let path = recent_actions_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let keys: Vec<&str> = recent.iter().map(|a| a.key()).collect();
+    config::write_atomic(&path, serde_json::to_string(&keys)?.as_bytes())
+}
+
+async fn quick_actions_menu() -> Result<()> {
+    let l = lang();
+    let mut recent = load_recent_actions();
+    let actions = ordered_quick_actions(&recent);
+
+    let mut options: Vec<&str> = actions.iter().map(|a| a.label()).collect();
+    options.push(t!(l, "◀️  Back", "◀️  返回主菜单"));
+
+    let sel = prompt::select_opt(t!(l, "Quick Actions", "快捷操作"), &options, Some(0));
+    let Some(action) = sel.and_then(|i| actions.get(i).copied()) else {
+        return Ok(());
+    };
+
+    record_recent_action(&mut recent, action);
+    // Remembering the order is best-effort.
+    let _ = save_recent_actions(&recent);
+    action.run().await
+}
+
 // ---------------------------------------------------------------------------
 // Tunnel sub-menu
 // ---------------------------------------------------------------------------
@@ -180,26 +330,32 @@ async fn tunnel_menu() -> Result<()> {
 
     let options = vec![
         t!(l, "📋 Show mappings", "📋 查看当前映射"),
+        t!(l, "🔍 Diff mappings", "🔍 对比映射快照"),
         t!(l, "➕ Add domain mapping", "➕ 添加域名映射"),
         t!(l, "➖ Remove domain mapping", "➖ 移除域名映射"),
         t!(l, "📋 List tunnels", "📋 查看隧道列表"),
         t!(l, "🆕 Create tunnel", "🆕 创建新隧道"),
         t!(l, "🗑️  Delete tunnel", "🗑️  删除隧道"),
         t!(l, "🔑 Get tunnel token", "🔑 获取隧道 Token"),
+        t!(l, "⚡ Force disconnect", "⚡ 强制断开连接"),
+        t!(l, "🔁 Rotate tunnel secret", "🔁 轮换隧道密钥"),
         t!(l, "◀️  Back", "◀️  返回主菜单"),
     ];
 
     let sel = prompt::select_opt(t!(l, "Tunnel Management", "隧道管理"), &options, None);
 
     match sel {
-        Some(0) => tunnel::show_mappings(&client, None).await?,
-        Some(1) => tunnel::add_mapping(&client, None, None, None).await?,
-        Some(2) => tunnel::remove_mapping(&client, None, None).await?,
-        Some(3) => tunnel::list_tunnels(&client).await?,
-        Some(4) => tunnel::create_tunnel(&client, None).await?,
-        Some(5) => tunnel::delete_tunnel(&client).await?,
-        Some(6) => tunnel::get_token(&client, None).await?,
-        Some(7) | None => {}
+        Some(0) => tunnel::show_mappings(&client, None, tunnel::MappingsFormat::Table).await?,
+        Some(1) => tunnel::diff_mappings(&client, None).await?,
+        Some(2) => tunnel::add_mapping(&client, None, None, None, false).await?,
+        Some(3) => tunnel::remove_mapping(&client, None, None).await?,
+        Some(4) => tunnel::list_tunnels(&client, true, None, None, false).await?,
+        Some(5) => tunnel::create_tunnel(&client, None, None).await?,
+        Some(6) => tunnel::delete_tunnel(&client).await?,
+        Some(7) => tunnel::get_token(&client, None).await?,
+        Some(8) => tunnel::force_disconnect(&client, None).await?,
+        Some(9) => tunnel::rotate_secret(&client, None).await?,
+        Some(10) | None => {}
         _ => {}
     }
     Ok(())
@@ -226,12 +382,12 @@ async fn tunnel_service_menu() -> Result<()> {
         Some(0) => service::status().await?,
         Some(1) => {
             if let Some(client) = try_build_client() {
-                service::install(&client, None).await?;
+                service::install(&client, None, None).await?;
             }
         }
         Some(2) => service::start()?,
         Some(3) => service::stop()?,
-        Some(4) => service::restart()?,
+        Some(4) => service::restart(true).await?,
         Some(5) => service::logs(100)?,
         Some(6) | None => {}
         _ => {}
@@ -260,21 +416,27 @@ async fn dns_menu() -> Result<()> {
     let options = vec![
         t!(l, "📋 List DNS records", "📋 查看 DNS 记录"),
         t!(l, "➕ Add DNS record", "➕ 添加 DNS 记录"),
+        t!(l, "✏️  Update DNS record", "✏️  更新 DNS 记录"),
         t!(l, "🗑️  Delete DNS record", "🗑️  删除 DNS 记录"),
         t!(l, "🔄 Sync tunnel routes", "🔄 同步隧道路由"),
-        t!(l, "🔒 Zone Settings (HTTPS, etc.)", "🔒 域名设置 (强制 HTTPS 等)"),
+        t!(
+            l,
+            "🔒 Zone Settings (HTTPS, etc.)",
+            "🔒 域名设置 (强制 HTTPS 等)"
+        ),
         t!(l, "◀️  Back", "◀️  返回主菜单"),
     ];
 
     let sel = prompt::select_opt(t!(l, "DNS Management", "DNS 管理"), &options, None);
 
     match sel {
-        Some(0) => dns::list_records(&client).await?,
-        Some(1) => dns::add_record(&client, None, None, None, true).await?,
-        Some(2) => dns::delete_record(&client, None).await?,
-        Some(3) => dns::sync_tunnel_routes(&client, None).await?,
-        Some(4) => dns::zone_settings_menu(&client).await?,
-        Some(5) | None => {}
+        Some(0) => dns::list_records(&client, None).await?,
+        Some(1) => dns::add_record(&client, None, None, None, None, None, false).await?,
+        Some(2) => dns::update_record(&client, None).await?,
+        Some(3) => dns::delete_record(&client, None, false).await?,
+        Some(4) => dns::sync_tunnel_routes(&client, None, false).await?,
+        Some(5) => dns::zone_settings_menu(&client).await?,
+        Some(6) | None => {}
         _ => {}
     }
     Ok(())
@@ -294,8 +456,11 @@ async fn access_menu() -> Result<()> {
     let options = vec![
         t!(l, "📋 List Access apps", "📋 查看 Access 应用"),
         t!(l, "🆕 Create app", "🆕 创建新应用"),
+        t!(l, "✏️  Edit application", "✏️  编辑应用"),
         t!(l, "🗑️  Delete app", "🗑️  删除应用"),
         t!(l, "🔐 Manage policies", "🔐 管理访问策略"),
+        t!(l, "📋 View audit logs", "📋 查看审计日志"),
+        t!(l, "↕️  Reorder apps", "↕️  调整应用顺序"),
         t!(l, "◀️  Back", "◀️  返回主菜单"),
     ];
 
@@ -306,11 +471,14 @@ async fn access_menu() -> Result<()> {
     );
 
     match sel {
-        Some(0) => access::list_apps(&client).await?,
-        Some(1) => access::create_app(&client, None, None).await?,
-        Some(2) => access::delete_app(&client, None).await?,
-        Some(3) => access::manage_policies(&client, None).await?,
-        Some(4) | None => {}
+        Some(0) => access::list_apps(&client, false).await?,
+        Some(1) => access::create_app(&client, None, None, None).await?,
+        Some(2) => access::update_app(&client, None).await?,
+        Some(3) => access::delete_app(&client, None).await?,
+        Some(4) => access::manage_policies(&client, None).await?,
+        Some(5) => access::show_audit_logs(&client, None, None).await?,
+        Some(6) => access::reorder_apps(&client).await?,
+        Some(7) | None => {}
         _ => {}
     }
     Ok(())
@@ -327,17 +495,36 @@ async fn monitoring_scan_menu() -> Result<()> {
         t!(l, "📈 Real-time monitor (TUI)", "📈 实时监控 (TUI)"),
         t!(l, "📺 TUI Dashboard", "📺 TUI 仪表盘"),
         t!(l, "🔍 Scan local services", "🔍 扫描本地服务"),
+        t!(l, "💡 Suggest mappings", "💡 建议隧道映射"),
         t!(l, "◀️  Back", "◀️  返回主菜单"),
     ];
 
     let sel = prompt::select_opt(t!(l, "Monitoring & Scan", "监控与扫描"), &options, None);
 
     match sel {
-        Some(0) => monitor::show_stats().await?,
+        Some(0) => {
+            monitor::show_stats().await?;
+        }
         Some(1) => monitor::real_time_monitor().await?,
         Some(2) => crate::dashboard::run_dashboard().await?,
-        Some(3) => scan::scan_local_services(None, 500).await?,
-        Some(4) | None => {}
+        Some(3) => {
+            scan::scan_local_services(
+                None,
+                500,
+                scan::DEFAULT_CONCURRENCY,
+                false,
+                false,
+                false,
+                None,
+            )
+            .await?
+        }
+        Some(4) => {
+            if let Some(client) = try_build_client_with_zone() {
+                tools::suggest_config(&client, None).await?;
+            }
+        }
+        Some(5) | None => {}
         _ => {}
     }
     Ok(())
@@ -357,7 +544,10 @@ async fn settings_menu() -> Result<()> {
         t!(l, "🧪 Test API connection", "🧪 测试 API 连接"),
         t!(l, "🔧 Health check", "🔧 健康检查"),
         t!(l, "🐛 Debug info", "🐛 调试信息"),
+        t!(l, "⏱ Benchmark API", "⏱ API 性能测试"),
         t!(l, "📦 Export config", "📦 导出配置"),
+        t!(l, "🏗️  Export Terraform", "🏗️  导出 Terraform"),
+        t!(l, "📥 Import config", "📥 导入配置"),
         t!(l, "🗑️  Clear config", "🗑️  清除配置"),
         t!(l, "◀️  Back", "◀️  返回主菜单"),
     ];
@@ -371,15 +561,56 @@ async fn settings_menu() -> Result<()> {
         Some(3) => show_api_config()?,
         Some(4) => test_api_connection().await?,
         Some(5) => tools::health_check().await?,
-        Some(6) => tools::debug_mode()?,
-        Some(7) => tools::export_config()?,
-        Some(8) => clear_config()?,
-        Some(9) | None => {}
+        Some(6) => tools::debug_mode(false, None)?,
+        Some(7) => {
+            if let Some(client) = try_build_client() {
+                tools::benchmark(&client).await?;
+            }
+        }
+        Some(8) => tools::export_config(tools::ConfigFormat::Json, None, false).await?,
+        Some(9) => export_terraform_interactive().await?,
+        Some(10) => import_config_interactive().await?,
+        Some(11) => clear_config()?,
+        Some(12) | None => {}
         _ => {}
     }
     Ok(())
 }
 
+async fn export_terraform_interactive() -> Result<()> {
+    let l = lang();
+    let client = match try_build_client() {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+    let path = match prompt::input_opt(
+        t!(l, "Output file", "输出文件"),
+        false,
+        Some("opentunnel.tf"),
+    ) {
+        Some(v) => std::path::PathBuf::from(v.trim()),
+        None => return Ok(()),
+    };
+    tools::export_terraform(&client, &path).await
+}
+
+async fn import_config_interactive() -> Result<()> {
+    let l = lang();
+    let path = match prompt::input_opt(
+        t!(l, "Path to exported config file", "导出配置文件路径"),
+        false,
+        None,
+    ) {
+        Some(v) => std::path::PathBuf::from(v.trim()),
+        None => return Ok(()),
+    };
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => tools::ConfigFormat::Yaml,
+        _ => tools::ConfigFormat::Json,
+    };
+    tools::import_config(path, format).await
+}
+
 async fn account_menu() -> Result<()> {
     let l = lang();
     let options = vec![
@@ -497,15 +728,18 @@ async fn set_api_token() -> Result<()> {
             "{}",
             t!(l, "⚠️  No accounts found.", "⚠️  未找到账户。").yellow()
         );
-        println!(
-            "{}",
-            t!(
-                l,
-                "Tip: ensure the token has 'Account - Account Settings: Edit' permission.",
-                "提示：请确认 Token 包含 'Account - Account Settings: Edit' 权限。"
-            )
-            .yellow()
-        );
+        match &account_err {
+            Some(e) => println!("{}", e.to_string().yellow()),
+            None => println!(
+                "{}",
+                t!(
+                    l,
+                    "Tip: ensure the token has 'Account - Account Settings: Edit' permission.",
+                    "提示：请确认 Token 包含 'Account - Account Settings: Edit' 权限。"
+                )
+                .yellow()
+            ),
+        }
         None
     };
 
@@ -542,9 +776,7 @@ async fn set_api_token() -> Result<()> {
         let tmp_cfg = config::ApiConfig {
             api_token: Some(token.clone()),
             account_id: Some(acct.clone()),
-            zone_id: None,
-            zone_name: None,
-            language: None,
+            ..Default::default()
         };
         let tmp_client = CloudflareClient::from_config(&tmp_cfg)?;
         match tmp_client.list_tunnels().await {
@@ -569,7 +801,11 @@ async fn set_api_token() -> Result<()> {
 
     // 3. Zone / DNS permission (fetch zones)
     let mut zone_err = None;
-    let zones = match CloudflareClient::fetch_zones(&token).await {
+    let zones = match &account_id {
+        Some(acct) => CloudflareClient::fetch_zones_for_account(&token, acct).await,
+        None => CloudflareClient::fetch_zones(&token).await,
+    };
+    let zones = match zones {
         Ok(v) => {
             println!(
                 "  {} {} ({} {})",
@@ -658,15 +894,26 @@ async fn set_api_token() -> Result<()> {
         return Ok(());
     }
 
-    // Save config
+    // Save config, keeping any labelled tokens but making the new token active
+    let account_name = match &account_id {
+        Some(acct) => fetch_account_name(&token, acct).await,
+        None => None,
+    };
+    let previous = config::load_api_config().ok().flatten();
+    let kept = previous.clone().unwrap_or_default();
     let cfg = config::ApiConfig {
         api_token: Some(token),
         account_id,
+        account_name,
         zone_id,
         zone_name,
         language: None,
+        tokens: kept.tokens,
+        active_token: None,
+        response_size_limit_mb: kept.response_size_limit_mb,
+        audit_log: kept.audit_log,
     };
-    config::save_api_config(&cfg)?;
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
     println!(
         "\n{} {}",
         "✅".green(),
@@ -675,6 +922,17 @@ async fn set_api_token() -> Result<()> {
     Ok(())
 }
 
+/// Name of `account_id`, or `None` if it cannot be fetched with `token`.
+async fn fetch_account_name(token: &str, account_id: &str) -> Option<String> {
+    let cfg = config::ApiConfig {
+        api_token: Some(token.to_string()),
+        account_id: Some(account_id.to_string()),
+        ..Default::default()
+    };
+    let client = CloudflareClient::from_config(&cfg).ok()?;
+    client.get_account_details().await.ok().map(|a| a.name)
+}
+
 fn show_api_config() -> Result<()> {
     let l = lang();
     match config::load_api_config()? {
@@ -684,12 +942,17 @@ fn show_api_config() -> Result<()> {
                 t!(l, "Current API Configuration:", "当前 API 配置:").bold()
             );
             println!("├─ API Token: {}", cfg.masked_token());
+            print_token_type_warning(&cfg);
+            print_token_list(&cfg);
             println!(
                 "├─ Account ID: {}",
                 cfg.account_id
                     .as_deref()
                     .unwrap_or(t!(l, "not set", "未设置"))
             );
+            if let Some(name) = &cfg.account_name {
+                println!("├─ Account Name: {name}");
+            }
             println!(
                 "├─ Zone ID: {}",
                 cfg.zone_id.as_deref().unwrap_or(t!(l, "not set", "未设置"))
@@ -719,8 +982,8 @@ fn show_api_config() -> Result<()> {
 async fn test_api_connection() -> Result<()> {
     let l = lang();
 
-    let cfg = match config::load_api_config()? {
-        Some(c) if c.api_token.is_some() => c,
+    let cfg = match config::load_effective_api_config()? {
+        Some(c) if c.effective_token().is_some() => c,
         _ => {
             println!(
                 "{} {}",
@@ -736,8 +999,7 @@ async fn test_api_connection() -> Result<()> {
     };
 
     let token = cfg
-        .api_token
-        .as_deref()
+        .effective_token()
         .ok_or_else(|| anyhow::anyhow!("missing api token in config"))?;
 
     println!(
@@ -822,8 +1084,8 @@ async fn test_api_connection() -> Result<()> {
 pub async fn list_accounts() -> Result<()> {
     let l = lang();
 
-    let cfg = match config::load_api_config()? {
-        Some(c) if c.api_token.is_some() => c,
+    let cfg = match config::load_effective_api_config()? {
+        Some(c) if c.effective_token().is_some() => c,
         _ => {
             println!(
                 "{} {}",
@@ -839,8 +1101,7 @@ pub async fn list_accounts() -> Result<()> {
     };
 
     let token = cfg
-        .api_token
-        .as_deref()
+        .effective_token()
         .ok_or_else(|| anyhow::anyhow!("missing api token in config"))?;
 
     let accounts = match CloudflareClient::fetch_accounts(token).await {
@@ -881,7 +1142,7 @@ pub async fn set_account(id: Option<String>) -> Result<()> {
     let l = lang();
 
     let mut cfg = match config::load_api_config()? {
-        Some(c) if c.api_token.is_some() => c,
+        Some(c) if c.effective_token().is_some() => c,
         _ => {
             println!(
                 "{} {}",
@@ -896,9 +1157,10 @@ pub async fn set_account(id: Option<String>) -> Result<()> {
         }
     };
 
-    let token = cfg
-        .api_token
-        .as_deref()
+    // Look the accounts up with a `--token` override, but save only the choice.
+    let effective = config::load_effective_api_config()?.unwrap_or_else(|| cfg.clone());
+    let token = effective
+        .effective_token()
         .ok_or_else(|| anyhow::anyhow!("missing api token in config"))?;
 
     let accounts = CloudflareClient::fetch_accounts(token).await?;
@@ -940,8 +1202,10 @@ pub async fn set_account(id: Option<String>) -> Result<()> {
         }
     };
 
+    let previous = cfg.clone();
     cfg.account_id = Some(selected.id.clone());
-    config::save_api_config(&cfg)?;
+    cfg.account_name = Some(selected.name.clone());
+    config::save_api_config_if_changed(&cfg, Some(&previous))?;
     println!(
         "{} {} {}",
         "✅".green(),
@@ -951,12 +1215,185 @@ pub async fn set_account(id: Option<String>) -> Result<()> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Labelled tokens
+// ---------------------------------------------------------------------------
+
+/// Warn when the effective credential is a Global API Key.
+pub fn print_token_type_warning(cfg: &config::ApiConfig) {
+    if cfg.token_type() == config::TokenType::GlobalApiKey {
+        println!(
+            "│  {} {}",
+            "⚠️".yellow(),
+            t!(
+                lang(),
+                "Global API Key has full account access; scoped tokens are preferred",
+                "Global API Key 拥有完整账户权限，建议使用限定权限的 API Token"
+            )
+            .yellow()
+        );
+    }
+}
+
+/// One labelled token as `label: masked [scopes] (active)`, shared by the
+/// config tree view and `tunnel config token list`.
+fn format_token_line(l: Lang, cfg: &config::ApiConfig, tok: &config::NamedToken) -> String {
+    let mark = if cfg.active_token.as_deref() == Some(tok.label.as_str()) {
+        t!(l, " (active)", " (当前)")
+    } else {
+        ""
+    };
+    let scopes = if tok.scopes.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", tok.scopes.join(", "))
+    };
+    format!(
+        "{}: {}{}{}",
+        tok.label,
+        config::mask_token(&tok.token),
+        scopes,
+        mark
+    )
+}
+
+/// Print labelled tokens as part of the config tree view.
+pub fn print_token_list(cfg: &config::ApiConfig) {
+    let l = lang();
+    if cfg.tokens.is_empty() {
+        return;
+    }
+    println!("├─ {}:", t!(l, "Tokens", "Token 列表"));
+    for tok in &cfg.tokens {
+        println!("│   • {}", format_token_line(l, cfg, tok));
+    }
+}
+
+pub fn list_tokens() -> Result<()> {
+    let l = lang();
+    let cfg = config::load_effective_api_config()?.unwrap_or_default();
+    if cfg.tokens.is_empty() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "⚠️  No labelled tokens. Add one with `tunnel config token add <label>`.",
+                "⚠️  没有带标签的 Token。使用 `tunnel config token add <label>` 添加。"
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    println!("\n{}", t!(l, "🔑 Tokens:", "🔑 Token 列表:").bold());
+    for tok in &cfg.tokens {
+        println!("• {}", format_token_line(l, &cfg, tok));
+    }
+    Ok(())
+}
+
+pub fn add_token(label: String, scopes: Vec<String>) -> Result<()> {
+    let l = lang();
+    let token = match prompt::input_opt("API Token", false, None) {
+        Some(v) => v.trim().to_string(),
+        None => return Ok(()),
+    };
+    if token.is_empty() {
+        return Ok(());
+    }
+
+    let previous = config::load_api_config()?;
+    let mut cfg = previous.clone().unwrap_or_default();
+    cfg.add_token(config::NamedToken {
+        label: label.clone(),
+        token,
+        scopes,
+    });
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
+    println!(
+        "{} {} '{}'",
+        "✅".green(),
+        t!(l, "Token saved:", "Token 已保存:"),
+        label
+    );
+    Ok(())
+}
+
+pub fn remove_token(label: String) -> Result<()> {
+    let l = lang();
+    let previous = config::load_api_config()?;
+    let mut cfg = previous.clone().unwrap_or_default();
+    let was_active = cfg.active_token.as_deref() == Some(label.as_str());
+
+    if !cfg.remove_token(&label) {
+        println!(
+            "{} {} '{}'",
+            "❌".red(),
+            t!(l, "No token labelled", "未找到标签为"),
+            label
+        );
+        return Ok(());
+    }
+
+    // The active token is gone — ask which one to use instead
+    if was_active && !cfg.tokens.is_empty() {
+        let items: Vec<&str> = cfg.tokens.iter().map(|t| t.label.as_str()).collect();
+        let sel = prompt::select_opt(
+            t!(
+                l,
+                "Removed the active token. Select a new one",
+                "已移除当前 Token，请选择新的 Token"
+            ),
+            &items,
+            None,
+        );
+        if let Some(next) = sel.and_then(|i| items.get(i)).map(|s| s.to_string()) {
+            cfg.use_token(&next)?;
+        }
+    }
+
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
+    println!(
+        "{} {} '{}'",
+        "✅".green(),
+        t!(l, "Token removed:", "Token 已移除:"),
+        label
+    );
+    if cfg.effective_token().is_none() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "⚠️  No active token. Run `tunnel config token use <label>` or `tunnel config set`.",
+                "⚠️  当前无可用 Token。请运行 `tunnel config token use <label>` 或 `tunnel config set`。"
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+pub fn use_token(label: String) -> Result<()> {
+    let l = lang();
+    let previous = config::load_api_config()?;
+    let mut cfg = previous.clone().unwrap_or_default();
+    cfg.use_token(&label)?;
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
+    println!(
+        "{} {} '{}'",
+        "✅".green(),
+        t!(l, "Active token set to", "当前 Token 已设置为"),
+        label
+    );
+    Ok(())
+}
+
 fn switch_language() -> Result<()> {
     let l = lang();
     let options = vec!["English", "中文"];
     let current = match l {
-        crate::i18n::Lang::En => 0,
-        crate::i18n::Lang::Zh => 1,
+        Lang::En => 0,
+        Lang::Zh => 1,
     };
 
     let sel = prompt::select_opt(
@@ -966,18 +1403,22 @@ fn switch_language() -> Result<()> {
     );
 
     let (code, new_lang) = match sel {
-        Some(0) => ("en", crate::i18n::Lang::En),
-        Some(1) => ("zh", crate::i18n::Lang::Zh),
+        Some(0) => ("en", Lang::En),
+        Some(1) => ("zh", Lang::Zh),
         _ => return Ok(()),
     };
 
     // Save to config
-    let mut cfg = config::load_api_config()?.unwrap_or_default();
+    let previous = config::load_api_config()?;
+    let mut cfg = previous.clone().unwrap_or_default();
     cfg.language = Some(code.to_string());
-    config::save_api_config(&cfg)?;
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
 
-    // Apply immediately
+    // Apply immediately and redraw the header in the new language
     crate::i18n::set_lang(new_lang);
+    clear_screen();
+    print_banner();
+    tools::print_status(&tools::get_system_status());
 
     let l = lang();
     println!(
@@ -1006,3 +1447,80 @@ fn clear_config() -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_line_shows_scopes_and_active_mark() {
+        let token = |label: &str, scopes: &[&str]| config::NamedToken {
+            label: label.to_string(),
+            token: "abcdefghijklmnopqrstuvwxyz".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        };
+        let cfg = config::ApiConfig {
+            tokens: vec![token("ro", &["dns:read"]), token("admin", &[])],
+            active_token: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let masked = config::mask_token("abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(
+            format_token_line(Lang::En, &cfg, &cfg.tokens[0]),
+            format!("ro: {masked} [dns:read]")
+        );
+        assert_eq!(
+            format_token_line(Lang::En, &cfg, &cfg.tokens[1]),
+            format!("admin: {masked} (active)")
+        );
+    }
+
+    #[test]
+    fn main_menu_follows_language_switch() {
+        use crate::i18n::{set_lang, LANG_TEST_LOCK};
+
+        // Each render reads the language afresh, so a switch mid-session
+        // changes the next menu.
+        let _lang = LANG_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        set_lang(Lang::En);
+        let before = main_menu_options(lang());
+        set_lang(Lang::Zh);
+        let after = main_menu_options(lang());
+        set_lang(Lang::En);
+        assert!(before.contains(&"🔧 Settings"));
+        assert!(after.contains(&"🔧 设置"));
+        assert_eq!(before.len(), after.len());
+    }
+
+    #[test]
+    fn recent_actions_move_to_front() {
+        use QuickAction::*;
+
+        let mut recent = Vec::new();
+        record_recent_action(&mut recent, SyncDns);
+        record_recent_action(&mut recent, ShowStats);
+        assert_eq!(recent, vec![ShowStats, SyncDns]);
+
+        record_recent_action(&mut recent, SyncDns);
+        assert_eq!(recent, vec![SyncDns, ShowStats]);
+
+        record_recent_action(&mut recent, AddMapping);
+        record_recent_action(&mut recent, RestartService);
+        assert_eq!(recent, vec![RestartService, AddMapping, SyncDns]);
+    }
+
+    #[test]
+    fn quick_actions_list_recent_first() {
+        use QuickAction::*;
+
+        assert_eq!(ordered_quick_actions(&[]), QuickAction::ALL.to_vec());
+        assert_eq!(
+            ordered_quick_actions(&[ShowStats, SyncDns]),
+            vec![ShowStats, SyncDns, AddMapping, ShowMappings, RestartService]
+        );
+        for action in QuickAction::ALL {
+            assert_eq!(QuickAction::from_key(action.key()), Some(action));
+        }
+        assert_eq!(QuickAction::from_key("unknown"), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! A local HTTP server standing in for the Cloudflare API in tests.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+type Responder = dyn Fn(&str, &str) -> (u16, String) + Send + Sync;
+
+/// Answers every request with `respond(method, path)` and remembers what
+/// was asked. Point a client at it with `with_base_url(&api.base)`.
+pub struct MockApi {
+    /// `http://127.0.0.1:<port>`, without a trailing slash.
+    pub base: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockApi {
+    /// Start a server whose response is `(status, body)` for each request.
+    pub async fn start(
+        respond: impl Fn(&str, &str) -> (u16, String) + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let respond: Arc<Responder> = Arc::new(respond);
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((sock, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(serve(sock, respond.clone(), seen.clone()));
+            }
+        });
+        Self { base, requests }
+    }
+
+    /// Answer with the body of the first `(method, path prefix, body)` route
+    /// that matches, or an API error when none does.
+    pub async fn routes(routes: Vec<(&'static str, &'static str, &'static str)>) -> Self {
+        Self::start(move |method, path| {
+            let body = routes
+                .iter()
+                .find(|(m, prefix, _)| *m == method && path.starts_with(prefix))
+                .map(|(_, _, body)| *body)
+                .unwrap_or(r#"{"success":false,"errors":[{"code":1,"message":"no route"}]}"#);
+            (200, body.to_string())
+        })
+        .await
+    }
+
+    /// Answer every request with `200 OK` and `body`.
+    pub async fn json(body: &'static str) -> Self {
+        Self::start(move |_, _| (200, body.to_string())).await
+    }
+
+    /// The requests seen so far as "METHOD path" lines.
+    pub fn requests(&self) -> Vec<String> {
+        self.raw_requests()
+            .iter()
+            .map(|raw| raw.split_whitespace().take(2).collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+
+    /// The requests seen so far, head and body, as sent.
+    pub fn raw_requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Read one request, record it, then write the response and close.
+async fn serve(mut sock: TcpStream, respond: Arc<Responder>, seen: Arc<Mutex<Vec<String>>>) {
+    let mut request = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    let mut expected = None;
+    while expected.is_none_or(|len| request.len() < len) {
+        let n = match sock.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        request.extend_from_slice(&buf[..n]);
+        if expected.is_none() {
+            expected = request_len(&request);
+        }
+    }
+    let request = String::from_utf8_lossy(&request).to_string();
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+    seen.lock().unwrap().push(request);
+
+    let (status, body) = respond(&method, &path);
+    let reason = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    let head = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = sock.write_all(head.as_bytes()).await;
+    let _ = sock.write_all(body.as_bytes()).await;
+}
+
+/// Total length of a request once its head has arrived: head plus
+/// `Content-Length` bytes of body.
+fn request_len(request: &[u8]) -> Option<usize> {
+    let head_end = request.windows(4).position(|w| w == b"\r\n\r\n")? + 4;
+    let head = String::from_utf8_lossy(&request[..head_end]).to_lowercase();
+    let body_len = head
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:"))
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    Some(head_end + body_len)
+}
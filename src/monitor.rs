@@ -1,12 +1,43 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Context;
 use colored::Colorize;
 use comfy_table::{presets::UTF8_FULL, Table};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal;
+use serde::{Deserialize, Serialize};
 
 use crate::error::Result;
 use crate::i18n::lang;
-use crate::t;
+use crate::{config, t, util};
+
+/// Metrics port cloudflared listens on by default.
+const DEFAULT_METRICS_PORT: u16 = 20241;
+
+/// Ports cloudflared falls back to when the default one is taken.
+pub const METRICS_PORT_RANGE: RangeInclusive<u16> = 20241..=20249;
 
-const METRICS_URL: &str = "http://127.0.0.1:20241/metrics";
+/// Port found by auto-discovery (0 until one is found).
+static DISCOVERED_PORT: AtomicU16 = AtomicU16::new(0);
+
+fn metrics_url(port: u16) -> String {
+    format!("http://127.0.0.1:{port}/metrics")
+}
+
+/// Metrics port to query: the discovered one if any, else the default.
+pub fn current_metrics_port() -> u16 {
+    match DISCOVERED_PORT.load(Ordering::Relaxed) {
+        0 => DEFAULT_METRICS_PORT,
+        port => port,
+    }
+}
 
 /// Parsed Prometheus metrics from cloudflared.
 #[derive(Debug, Default)]
@@ -16,6 +47,52 @@ pub struct TunnelMetrics {
     pub response_time_avg: Option<f64>,
     pub request_errors: Option<f64>,
     pub connections: Vec<ConnectionMetric>,
+    /// Active stream counts aggregated per edge data center (`colo_name` label).
+    pub streams_by_colo: HashMap<String, f64>,
+    /// Total requests aggregated per hostname (`host` label).
+    pub requests_by_host: HashMap<String, f64>,
+    /// Bytes sent to the edge, summed over connections.
+    pub bytes_sent: Option<f64>,
+    /// Bytes received from the edge, summed over connections.
+    pub bytes_received: Option<f64>,
+}
+
+impl TunnelMetrics {
+    /// Edge data centers sorted by stream count (descending), then name.
+    pub fn colos_by_streams(&self) -> Vec<(&str, f64)> {
+        let mut colos: Vec<(&str, f64)> = self
+            .streams_by_colo
+            .iter()
+            .map(|(k, v)| (k.as_str(), *v))
+            .collect();
+        colos.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        colos
+    }
+
+    /// Upload/download counters as `↑ 12.5 MB ↓ 34.2 MB`, or `None` when
+    /// cloudflared reports neither.
+    pub fn traffic_summary(&self) -> Option<String> {
+        if self.bytes_sent.is_none() && self.bytes_received.is_none() {
+            return None;
+        }
+        let bytes = |v: Option<f64>| v.map(util::format_bytes).unwrap_or_else(|| "-".to_string());
+        Some(format!(
+            "↑ {} ↓ {}",
+            bytes(self.bytes_sent),
+            bytes(self.bytes_received)
+        ))
+    }
+
+    /// Hostnames sorted by request count (descending), then name.
+    pub fn hosts_by_requests(&self) -> Vec<(String, f64)> {
+        let mut hosts: Vec<(String, f64)> = self
+            .requests_by_host
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        hosts.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hosts
+    }
 }
 
 #[derive(Debug)]
@@ -24,12 +101,215 @@ pub struct ConnectionMetric {
     pub value: f64,
 }
 
+/// Error rate above which a tunnel counts as degraded.
+const MAX_ERROR_RATE: f64 = 0.05;
+
+/// Overall tunnel health derived from its metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TunnelHealth {
+    Healthy,
+    Degraded {
+        reason: String,
+    },
+    /// No active streams, or metrics unreachable.
+    Inactive,
+}
+
+impl TunnelHealth {
+    /// Classify `metrics`: no active streams is inactive, an error rate above
+    /// 5% of total requests is degraded.
+    pub fn from_metrics(metrics: &TunnelMetrics) -> Self {
+        if metrics.active_streams.unwrap_or(0.0) < 1.0 {
+            return Self::Inactive;
+        }
+        let total = metrics.total_requests.unwrap_or(0.0);
+        let errors = metrics.request_errors.unwrap_or(0.0);
+        if errors > total * MAX_ERROR_RATE {
+            let rate = if total > 0.0 {
+                errors / total * 100.0
+            } else {
+                100.0
+            };
+            return Self::Degraded {
+                reason: format!("error rate {rate:.1}% ({errors} / {total})"),
+            };
+        }
+        Self::Healthy
+    }
+
+    /// Process exit code for `tunnel stats --exit-code`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Healthy => 0,
+            Self::Inactive => 2,
+            Self::Degraded { .. } => 3,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Metrics history (<config dir>/metrics_history.jsonl)
+// ---------------------------------------------------------------------------
+
+/// Number of samples kept in the history file.
+const HISTORY_LIMIT: usize = 120;
+
+/// Sparkline blocks from lowest to highest.
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One stored `show_stats` sample.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct MetricsSample {
+    timestamp: i64,
+    total_requests: Option<f64>,
+    active_streams: Option<f64>,
+    request_errors: Option<f64>,
+}
+
+impl MetricsSample {
+    fn from_metrics(metrics: &TunnelMetrics, timestamp: i64) -> Self {
+        Self {
+            timestamp,
+            total_requests: metrics.total_requests,
+            active_streams: metrics.active_streams,
+            request_errors: metrics.request_errors,
+        }
+    }
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("metrics_history.jsonl"))
+}
+
+/// Samples closer together than this are not stored, so calling
+/// `show_stats` repeatedly does not rewrite the history each time.
+const HISTORY_MIN_INTERVAL_SECS: i64 = 60;
+
+/// Append `sample` to the history at `path`, keeping the newest
+/// `HISTORY_LIMIT` entries, and return the stored samples oldest first.
+/// The file is only rewritten when the sample is actually appended: it has
+/// at least one value and comes `HISTORY_MIN_INTERVAL_SECS` after the last.
+fn record_sample(path: &Path, sample: MetricsSample) -> Result<Vec<MetricsSample>> {
+    let mut samples: Vec<MetricsSample> = std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let has_values = sample.total_requests.is_some()
+        || sample.active_streams.is_some()
+        || sample.request_errors.is_some();
+    let due = samples
+        .last()
+        .is_none_or(|last| sample.timestamp - last.timestamp >= HISTORY_MIN_INTERVAL_SECS);
+    if !has_values || !due {
+        return Ok(samples);
+    }
+    samples.push(sample);
+    let skip = samples.len().saturating_sub(HISTORY_LIMIT);
+    samples.drain(..skip);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut out = String::new();
+    for s in &samples {
+        out.push_str(&serde_json::to_string(s)?);
+        out.push('\n');
+    }
+    config::write_atomic(path, out.as_bytes())?;
+    Ok(samples)
+}
+
+/// Render `values` as unicode blocks scaled to their min/max range. A flat
+/// series renders as a baseline (all zero) or mid-height line.
+fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    values
+        .iter()
+        .map(|v| {
+            if range <= 0.0 {
+                if max == 0.0 {
+                    SPARK_BLOCKS[0]
+                } else {
+                    SPARK_BLOCKS[3]
+                }
+            } else {
+                let idx = ((v - min) / range * (SPARK_BLOCKS.len() - 1) as f64).round();
+                SPARK_BLOCKS[idx as usize]
+            }
+        })
+        .collect()
+}
+
+/// Current terminal width in columns, defaulting to 80.
+fn terminal_width() -> usize {
+    ratatui::crossterm::terminal::size()
+        .map(|(cols, _)| cols as usize)
+        .unwrap_or(80)
+}
+
+/// Sparkline width for the current terminal: whatever the stats table leaves
+/// free, between 8 and 60 columns.
+fn sparkline_width() -> usize {
+    terminal_width().saturating_sub(45).clamp(8, 60)
+}
+
+/// Render `data` as a horizontal bar chart, one line per entry: the label
+/// left-aligned, a `█` bar scaled to the largest value, then the count.
+/// `width` is the full line width; bars get whatever the labels and counts
+/// leave free (at least one column).
+fn bar_chart(data: &[(String, f64)], width: usize) -> String {
+    let counts: Vec<String> = data.iter().map(|(_, v)| format_metric(Some(*v))).collect();
+    let label_width = data
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let count_width = counts.iter().map(|c| c.len()).max().unwrap_or(0);
+    let bar_width = width.saturating_sub(label_width + count_width + 4).max(1);
+    let max = data.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+
+    data.iter()
+        .zip(&counts)
+        .map(|((label, value), count)| {
+            let len = if max > 0.0 && *value > 0.0 {
+                ((value / max * bar_width as f64).round() as usize).clamp(1, bar_width)
+            } else {
+                0
+            };
+            let bar = "█".repeat(len);
+            format!(
+                "{label:<label_width$}  {bar}{}  {count:>count_width$}",
+                " ".repeat(bar_width - len)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Sparkline of the newest `width` values picked from `samples` by `field`.
+fn history_sparkline(
+    samples: &[MetricsSample],
+    width: usize,
+    field: impl Fn(&MetricsSample) -> Option<f64>,
+) -> String {
+    let values: Vec<f64> = samples.iter().filter_map(field).collect();
+    if values.len() < 2 {
+        return String::new();
+    }
+    let skip = values.len().saturating_sub(width);
+    sparkline(&values[skip..])
+}
+
 // ---------------------------------------------------------------------------
 // Show stats (one-shot)
 // ---------------------------------------------------------------------------
 
-/// Fetch and display tunnel statistics.
-pub async fn show_stats() -> Result<()> {
+/// Fetch and display tunnel statistics, returning the tunnel's health.
+pub async fn show_stats() -> Result<TunnelHealth> {
     let l = lang();
     println!(
         "\n{}",
@@ -40,36 +320,80 @@ pub async fn show_stats() -> Result<()> {
         Ok(metrics) => metrics,
         Err(_) => {
             print_metrics_unavailable_hint();
-            return Ok(());
+            return Ok(TunnelHealth::Inactive);
         }
     };
 
+    // History is a nice-to-have; stats still print if it can't be stored.
+    let sample = MetricsSample::from_metrics(&metrics, chrono::Utc::now().timestamp());
+    let history = history_path()
+        .and_then(|path| record_sample(&path, sample))
+        .unwrap_or_default();
+    let width = sparkline_width();
+
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
-    table.set_header(vec![t!(l, "Metric", "指标"), t!(l, "Value", "值")]);
+    table.set_header(vec![
+        t!(l, "Metric", "指标"),
+        t!(l, "Value", "值"),
+        t!(l, "History", "历史"),
+    ]);
 
     table.add_row(vec![
         t!(l, "Total requests", "总请求数"),
         &format_metric(metrics.total_requests),
+        &history_sparkline(&history, width, |s| s.total_requests),
     ]);
     table.add_row(vec![
         t!(l, "Active streams", "活跃连接"),
         &format_metric(metrics.active_streams),
+        &history_sparkline(&history, width, |s| s.active_streams),
     ]);
     table.add_row(vec![
         t!(l, "Request errors", "请求错误"),
         &format_metric(metrics.request_errors),
+        &history_sparkline(&history, width, |s| s.request_errors),
     ]);
 
     if let Some(avg) = metrics.response_time_avg {
         table.add_row(vec![
             t!(l, "Avg response time", "平均响应时间"),
             &format!("{avg:.2}ms"),
+            "",
         ]);
     }
+    if let Some(traffic) = metrics.traffic_summary() {
+        table.add_row(vec![t!(l, "Traffic", "流量"), &traffic, ""]);
+    }
 
     println!("{table}");
 
+    if !metrics.streams_by_colo.is_empty() {
+        println!("\n{}", t!(l, "Edge Distribution:", "边缘节点分布:").bold());
+        let mut edge_table = Table::new();
+        edge_table.load_preset(UTF8_FULL);
+        edge_table.set_header(vec![t!(l, "Colo", "节点"), t!(l, "Streams", "连接"), ""]);
+        for (colo, streams) in metrics.colos_by_streams() {
+            edge_table.add_row(vec![
+                colo.to_string(),
+                format_metric(Some(streams)),
+                stream_bar(streams),
+            ]);
+        }
+        println!("{edge_table}");
+    }
+
+    if !metrics.requests_by_host.is_empty() {
+        println!(
+            "\n{}",
+            t!(l, "Traffic by hostname:", "按主机名的流量:").bold()
+        );
+        let chart = bar_chart(&metrics.hosts_by_requests(), terminal_width().min(100));
+        for line in chart.lines() {
+            println!("  {line}");
+        }
+    }
+
     if !metrics.connections.is_empty() {
         println!("\n{}", t!(l, "Connection details:", "连接详情:").bold());
         for conn in &metrics.connections {
@@ -77,7 +401,24 @@ pub async fn show_stats() -> Result<()> {
         }
     }
 
-    Ok(())
+    let health = TunnelHealth::from_metrics(&metrics);
+    match &health {
+        TunnelHealth::Healthy => {
+            println!("\n{} {}", "✅".green(), t!(l, "Healthy", "健康"))
+        }
+        TunnelHealth::Degraded { reason } => println!(
+            "\n{} {}: {reason}",
+            "⚠️".yellow(),
+            t!(l, "Degraded", "降级")
+        ),
+        TunnelHealth::Inactive => println!(
+            "\n{} {}",
+            "❌".red(),
+            t!(l, "Inactive: no active streams", "未活跃: 没有活跃连接")
+        ),
+    }
+
+    Ok(health)
 }
 
 // ---------------------------------------------------------------------------
@@ -87,71 +428,207 @@ pub async fn show_stats() -> Result<()> {
 /// Continuously display metrics with a refresh interval.
 pub async fn real_time_monitor() -> Result<()> {
     let l = lang();
-    println!(
-        "{}",
+    // Space-to-pause needs raw keystrokes, so only on an interactive terminal.
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let title = if interactive {
+        t!(
+            l,
+            "📈 Real-time Monitor (Space to pause, Ctrl+C to exit)",
+            "📈 实时监控 (空格暂停，Ctrl+C 退出)"
+        )
+    } else {
         t!(
             l,
             "📈 Real-time Monitor (press Ctrl+C to exit)",
             "📈 实时监控 (按 Ctrl+C 退出)"
         )
-        .bold()
-    );
+    };
+    println!("{}", title.bold());
 
     // Install a Ctrl+C handler so we can exit cleanly
-    let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
     ctrlc::set_handler(move || {
-        r.store(false, std::sync::atomic::Ordering::SeqCst);
+        r.store(false, Ordering::SeqCst);
     })
     .context("failed to set Ctrl+C handler")?;
 
-    while running.load(std::sync::atomic::Ordering::SeqCst) {
-        // Clear screen
-        print!("\x1B[2J\x1B[1;1H");
+    let paused = Arc::new(PauseState::default());
+    let raw_mode = if interactive {
+        let guard = RawModeGuard::enable()?;
+        spawn_key_reader(paused.clone(), running.clone());
+        Some(guard)
+    } else {
+        None
+    };
 
-        println!(
-            "{}\n",
-            t!(
-                l,
-                "📈 Real-time Monitor (press Ctrl+C to exit)",
-                "📈 实时监控 (按 Ctrl+C 退出)"
-            )
-            .bold()
-        );
+    let mut switched_to = None;
+    while running.load(Ordering::SeqCst) {
+        let mut result = fetch_metrics().await;
+        if result.is_err() {
+            if let Some(port) = auto_discover_metrics_port(METRICS_PORT_RANGE).await {
+                if port != current_metrics_port() {
+                    DISCOVERED_PORT.store(port, Ordering::Relaxed);
+                    switched_to = Some(port);
+                }
+                result = fetch_metrics_from(port).await;
+            }
+        }
 
-        match fetch_metrics().await {
-            Ok(m) => print_compact_metrics(&m),
+        // Clear screen
+        let mut frame = String::from("\x1B[2J\x1B[1;1H");
+        frame.push_str(&format!("{}\n", title.bold()));
+        if let Some(port) = switched_to {
+            frame.push_str(&format!(
+                "{}\n",
+                format!("{} {port}", t!(l, "Switched to port", "已切换到端口")).cyan()
+            ));
+        }
+        frame.push('\n');
+
+        match result {
+            Ok(m) => frame.push_str(&compact_metrics(&m)),
             Err(_) => {
-                println!(
-                    "{}",
+                frame.push_str(&format!(
+                    "{}\n",
                     t!(
                         l,
                         "⚠️  Cannot reach metrics endpoint. Is cloudflared running?",
                         "⚠️  无法连接指标端点。cloudflared 是否在运行?"
                     )
                     .yellow()
-                );
+                ));
             }
         }
 
         let ts = chrono::Local::now().format("%H:%M:%S");
-        println!(
-            "\n{} {}",
+        frame.push_str(&format!(
+            "\n{} {}\n",
             t!(l, "Last update:", "上次更新:").dimmed(),
             ts.to_string().dimmed()
-        );
+        ));
+        emit(&frame, interactive);
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+        // Wait for the next refresh, or longer while paused.
+        let mut waited = Duration::ZERO;
+        let mut shown_paused = false;
+        while running.load(Ordering::SeqCst) && (waited < REFRESH_INTERVAL || paused.is_paused()) {
+            if paused.is_paused() && !shown_paused {
+                let banner = t!(
+                    l,
+                    "⏸ PAUSED — press Space to resume",
+                    "⏸ 已暂停 — 按空格继续"
+                );
+                emit(
+                    &format!("\x1B[1;1H\x1B[2K{}", banner.yellow().bold()),
+                    interactive,
+                );
+                shown_paused = true;
+            }
+            if shown_paused && !paused.is_paused() {
+                break;
+            }
+            tokio::time::sleep(KEY_POLL_INTERVAL).await;
+            waited += KEY_POLL_INTERVAL;
+        }
     }
 
+    drop(raw_mode);
     println!("\n{}", t!(l, "Monitor stopped.", "监控已停止。"));
     Ok(())
 }
 
-fn print_compact_metrics(m: &TunnelMetrics) {
+/// Seconds between monitor refreshes.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the monitor checks for key presses and the pause flag.
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Pause flag of the real-time monitor, toggled by the space bar.
+#[derive(Debug, Default)]
+struct PauseState(AtomicBool);
+
+impl PauseState {
+    /// Flip the flag and return whether the monitor is now paused.
+    fn toggle(&self) -> bool {
+        !self.0.fetch_xor(true, Ordering::SeqCst)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// What a key press does in the real-time monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MonitorKey {
+    TogglePause,
+    Quit,
+    Ignore,
+}
+
+/// Space pauses/resumes; Ctrl+C quits (raw mode swallows the signal).
+fn classify_key(code: KeyCode, modifiers: KeyModifiers) -> MonitorKey {
+    match code {
+        KeyCode::Char(' ') => MonitorKey::TogglePause,
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => MonitorKey::Quit,
+        _ => MonitorKey::Ignore,
+    }
+}
+
+/// Read key presses on a background thread until `running` is cleared.
+fn spawn_key_reader(paused: Arc<PauseState>, running: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        while running.load(Ordering::SeqCst) {
+            if !event::poll(KEY_POLL_INTERVAL).unwrap_or(false) {
+                continue;
+            }
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match classify_key(key.code, key.modifiers) {
+                    MonitorKey::TogglePause => {
+                        paused.toggle();
+                    }
+                    MonitorKey::Quit => running.store(false, Ordering::SeqCst),
+                    MonitorKey::Ignore => {}
+                }
+            }
+        }
+    });
+}
+
+/// Keeps the terminal in raw mode while alive.
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw mode")?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Print `text`; raw mode needs explicit carriage returns.
+fn emit(text: &str, raw: bool) {
+    if raw {
+        print!("{}", text.replace('\n', "\r\n"));
+    } else {
+        print!("{text}");
+    }
+    let _ = std::io::stdout().flush();
+}
+
+fn compact_metrics(m: &TunnelMetrics) -> String {
     let l = lang();
-    println!(
-        "  {} {:>12}   {} {:>8}   {} {:>8}",
+    let mut out = format!(
+        "  {} {:>12}   {} {:>8}   {} {:>8}\n",
         t!(l, "Requests:", "请求数:").bold(),
         format_metric(m.total_requests).cyan(),
         t!(l, "Streams:", "连接:").bold(),
@@ -159,6 +636,35 @@ fn print_compact_metrics(m: &TunnelMetrics) {
         t!(l, "Errors:", "错误:").bold(),
         format_metric(m.request_errors).normal().red()
     );
+    if let Some(traffic) = m.traffic_summary() {
+        out.push_str(&format!(
+            "  {} {traffic}\n",
+            t!(l, "Traffic:", "流量:").bold()
+        ));
+    }
+
+    let top: Vec<String> = m
+        .colos_by_streams()
+        .into_iter()
+        .take(3)
+        .map(|(colo, streams)| format!("{colo} {}", format_metric(Some(streams))))
+        .collect();
+    if !top.is_empty() {
+        out.push_str(&format!(
+            "  {} {}\n",
+            t!(l, "Top edges:", "主要节点:").bold(),
+            top.join("  ·  ")
+        ));
+    }
+    out
+}
+
+/// Bar chart cell: one `█` per 10 streams (at least one for any non-zero count).
+fn stream_bar(streams: f64) -> String {
+    if streams <= 0.0 {
+        return String::new();
+    }
+    "█".repeat((streams / 10.0).ceil() as usize)
 }
 
 fn print_metrics_unavailable_hint() {
@@ -202,20 +708,66 @@ fn print_metrics_unavailable_hint() {
 // Fetch & parse Prometheus metrics
 // ---------------------------------------------------------------------------
 
+/// Fetch metrics from the current metrics port (default or auto-discovered).
 pub async fn fetch_metrics() -> Result<TunnelMetrics> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()?;
+    fetch_metrics_from(current_metrics_port()).await
+}
+
+async fn fetch_metrics_from(port: u16) -> Result<TunnelMetrics> {
+    let body = fetch_metrics_body(port, std::time::Duration::from_secs(5)).await?;
+    Ok(parse_prometheus(&body))
+}
+
+async fn fetch_metrics_body(port: u16, timeout: std::time::Duration) -> Result<String> {
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
 
     let body = client
-        .get(METRICS_URL)
+        .get(metrics_url(port))
         .send()
         .await
         .context("failed to reach cloudflared metrics endpoint")?
         .text()
         .await?;
+    Ok(body)
+}
 
-    Ok(parse_prometheus(&body))
+/// Whether `body` looks like Prometheus text exposition output.
+fn looks_like_prometheus(body: &str) -> bool {
+    body.lines()
+        .any(|line| line.starts_with("# TYPE ") || line.starts_with("cloudflared_"))
+}
+
+/// First port in `ports` for which `probe` returns a Prometheus body.
+async fn probe_metrics_ports<F, Fut>(ports: RangeInclusive<u16>, probe: F) -> Option<u16>
+where
+    F: Fn(u16) -> Fut,
+    Fut: Future<Output = Option<String>>,
+{
+    for port in ports {
+        if let Some(body) = probe(port).await {
+            if looks_like_prometheus(&body) {
+                return Some(port);
+            }
+        }
+    }
+    None
+}
+
+/// Whether the cloudflared metrics endpoint answers on the current port.
+pub async fn metrics_reachable() -> bool {
+    fetch_metrics_body(current_metrics_port(), std::time::Duration::from_secs(1))
+        .await
+        .is_ok()
+}
+
+/// Probe `port_range` on localhost for a cloudflared metrics endpoint.
+pub async fn auto_discover_metrics_port(port_range: RangeInclusive<u16>) -> Option<u16> {
+    probe_metrics_ports(port_range, |port| async move {
+        fetch_metrics_body(port, std::time::Duration::from_millis(300))
+            .await
+            .ok()
+    })
+    .await
 }
 
 fn parse_prometheus(body: &str) -> TunnelMetrics {
@@ -227,8 +779,18 @@ fn parse_prometheus(body: &str) -> TunnelMetrics {
         }
         if let Some(val) = extract_metric(line, "cloudflared_tunnel_total_requests") {
             m.total_requests = Some(m.total_requests.unwrap_or(0.0) + val);
+            if let Some(host) = extract_label(line, "host") {
+                *m.requests_by_host.entry(host).or_insert(0.0) += val;
+            }
         } else if let Some(val) = extract_metric(line, "cloudflared_tunnel_active_streams") {
             m.active_streams = Some(m.active_streams.unwrap_or(0.0) + val);
+            if let Some(colo) = extract_label(line, "colo_name") {
+                *m.streams_by_colo.entry(colo).or_insert(0.0) += val;
+            }
+        } else if let Some(val) = extract_metric(line, "cloudflared_tunnel_bytes_sent") {
+            m.bytes_sent = Some(m.bytes_sent.unwrap_or(0.0) + val);
+        } else if let Some(val) = extract_metric(line, "cloudflared_tunnel_bytes_received") {
+            m.bytes_received = Some(m.bytes_received.unwrap_or(0.0) + val);
         } else if let Some(val) = extract_metric(line, "cloudflared_tunnel_request_errors") {
             m.request_errors = Some(m.request_errors.unwrap_or(0.0) + val);
         } else if let Some(val) = extract_metric(line, "cloudflared_tunnel_response_by_code") {
@@ -254,6 +816,94 @@ fn extract_metric(line: &str, prefix: &str) -> Option<f64> {
     }
 }
 
+/// Extract a label value from a Prometheus sample line,
+/// e.g. `colo_name` from `metric{connection_id="0",colo_name="SJC"} 3`.
+fn extract_label(line: &str, key: &str) -> Option<String> {
+    let labels = line.split_once('{')?.1.split_once('}')?.0;
+    labels.split(',').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        if k.trim() == key {
+            Some(v.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Prometheus file export (textfile federation)
+// ---------------------------------------------------------------------------
+
+/// Prefix added to every exported metric name.
+const EXPORT_NAMESPACE: &str = "opentunnel_";
+
+/// Re-export interval used by `tunnel export-metrics --watch` by default.
+pub const DEFAULT_EXPORT_INTERVAL_SECS: u64 = 15;
+
+/// Rewrite Prometheus text with every metric name prefixed by
+/// [`EXPORT_NAMESPACE`], under a generated-by header.
+fn namespace_metrics(body: &str) -> String {
+    let mut out = String::from("# GENERATED BY openTunnel\n");
+    for line in body.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let rewritten = if let Some(rest) = line.strip_prefix("# HELP ") {
+            format!("# HELP {EXPORT_NAMESPACE}{rest}")
+        } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+            format!("# TYPE {EXPORT_NAMESPACE}{rest}")
+        } else if line.starts_with('#') {
+            line.to_string()
+        } else {
+            format!("{EXPORT_NAMESPACE}{line}")
+        };
+        out.push_str(&rewritten);
+        out.push('\n');
+    }
+    out
+}
+
+/// Fetch cloudflared metrics and write them, namespaced, to `path`.
+/// The file is replaced atomically so a collector never reads half of it.
+pub async fn export_metrics_prometheus(path: &Path) -> Result<()> {
+    let body =
+        fetch_metrics_body(current_metrics_port(), std::time::Duration::from_secs(5)).await?;
+    config::write_atomic(path, namespace_metrics(&body).as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// `tunnel export-metrics`: export once, or every `interval` seconds with `watch`.
+pub async fn export_metrics(path: &Path, watch: bool, interval: u64) -> Result<()> {
+    let l = lang();
+    if !watch {
+        export_metrics_prometheus(path).await?;
+        println!(
+            "{} {} {}",
+            "✅".green(),
+            t!(l, "Metrics written to", "指标已写入"),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {} {}s {}",
+        t!(l, "Exporting metrics to", "正在导出指标到"),
+        path.display(),
+        interval.max(1),
+        t!(l, "interval (Ctrl+C to stop)", "间隔（Ctrl+C 停止）")
+    );
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+    loop {
+        ticker.tick().await;
+        let now = chrono::Local::now().format("%H:%M:%S");
+        match export_metrics_prometheus(path).await {
+            Ok(()) => println!("  {} {now}", "✅".green()),
+            Err(e) => println!("  {} {now} {e:#}", "⚠️".yellow()),
+        }
+    }
+}
+
 fn format_metric(val: Option<f64>) -> String {
     match val {
         Some(v) if v >= 1_000_000.0 => format!("{:.1}M", v / 1_000_000.0),
@@ -267,6 +917,79 @@ fn format_metric(val: Option<f64>) -> String {
 mod tests {
     use super::*;
 
+    fn metrics(total: f64, streams: f64, errors: f64) -> TunnelMetrics {
+        TunnelMetrics {
+            total_requests: Some(total),
+            active_streams: Some(streams),
+            request_errors: Some(errors),
+            ..Default::default()
+        }
+    }
+
+    fn is_metric_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    }
+
+    #[test]
+    fn exported_metrics_are_valid_prometheus_text() {
+        let body = "\
+# HELP cloudflared_tunnel_total_requests Amount of requests proxied
+# TYPE cloudflared_tunnel_total_requests counter
+cloudflared_tunnel_total_requests 42
+
+cloudflared_tunnel_active_streams{connection_id=\"0\",colo_name=\"SJC\"} 3
+go_goroutines 17
+";
+        let out = namespace_metrics(body);
+        assert!(out.starts_with("# GENERATED BY openTunnel\n"));
+        assert!(out.contains("# TYPE opentunnel_cloudflared_tunnel_total_requests counter"));
+        assert!(out.contains("opentunnel_go_goroutines 17"));
+
+        let mut samples = 0;
+        for line in out.lines() {
+            assert!(!line.is_empty());
+            if line.starts_with('#') {
+                continue;
+            }
+            let name = line.split(['{', ' ']).next().unwrap();
+            assert!(is_metric_name(name), "bad metric name: {name}");
+            assert!(name.starts_with(EXPORT_NAMESPACE));
+            samples += 1;
+        }
+        assert_eq!(samples, 3);
+    }
+
+    #[test]
+    fn health_thresholds() {
+        assert_eq!(
+            TunnelHealth::from_metrics(&metrics(100.0, 0.0, 0.0)),
+            TunnelHealth::Inactive
+        );
+        assert_eq!(
+            TunnelHealth::from_metrics(&TunnelMetrics::default()).exit_code(),
+            2
+        );
+        assert_eq!(
+            TunnelHealth::from_metrics(&metrics(100.0, 1.0, 5.0)),
+            TunnelHealth::Healthy
+        );
+        let degraded = TunnelHealth::from_metrics(&metrics(100.0, 1.0, 6.0));
+        assert!(matches!(degraded, TunnelHealth::Degraded { .. }));
+        assert_eq!(degraded.exit_code(), 3);
+        assert_eq!(
+            TunnelHealth::from_metrics(&metrics(0.0, 3.0, 0.0)).exit_code(),
+            0
+        );
+        assert_eq!(
+            TunnelHealth::from_metrics(&metrics(0.0, 3.0, 1.0)).exit_code(),
+            3
+        );
+    }
+
     #[test]
     fn parse_prometheus_metrics() {
         let input = r#"# HELP cloudflared_tunnel_total_requests Total number of requests
@@ -281,6 +1004,128 @@ cloudflared_tunnel_request_errors 3
         assert_eq!(m.request_errors, Some(3.0));
     }
 
+    #[test]
+    fn parse_prometheus_streams_by_colo() {
+        let input = r#"# TYPE cloudflared_tunnel_active_streams gauge
+cloudflared_tunnel_active_streams{connection_id="0",colo_name="SJC"} 12
+cloudflared_tunnel_active_streams{connection_id="1",colo_name="LAX"} 4
+cloudflared_tunnel_active_streams{connection_id="2",colo_name="SJC"} 8
+cloudflared_tunnel_active_streams{connection_id="3"} 1
+"#;
+        let m = parse_prometheus(input);
+        assert_eq!(m.active_streams, Some(25.0));
+        assert_eq!(m.streams_by_colo.get("SJC"), Some(&20.0));
+        assert_eq!(m.streams_by_colo.get("LAX"), Some(&4.0));
+        assert_eq!(m.streams_by_colo.len(), 2);
+        assert_eq!(m.colos_by_streams(), vec![("SJC", 20.0), ("LAX", 4.0)]);
+    }
+
+    #[test]
+    fn parse_prometheus_sums_bytes_over_connections() {
+        let input = r#"# TYPE cloudflared_tunnel_bytes_sent counter
+cloudflared_tunnel_bytes_sent{connection_id="0"} 1048576
+cloudflared_tunnel_bytes_sent{connection_id="1"} 12058624
+cloudflared_tunnel_bytes_received{connection_id="0"} 35861299
+cloudflared_tunnel_bytes_received{connection_id="1"} 0
+"#;
+        let m = parse_prometheus(input);
+        assert_eq!(m.bytes_sent, Some(13_107_200.0));
+        assert_eq!(m.bytes_received, Some(35_861_299.0));
+        assert_eq!(m.traffic_summary().as_deref(), Some("↑ 12.5 MB ↓ 34.2 MB"));
+
+        let none = parse_prometheus("cloudflared_tunnel_total_requests 1\n");
+        assert_eq!(none.bytes_sent, None);
+        assert_eq!(none.traffic_summary(), None);
+    }
+
+    #[test]
+    fn parse_prometheus_requests_by_host() {
+        let input = r#"cloudflared_tunnel_total_requests{host="app.example.com"} 30
+cloudflared_tunnel_total_requests{host="api.example.com"} 70
+cloudflared_tunnel_total_requests{host="app.example.com"} 10
+"#;
+        let m = parse_prometheus(input);
+        assert_eq!(m.total_requests, Some(110.0));
+        assert_eq!(
+            m.hosts_by_requests(),
+            vec![
+                ("api.example.com".to_string(), 70.0),
+                ("app.example.com".to_string(), 40.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn bar_chart_scales_and_pads() {
+        let data = vec![
+            ("api.example.com".to_string(), 100.0),
+            ("app.io".to_string(), 50.0),
+            ("idle".to_string(), 0.0),
+        ];
+        // 15 label + 3 count + 4 separators leaves 10 columns of bar.
+        let chart = bar_chart(&data, 32);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            format!("api.example.com  {}  100", "█".repeat(10))
+        );
+        assert_eq!(
+            lines[1],
+            format!("app.io           {}{}   50", "█".repeat(5), " ".repeat(5))
+        );
+        assert_eq!(
+            lines[2],
+            format!("idle             {}    0", " ".repeat(10))
+        );
+        assert!(lines.iter().all(|l| l.chars().count() == 32));
+    }
+
+    #[test]
+    fn bar_chart_keeps_one_column_for_bars() {
+        let data = vec![("host".to_string(), 5.0)];
+        assert_eq!(bar_chart(&data, 0), "host  █  5");
+        assert_eq!(bar_chart(&[], 40), "");
+    }
+
+    #[test]
+    fn extract_label_from_multi_label_line() {
+        let line = r#"metric{connection_id="abc", colo_name="AMS", ha="true"} 2"#;
+        assert_eq!(extract_label(line, "colo_name").as_deref(), Some("AMS"));
+        assert_eq!(extract_label(line, "ha").as_deref(), Some("true"));
+        assert_eq!(extract_label(line, "missing"), None);
+        assert_eq!(extract_label("metric 2", "colo_name"), None);
+    }
+
+    #[tokio::test]
+    async fn probe_finds_only_responding_port() {
+        let found = probe_metrics_ports(METRICS_PORT_RANGE, |port| async move {
+            match port {
+                20243 => Some(
+                    "# TYPE cloudflared_tunnel_total_requests counter\n\
+                     cloudflared_tunnel_total_requests 7\n"
+                        .to_string(),
+                ),
+                // Something else answering HTTP is not a metrics endpoint
+                20242 => Some("<html>hello</html>".to_string()),
+                _ => None,
+            }
+        })
+        .await;
+        assert_eq!(found, Some(20243));
+
+        let none = probe_metrics_ports(METRICS_PORT_RANGE, |_| async { None }).await;
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn stream_bar_one_block_per_ten() {
+        assert_eq!(stream_bar(0.0), "");
+        assert_eq!(stream_bar(3.0), "█");
+        assert_eq!(stream_bar(20.0), "██");
+        assert_eq!(stream_bar(21.0), "███");
+    }
+
     #[test]
     fn format_metric_values() {
         assert_eq!(format_metric(Some(500.0)), "500");
@@ -288,4 +1133,82 @@ cloudflared_tunnel_request_errors 3
         assert_eq!(format_metric(Some(2_500_000.0)), "2.5M");
         assert_eq!(format_metric(None), "-");
     }
+
+    #[test]
+    fn sparkline_edge_cases() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[0.0, 0.0, 0.0]), "▁▁▁");
+        assert_eq!(sparkline(&[42.0]), "▄");
+        assert_eq!(sparkline(&[7.0, 7.0, 7.0, 7.0]), "▄▄▄▄");
+        assert_eq!(
+            sparkline(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[10.0, 20.0, 15.0]), "▁█▅");
+    }
+
+    #[test]
+    fn history_keeps_newest_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics_history.jsonl");
+
+        let mut samples = Vec::new();
+        for i in 0..(HISTORY_LIMIT + 5) {
+            let m = metrics(i as f64, 1.0, 0.0);
+            let at = i as i64 * HISTORY_MIN_INTERVAL_SECS;
+            samples = record_sample(&path, MetricsSample::from_metrics(&m, at)).unwrap();
+        }
+        assert_eq!(samples.len(), HISTORY_LIMIT);
+        assert_eq!(samples[0].timestamp, 5 * HISTORY_MIN_INTERVAL_SECS);
+        assert_eq!(
+            samples.last().unwrap().total_requests,
+            Some((HISTORY_LIMIT + 4) as f64)
+        );
+
+        // Too soon after the last sample: nothing is appended or written
+        let written = std::fs::read_to_string(&path).unwrap();
+        let last = samples.last().unwrap().timestamp;
+        let m = metrics(0.0, 1.0, 0.0);
+        let again = record_sample(&path, MetricsSample::from_metrics(&m, last + 1)).unwrap();
+        assert_eq!(again, samples);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), written);
+
+        let line = history_sparkline(&samples, 4, |s| s.total_requests);
+        assert_eq!(line.chars().count(), 4);
+        assert!(line.ends_with('█'));
+        assert_eq!(
+            history_sparkline(&samples[..1], 4, |s| s.total_requests),
+            ""
+        );
+    }
+
+    #[test]
+    fn pause_state_toggles() {
+        let state = PauseState::default();
+        assert!(!state.is_paused());
+        assert!(state.toggle());
+        assert!(state.is_paused());
+        assert!(!state.toggle());
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn monitor_keys() {
+        assert_eq!(
+            classify_key(KeyCode::Char(' '), KeyModifiers::NONE),
+            MonitorKey::TogglePause
+        );
+        assert_eq!(
+            classify_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            MonitorKey::Quit
+        );
+        assert_eq!(
+            classify_key(KeyCode::Char('c'), KeyModifiers::NONE),
+            MonitorKey::Ignore
+        );
+        assert_eq!(
+            classify_key(KeyCode::Enter, KeyModifiers::NONE),
+            MonitorKey::Ignore
+        );
+    }
 }
@@ -1,8 +1,100 @@
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use comfy_table::{presets::UTF8_FULL, Table};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Password, Select, Sort};
+
+use crate::i18n::lang;
+use crate::t;
+
+// ---------------------------------------------------------------------------
+// Idle timeout (`tunnel --session-timeout`)
+// ---------------------------------------------------------------------------
+
+/// When the prompt now waiting for input was shown; `None` between prompts,
+/// so long-running commands are never cut short.
+static WAITING_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Marks a prompt as waiting for input until dropped.
+struct Waiting;
+
+impl Waiting {
+    fn start() -> Self {
+        if let Ok(mut since) = WAITING_SINCE.lock() {
+            *since = Some(Instant::now());
+        }
+        Waiting
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if let Ok(mut since) = WAITING_SINCE.lock() {
+            *since = None;
+        }
+    }
+}
+
+/// Whether a prompt shown at `since` has waited longer than `limit`.
+fn idle_expired(since: Option<Instant>, limit: Duration, now: Instant) -> bool {
+    since.is_some_and(|since| now.saturating_duration_since(since) >= limit)
+}
+
+/// Exit the process once any prompt has waited `limit` for input. The
+/// terminal settings in effect now are restored first, since the prompt
+/// being abandoned may have switched the terminal to raw mode.
+pub fn enable_idle_timeout(limit: Duration) {
+    let saved_tty = saved_terminal_mode();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        let since = WAITING_SINCE.lock().ok().and_then(|since| *since);
+        if idle_expired(since, limit, Instant::now()) {
+            restore_terminal(saved_tty.as_deref());
+            let l = lang();
+            eprintln!(
+                "\n{}",
+                t!(l, "Session timed out, exiting.", "会话超时，正在退出。")
+            );
+            std::process::exit(0);
+        }
+    });
+}
+
+/// `stty -g` output for the controlling terminal, if there is one.
+fn saved_terminal_mode() -> Option<String> {
+    if cfg!(windows) || !std::io::stdin().is_terminal() {
+        return None;
+    }
+    let output = Command::new("stty")
+        .arg("-g")
+        .stdin(std::process::Stdio::inherit())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Put back the terminal mode saved by [`saved_terminal_mode`] and show the
+/// cursor, which dialoguer hides while a prompt is active.
+fn restore_terminal(saved: Option<&str>) {
+    if let Some(mode) = saved {
+        let _ = Command::new("stty")
+            .arg(mode)
+            .stdin(std::process::Stdio::inherit())
+            .status();
+    }
+    eprint!("\x1B[?25h");
+    let _ = std::io::stderr().flush();
+}
 
 /// Show a selection list and return the selected index.
 /// Appends a "← Back (ESC)" item; returns `None` when that item is chosen or ESC is pressed.
 pub fn select_opt<T: ToString>(prompt: &str, items: &[T], default: Option<usize>) -> Option<usize> {
+    let _waiting = Waiting::start();
     let theme = ColorfulTheme::default();
     let mut all: Vec<String> = items.iter().map(|i| i.to_string()).collect();
     all.push("← Back (ESC)".to_string());
@@ -25,6 +117,7 @@ pub fn select_opt_result<T: ToString>(
     items: &[T],
     default: Option<usize>,
 ) -> anyhow::Result<Option<usize>> {
+    let _waiting = Waiting::start();
     let theme = ColorfulTheme::default();
     let mut all: Vec<String> = items.iter().map(|i| i.to_string()).collect();
     all.push("← Back (ESC)".to_string());
@@ -40,9 +133,161 @@ pub fn select_opt_result<T: ToString>(
     })
 }
 
+/// Page size used by pickers over API-sized lists (tunnels, records, apps).
+pub const DEFAULT_PAGE_SIZE: usize = 15;
+
+/// One entry on a page of [`paged_select`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageEntry {
+    Previous,
+    Next,
+    Search,
+    Item(usize),
+}
+
+/// Current position of a [`paged_select`] list: page number and search filter.
+#[derive(Debug, Default)]
+struct PagedView {
+    page: usize,
+    filter: Option<String>,
+}
+
+impl PagedView {
+    /// Indices of the labels matching the filter (case-insensitive substring).
+    fn matches(&self, labels: &[String]) -> Vec<usize> {
+        match self.filter.as_deref() {
+            Some(f) => {
+                let f = f.to_lowercase();
+                labels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, label)| label.to_lowercase().contains(&f))
+                    .map(|(i, _)| i)
+                    .collect()
+            }
+            None => (0..labels.len()).collect(),
+        }
+    }
+
+    /// Entries of the current page: navigation first, then the items on that page.
+    fn entries(&self, labels: &[String], page_size: usize) -> Vec<PageEntry> {
+        let matches = self.matches(labels);
+        let page_size = page_size.max(1);
+        let pages = matches.len().div_ceil(page_size).max(1);
+        let page = self.page.min(pages - 1);
+
+        let mut entries = Vec::new();
+        if page > 0 {
+            entries.push(PageEntry::Previous);
+        }
+        if page + 1 < pages {
+            entries.push(PageEntry::Next);
+        }
+        entries.push(PageEntry::Search);
+        entries.extend(
+            matches
+                .iter()
+                .skip(page * page_size)
+                .take(page_size)
+                .map(|&i| PageEntry::Item(i)),
+        );
+        entries
+    }
+}
+
+/// Show a selection list split into pages of `page_size` items.
+/// Lists that fit on one page behave like [`select_opt`]; longer lists get
+/// previous/next/search entries. Returns the index into `items`, or `None` when cancelled.
+pub fn paged_select<T: ToString>(
+    prompt: &str,
+    items: &[T],
+    page_size: usize,
+    default: Option<usize>,
+) -> Option<usize> {
+    if items.len() <= page_size {
+        return select_opt(prompt, items, default);
+    }
+
+    let labels: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+    let mut view = PagedView {
+        page: default.map(|d| d / page_size.max(1)).unwrap_or(0),
+        filter: None,
+    };
+
+    let l = lang();
+    loop {
+        let entries = view.entries(&labels, page_size);
+        let shown: Vec<String> = entries
+            .iter()
+            .map(|entry| match entry {
+                PageEntry::Previous => t!(l, "↑ Previous page", "↑ 上一页").to_string(),
+                PageEntry::Next => t!(l, "↓ Next page", "↓ 下一页").to_string(),
+                PageEntry::Search => {
+                    let search = t!(l, "🔍 Search...", "🔍 搜索...");
+                    match &view.filter {
+                        Some(f) => format!("{search} (\"{f}\")"),
+                        None => search.to_string(),
+                    }
+                }
+                PageEntry::Item(i) => labels[*i].clone(),
+            })
+            .collect();
+        let default_pos =
+            default.and_then(|d| entries.iter().position(|e| *e == PageEntry::Item(d)));
+
+        match entries.get(select_opt(prompt, &shown, default_pos)?)? {
+            PageEntry::Previous => view.page = view.page.saturating_sub(1),
+            PageEntry::Next => view.page += 1,
+            PageEntry::Search => {
+                let term = input_opt(
+                    t!(l, "Search (empty to clear)", "搜索（留空清除）"),
+                    true,
+                    view.filter.as_deref(),
+                )?;
+                let term = term.trim();
+                view.filter = (!term.is_empty()).then(|| term.to_string());
+                view.page = 0;
+            }
+            PageEntry::Item(i) => return Some(*i),
+        }
+    }
+}
+
+/// Let the user tick any number of `items` (space to toggle, enter to accept).
+/// Returns the selected indices, or `None` when cancelled or on interaction failure.
+pub fn multi_select_opt<T: ToString>(prompt: &str, items: &[T]) -> Option<Vec<usize>> {
+    let _waiting = Waiting::start();
+    MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .interact_opt()
+        .ok()
+        .flatten()
+}
+
+/// Let the user reorder `items` (space to pick up, arrows to move, enter to
+/// accept). Returns the new order as indices into `items`, or `None` when
+/// cancelled or on interaction failure.
+pub fn sort_opt<T: ToString>(prompt: &str, items: &[T]) -> Option<Vec<usize>> {
+    let _waiting = Waiting::start();
+    Sort::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .interact_opt()
+        .ok()
+        .flatten()
+}
+
+/// Whether prompts can be answered: stdin and stderr (where dialoguer draws)
+/// are both terminals. Scripts and pipes get defaults instead.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
 /// Show a confirmation prompt.
 /// Returns `Some(bool)` when answered, `None` when cancelled or on interaction failure.
 pub fn confirm_opt(prompt: &str, default: bool) -> Option<bool> {
+    let _waiting = Waiting::start();
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
         .default(default)
@@ -51,9 +296,75 @@ pub fn confirm_opt(prompt: &str, default: bool) -> Option<bool> {
         .flatten()
 }
 
+/// Items that [`confirm_with_table`] can preview as table rows.
+pub trait TableRow {
+    /// Column headers.
+    fn header() -> Vec<String>;
+    /// This item's cells, one per header.
+    fn row(&self) -> Vec<String>;
+}
+
+/// `items` as a bulleted list, one per line.
+fn bullet_list<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| format!("  • {}\n", i.to_string()))
+        .collect()
+}
+
+/// `rows` as a table with the type's headers.
+fn preview_table<T: TableRow>(rows: &[T]) -> String {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(T::header());
+    for row in rows {
+        table.add_row(row.row());
+    }
+    format!("{table}\n")
+}
+
+/// Print `preview` (if any), then ask for confirmation.
+fn confirm_after_preview(prompt: &str, preview: &str, default: bool) -> Option<bool> {
+    if !preview.is_empty() {
+        print!("{preview}");
+        let _ = std::io::stdout().flush();
+    }
+    confirm_opt(prompt, default)
+}
+
+/// Show what an operation will affect as a bulleted list, then ask for
+/// confirmation. Returns `None` when cancelled, like [`confirm_opt`].
+pub fn confirm_with_details<T: ToString>(
+    prompt: &str,
+    preview: &[T],
+    default: bool,
+) -> Option<bool> {
+    confirm_after_preview(prompt, &bullet_list(preview), default)
+}
+
+/// Like [`confirm_with_details`], but previews `rows` as a table.
+pub fn confirm_with_table<T: TableRow>(prompt: &str, rows: &[T], default: bool) -> Option<bool> {
+    let preview = if rows.is_empty() {
+        String::new()
+    } else {
+        preview_table(rows)
+    };
+    confirm_after_preview(prompt, &preview, default)
+}
+
+/// Require the user to type `phrase` exactly to confirm a destructive action.
+/// Returns `false` on mismatch, cancellation, or interaction failure.
+pub fn confirm_by_typing(prompt: &str, phrase: &str) -> bool {
+    let full_prompt = format!("{prompt} (type '{phrase}' to confirm)");
+    input_opt(&full_prompt, true, None)
+        .map(|v| v.trim() == phrase)
+        .unwrap_or(false)
+}
+
 /// Show a text input prompt.
 /// Returns `None` when cancelled or on interaction failure.
 pub fn input_opt(prompt: &str, allow_empty: bool, initial: Option<&str>) -> Option<String> {
+    let _waiting = Waiting::start();
     let theme = ColorfulTheme::default();
     let mut input = Input::<String>::with_theme(&theme).with_prompt(prompt);
     if allow_empty {
@@ -65,10 +376,287 @@ pub fn input_opt(prompt: &str, allow_empty: bool, initial: Option<&str>) -> Opti
     input.interact_text().ok()
 }
 
+/// Show a hidden input prompt for secrets such as API tokens.
+/// Returns `None` when cancelled or on interaction failure.
+pub fn password_opt(prompt: &str) -> Option<String> {
+    let _waiting = Waiting::start();
+    let theme = ColorfulTheme::default();
+    Password::with_theme(&theme)
+        .with_prompt(prompt)
+        .interact()
+        .ok()
+}
+
+/// Show a text input prompt that re-asks until `validate` accepts the value.
+/// Returns `None` when cancelled or on interaction failure.
+pub fn input_validated<F>(prompt: &str, initial: Option<&str>, validate: F) -> Option<String>
+where
+    F: Fn(&str) -> Result<(), String>,
+{
+    let _waiting = Waiting::start();
+    let theme = ColorfulTheme::default();
+    let mut input = Input::<String>::with_theme(&theme)
+        .with_prompt(prompt)
+        .validate_with(|v: &String| validate(v));
+    if let Some(v) = initial {
+        input = input.with_initial_text(v);
+    }
+    input.interact_text().ok()
+}
+
+/// Editor used by [`multiline_input`]: `$EDITOR`, else `nano` (`notepad` on Windows).
+fn editor_command() -> String {
+    std::env::var("EDITOR")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "nano".to_string()
+            }
+        })
+}
+
+/// Open `initial` in `editor` (a command line such as `code --wait`) and
+/// return the saved text without trailing newlines. The temp file is created
+/// fresh with a random name and removed afterwards.
+fn edit_in_editor(editor: &str, initial: Option<&str>) -> std::io::Result<String> {
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no editor"))?;
+
+    let mut file = tempfile::Builder::new()
+        .prefix("opentunnel-edit-")
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(initial.unwrap_or("").as_bytes())?;
+    // Close our handle so the editor may replace the file; the path is
+    // still removed when dropped
+    let path = file.into_temp_path();
+    let status = Command::new(program).args(parts).arg(&path).status();
+    let text = match status {
+        Ok(s) if s.success() => std::fs::read_to_string(&path)?,
+        Ok(s) => return Err(std::io::Error::other(format!("editor exited with {s}"))),
+        Err(e) => return Err(e),
+    };
+    Ok(text.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Edit a multi-line value in `$EDITOR`. Falls back to single-line input
+/// when no editor can be started. Returns `None` when cancelled.
+pub fn multiline_input(prompt: &str, initial: Option<&str>) -> Option<String> {
+    let editor = editor_command();
+    println!("{prompt} ({editor})");
+    match edit_in_editor(&editor, initial) {
+        Ok(text) if !text.trim().is_empty() => Some(text),
+        Ok(_) => None,
+        Err(e) => {
+            println!("⚠️  {editor}: {e}");
+            input_opt(prompt, false, initial)
+        }
+    }
+}
+
 /// Wait for the user to press Enter.
 pub fn pause(prompt: &str) {
     use std::io::{self, Write};
+    let _waiting = Waiting::start();
     print!("{}", prompt);
     let _ = io::stdout().flush();
     let _ = io::stdin().read_line(&mut String::new());
 }
+
+/// Range Cloudflare accepts for a non-automatic TTL, in seconds.
+const CUSTOM_TTL_RANGE: std::ops::RangeInclusive<u32> = 60..=86400;
+
+/// Check a custom TTL entered at the prompt.
+fn validate_custom_ttl(value: &str) -> Result<(), String> {
+    let l = lang();
+    match value.trim().parse::<u32>() {
+        Ok(secs) if CUSTOM_TTL_RANGE.contains(&secs) => Ok(()),
+        _ => Err(t!(
+            l,
+            "enter a whole number of seconds from 60 to 86400",
+            "请输入 60 到 86400 之间的整数秒数"
+        )
+        .to_string()),
+    }
+}
+
+/// Show a TTL picker with common presets.
+/// Returns the TTL in seconds (`1` means automatic), or `None` when cancelled.
+pub fn select_ttl(prompt: &str, default: Option<u32>) -> Option<u32> {
+    let l = lang();
+    let presets: [(u32, &str); 7] = [
+        (1, t!(l, "Auto", "自动")),
+        (120, t!(l, "2 minutes", "2 分钟")),
+        (300, t!(l, "5 minutes", "5 分钟")),
+        (600, t!(l, "10 minutes", "10 分钟")),
+        (1800, t!(l, "30 minutes", "30 分钟")),
+        (3600, t!(l, "1 hour", "1 小时")),
+        (86400, t!(l, "1 day", "1 天")),
+    ];
+
+    let mut items: Vec<String> = presets
+        .iter()
+        .map(|(secs, label)| format!("{label} ({secs})"))
+        .collect();
+    items.push(t!(l, "Custom...", "自定义...").to_string());
+    let custom_idx = items.len() - 1;
+
+    let default_idx = match default {
+        Some(d) => presets
+            .iter()
+            .position(|(secs, _)| *secs == d)
+            .unwrap_or(custom_idx),
+        None => 0,
+    };
+
+    match select_opt(prompt, &items, Some(default_idx))? {
+        i if i == custom_idx => {
+            let initial = default
+                .filter(|d| CUSTOM_TTL_RANGE.contains(d))
+                .map(|d| d.to_string());
+            let raw = input_validated(
+                t!(l, "TTL (seconds, 60-86400)", "TTL（秒，60-86400）"),
+                initial.as_deref(),
+                validate_custom_ttl,
+            )?;
+            raw.trim().parse::<u32>().ok()
+        }
+        i => presets.get(i).map(|(secs, _)| *secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_timeout_only_counts_waiting_prompts() {
+        let now = Instant::now();
+        let limit = Duration::from_secs(60);
+        assert!(!idle_expired(None, limit, now));
+        assert!(!idle_expired(Some(now), limit, now));
+        let shown = now - Duration::from_secs(61);
+        assert!(idle_expired(Some(shown), limit, now));
+    }
+
+    #[test]
+    fn custom_ttl_must_be_in_range() {
+        assert!(validate_custom_ttl("60").is_ok());
+        assert!(validate_custom_ttl(" 86400 ").is_ok());
+        assert!(validate_custom_ttl("59").is_err());
+        assert!(validate_custom_ttl("86401").is_err());
+        assert!(validate_custom_ttl("1h").is_err());
+    }
+
+    struct Rule(&'static str, &'static str);
+
+    impl TableRow for Rule {
+        fn header() -> Vec<String> {
+            vec!["Hostname".to_string(), "Service".to_string()]
+        }
+
+        fn row(&self) -> Vec<String> {
+            vec![self.0.to_string(), self.1.to_string()]
+        }
+    }
+
+    #[test]
+    fn bullet_list_has_one_line_per_item() {
+        let preview = bullet_list(&["app.example.com", "api.example.com"]);
+        assert_eq!(preview, "  • app.example.com\n  • api.example.com\n");
+        assert!(bullet_list::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn table_preview_has_header_and_rows() {
+        let table = preview_table(&[Rule("app.example.com", "http://localhost:3000")]);
+        assert!(table.contains("Hostname"));
+        assert!(table.contains("app.example.com"));
+        assert!(table.contains("http://localhost:3000"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn edit_in_editor_round_trips_temp_file() {
+        // `true` leaves the file untouched, so the initial text comes back
+        let text =
+            edit_in_editor("true", Some("v=spf1\ninclude:_spf.example.com -all\n\n")).unwrap();
+        assert_eq!(text, "v=spf1\ninclude:_spf.example.com -all");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn edit_in_editor_reports_failures() {
+        assert!(edit_in_editor("opentunnel-no-such-editor", None).is_err());
+        assert!(edit_in_editor("false", Some("x")).is_err());
+        assert!(edit_in_editor("  ", None).is_err());
+    }
+
+    fn labels() -> Vec<String> {
+        (0..100).map(|i| format!("item-{i:03}")).collect()
+    }
+
+    fn items(entries: &[PageEntry]) -> Vec<usize> {
+        entries
+            .iter()
+            .filter_map(|e| match e {
+                PageEntry::Item(i) => Some(*i),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paged_view_navigates_pages() {
+        let labels = labels();
+        let mut view = PagedView::default();
+
+        let first = view.entries(&labels, 15);
+        assert_eq!(first[..2], [PageEntry::Next, PageEntry::Search]);
+        assert_eq!(items(&first), (0..15).collect::<Vec<_>>());
+
+        view.page = 1;
+        let second = view.entries(&labels, 15);
+        assert_eq!(
+            second[..3],
+            [PageEntry::Previous, PageEntry::Next, PageEntry::Search]
+        );
+        assert_eq!(items(&second), (15..30).collect::<Vec<_>>());
+
+        view.page = 6;
+        let last = view.entries(&labels, 15);
+        assert_eq!(last[..2], [PageEntry::Previous, PageEntry::Search]);
+        assert_eq!(items(&last), (90..100).collect::<Vec<_>>());
+
+        // Pages past the end clamp to the last page.
+        view.page = 42;
+        assert_eq!(
+            items(&view.entries(&labels, 15)),
+            (90..100).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn paged_view_search_filters_by_substring() {
+        let labels = labels();
+        let view = PagedView {
+            page: 0,
+            filter: Some("ITEM-05".to_string()),
+        };
+
+        let entries = view.entries(&labels, 15);
+        assert_eq!(entries[0], PageEntry::Search);
+        assert_eq!(items(&entries), (50..60).collect::<Vec<_>>());
+
+        let none = PagedView {
+            page: 0,
+            filter: Some("nope".to_string()),
+        };
+        assert_eq!(none.entries(&labels, 15), vec![PageEntry::Search]);
+    }
+}
@@ -1,14 +1,25 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{bail, Context};
 use colored::Colorize;
 use comfy_table::{presets::UTF8_FULL, Table};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time::{timeout, Duration};
 
+use crate::client::CloudflareClient;
 use crate::error::Result;
-use crate::i18n::lang;
+use crate::i18n::{lang, Lang};
 use crate::t;
+use crate::util::{self, spawn_bounded};
+use crate::{config, dns, prompt, tunnel};
 
 /// Well-known development ports and their descriptions.
-const DEFAULT_PORTS: &[(u16, &str, &str)] = &[
+pub(crate) const DEFAULT_PORTS: &[(u16, &str, &str)] = &[
     (80, "HTTP", "HTTP"),
     (443, "HTTPS", "HTTPS"),
     (3000, "React / Node.js", "React / Node.js"),
@@ -28,44 +39,143 @@ const DEFAULT_PORTS: &[(u16, &str, &str)] = &[
     (27017, "MongoDB", "MongoDB"),
 ];
 
-/// Scan local ports for running services, optionally with custom ports.
-pub async fn scan_local_services(extra_ports: Option<String>, timeout_ms: u64) -> Result<()> {
-    let l = lang();
-    println!(
-        "\n{}",
-        t!(l, "🔍 Scanning local services...", "🔍 扫描本地服务...").bold()
-    );
+/// Default number of ports probed at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 100;
+
+/// Scans with more ports than this show a progress bar.
+const PROGRESS_THRESHOLD: usize = 100;
+
+/// Well-known development ports whose usual service answers plain HTTP with
+/// a recognisable banner. Only these are sent a `GET /`; other open ports,
+/// including custom and `--ports` ones, are never probed.
+const FINGERPRINT_PORTS: &[u16] = &[3000, 3001, 4000, 4200, 5000, 5173, 8000, 8888, 9000, 9090];
+
+/// How long to listen for a greeting before sending `GET /`. Protocols that
+/// speak first (SSH, SMTP, MySQL...) are not HTTP and are left alone.
+const PASSIVE_BANNER_WAIT: Duration = Duration::from_millis(200);
+
+/// Response fragments (lowercase) that identify an HTTP service.
+const HTTP_FINGERPRINTS: &[(&str, &str)] = &[
+    ("jupyter", "Jupyter"),
+    ("grafana", "Grafana"),
+    ("prometheus", "Prometheus"),
+    ("sonarqube", "SonarQube"),
+    ("werkzeug", "Flask"),
+    ("wsgiserver", "Django"),
+    ("server: uvicorn", "Uvicorn"),
+    ("/@vite/client", "Vite"),
+    ("ng-version", "Angular"),
+    ("__next", "Next.js / Node.js"),
+    ("x-powered-by: express", "Express / Node.js"),
+    ("phoenix", "Phoenix"),
+];
+
+/// Bytes of the HTTP response inspected when fingerprinting.
+const FINGERPRINT_READ_LIMIT: usize = 8192;
+
+/// Name the service behind an HTTP response, if it carries a known banner.
+fn identify_banner(response: &str) -> Option<&'static str> {
+    let lower = response.to_lowercase();
+    HTTP_FINGERPRINTS
+        .iter()
+        .find(|(needle, _)| lower.contains(needle))
+        .map(|&(_, name)| name)
+}
+
+/// Whether `detected` contradicts the expected `description`. Generic
+/// descriptions (HTTP, HTTPS, custom) never conflict.
+fn is_misidentified(description: &str, detected: &str) -> bool {
+    let expected = description.to_lowercase();
+    if expected == "custom" || expected.starts_with("http") {
+        return false;
+    }
+    !detected
+        .split('/')
+        .map(|part| part.trim().to_lowercase())
+        .any(|part| !part.is_empty() && expected.contains(&part))
+}
 
+/// Send a plain `GET /` to a local port and identify the response banner.
+/// Nothing is sent when the service greets first.
+async fn http_fingerprint(port: u16, timeout_ms: u64) -> Option<&'static str> {
     let dur = Duration::from_millis(timeout_ms);
+    let mut stream = timeout(dur, TcpStream::connect(("127.0.0.1", port)))
+        .await
+        .ok()?
+        .ok()?;
+    // Any read that finishes in time is a greeting (a non-HTTP protocol) or
+    // a closed connection; either way there is nothing to fingerprint
+    let mut greeting = [0u8; 64];
+    if timeout(PASSIVE_BANNER_WAIT.min(dur), stream.read(&mut greeting))
+        .await
+        .is_ok()
+    {
+        return None;
+    }
+    let request = format!("GET / HTTP/1.0\r\nHost: localhost:{port}\r\n\r\n");
+    timeout(dur, stream.write_all(request.as_bytes()))
+        .await
+        .ok()?
+        .ok()?;
 
-    // Build full port list
-    let mut ports: Vec<(u16, String)> = DEFAULT_PORTS
+    let mut buf = Vec::with_capacity(FINGERPRINT_READ_LIMIT);
+    let mut chunk = [0u8; 2048];
+    while buf.len() < FINGERPRINT_READ_LIMIT {
+        match timeout(dur, stream.read(&mut chunk)).await {
+            Ok(Ok(n)) if n > 0 => buf.extend_from_slice(&chunk[..n]),
+            _ => break,
+        }
+    }
+    identify_banner(&String::from_utf8_lossy(&buf))
+}
+
+/// Fingerprint open HTTP ports and return `(port, expected, detected)` for
+/// each one whose banner contradicts its well-known description.
+async fn find_misidentified(
+    found: &[(u16, String)],
+    timeout_ms: u64,
+    concurrency: usize,
+) -> Vec<(u16, String, &'static str)> {
+    let candidates: Vec<(u16, String)> = found
         .iter()
-        .map(|&(p, en, _zh)| (p, en.to_string()))
+        .filter(|(port, _)| FINGERPRINT_PORTS.contains(port))
+        .cloned()
         .collect();
+    let handles = spawn_bounded(candidates, concurrency, |(port, desc)| async move {
+        (port, desc, http_fingerprint(port, timeout_ms).await)
+    });
 
-    // Parse extra ports
-    if let Some(extra) = extra_ports {
-        for part in extra.split(',') {
-            if let Ok(p) = part.trim().parse::<u16>() {
-                if !ports.iter().any(|(pp, _)| *pp == p) {
-                    ports.push((p, "custom".to_string()));
-                }
+    let mut conflicts = Vec::new();
+    for handle in handles {
+        if let Ok((port, desc, Some(detected))) = handle.await {
+            if is_misidentified(&desc, detected) {
+                conflicts.push((port, desc, detected));
             }
         }
     }
+    conflicts.sort_by_key(|(port, _, _)| *port);
+    conflicts
+}
 
-    // Scan concurrently
-    let mut handles = Vec::new();
-    for (port, desc) in &ports {
-        let port = *port;
-        let desc = desc.clone();
-        handles.push(tokio::spawn(async move {
+/// Probe `ports` on localhost and return the open ones, sorted by port.
+async fn probe_ports(
+    ports: Vec<(u16, String)>,
+    timeout_ms: u64,
+    concurrency: usize,
+    progress: Option<ProgressBar>,
+) -> Vec<(u16, String)> {
+    let dur = Duration::from_millis(timeout_ms);
+    let handles = spawn_bounded(ports, concurrency, |(port, desc)| {
+        let progress = progress.clone();
+        async move {
             let addr = format!("127.0.0.1:{port}");
             let open = matches!(timeout(dur, TcpStream::connect(&addr)).await, Ok(Ok(_)));
+            if let Some(pb) = progress {
+                pb.inc(1);
+            }
             (port, desc, open)
-        }));
-    }
+        }
+    });
 
     let mut found = Vec::new();
     for handle in handles {
@@ -75,49 +185,628 @@ pub async fn scan_local_services(extra_ports: Option<String>, timeout_ms: u64) -
             }
         }
     }
+    if let Some(pb) = progress {
+        pb.finish_and_clear();
+    }
 
     found.sort_by_key(|(p, _)| *p);
+    found
+}
 
-    // Display results
-    if found.is_empty() {
+// ---------------------------------------------------------------------------
+// Custom scan ports (<config dir>/scan_ports.json)
+// ---------------------------------------------------------------------------
+
+/// A user-defined port added to the default scan list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomPort {
+    pub port: u16,
+    pub description: String,
+}
+
+fn custom_ports_path() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("scan_ports.json"))
+}
+
+fn read_custom_ports() -> Result<Vec<CustomPort>> {
+    let path = custom_ports_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn save_custom_ports(ports: &[CustomPort]) -> Result<()> {
+    let path = custom_ports_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    config::write_atomic(&path, serde_json::to_string_pretty(ports)?.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Custom ports from `<config dir>/scan_ports.json`; empty (with a warning)
+/// when the file is missing or unreadable.
+pub fn load_custom_ports() -> Vec<(u16, String)> {
+    match read_custom_ports() {
+        Ok(ports) => ports.into_iter().map(|p| (p.port, p.description)).collect(),
+        Err(e) => {
+            eprintln!("{} {e:#}", "⚠️".yellow());
+            Vec::new()
+        }
+    }
+}
+
+/// Merge `custom` into `ports`: a custom entry replaces the description of
+/// an existing port, otherwise it is appended.
+fn merge_ports(ports: &mut Vec<(u16, String)>, custom: Vec<(u16, String)>) {
+    for (port, description) in custom {
+        match ports.iter_mut().find(|(p, _)| *p == port) {
+            Some(existing) => existing.1 = description,
+            None => ports.push((port, description)),
+        }
+    }
+}
+
+/// The default ports (or `base`, when given) plus the user's custom ports.
+fn scan_port_list(base: Option<Vec<(u16, String)>>) -> Vec<(u16, String)> {
+    let mut ports = base.unwrap_or_else(|| {
+        DEFAULT_PORTS
+            .iter()
+            .map(|&(p, en, _zh)| (p, en.to_string()))
+            .collect()
+    });
+    merge_ports(&mut ports, load_custom_ports());
+    ports
+}
+
+// ---------------------------------------------------------------------------
+// IANA service name registry
+// ---------------------------------------------------------------------------
+
+/// Split CSV `content` into records. Handles quoted fields (with `""` escapes
+/// and embedded commas or newlines) and skips blank and `#` comment lines.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut at_record_start = true;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if at_record_start && !in_quotes {
+            if c == '#' {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            if c == '\n' || c == '\r' {
+                continue;
+            }
+        }
+        at_record_start = false;
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                at_record_start = true;
+            }
+            c => field.push(c),
+        }
+    }
+    if !at_record_start {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// TCP ports from IANA service registry CSV `content`. Columns are located by
+/// the header row ("Service Name", "Port Number", "Transport Protocol",
+/// "Description"); without one, rows are read as
+/// `port-number,transport,service-name,description`. Port ranges and rows
+/// without a service name are skipped; the first entry for a port wins.
+fn parse_iana_ports(content: &str) -> Vec<(u16, String)> {
+    let mut records = parse_csv(content).into_iter().peekable();
+    let mut columns = (0, 1, 2, 3);
+    if let Some(header) = records.next_if(|r| r.iter().any(|f| f.trim() == "Port Number")) {
+        let find = |name: &str| header.iter().position(|f| f.trim() == name);
+        if let (Some(port), Some(transport), Some(name), Some(description)) = (
+            find("Port Number"),
+            find("Transport Protocol"),
+            find("Service Name"),
+            find("Description"),
+        ) {
+            columns = (port, transport, name, description);
+        }
+    }
+    let (port_col, transport_col, name_col, description_col) = columns;
+    let field = |r: &[String], i: usize| r.get(i).map(|f| f.trim().to_string()).unwrap_or_default();
+
+    let mut ports: Vec<(u16, String)> = Vec::new();
+    for record in records {
+        if !field(&record, transport_col).eq_ignore_ascii_case("tcp") {
+            continue;
+        }
+        let Ok(port) = field(&record, port_col).parse::<u16>() else {
+            continue;
+        };
+        let name = field(&record, name_col);
+        if name.is_empty() || ports.iter().any(|(p, _)| *p == port) {
+            continue;
+        }
+        let description = field(&record, description_col);
+        let description = match description.lines().next() {
+            Some(line) if !line.trim().is_empty() => line.trim().to_string(),
+            _ => name,
+        };
+        ports.push((port, description));
+    }
+    ports
+}
+
+/// Load the TCP ports of an IANA service name registry CSV
+/// (`service-names-port-numbers.csv`), for scanning instead of the default list.
+pub fn load_iana_ports(path: &Path) -> Result<Vec<(u16, String)>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let ports = parse_iana_ports(&content);
+    if ports.is_empty() {
+        bail!("no TCP ports found in {}", path.display());
+    }
+    Ok(ports)
+}
+
+pub fn list_custom_ports() -> Result<()> {
+    let l = lang();
+    let ports = read_custom_ports()?;
+    if ports.is_empty() {
         println!(
-            "\n{}",
+            "{}",
             t!(
                 l,
-                "No running services detected on common ports.",
-                "未在常见端口上发现运行中的服务。"
+                "No custom scan ports. Add one with `tunnel config scan-ports add <port> <description>`.",
+                "暂无自定义扫描端口。使用 `tunnel config scan-ports add <端口> <描述>` 添加。"
             )
-            .yellow()
         );
         return Ok(());
     }
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![t!(l, "Port", "端口"), t!(l, "Service", "服务")]);
+    for p in &ports {
+        table.add_row(vec![p.port.to_string(), p.description.clone()]);
+    }
+    println!("{table}");
+    Ok(())
+}
 
+pub fn add_custom_port(port: u16, description: String) -> Result<()> {
+    let l = lang();
+    let mut ports = read_custom_ports()?;
+    match ports.iter_mut().find(|p| p.port == port) {
+        Some(existing) => existing.description = description,
+        None => ports.push(CustomPort { port, description }),
+    }
+    ports.sort_by_key(|p| p.port);
+    save_custom_ports(&ports)?;
     println!(
-        "\n{} {} {}:\n",
+        "{} {} {port}",
         "✅".green(),
-        t!(l, "Found", "发现"),
-        found.len()
+        t!(l, "Saved scan port", "已保存扫描端口")
     );
+    Ok(())
+}
 
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
-    table.set_header(vec![t!(l, "Port", "端口"), t!(l, "Service", "服务")]);
+pub fn remove_custom_port(port: u16) -> Result<()> {
+    let l = lang();
+    let mut ports = read_custom_ports()?;
+    let before = ports.len();
+    ports.retain(|p| p.port != port);
+    if ports.len() == before {
+        println!(
+            "{} {port} {}",
+            "⚠️".yellow(),
+            t!(l, "is not a custom scan port.", "不是自定义扫描端口。")
+        );
+        return Ok(());
+    }
+    save_custom_ports(&ports)?;
+    println!(
+        "{} {} {port}",
+        "✅".green(),
+        t!(l, "Removed scan port", "已移除扫描端口")
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Scan baseline (<config dir>/scan_baseline.json)
+// ---------------------------------------------------------------------------
+
+/// A service seen in a saved scan.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BaselineService {
+    pub port: u16,
+    pub service: String,
+}
+
+/// Services found by a scan saved with `--save-baseline`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScanBaseline {
+    /// RFC 3339 time of the saved scan.
+    pub scanned_at: String,
+    pub services: Vec<BaselineService>,
+}
 
-    for (port, desc) in &found {
-        table.add_row(vec![&port.to_string(), desc.as_str()]);
+impl ScanBaseline {
+    fn from_found(found: &[(u16, String)]) -> Self {
+        Self {
+            scanned_at: chrono::Utc::now().to_rfc3339(),
+            services: found
+                .iter()
+                .map(|(port, service)| BaselineService {
+                    port: *port,
+                    service: service.clone(),
+                })
+                .collect(),
+        }
     }
+}
 
-    println!("{table}");
+/// Services that appeared or went away since the baseline, matched by port.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ScanDiff {
+    new: Vec<(u16, String)>,
+    disappeared: Vec<(u16, String)>,
+}
+
+impl ScanDiff {
+    fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.disappeared.is_empty()
+    }
+}
+
+fn compare_scans(baseline: &ScanBaseline, found: &[(u16, String)]) -> ScanDiff {
+    ScanDiff {
+        new: found
+            .iter()
+            .filter(|(port, _)| !baseline.services.iter().any(|s| s.port == *port))
+            .cloned()
+            .collect(),
+        disappeared: baseline
+            .services
+            .iter()
+            .filter(|s| !found.iter().any(|(port, _)| *port == s.port))
+            .map(|s| (s.port, s.service.clone()))
+            .collect(),
+    }
+}
+
+fn scan_baseline_path() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("scan_baseline.json"))
+}
+
+/// The saved baseline, or `None` when there is none or it cannot be read.
+fn load_scan_baseline() -> Option<ScanBaseline> {
+    let content = fs::read_to_string(scan_baseline_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_scan_baseline(found: &[(u16, String)]) -> Result<PathBuf> {
+    let path = scan_baseline_path()?;
+    let json = serde_json::to_string_pretty(&ScanBaseline::from_found(found))?;
+    config::write_atomic(&path, json.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Print what changed since `baseline`, or that nothing did.
+fn print_scan_diff(baseline: &ScanBaseline, found: &[(u16, String)]) {
+    let l = lang();
+    let since =
+        util::age_since(&baseline.scanned_at).unwrap_or_else(|| baseline.scanned_at.clone());
+    let diff = compare_scans(baseline, found);
+    if diff.is_empty() {
+        println!(
+            "\n{} ({since})",
+            t!(l, "No changes since last scan", "与上次扫描相比无变化")
+        );
+        return;
+    }
+    println!(
+        "\n{} ({since}):",
+        t!(l, "Changes since last scan", "与上次扫描相比的变化").bold()
+    );
+    for (port, service) in &diff.new {
+        println!(
+            "  {} {} {port} {service}",
+            "🆕".green(),
+            t!(l, "New:", "新增:")
+        );
+    }
+    for (port, service) in &diff.disappeared {
+        println!(
+            "  {} {} {port} {service}",
+            "❌".red(),
+            t!(l, "Gone:", "消失:")
+        );
+    }
+}
+
+/// Probe the well-known ports without printing anything.
+/// Returns `(port, description)` for each running service.
+pub async fn discover_services(timeout_ms: u64, concurrency: usize) -> Vec<(u16, String)> {
+    probe_ports(scan_port_list(None), timeout_ms, concurrency, None).await
+}
 
+/// Scan local ports for running services, optionally with custom ports.
+/// With `api`, offers to map the services onto a tunnel through the API.
+/// Changes since the saved baseline are always shown; `summary` shows only
+/// those, and `save_baseline` replaces the baseline with this scan.
+pub async fn scan_local_services(
+    extra_ports: Option<String>,
+    timeout_ms: u64,
+    concurrency: usize,
+    api: bool,
+    summary: bool,
+    save_baseline: bool,
+    iana_db: Option<PathBuf>,
+) -> Result<()> {
+    let l = lang();
+    let base_ports = iana_db.as_deref().map(load_iana_ports).transpose()?;
     println!(
-        "\n💡 {}",
+        "\n{}",
+        t!(l, "🔍 Scanning local services...", "🔍 扫描本地服务...").bold()
+    );
+
+    // Build full port list
+    let mut ports = scan_port_list(base_ports);
+
+    // Parse extra ports
+    if let Some(extra) = extra_ports {
+        for part in extra.split(',') {
+            if let Ok(p) = part.trim().parse::<u16>() {
+                if !ports.iter().any(|(pp, _)| *pp == p) {
+                    ports.push((p, "custom".to_string()));
+                }
+            }
+        }
+    }
+
+    let total = ports.len();
+    let progress = if total > PROGRESS_THRESHOLD {
+        let pb = ProgressBar::new(total as u64);
+        if let Ok(style) = ProgressStyle::with_template(&format!(
+            "{{bar:40.cyan/blue}} {} {{pos}}/{{len}} {}",
+            t!(l, "Scanned", "已扫描"),
+            t!(l, "ports", "个端口")
+        )) {
+            pb.set_style(style);
+        }
+        Some(pb)
+    } else {
+        None
+    };
+
+    // Scan concurrently, bounded by the semaphore
+    let started = Instant::now();
+    let found = probe_ports(ports, timeout_ms, concurrency, progress).await;
+    let elapsed = started.elapsed();
+
+    println!(
+        "\n⏱️  {} {} {} {:.2}s",
+        t!(l, "Scanned", "已扫描"),
+        total,
+        t!(l, "ports in", "个端口，用时"),
+        elapsed.as_secs_f64()
+    );
+
+    // Display results
+    if !summary && found.is_empty() {
+        println!(
+            "\n{}",
+            t!(
+                l,
+                "No running services detected on common ports.",
+                "未在常见端口上发现运行中的服务。"
+            )
+            .yellow()
+        );
+    } else if !summary {
+        println!(
+            "\n{} {} {}:\n",
+            "✅".green(),
+            t!(l, "Found", "发现"),
+            found.len()
+        );
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec![t!(l, "Port", "端口"), t!(l, "Service", "服务")]);
+
+        for (port, desc) in &found {
+            table.add_row(vec![&port.to_string(), desc.as_str()]);
+        }
+
+        println!("{table}");
+    }
+
+    match load_scan_baseline() {
+        Some(baseline) => print_scan_diff(&baseline, &found),
+        None if summary && !save_baseline => println!(
+            "\n{}",
+            t!(
+                l,
+                "No baseline yet. Save one with `tunnel scan --save-baseline`.",
+                "尚无基线。使用 `tunnel scan --save-baseline` 保存。"
+            )
+        ),
+        None => {}
+    }
+    if save_baseline {
+        let path = save_scan_baseline(&found)?;
+        println!(
+            "\n{} {} {}",
+            "✅".green(),
+            t!(l, "Baseline saved to", "基线已保存到"),
+            path.display()
+        );
+    }
+    if summary || found.is_empty() {
+        return Ok(());
+    }
+
+    let conflicts = find_misidentified(&found, timeout_ms, concurrency).await;
+    if !conflicts.is_empty() {
+        println!("\n{}", t!(l, "Conflicts:", "冲突:").bold());
+        for (port, expected, detected) in &conflicts {
+            println!(
+                "  {} {} {port} {} {detected}, {} {expected}",
+                "⚠️".yellow(),
+                t!(l, "Possible misidentification: port", "可能识别错误: 端口"),
+                t!(l, "reports", "实际为"),
+                t!(l, "not", "而非")
+            );
+        }
+    }
+
+    if let Some(client) = mapping_client(api) {
+        return offer_mapping_creation(&client, &found).await;
+    }
+
+    println!("\n{}", local_mapping_hint(l));
+
+    Ok(())
+}
+
+/// Closing hint of a scan without `--api`, pointing at `tunnel map`.
+fn local_mapping_hint(l: Lang) -> String {
+    format!(
+        "💡 {}",
         t!(
             l,
             "Use `tunnel map` to create tunnel mappings for these services.",
             "使用 `tunnel map` 为这些服务创建隧道映射。"
         )
+    )
+}
+
+/// One line of the summary of mappings created through the API.
+fn api_mapping_line(hostname: &str, service: &str) -> String {
+    format!("  {} {hostname} → {service}", "[API]".cyan())
+}
+
+/// API client for `scan --api`; `None` without the flag or when the API is
+/// not configured (with a warning), leaving the plain scan output unchanged.
+fn mapping_client(api: bool) -> Option<CloudflareClient> {
+    if !api {
+        return None;
+    }
+    match CloudflareClient::from_effective_config() {
+        Ok(client) => Some(client),
+        Err(e) => {
+            println!("\n{} {e:#}", "⚠️".yellow());
+            None
+        }
+    }
+}
+
+/// Ports whose services speak TLS, mapped as `https://` origins.
+const TLS_PORTS: [u16; 2] = [443, 8443];
+
+/// Default origin URL for a service found on `port`.
+pub fn local_service_url(port: u16) -> String {
+    let scheme = if TLS_PORTS.contains(&port) {
+        "https"
+    } else {
+        "http"
+    };
+    format!("{scheme}://localhost:{port}")
+}
+
+/// Ask which of `found` to map onto a tunnel, add each mapping to the tunnel's
+/// remote configuration, then offer to sync DNS records.
+async fn offer_mapping_creation(client: &CloudflareClient, found: &[(u16, String)]) -> Result<()> {
+    let l = lang();
+    println!(
+        "\n{}",
+        t!(l, "Map services via the API:", "通过 API 创建映射:").bold()
     );
 
+    let tunnel_id = match tunnel::select_tunnel(client).await? {
+        Some(t) => t.id,
+        None => return Ok(()),
+    };
+
+    let mut created = Vec::new();
+    for (port, desc) in found {
+        let accept = prompt::confirm_opt(
+            &format!(
+                "{} {port} ({desc})?",
+                t!(l, "Map service on port", "映射端口上的服务")
+            ),
+            false,
+        );
+        match accept {
+            Some(true) => {}
+            Some(false) => continue,
+            None => break,
+        }
+
+        let hostname = match prompt::input_opt(
+            t!(
+                l,
+                "Hostname (e.g. app.example.com)",
+                "域名 (如 app.example.com)"
+            ),
+            false,
+            None,
+        ) {
+            Some(v) => v.trim().to_string(),
+            None => break,
+        };
+
+        let service = local_service_url(*port);
+        match tunnel::put_mapping(client, &tunnel_id, &hostname, &service).await {
+            Ok(()) => created.push((hostname, service)),
+            Err(e) => println!("  {} {} — {:#}", "❌".red(), hostname, e),
+        }
+    }
+
+    if created.is_empty() {
+        return Ok(());
+    }
+    println!();
+    for (hostname, service) in &created {
+        println!("{}", api_mapping_line(hostname, service));
+    }
+
+    if client.zone_id.is_some()
+        && prompt::confirm_opt(
+            t!(
+                l,
+                "Sync DNS records for this tunnel now?",
+                "现在同步该隧道的 DNS 记录？"
+            ),
+            true,
+        ) == Some(true)
+    {
+        dns::sync_tunnel_routes(client, Some(tunnel_id), true).await?;
+    }
     Ok(())
 }
 
@@ -125,6 +814,148 @@ pub async fn scan_local_services(extra_ports: Option<String>, timeout_ms: u64) -
 mod tests {
     use super::*;
 
+    const IANA_FIXTURE: &str = r#"# IANA service name registry excerpt
+Service Name,Port Number,Transport Protocol,Description,Assignee,Contact
+ssh,22,tcp,The Secure Shell (SSH) Protocol,[RFC4251],
+ssh,22,udp,The Secure Shell (SSH) Protocol,[RFC4251],
+http,80,tcp,World Wide Web HTTP,,
+www,80,tcp,World Wide Web HTTP,,
+,81,tcp,Unassigned,,
+x11,6000-6063,tcp,X Window System,,
+# commented out row
+postgresql,5432,tcp,"PostgreSQL Database, ""pg""",,
+redis,6379,tcp,"An advanced key-value cache
+and store",,
+http-alt,8080,tcp,,,
+domain,53,udp,Domain Name Server,,
+"#;
+
+    #[test]
+    fn iana_csv_keeps_tcp_single_ports() {
+        assert_eq!(
+            parse_iana_ports(IANA_FIXTURE),
+            vec![
+                (22, "The Secure Shell (SSH) Protocol".to_string()),
+                (80, "World Wide Web HTTP".to_string()),
+                (5432, r#"PostgreSQL Database, "pg""#.to_string()),
+                (6379, "An advanced key-value cache".to_string()),
+                (8080, "http-alt".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn iana_csv_without_header_uses_documented_column_order() {
+        let content = "443,tcp,https,HTTP over TLS\n443,udp,https,QUIC\n";
+        assert_eq!(
+            parse_iana_ports(content),
+            vec![(443, "HTTP over TLS".to_string())]
+        );
+    }
+
+    #[test]
+    fn csv_parser_handles_quotes_and_comments() {
+        let records = parse_csv("# note\na,\"b,c\",\"d \"\"e\"\"\"\r\n\nf,\"g\nh\"");
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_string(), "b,c".to_string(), r#"d "e""#.to_string()],
+                vec!["f".to_string(), "g\nh".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_scan_without_api_flag() {
+        assert!(mapping_client(false).is_none());
+        assert_eq!(
+            local_mapping_hint(Lang::En),
+            "💡 Use `tunnel map` to create tunnel mappings for these services."
+        );
+        assert_eq!(
+            local_mapping_hint(Lang::Zh),
+            "💡 使用 `tunnel map` 为这些服务创建隧道映射。"
+        );
+
+        let line = api_mapping_line("app.example.com", &local_service_url(3000));
+        assert!(line.contains("[API]"));
+        assert!(line.ends_with(" app.example.com → http://localhost:3000"));
+    }
+
+    #[test]
+    fn tls_ports_map_to_https() {
+        assert_eq!(local_service_url(443), "https://localhost:443");
+        assert_eq!(local_service_url(8443), "https://localhost:8443");
+        assert_eq!(local_service_url(8080), "http://localhost:8080");
+    }
+
+    #[test]
+    fn custom_ports_override_and_extend_defaults() {
+        let mut ports = vec![
+            (3000, "React / Node.js".to_string()),
+            (8080, "HTTP".to_string()),
+        ];
+        let custom: Vec<CustomPort> = serde_json::from_str(
+            r#"[{"port": 8080, "description": "Internal API"},
+                {"port": 7070, "description": "Billing"},
+                {"port": 7070, "description": "Billing v2"}]"#,
+        )
+        .unwrap();
+        merge_ports(
+            &mut ports,
+            custom
+                .into_iter()
+                .map(|p| (p.port, p.description))
+                .collect(),
+        );
+        assert_eq!(
+            ports,
+            vec![
+                (3000, "React / Node.js".to_string()),
+                (8080, "Internal API".to_string()),
+                (7070, "Billing v2".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_port_is_reported_by_scan() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut ports = Vec::new();
+        merge_ports(&mut ports, vec![(port, "Billing".to_string())]);
+        let found = probe_ports(ports, 500, 4, None).await;
+        assert_eq!(found, vec![(port, "Billing".to_string())]);
+    }
+
+    #[test]
+    fn scan_comparison_finds_new_and_disappeared() {
+        let baseline = ScanBaseline {
+            scanned_at: "2026-01-01T00:00:00Z".to_string(),
+            services: vec![
+                BaselineService {
+                    port: 3000,
+                    service: "React / Node.js".to_string(),
+                },
+                BaselineService {
+                    port: 5432,
+                    service: "PostgreSQL".to_string(),
+                },
+            ],
+        };
+        let found = vec![
+            (3000, "React / Node.js".to_string()),
+            (8080, "HTTP Alternate".to_string()),
+        ];
+        let diff = compare_scans(&baseline, &found);
+        assert_eq!(diff.new, vec![(8080, "HTTP Alternate".to_string())]);
+        assert_eq!(diff.disappeared, vec![(5432, "PostgreSQL".to_string())]);
+
+        let same = ScanBaseline::from_found(&found);
+        assert!(compare_scans(&same, &found).is_empty());
+        assert_eq!(compare_scans(&same, &[]).disappeared.len(), 2);
+    }
+
     #[test]
     fn default_ports_no_duplicates() {
         let mut seen = std::collections::HashSet::new();
@@ -140,6 +971,56 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn fingerprint_skips_services_that_greet_first() {
+        use tokio::net::TcpListener;
+
+        let ssh = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ssh_port = ssh.local_addr().unwrap().port();
+        let greeter = tokio::spawn(async move {
+            let (mut sock, _) = ssh.accept().await.unwrap();
+            sock.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await.unwrap();
+            let mut buf = Vec::new();
+            let _ = sock.read_to_end(&mut buf).await;
+            buf
+        });
+        assert_eq!(http_fingerprint(ssh_port, 1000).await, None);
+        assert!(greeter.await.unwrap().is_empty(), "GET sent to a greeter");
+
+        let jupyter = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jupyter_port = jupyter.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut sock, _) = jupyter.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = sock.read(&mut buf).await;
+            let _ = sock
+                .write_all(b"HTTP/1.1 200 OK\r\n\r\n<title>Jupyter Server</title>")
+                .await;
+        });
+        assert_eq!(http_fingerprint(jupyter_port, 1000).await, Some("Jupyter"));
+    }
+
+    #[test]
+    fn identify_banner_matches_known_services() {
+        let jupyter = "HTTP/1.1 302 Found\r\nLocation: /tree\r\n\r\n<title>Jupyter Server</title>";
+        assert_eq!(identify_banner(jupyter), Some("Jupyter"));
+        assert_eq!(
+            identify_banner("HTTP/1.1 200 OK\r\nX-Powered-By: Express\r\n\r\n"),
+            Some("Express / Node.js")
+        );
+        assert_eq!(identify_banner("HTTP/1.1 200 OK\r\n\r\nhello"), None);
+    }
+
+    #[test]
+    fn misidentification_rules() {
+        assert!(is_misidentified("React / Node.js", "Jupyter"));
+        assert!(!is_misidentified("React / Node.js", "Express / Node.js"));
+        assert!(!is_misidentified("Jupyter", "Jupyter"));
+        assert!(!is_misidentified("Flask / Python", "Flask"));
+        assert!(!is_misidentified("HTTP Alternate", "Grafana"));
+        assert!(!is_misidentified("custom", "Grafana"));
+    }
+
     #[test]
     fn default_ports_valid_range() {
         for &(port, _, _) in DEFAULT_PORTS {
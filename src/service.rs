@@ -1,12 +1,15 @@
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
+use serde::Deserialize;
 
 use crate::client::CloudflareClient;
 use crate::i18n::lang;
 use crate::prompt;
-use crate::{t, tunnel};
+use crate::{config, monitor, t, tunnel, util};
 
 const SERVICE_NAME: &str = "cloudflared";
 const LAUNCHD_LABEL: &str = "com.cloudflare.cloudflared";
@@ -21,8 +24,11 @@ pub async fn status() -> Result<()> {
         "{}",
         t!(l, "🔎 Checking service status...", "🔎 正在检查服务状态...").bold()
     );
+    if let Ok(Some(path)) = get_service_config_path() {
+        println!("{} {}", t!(l, "Config file:", "配置文件:"), path.display());
+    }
 
-    match std::env::consts::OS {
+    let result = match std::env::consts::OS {
         "linux" => run_and_print(
             Command::new("systemctl")
                 .arg("status")
@@ -49,11 +55,113 @@ pub async fn status() -> Result<()> {
             "Service management is currently supported on Linux/macOS/Windows only.",
             "服务管理当前仅支持 Linux/macOS/Windows。"
         ))),
+    };
+    result?;
+
+    if is_running() {
+        print_running_tunnel().await;
     }
+    Ok(())
 }
 
-/// Install and enable cloudflared service with a tunnel token.
-pub async fn install(client: &CloudflareClient, tunnel_id: Option<String>) -> Result<()> {
+/// Print which tunnel the running service serves, based on the token in its
+/// command line. Silently does nothing if no token can be decoded.
+async fn print_running_tunnel() {
+    let l = lang();
+    let Some(tunnel_id) = service_command_args()
+        .ok()
+        .flatten()
+        .and_then(|args| token_arg(&args))
+        .and_then(|token| decode_token_tunnel_id(&token))
+    else {
+        return;
+    };
+    let short_id: String = tunnel_id.chars().take(8).collect();
+
+    let client = config::load_effective_api_config()
+        .ok()
+        .flatten()
+        .and_then(|cfg| CloudflareClient::from_config(&cfg).ok());
+    let name = match client {
+        Some(client) => client.get_tunnel(&tunnel_id).await.ok().map(|t| t.name),
+        None => None,
+    };
+    match name {
+        Some(name) => println!(
+            "\n🔗 {} {} (ID: {short_id})",
+            t!(l, "Running tunnel:", "运行中的隧道:"),
+            name.bold()
+        ),
+        None => println!(
+            "\n🔗 {} {short_id}",
+            t!(l, "Running tunnel:", "运行中的隧道:")
+        ),
+    }
+}
+
+/// The value of `--token` (or `--token=`) in `args`.
+fn token_arg(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--token" {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--token=").map(str::to_string)
+        }
+    })
+}
+
+/// Tunnel ID carried in a cloudflared service token. JWT-style tokens carry it
+/// in the middle (payload) section; plain tokens are a single base64 JSON
+/// object. Either way the ID is under `t` (or `tunnel_id`).
+fn decode_token_tunnel_id(token: &str) -> Option<String> {
+    use base64::Engine;
+
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let payload = match parts.as_slice() {
+        [_, payload, _] => *payload,
+        [payload] => *payload,
+        _ => return None,
+    };
+    let payload = payload.trim_end_matches('=');
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload)
+        .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(payload))
+        .ok()?;
+    let json: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    ["t", "tunnel_id", "tunnelID"]
+        .iter()
+        .find_map(|key| json.get(key)?.as_str())
+        .map(str::to_string)
+}
+
+/// Whether the cloudflared system service is currently running.
+pub fn is_running() -> bool {
+    match std::env::consts::OS {
+        "linux" => Command::new("systemctl")
+            .arg("is-active")
+            .arg("--quiet")
+            .arg(SERVICE_NAME)
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false),
+        "macos" => macos_find_loaded_target().is_some(),
+        "windows" => Command::new("sc")
+            .arg("query")
+            .arg(SERVICE_NAME)
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains("RUNNING"))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Install and enable cloudflared service with a tunnel token. With
+/// `config_path`, the service runs cloudflared with `--config <path>`.
+pub async fn install(
+    client: &CloudflareClient,
+    tunnel_id: Option<String>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
     let l = lang();
     ensure_cloudflared_installed()?;
     print_package_maintenance_hint();
@@ -65,6 +173,8 @@ pub async fn install(client: &CloudflareClient, tunnel_id: Option<String>) -> Re
         },
     };
 
+    let options = prompt_origin_request_options().filter(|o| !o.is_empty());
+
     let token = client.get_tunnel_token(&tunnel_id).await?;
     println!(
         "{}",
@@ -94,6 +204,7 @@ pub async fn install(client: &CloudflareClient, tunnel_id: Option<String>) -> Re
             t!(l, "Service installed for tunnel", "服务已安装到隧道"),
             tunnel_id
         );
+        configure_installed_service(config_path.as_deref(), options.as_ref())?;
         prompt_start_service()?;
         return Ok(());
     }
@@ -154,6 +265,7 @@ pub async fn install(client: &CloudflareClient, tunnel_id: Option<String>) -> Re
                     t!(l, "Service reinstalled for tunnel", "服务已重新安装到隧道"),
                     tunnel_id
                 );
+                configure_installed_service(config_path.as_deref(), options.as_ref())?;
                 prompt_start_service()?;
             }
             _ => {
@@ -184,6 +296,247 @@ pub async fn install(client: &CloudflareClient, tunnel_id: Option<String>) -> Re
     Ok(())
 }
 
+/// cloudflared defaults for the advanced install options.
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_RETRIES: u32 = 1;
+const DEFAULT_KEEP_ALIVE_CONNECTIONS: u32 = 100;
+
+/// Advanced cloudflared options chosen during `service install`;
+/// `None` fields were left at their default and are not written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct OriginRequestOptions {
+    connect_timeout_secs: Option<u64>,
+    retries: Option<u32>,
+    keep_alive_connections: Option<u32>,
+}
+
+impl OriginRequestOptions {
+    fn is_empty(&self) -> bool {
+        self.connect_timeout_secs.is_none()
+            && self.retries.is_none()
+            && self.keep_alive_connections.is_none()
+    }
+}
+
+/// Ask for a number, pre-filled with `default`; `None` when unchanged or cancelled.
+fn prompt_changed_number<T>(prompt_text: &str, default: T) -> Option<T>
+where
+    T: std::str::FromStr + PartialEq + ToString,
+{
+    let value = prompt::input_validated(prompt_text, Some(&default.to_string()), |v| {
+        v.trim()
+            .parse::<T>()
+            .map(|_| ())
+            .map_err(|_| "enter a whole number".to_string())
+    })?;
+    let value = value.trim().parse::<T>().ok()?;
+    (value != default).then_some(value)
+}
+
+/// "Advanced options?" step of `service install`. `None` when declined.
+fn prompt_origin_request_options() -> Option<OriginRequestOptions> {
+    let l = lang();
+    if prompt::confirm_opt(t!(l, "Advanced options?", "高级选项？"), false) != Some(true) {
+        return None;
+    }
+    Some(OriginRequestOptions {
+        connect_timeout_secs: prompt_changed_number(
+            t!(
+                l,
+                "Origin connect timeout (seconds, default 30)",
+                "源站连接超时（秒，默认 30）"
+            ),
+            DEFAULT_CONNECT_TIMEOUT_SECS,
+        ),
+        retries: prompt_changed_number(
+            t!(
+                l,
+                "Retries on origin connection failure (default 1)",
+                "源站连接失败重试次数（默认 1）"
+            ),
+            DEFAULT_RETRIES,
+        ),
+        keep_alive_connections: prompt_changed_number(
+            t!(
+                l,
+                "Keep-alive connections (default 100)",
+                "保持连接数（默认 100）"
+            ),
+            DEFAULT_KEEP_ALIVE_CONNECTIONS,
+        ),
+    })
+}
+
+/// Set the chosen options in a cloudflared config document, leaving every
+/// other key alone. `connectTimeout` and `keepAliveConnections` go in the
+/// global `originRequest` block; `retries` is a top-level cloudflared key.
+fn apply_origin_request_options(yaml: &mut serde_yaml::Value, options: &OriginRequestOptions) {
+    use serde_yaml::{Mapping, Value};
+
+    if !yaml.is_mapping() {
+        *yaml = Value::Mapping(Mapping::new());
+    }
+    let Some(root) = yaml.as_mapping_mut() else {
+        return;
+    };
+    if let Some(retries) = options.retries {
+        root.insert("retries".into(), retries.into());
+    }
+
+    let origin = root
+        .entry("originRequest".into())
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    if !origin.is_mapping() {
+        *origin = Value::Mapping(Mapping::new());
+    }
+    let Some(origin) = origin.as_mapping_mut() else {
+        return;
+    };
+    if let Some(secs) = options.connect_timeout_secs {
+        origin.insert("connectTimeout".into(), format!("{secs}s").into());
+    }
+    if let Some(n) = options.keep_alive_connections {
+        origin.insert("keepAliveConnections".into(), n.into());
+    }
+    if origin.is_empty() {
+        root.remove("originRequest");
+    }
+}
+
+/// Point the freshly installed service at `config_path` and write the
+/// advanced options into the config file it runs with. A token-only service
+/// reads no config file, so one chosen here is added as `--config`. Failing
+/// to save the options only warns: the service itself is installed.
+fn configure_installed_service(
+    config_path: Option<&Path>,
+    options: Option<&OriginRequestOptions>,
+) -> Result<()> {
+    let l = lang();
+    let Some(options) = options else {
+        if let Some(path) = config_path {
+            set_service_config_path(path)?;
+        }
+        return Ok(());
+    };
+    let path = match config_path {
+        Some(path) => path.to_path_buf(),
+        None => match origin_options_path() {
+            Ok(path) => path,
+            Err(e) => {
+                warn_origin_options_not_saved(&e);
+                return Ok(());
+            }
+        },
+    };
+    match write_origin_request_options(&path, options) {
+        Ok(true) => println!(
+            "{} {} {}",
+            "✅".green(),
+            t!(l, "Origin options saved to", "源站选项已保存到"),
+            path.display()
+        ),
+        Ok(false) => return Ok(()),
+        Err(e) => {
+            warn_origin_options_not_saved(&e);
+            return Ok(());
+        }
+    }
+    if get_service_config_path().ok().flatten().as_deref() != Some(path.as_path()) {
+        set_service_config_path(&path)?;
+    }
+    Ok(())
+}
+
+fn warn_origin_options_not_saved(error: &anyhow::Error) {
+    let l = lang();
+    println!(
+        "{} {}: {error:#}",
+        "⚠️".yellow(),
+        t!(
+            l,
+            "Origin options were not saved; the service uses cloudflared defaults",
+            "源站选项未保存，服务将使用 cloudflared 默认值"
+        )
+    );
+}
+
+/// Config file for the advanced options when no `--config-path` was given:
+/// the one the service already runs with, else the first cloudflared config
+/// found, else `~/.cloudflared/config.yml`.
+fn origin_options_path() -> Result<PathBuf> {
+    if let Some(path) = get_service_config_path()? {
+        return Ok(path);
+    }
+    if let Some(cfg) = config::load_tunnel_config()? {
+        return Ok(cfg.path);
+    }
+    let dir = dirs::home_dir()
+        .ok_or_else(|| anyhow!("cannot determine home directory"))?
+        .join(".cloudflared");
+    Ok(dir.join("config.yml"))
+}
+
+/// Whether a YAML document has comments, which a round-trip through
+/// `serde_yaml` would drop.
+fn has_yaml_comments(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with('#') || line.contains(" #")
+    })
+}
+
+/// Set the options in the cloudflared config at `path`, creating it when
+/// missing. Asks first when the file has comments, since they are not
+/// kept; `Ok(false)` when the user declines.
+fn write_origin_request_options(path: &Path, options: &OriginRequestOptions) -> Result<bool> {
+    let l = lang();
+    let existing = match std::fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    let mut yaml = match &existing {
+        Some(text) => serde_yaml::from_str(text)
+            .with_context(|| format!("failed to parse {}", path.display()))?,
+        None => serde_yaml::Value::Null,
+    };
+    if existing.as_deref().is_some_and(has_yaml_comments) {
+        println!(
+            "{} {} {}",
+            "⚠️".yellow(),
+            path.display(),
+            t!(
+                l,
+                "has comments, which are removed when the options are saved.",
+                "包含注释，保存选项时注释会被删除。"
+            )
+        );
+        if prompt::confirm_opt(t!(l, "Save the options anyway?", "仍要保存选项吗？"), false)
+            != Some(true)
+        {
+            println!(
+                "{}",
+                t!(
+                    l,
+                    "Origin options not saved; add them to the config by hand.",
+                    "未保存源站选项，请手动添加到配置文件。"
+                )
+            );
+            return Ok(false);
+        }
+    }
+    apply_origin_request_options(&mut yaml, options);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    config::write_atomic(path, serde_yaml::to_string(&yaml)?.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(true)
+}
+
 /// Start cloudflared service.
 pub fn start() -> Result<()> {
     let l = lang();
@@ -208,8 +561,16 @@ pub fn stop() -> Result<()> {
     run_control_cmd("stop")
 }
 
-/// Restart cloudflared service.
-pub fn restart() -> Result<()> {
+/// How long `restart` waits for the service to stop.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long `restart` waits for the metrics endpoint after starting.
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Restart cloudflared service: stop, wait for it to exit, start, then wait
+/// for the tunnel's metrics endpoint to answer. Fails when it never does,
+/// unless `wait_metrics` is off (for services running without metrics).
+pub async fn restart(wait_metrics: bool) -> Result<()> {
     let l = lang();
     ensure_cloudflared_installed()?;
     print_package_maintenance_hint();
@@ -217,7 +578,76 @@ pub fn restart() -> Result<()> {
         "{}",
         t!(l, "🔄 Restarting service...", "🔄 正在重启服务...").bold()
     );
-    run_control_cmd("restart")
+
+    run_control_cmd("stop")?;
+    let poll = Duration::from_secs(1);
+    if !util::wait_until(STOP_TIMEOUT, poll, || async { !is_running() }).await {
+        println!(
+            "{} {}",
+            "⚠️".yellow(),
+            t!(
+                l,
+                "Service did not stop within 10s; starting anyway.",
+                "服务未在 10 秒内停止，继续启动。"
+            )
+        );
+    }
+
+    run_control_cmd("start")?;
+    if !wait_metrics {
+        println!(
+            "{} {}",
+            "✅".green(),
+            t!(l, "Service restarted.", "服务已重启。")
+        );
+        return Ok(());
+    }
+    println!(
+        "{}",
+        t!(
+            l,
+            "⏳ Waiting for the tunnel to become active...",
+            "⏳ 等待隧道就绪..."
+        )
+    );
+    if util::wait_until(READY_TIMEOUT, poll, monitor::metrics_reachable).await {
+        println!(
+            "{} {}",
+            "✅".green(),
+            t!(
+                l,
+                "Service restarted; tunnel is active.",
+                "服务已重启，隧道已就绪。"
+            )
+        );
+        return Ok(());
+    }
+    if !is_running() {
+        return Err(anyhow!(t!(
+            l,
+            "Service is not running after restart",
+            "重启后服务未运行"
+        )));
+    }
+    Err(anyhow!(
+        "{} ({} {}; {})",
+        t!(
+            l,
+            "Service started but tunnel not yet active",
+            "服务已启动，但隧道尚未就绪"
+        ),
+        t!(
+            l,
+            "no metrics endpoint answered on port",
+            "指标端点无响应，端口"
+        ),
+        monitor::current_metrics_port(),
+        t!(
+            l,
+            "use --no-wait-metrics if metrics are disabled",
+            "如未启用指标，请使用 --no-wait-metrics"
+        )
+    ))
 }
 
 /// Show recent cloudflared service logs.
@@ -276,6 +706,616 @@ pub fn logs(lines: usize) -> Result<()> {
     }
 }
 
+/// Show recent cloudflared service logs containing `pattern`, with matches highlighted.
+pub fn logs_filtered(lines: usize, pattern: &str) -> Result<()> {
+    let l = lang();
+    ensure_cloudflared_installed()?;
+    print_package_maintenance_hint();
+    let lines = lines.max(1);
+    println!(
+        "{} {} ({} \"{}\")",
+        t!(l, "📜 Showing recent logs:", "📜 显示最近日志:").bold(),
+        lines,
+        t!(l, "filter", "过滤"),
+        pattern
+    );
+
+    let output = match std::env::consts::OS {
+        // journalctl filters natively; the pattern is escaped so it matches
+        // literally, the same way it is highlighted below
+        "linux" => run_and_capture_matches(
+            Command::new("journalctl")
+                .arg("-u")
+                .arg(SERVICE_NAME)
+                .arg("-n")
+                .arg(lines.to_string())
+                .arg("--grep")
+                .arg(escape_grep_pattern(pattern))
+                .arg("--case-sensitive=yes")
+                .arg("--no-pager"),
+        )?,
+        // `log show` has no substring filter, so filter here
+        "macos" => {
+            let raw = run_and_capture(
+                Command::new("log")
+                    .arg("show")
+                    .arg("--last")
+                    .arg("10m")
+                    .arg("--predicate")
+                    .arg(format!("process == \"{SERVICE_NAME}\""))
+                    .arg("--style")
+                    .arg("compact"),
+            )?;
+            let matched = filter_log_lines(&raw, pattern);
+            let skip = matched.len().saturating_sub(lines);
+            matched[skip..].join("\n")
+        }
+        "windows" => {
+            let ps = format!(
+                "Get-WinEvent -LogName System -MaxEvents {max} | \
+                 Where-Object {{ $_.ProviderName -eq 'Service Control Manager' -and $_.Message -like '*{svc}*' -and $_.Message -like '*{pat}*' }} | \
+                 Select-Object -First {take} TimeCreated, Id, LevelDisplayName, Message | \
+                 Format-Table -AutoSize",
+                max = lines.saturating_mul(10),
+                svc = SERVICE_NAME,
+                pat = pattern.replace('\'', "''"),
+                take = lines
+            );
+            run_and_capture(
+                Command::new("powershell")
+                    .arg("-NoProfile")
+                    .arg("-Command")
+                    .arg(ps),
+            )?
+        }
+        _ => {
+            return Err(anyhow!(t!(
+                l,
+                "Service logs are currently supported on Linux/macOS/Windows only.",
+                "服务日志当前仅支持 Linux/macOS/Windows。"
+            )))
+        }
+    };
+
+    if output.trim().is_empty() {
+        println!("{}", t!(l, "No matching log lines.", "没有匹配的日志。"));
+        return Ok(());
+    }
+    for line in output.lines() {
+        println!(
+            "{}",
+            highlight_matches(line, pattern, |m| m.red().bold().to_string())
+        );
+    }
+    Ok(())
+}
+
+/// One line of a cloudflared JSON log file.
+#[derive(Debug, Deserialize)]
+struct StructuredLogLine {
+    #[serde(default)]
+    level: String,
+    #[serde(default, alias = "time")]
+    ts: String,
+    #[serde(default, alias = "message")]
+    msg: String,
+    #[serde(default)]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Parse a JSON log line; `None` for anything that isn't a JSON object.
+fn parse_structured_line(line: &str) -> Option<StructuredLogLine> {
+    serde_json::from_str(line.trim()).ok()
+}
+
+/// Color a log level: INFO green, WARN yellow, ERROR red.
+fn color_level(level: &str) -> String {
+    let upper = level.to_ascii_uppercase();
+    match upper.as_str() {
+        "INFO" | "INF" => upper.green().to_string(),
+        "WARN" | "WARNING" | "WRN" => upper.yellow().to_string(),
+        "ERROR" | "ERR" | "FATAL" | "FTL" | "PANIC" => upper.red().bold().to_string(),
+        _ => upper,
+    }
+}
+
+/// `ts LEVEL msg key=value ...` for one parsed log line.
+fn format_structured_line(entry: &StructuredLogLine) -> String {
+    let mut out = format!(
+        "{} {} {}",
+        entry.ts.dimmed(),
+        color_level(&entry.level),
+        entry.msg
+    );
+    for (key, value) in &entry.fields {
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        out.push_str(&format!(" {}={}", key.cyan(), value));
+    }
+    out
+}
+
+/// Show the last `lines` entries of a cloudflared JSON log file (`--logfile`).
+/// Lines that are not JSON are printed unchanged.
+pub fn show_structured_logs(path: &Path, lines: usize) -> Result<()> {
+    let l = lang();
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let all: Vec<&str> = content.lines().filter(|x| !x.trim().is_empty()).collect();
+    let skip = all.len().saturating_sub(lines.max(1));
+
+    println!(
+        "{} {}",
+        t!(l, "📜 Showing recent logs:", "📜 显示最近日志:").bold(),
+        path.display()
+    );
+    if all.is_empty() {
+        println!("{}", t!(l, "Log file is empty.", "日志文件为空。"));
+        return Ok(());
+    }
+    for line in &all[skip..] {
+        match parse_structured_line(line) {
+            Some(entry) => println!("{}", format_structured_line(&entry)),
+            None => println!("{line}"),
+        }
+    }
+    Ok(())
+}
+
+/// Lines of `output` containing `pattern`.
+fn filter_log_lines<'a>(output: &'a str, pattern: &str) -> Vec<&'a str> {
+    output
+        .lines()
+        .filter(|line| line.contains(pattern))
+        .collect()
+}
+
+/// Escape the regex metacharacters in `pattern` so `journalctl --grep`
+/// matches it as a plain substring.
+fn escape_grep_pattern(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Replace every occurrence of `pattern` in `line` with `style(occurrence)`.
+fn highlight_matches(line: &str, pattern: &str, style: impl Fn(&str) -> String) -> String {
+    if pattern.is_empty() {
+        return line.to_string();
+    }
+    line.split(pattern)
+        .collect::<Vec<_>>()
+        .join(&style(pattern))
+}
+
+/// State of the tunnel credentials file named by the cloudflared config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialStatus {
+    Valid { tunnel_id: String },
+    Missing { path: String },
+    Corrupt { path: String, error: String },
+}
+
+/// Check the `credentials-file` of the local cloudflared config.
+pub fn validate_credentials_file() -> Result<CredentialStatus> {
+    let cfg =
+        config::load_tunnel_config()?.ok_or_else(|| anyhow!("no cloudflared config.yml found"))?;
+    let path = cfg
+        .credentials_file()
+        .ok_or_else(|| anyhow!("{} has no credentials-file", cfg.path.display()))?;
+    Ok(credential_status(&path))
+}
+
+/// Classify a credentials file: it must exist and be JSON with a `TunnelID`.
+pub fn credential_status(path: &Path) -> CredentialStatus {
+    let display = path.display().to_string();
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return CredentialStatus::Missing { path: display }
+        }
+        Err(e) => {
+            return CredentialStatus::Corrupt {
+                path: display,
+                error: e.to_string(),
+            }
+        }
+    };
+    let json: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => {
+            return CredentialStatus::Corrupt {
+                path: display,
+                error: e.to_string(),
+            }
+        }
+    };
+    match json.get("TunnelID").and_then(|v| v.as_str()) {
+        Some(id) => CredentialStatus::Valid {
+            tunnel_id: id.to_string(),
+        },
+        None => CredentialStatus::Corrupt {
+            path: display,
+            error: "missing TunnelID field".to_string(),
+        },
+    }
+}
+
+/// Write a fresh credentials file for `tunnel` to `path` with cloudflared.
+pub fn refetch_credentials(tunnel: &str, path: &str) -> Result<()> {
+    if !command_exists("cloudflared") {
+        return Err(anyhow!("cloudflared is not installed"));
+    }
+    run_and_print(Command::new("cloudflared").args([
+        "tunnel",
+        "token",
+        "--cred-file",
+        path,
+        tunnel,
+    ]))
+}
+
+/// systemd unit written by `cloudflared service install`.
+const SYSTEMD_UNIT: &str = "/etc/systemd/system/cloudflared.service";
+
+/// Split a command line on whitespace, keeping double-quoted parts together.
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+/// The value of `--config` (or `--config=`) in `args`.
+fn config_arg(args: &[String]) -> Option<PathBuf> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--config" {
+            args.get(i + 1).map(PathBuf::from)
+        } else {
+            arg.strip_prefix("--config=").map(PathBuf::from)
+        }
+    })
+}
+
+/// `args` with any existing `--config` removed and `--config <path>` placed
+/// right after the binary, where cloudflared expects global flags.
+fn args_with_config(args: &[String], config: &Path) -> Vec<String> {
+    let mut rest = Vec::new();
+    let mut skip_next = false;
+    for arg in args.iter().skip(1) {
+        if skip_next {
+            skip_next = false;
+        } else if arg == "--config" {
+            skip_next = true;
+        } else if !arg.starts_with("--config=") {
+            rest.push(arg.clone());
+        }
+    }
+    let mut out: Vec<String> = args.iter().take(1).cloned().collect();
+    out.push("--config".to_string());
+    out.push(config.display().to_string());
+    out.extend(rest);
+    out
+}
+
+/// Rewrite a systemd `ExecStart=` line to run cloudflared with `config`.
+fn exec_start_with_config(line: &str, config: &Path) -> String {
+    let command = line
+        .trim()
+        .strip_prefix("ExecStart=")
+        .unwrap_or(line.trim());
+    let args = args_with_config(&split_command_line(command), config);
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|a| {
+            if a.contains(char::is_whitespace) {
+                format!("\"{a}\"")
+            } else {
+                a.clone()
+            }
+        })
+        .collect();
+    format!("ExecStart={}", quoted.join(" "))
+}
+
+/// Byte range of the `ProgramArguments` `<array>` contents in a launchd plist.
+fn plist_arguments_range(plist: &str) -> Option<std::ops::Range<usize>> {
+    let key = plist.find("<key>ProgramArguments</key>")?;
+    let start = key + plist[key..].find("<array>")? + "<array>".len();
+    let end = start + plist[start..].find("</array>")?;
+    Some(start..end)
+}
+
+/// The `<string>` values of the plist's `ProgramArguments`.
+fn plist_arguments(plist: &str) -> Option<Vec<String>> {
+    let body = &plist[plist_arguments_range(plist)?];
+    Some(
+        body.split("<string>")
+            .skip(1)
+            .filter_map(|part| part.split_once("</string>"))
+            .map(|(value, _)| {
+                value
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&amp;", "&")
+            })
+            .collect(),
+    )
+}
+
+/// Rewrite a launchd plist so `ProgramArguments` runs cloudflared with `config`.
+fn plist_with_config(plist: &str, config: &Path) -> Option<String> {
+    let range = plist_arguments_range(plist)?;
+    let args = args_with_config(&plist_arguments(plist)?, config);
+    let mut body = String::new();
+    for arg in &args {
+        let escaped = arg
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        body.push_str(&format!("\n\t\t<string>{escaped}</string>"));
+    }
+    body.push_str("\n\t");
+    Some(format!(
+        "{}{body}{}",
+        &plist[..range.start],
+        &plist[range.end..]
+    ))
+}
+
+/// First cloudflared launchd plist found in the usual locations.
+fn macos_service_plist() -> Option<PathBuf> {
+    macos_bootstrap_source().map(|(_, plist)| PathBuf::from(plist))
+}
+
+/// The command line the installed service runs cloudflared with, taken from
+/// the systemd `ExecStart=` line or the launchd `ProgramArguments`.
+fn service_command_args() -> Result<Option<Vec<String>>> {
+    match std::env::consts::OS {
+        "linux" => {
+            let Ok(unit) = std::fs::read_to_string(SYSTEMD_UNIT) else {
+                return Ok(None);
+            };
+            Ok(unit
+                .lines()
+                .find_map(|l| l.trim().strip_prefix("ExecStart="))
+                .map(split_command_line))
+        }
+        "macos" => {
+            let Some(path) = macos_service_plist() else {
+                return Ok(None);
+            };
+            let plist = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            Ok(plist_arguments(&plist))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The `--config` path the installed service runs cloudflared with, if any.
+pub fn get_service_config_path() -> Result<Option<PathBuf>> {
+    Ok(service_command_args()?.and_then(|args| config_arg(&args)))
+}
+
+/// Make the installed service run cloudflared with `--config <config>`.
+fn set_service_config_path(config: &Path) -> Result<()> {
+    let l = lang();
+    let written = match std::env::consts::OS {
+        "linux" => {
+            let unit = std::fs::read_to_string(SYSTEMD_UNIT)
+                .with_context(|| format!("failed to read {SYSTEMD_UNIT}"))?;
+            let updated: Vec<String> = unit
+                .lines()
+                .map(|line| {
+                    if line.trim().starts_with("ExecStart=") {
+                        exec_start_with_config(line, config)
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect();
+            std::fs::write(SYSTEMD_UNIT, updated.join("\n") + "\n")
+                .with_context(|| format!("failed to write {SYSTEMD_UNIT} (try sudo)"))?;
+            run_and_print(Command::new("systemctl").arg("daemon-reload"))?;
+            PathBuf::from(SYSTEMD_UNIT)
+        }
+        "macos" => {
+            let path = macos_service_plist()
+                .ok_or_else(|| anyhow!("no cloudflared plist found in common launchd paths"))?;
+            let plist = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let updated = plist_with_config(&plist, config)
+                .ok_or_else(|| anyhow!("no ProgramArguments array in {}", path.display()))?;
+            std::fs::write(&path, updated)
+                .with_context(|| format!("failed to write {} (try sudo)", path.display()))?;
+            path
+        }
+        _ => {
+            println!(
+                "{} {}",
+                "⚠️".yellow(),
+                t!(
+                    l,
+                    "--config-path is only supported for systemd (Linux) and launchd (macOS) services.",
+                    "--config-path 仅支持 systemd (Linux) 和 launchd (macOS) 服务。"
+                )
+            );
+            return Ok(());
+        }
+    };
+    println!(
+        "{} {} {} → {}",
+        "✅".green(),
+        t!(l, "Service uses config", "服务使用配置"),
+        config.display(),
+        written.display()
+    );
+    Ok(())
+}
+
+/// systemd drop-in holding the resource limits for the cloudflared unit.
+const LIMITS_DROPIN: &str = "/etc/systemd/system/cloudflared.service.d/limits.conf";
+
+/// Check a systemd memory size such as `100M`, `512M` or `2G`.
+fn validate_memory_max(value: &str) -> Result<()> {
+    let digits = value.trim_end_matches(['K', 'M', 'G', 'T']);
+    let valid_suffix = value.len() == digits.len() + 1;
+    match digits.parse::<u64>() {
+        Ok(n) if n > 0 && valid_suffix => Ok(()),
+        _ => Err(anyhow!(
+            "invalid memory limit '{value}' (expected e.g. 100M, 512M, 2G)"
+        )),
+    }
+}
+
+/// Parse a CPU quota percentage (`50` or `50%`) in the range 1-100.
+fn parse_cpu_quota(value: &str) -> Result<u8> {
+    match value.trim().trim_end_matches('%').parse::<u8>() {
+        Ok(n) if (1..=100).contains(&n) => Ok(n),
+        _ => Err(anyhow!(
+            "invalid CPU quota '{value}' (expected a percentage from 1 to 100)"
+        )),
+    }
+}
+
+/// Render the `[Service]` drop-in for the given limits.
+fn limits_dropin(memory_max: Option<&str>, cpu_quota: Option<u8>) -> String {
+    let mut out = String::from("[Service]\n");
+    if let Some(mem) = memory_max {
+        out.push_str(&format!("MemoryMax={mem}\n"));
+    }
+    if let Some(cpu) = cpu_quota {
+        out.push_str(&format!("CPUQuota={cpu}%\n"));
+    }
+    out
+}
+
+/// Read `MemoryMax` and `CPUQuota` back out of a drop-in.
+fn parse_limits_dropin(content: &str) -> (Option<String>, Option<u8>) {
+    let mut memory = None;
+    let mut cpu = None;
+    for line in content.lines() {
+        if let Some(v) = line.trim().strip_prefix("MemoryMax=") {
+            memory = Some(v.to_string());
+        } else if let Some(v) = line.trim().strip_prefix("CPUQuota=") {
+            cpu = parse_cpu_quota(v).ok();
+        }
+    }
+    (memory, cpu)
+}
+
+fn ensure_linux_systemd() -> Result<()> {
+    let l = lang();
+    if std::env::consts::OS != "linux" {
+        return Err(anyhow!(t!(
+            l,
+            "Resource limits are only supported for systemd on Linux.",
+            "资源限制仅支持 Linux systemd。"
+        )));
+    }
+    Ok(())
+}
+
+/// Write memory/CPU limits into a systemd drop-in and reload systemd.
+/// A limit that is not given keeps its current value.
+pub fn set_resource_limits(memory_max: Option<&str>, cpu_quota: Option<&str>) -> Result<()> {
+    let l = lang();
+    ensure_linux_systemd()?;
+
+    if let Some(mem) = memory_max {
+        validate_memory_max(mem)?;
+    }
+    let cpu_quota = cpu_quota.map(parse_cpu_quota).transpose()?;
+
+    let (cur_memory, cur_cpu) = std::fs::read_to_string(LIMITS_DROPIN)
+        .map(|c| parse_limits_dropin(&c))
+        .unwrap_or_default();
+    let memory_max = memory_max.map(str::to_string).or(cur_memory);
+    let cpu_quota = cpu_quota.or(cur_cpu);
+
+    let path = std::path::Path::new(LIMITS_DROPIN);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {} (try sudo)", dir.display()))?;
+    }
+    std::fs::write(path, limits_dropin(memory_max.as_deref(), cpu_quota))
+        .with_context(|| format!("failed to write {LIMITS_DROPIN} (try sudo)"))?;
+
+    run_and_print(Command::new("systemctl").arg("daemon-reload"))?;
+    println!(
+        "{} {} {}",
+        "✅".green(),
+        t!(l, "Resource limits written to", "资源限制已写入"),
+        LIMITS_DROPIN
+    );
+    println!(
+        "💡 {}",
+        t!(
+            l,
+            "Restart the service to apply: tunnel service restart",
+            "重启服务后生效: tunnel service restart"
+        )
+    );
+    Ok(())
+}
+
+/// Show the memory/CPU limits currently set in the systemd drop-in.
+pub fn show_resource_limits() -> Result<()> {
+    let l = lang();
+    ensure_linux_systemd()?;
+
+    let content = match std::fs::read_to_string(LIMITS_DROPIN) {
+        Ok(c) => c,
+        Err(_) => {
+            println!(
+                "{}",
+                t!(l, "No resource limits configured.", "未配置资源限制。")
+            );
+            return Ok(());
+        }
+    };
+
+    let (memory, cpu) = parse_limits_dropin(&content);
+    println!("{}", LIMITS_DROPIN.dimmed());
+    println!("  MemoryMax: {}", memory.unwrap_or_else(|| "-".to_string()));
+    println!(
+        "  CPUQuota:  {}",
+        cpu.map(|c| format!("{c}%"))
+            .unwrap_or_else(|| "-".to_string())
+    );
+    Ok(())
+}
+
+/// Show the resource limits, or update them when any limit is given.
+pub fn configure_limits(memory: Option<String>, cpu: Option<String>) -> Result<()> {
+    if memory.is_none() && cpu.is_none() {
+        return show_resource_limits();
+    }
+    set_resource_limits(memory.as_deref(), cpu.as_deref())
+}
+
 /// After a successful service install, offer to start immediately.
 fn prompt_start_service() -> Result<()> {
     let l = lang();
@@ -382,6 +1422,39 @@ fn run_control_cmd(action: &str) -> Result<()> {
     }
 }
 
+/// Run a command and return its stdout; stderr is passed through.
+fn run_and_capture(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output().context("failed to execute command")?;
+    if !output.stderr.is_empty() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(anyhow!("command exited with status {}", output.status))
+    }
+}
+
+/// Like `run_and_capture` for a filtering command such as `journalctl --grep`,
+/// which exits with status 1 when nothing matches: that case is empty output.
+fn run_and_capture_matches(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output().context("failed to execute command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let no_matches =
+        output.status.code() == Some(1) && matches!(stdout.trim(), "" | "-- No entries --");
+    if no_matches {
+        return Ok(String::new());
+    }
+    if !output.stderr.is_empty() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        Err(anyhow!("command exited with status {}", output.status))
+    }
+}
+
 fn run_and_print(cmd: &mut Command) -> Result<()> {
     let output = cmd.output().context("failed to execute command")?;
     if !output.stdout.is_empty() {
@@ -484,9 +1557,260 @@ fn install_cloudflared() -> Result<()> {
     }
 }
 
-/// Install cloudflared on Linux by downloading the official binary.
+/// How cloudflared was installed, recorded so upgrades can reuse the same channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstallMethod {
+    Apt,
+    Dnf,
+    Yum,
+    Zypper,
+    /// Arch Linux via an AUR helper (`yay` / `paru`).
+    Aur(&'static str),
+    Homebrew,
+    Winget,
+    /// Direct binary download from GitHub releases.
+    Binary,
+}
+
+impl InstallMethod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Yum => "yum",
+            Self::Zypper => "zypper",
+            Self::Aur(helper) => helper,
+            Self::Homebrew => "homebrew",
+            Self::Winget => "winget",
+            Self::Binary => "binary",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "apt" => Some(Self::Apt),
+            "dnf" => Some(Self::Dnf),
+            "yum" => Some(Self::Yum),
+            "zypper" => Some(Self::Zypper),
+            "yay" => Some(Self::Aur("yay")),
+            "paru" => Some(Self::Aur("paru")),
+            "homebrew" => Some(Self::Homebrew),
+            "winget" => Some(Self::Winget),
+            "binary" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+
+    /// Shell commands that install cloudflared with this package manager.
+    fn install_script(&self) -> Option<&'static str> {
+        match self {
+            Self::Apt => Some(
+                "sudo mkdir -p --mode=0755 /usr/share/keyrings && \
+                 curl -fsSL https://pkg.cloudflare.com/cloudflare-main.gpg | sudo tee /usr/share/keyrings/cloudflare-main.gpg >/dev/null && \
+                 echo 'deb [signed-by=/usr/share/keyrings/cloudflare-main.gpg] https://pkg.cloudflare.com/cloudflared any main' | sudo tee /etc/apt/sources.list.d/cloudflared.list >/dev/null && \
+                 sudo apt-get update && sudo apt-get install -y cloudflared",
+            ),
+            Self::Dnf => Some(
+                "curl -fsSL https://pkg.cloudflare.com/cloudflared-ascii.repo | sudo tee /etc/yum.repos.d/cloudflared.repo >/dev/null && \
+                 sudo dnf install -y cloudflared",
+            ),
+            Self::Yum => Some(
+                "curl -fsSL https://pkg.cloudflare.com/cloudflared-ascii.repo | sudo tee /etc/yum.repos.d/cloudflared.repo >/dev/null && \
+                 sudo yum install -y cloudflared",
+            ),
+            Self::Zypper => Some(
+                "sudo zypper --non-interactive addrepo --refresh https://pkg.cloudflare.com/cloudflared-ascii.repo cloudflared && \
+                 sudo zypper --non-interactive --gpg-auto-import-keys install cloudflared",
+            ),
+            Self::Aur("paru") => Some("paru -S --noconfirm cloudflared"),
+            Self::Aur(_) => Some("yay -S --noconfirm cloudflared"),
+            Self::Homebrew | Self::Winget | Self::Binary => None,
+        }
+    }
+
+    /// Shell command that upgrades cloudflared through this channel.
+    fn upgrade_script(&self) -> Option<&'static str> {
+        match self {
+            Self::Apt => {
+                Some("sudo apt-get update && sudo apt-get install -y --only-upgrade cloudflared")
+            }
+            Self::Dnf => Some("sudo dnf upgrade -y cloudflared"),
+            Self::Yum => Some("sudo yum update -y cloudflared"),
+            Self::Zypper => Some("sudo zypper --non-interactive update cloudflared"),
+            Self::Aur("paru") => Some("paru -S --noconfirm cloudflared"),
+            Self::Aur(_) => Some("yay -S --noconfirm cloudflared"),
+            Self::Homebrew => Some("brew upgrade cloudflared"),
+            Self::Winget | Self::Binary => None,
+        }
+    }
+}
+
+/// Pick a Linux package manager in preference order, given a predicate that
+/// reports whether a command is available.
+fn detect_linux_package_manager(has_command: impl Fn(&str) -> bool) -> Option<InstallMethod> {
+    if has_command("apt-get") {
+        Some(InstallMethod::Apt)
+    } else if has_command("dnf") {
+        Some(InstallMethod::Dnf)
+    } else if has_command("yum") {
+        Some(InstallMethod::Yum)
+    } else if has_command("zypper") {
+        Some(InstallMethod::Zypper)
+    } else if has_command("pacman") {
+        // cloudflared is packaged in the AUR; pacman alone cannot install it
+        ["yay", "paru"]
+            .into_iter()
+            .find(|helper| has_command(helper))
+            .map(InstallMethod::Aur)
+    } else {
+        None
+    }
+}
+
+fn command_exists(cmd: &str) -> bool {
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("command -v {cmd}"))
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Where the install method is recorded: `install_method.txt` in the
+/// tool's config directory.
+fn install_method_path() -> Option<PathBuf> {
+    config::config_dir()
+        .ok()
+        .map(|dir| dir.join("install_method.txt"))
+}
+
+/// Record `method`. Best effort: without a record, upgrades fall back to
+/// `cloudflared update`.
+fn save_install_method(method: InstallMethod) {
+    let Some(path) = install_method_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = config::write_atomic(&path, method.as_str().as_bytes());
+}
+
+fn load_install_method() -> Option<InstallMethod> {
+    let path = install_method_path()?;
+    InstallMethod::parse(&std::fs::read_to_string(path).ok()?)
+}
+
+fn run_shell(script: &str) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .status()
+        .context("failed to run shell")?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("command exited with status {status}"))
+    }
+}
+
+/// Upgrade cloudflared using the channel it was installed with.
+pub fn upgrade_cloudflared() -> Result<()> {
+    let l = lang();
+    ensure_cloudflared_installed()?;
+
+    let method = load_install_method().or_else(|| {
+        if std::env::consts::OS == "macos" && brew_has_cloudflared() {
+            Some(InstallMethod::Homebrew)
+        } else {
+            None
+        }
+    });
+
+    println!(
+        "{}",
+        t!(
+            l,
+            "⬆️  Upgrading cloudflared...",
+            "⬆️  正在升级 cloudflared..."
+        )
+        .bold()
+    );
+
+    match method.and_then(|m| m.upgrade_script().map(|s| (m, s))) {
+        Some((m, script)) => {
+            println!("  {} {}", t!(l, "Using", "使用"), m.as_str().cyan());
+            run_shell(script)?;
+        }
+        None => match method {
+            Some(InstallMethod::Winget) => run_and_print(Command::new("winget").args([
+                "upgrade",
+                "--id",
+                "Cloudflare.cloudflared",
+            ]))?,
+            _ => run_and_print(Command::new("cloudflared").arg("update"))?,
+        },
+    }
+
+    if let Ok(output) = Command::new("cloudflared").arg("--version").output() {
+        println!(
+            "{} {}",
+            "✅".green(),
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Install cloudflared on Linux, preferring the system package manager and
+/// falling back to the official binary.
 fn install_cloudflared_linux() -> Result<()> {
     let l = lang();
+
+    if let Some(method) = detect_linux_package_manager(command_exists) {
+        println!(
+            "  {} {}",
+            t!(l, "Installing via package manager:", "通过包管理器安装:"),
+            method.as_str().cyan()
+        );
+        if let Some(script) = method.install_script() {
+            match run_shell(script) {
+                Ok(()) if cloudflared_installed() => {
+                    save_install_method(method);
+                    return Ok(());
+                }
+                Ok(()) => {}
+                Err(e) => println!("  {} {:#}", "⚠️".yellow(), e),
+            }
+        }
+        println!(
+            "  {}",
+            t!(
+                l,
+                "Package manager installation failed, falling back to binary download.",
+                "包管理器安装失败，改为下载二进制文件。"
+            )
+            .yellow()
+        );
+    } else {
+        println!(
+            "  {}",
+            t!(
+                l,
+                "No supported package manager found, downloading binary.",
+                "未找到支持的包管理器，改为下载二进制文件。"
+            )
+        );
+    }
+
+    install_cloudflared_linux_binary()?;
+    save_install_method(InstallMethod::Binary);
+    Ok(())
+}
+
+/// Install cloudflared on Linux by downloading the official binary.
+fn install_cloudflared_linux_binary() -> Result<()> {
+    let l = lang();
     let arch = std::env::consts::ARCH;
     let arch_suffix = match arch {
         "x86_64" => "amd64",
@@ -565,7 +1889,17 @@ fn install_cloudflared_linux() -> Result<()> {
 fn install_cloudflared_macos() -> Result<()> {
     let l = lang();
 
-    if brew_installed() {
+    if brew_installed() && !brew_formula_available() && !brew_tap_cloudflare() {
+        println!(
+            "  {} {}",
+            "⚠️".yellow(),
+            t!(
+                l,
+                "Could not tap cloudflare/cloudflare; falling back to the binary download.",
+                "无法添加 cloudflare/cloudflare tap，改为直接下载二进制文件。"
+            )
+        );
+    } else if brew_installed() {
         println!(
             "  {}",
             t!(l, "Installing via Homebrew...", "通过 Homebrew 安装中...")
@@ -582,6 +1916,7 @@ fn install_cloudflared_macos() -> Result<()> {
                 "通过 Homebrew 安装 cloudflared 失败。"
             )));
         }
+        save_install_method(InstallMethod::Homebrew);
         return Ok(());
     }
 
@@ -669,6 +2004,7 @@ fn install_cloudflared_macos() -> Result<()> {
         .status();
 
     let _ = std::fs::remove_dir_all(&tmp_dir);
+    save_install_method(InstallMethod::Binary);
 
     println!(
         "  {} {}",
@@ -714,6 +2050,7 @@ fn install_cloudflared_windows() -> Result<()> {
         )));
     }
 
+    save_install_method(InstallMethod::Winget);
     Ok(())
 }
 
@@ -732,6 +2069,66 @@ fn print_package_maintenance_hint() {
     }
 }
 
+/// Oldest cloudflared release the health check accepts without a warning.
+pub const MIN_CLOUDFLARED_VERSION: &str = "2024.1.0";
+
+/// What `cloudflared --version` reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloudflaredVersion {
+    /// A release version such as `2024.6.1`.
+    Release(String),
+    /// A build without a release version, e.g. `DEV` from a source build.
+    Dev(String),
+    /// Empty or unrecognised output.
+    Unknown,
+}
+
+impl CloudflaredVersion {
+    /// Classify `cloudflared --version` output, e.g.
+    /// `cloudflared version 2024.6.1 (built 2024-06-12-1200 UTC)`.
+    pub fn parse(output: &str) -> Self {
+        let mut words = output.split_whitespace();
+        let Some(version) = words
+            .by_ref()
+            .find(|w| w.eq_ignore_ascii_case("version"))
+            .and_then(|_| words.next())
+        else {
+            return Self::Unknown;
+        };
+        if release_parts(version).is_some() {
+            Self::Release(version.to_string())
+        } else {
+            Self::Dev(version.to_string())
+        }
+    }
+
+    /// Whether this is a release older than `MIN_CLOUDFLARED_VERSION`.
+    pub fn is_outdated(&self) -> bool {
+        match self {
+            Self::Release(v) => release_parts(v) < release_parts(MIN_CLOUDFLARED_VERSION),
+            _ => false,
+        }
+    }
+}
+
+/// `year.month.patch` as numbers, or `None` if `v` is not in that form.
+fn release_parts(v: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = v.split('.').map(|p| p.parse::<u32>().ok());
+    let parsed = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(parsed)
+}
+
+/// Version of the installed cloudflared, or `None` if it cannot be run.
+pub fn get_cloudflared_version() -> Option<CloudflaredVersion> {
+    let output = Command::new("cloudflared").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(CloudflaredVersion::parse(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
 fn cloudflared_installed() -> bool {
     Command::new("cloudflared")
         .arg("--version")
@@ -748,6 +2145,48 @@ fn brew_installed() -> bool {
         .unwrap_or(false)
 }
 
+/// Homebrew tap that provides the cloudflared formula.
+const CLOUDFLARE_TAP: &str = "cloudflare/cloudflare";
+
+/// Whether `brew search cloudflared` output lists the cloudflared formula,
+/// either from homebrew-core (`cloudflared`) or a tap (`<tap>/cloudflared`).
+fn search_lists_cloudflared(output: &str) -> bool {
+    output
+        .lines()
+        .filter(|line| !line.starts_with("==>"))
+        .flat_map(str::split_whitespace)
+        .any(|name| name == "cloudflared" || name.ends_with("/cloudflared"))
+}
+
+/// Whether `brew install cloudflared` can find the formula.
+fn brew_formula_available() -> bool {
+    Command::new("brew")
+        .args(["search", "cloudflared"])
+        .output()
+        .map(|o| {
+            o.status.success() && search_lists_cloudflared(&String::from_utf8_lossy(&o.stdout))
+        })
+        .unwrap_or(false)
+}
+
+/// Run `brew tap cloudflare/cloudflare`, returning whether it succeeded.
+fn brew_tap_cloudflare() -> bool {
+    let l = lang();
+    println!(
+        "  {} brew tap {CLOUDFLARE_TAP}",
+        t!(
+            l,
+            "Formula not found, running:",
+            "未找到 formula，正在执行:"
+        )
+    );
+    Command::new("brew")
+        .args(["tap", CLOUDFLARE_TAP])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
 fn brew_has_cloudflared() -> bool {
     if !brew_installed() {
         return false;
@@ -830,3 +2269,449 @@ fn macos_uid() -> Option<String> {
         Some(uid)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cloudflared_version_output_is_classified() {
+        let release =
+            CloudflaredVersion::parse("cloudflared version 2024.6.1 (built 2024-06-12-1200 UTC)\n");
+        assert_eq!(release, CloudflaredVersion::Release("2024.6.1".into()));
+        assert!(!release.is_outdated());
+
+        let old = CloudflaredVersion::parse("cloudflared version 2023.10.0 (built 2023-10-31)");
+        assert_eq!(old, CloudflaredVersion::Release("2023.10.0".into()));
+        assert!(old.is_outdated());
+        assert!(!CloudflaredVersion::Release("2024.1.0".into()).is_outdated());
+
+        let dev = CloudflaredVersion::parse("cloudflared version DEV (built unknown)");
+        assert_eq!(dev, CloudflaredVersion::Dev("DEV".into()));
+        assert!(!dev.is_outdated());
+        assert_eq!(
+            CloudflaredVersion::parse("cloudflared version 2024.6"),
+            CloudflaredVersion::Dev("2024.6".into())
+        );
+
+        assert_eq!(CloudflaredVersion::parse(""), CloudflaredVersion::Unknown);
+        assert_eq!(
+            CloudflaredVersion::parse("cloudflared version"),
+            CloudflaredVersion::Unknown
+        );
+        assert_eq!(
+            CloudflaredVersion::parse("garbage"),
+            CloudflaredVersion::Unknown
+        );
+    }
+
+    #[test]
+    fn exec_start_gets_config_flag() {
+        let line = "ExecStart=/usr/bin/cloudflared --no-autoupdate tunnel run --token eyJh";
+        let path = Path::new("/etc/cloudflared/custom.yml");
+        assert_eq!(
+            exec_start_with_config(line, path),
+            "ExecStart=/usr/bin/cloudflared --config /etc/cloudflared/custom.yml --no-autoupdate tunnel run --token eyJh"
+        );
+
+        // An existing --config is replaced, and paths with spaces are quoted.
+        let replaced = exec_start_with_config(
+            "ExecStart=/usr/bin/cloudflared --config /old.yml tunnel run",
+            Path::new("/srv/my tunnel/config.yml"),
+        );
+        assert_eq!(
+            replaced,
+            "ExecStart=/usr/bin/cloudflared --config \"/srv/my tunnel/config.yml\" tunnel run"
+        );
+        let cmd = replaced.strip_prefix("ExecStart=").unwrap();
+        assert_eq!(
+            config_arg(&split_command_line(cmd)),
+            Some(PathBuf::from("/srv/my tunnel/config.yml"))
+        );
+        assert_eq!(
+            config_arg(&split_command_line(
+                "cloudflared --config=/a.yml tunnel run"
+            )),
+            Some(PathBuf::from("/a.yml"))
+        );
+        assert_eq!(
+            config_arg(&split_command_line("cloudflared tunnel run")),
+            None
+        );
+    }
+
+    #[test]
+    fn plist_program_arguments_get_config_flag() {
+        let plist = "<plist><dict>
+	<key>Label</key>
+	<string>com.cloudflare.cloudflared</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>/opt/homebrew/bin/cloudflared</string>
+		<string>tunnel</string>
+		<string>run</string>
+	</array>
+</dict></plist>";
+        let updated = plist_with_config(plist, Path::new("/Users/me/custom.yml")).unwrap();
+        assert_eq!(
+            plist_arguments(&updated).unwrap(),
+            vec![
+                "/opt/homebrew/bin/cloudflared",
+                "--config",
+                "/Users/me/custom.yml",
+                "tunnel",
+                "run"
+            ]
+        );
+        assert!(updated.contains("<string>com.cloudflare.cloudflared</string>"));
+        assert_eq!(
+            config_arg(&plist_arguments(&updated).unwrap()),
+            Some(PathBuf::from("/Users/me/custom.yml"))
+        );
+    }
+
+    #[test]
+    fn service_token_tunnel_id_is_decoded() {
+        use base64::Engine;
+
+        let tunnel_id = "f70ff985-a4ef-4643-bbbc-4a0ed4fc8415";
+        let payload = format!(r#"{{"a":"acc123","t":"{tunnel_id}","s":"c2VjcmV0"}}"#);
+        let plain = base64::engine::general_purpose::STANDARD.encode(&payload);
+        assert_eq!(decode_token_tunnel_id(&plain).as_deref(), Some(tunnel_id));
+
+        let jwt = format!(
+            "eyJhbGciOiJIUzI1NiJ9.{}.c2lnbmF0dXJl",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&payload)
+        );
+        assert_eq!(decode_token_tunnel_id(&jwt).as_deref(), Some(tunnel_id));
+
+        assert_eq!(decode_token_tunnel_id("not-a-token"), None);
+        assert_eq!(decode_token_tunnel_id("a.b"), None);
+    }
+
+    #[test]
+    fn token_read_from_exec_start() {
+        let args =
+            split_command_line("/usr/bin/cloudflared --no-autoupdate tunnel run --token abc.def");
+        assert_eq!(token_arg(&args).as_deref(), Some("abc.def"));
+        let args = split_command_line("/usr/bin/cloudflared tunnel run --token=xyz");
+        assert_eq!(token_arg(&args).as_deref(), Some("xyz"));
+        assert_eq!(
+            token_arg(&split_command_line("cloudflared tunnel run")),
+            None
+        );
+    }
+
+    #[test]
+    fn origin_request_block_has_only_changed_fields() {
+        let mut yaml: serde_yaml::Value = serde_yaml::from_str(
+            "tunnel: abc\ncredentials-file: /root/.cloudflared/abc.json\noriginRequest:\n  noTLSVerify: true\n",
+        )
+        .unwrap();
+        let options = OriginRequestOptions {
+            connect_timeout_secs: Some(90),
+            retries: None,
+            keep_alive_connections: Some(200),
+        };
+        apply_origin_request_options(&mut yaml, &options);
+
+        let out = serde_yaml::to_string(&yaml).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        let origin = &parsed["originRequest"];
+        assert_eq!(origin["connectTimeout"].as_str(), Some("90s"));
+        assert_eq!(origin["keepAliveConnections"].as_u64(), Some(200));
+        assert_eq!(origin["noTLSVerify"].as_bool(), Some(true));
+        assert_eq!(parsed["tunnel"].as_str(), Some("abc"));
+        assert!(parsed.get("retries").is_none());
+    }
+
+    #[test]
+    fn origin_request_options_on_empty_config() {
+        let mut yaml = serde_yaml::Value::Null;
+        let options = OriginRequestOptions {
+            retries: Some(5),
+            ..Default::default()
+        };
+        apply_origin_request_options(&mut yaml, &options);
+        assert_eq!(serde_yaml::to_string(&yaml).unwrap(), "retries: 5\n");
+
+        apply_origin_request_options(
+            &mut yaml,
+            &OriginRequestOptions {
+                connect_timeout_secs: Some(45),
+                ..Default::default()
+            },
+        );
+        let out = serde_yaml::to_string(&yaml).unwrap();
+        assert!(out.contains("originRequest:\n  connectTimeout: 45s"));
+        assert!(out.contains("retries: 5"));
+    }
+
+    #[test]
+    fn origin_request_options_written_to_new_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloudflared").join("config.yml");
+        let options = OriginRequestOptions {
+            keep_alive_connections: Some(50),
+            ..Default::default()
+        };
+        assert!(write_origin_request_options(&path, &options).unwrap());
+        let yaml: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            yaml["originRequest"]["keepAliveConnections"].as_u64(),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn yaml_comments_detected() {
+        assert!(has_yaml_comments("# managed by ansible\ntunnel: abc\n"));
+        assert!(has_yaml_comments("tunnel: abc # prod\n"));
+        assert!(!has_yaml_comments(
+            "tunnel: abc\ningress:\n  - service: http_status:404\n"
+        ));
+    }
+
+    #[test]
+    fn credential_status_variants() {
+        let dir =
+            std::env::temp_dir().join(format!("opentunnel-credstatus-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let good = dir.join("good.json");
+        std::fs::write(
+            &good,
+            r#"{"AccountTag":"acc","TunnelSecret":"s","TunnelID":"tid-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            credential_status(&good),
+            CredentialStatus::Valid {
+                tunnel_id: "tid-1".to_string()
+            }
+        );
+
+        let missing = dir.join("missing.json");
+        assert!(matches!(
+            credential_status(&missing),
+            CredentialStatus::Missing { path } if path.ends_with("missing.json")
+        ));
+
+        let garbage = dir.join("garbage.json");
+        std::fs::write(&garbage, "{not json").unwrap();
+        assert!(matches!(
+            credential_status(&garbage),
+            CredentialStatus::Corrupt { .. }
+        ));
+
+        let no_id = dir.join("no-id.json");
+        std::fs::write(&no_id, r#"{"AccountTag":"acc"}"#).unwrap();
+        assert!(matches!(
+            credential_status(&no_id),
+            CredentialStatus::Corrupt { error, .. } if error.contains("TunnelID")
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    const LOG_SHOW_FIXTURE: &str = "\
+Timestamp               Ty Process[PID:TID]
+2026-10-16 09:12:01.120 Df cloudflared[812:1a2b] INF Starting tunnel tunnelID=abc
+2026-10-16 09:12:02.511 E  cloudflared[812:1a2b] ERR Failed to dial edge error=\"dial tcp: i/o timeout\"
+2026-10-16 09:12:05.004 Df cloudflared[812:1a2b] INF Registered tunnel connection connIndex=0
+2026-10-16 09:12:09.730 E  cloudflared[812:1a2b] ERR Serve tunnel error error=\"context canceled\"";
+
+    #[test]
+    fn macos_log_filter_keeps_matching_lines() {
+        let matched = filter_log_lines(LOG_SHOW_FIXTURE, "ERR");
+        assert_eq!(matched.len(), 2);
+        assert!(matched[0].contains("Failed to dial edge"));
+        assert!(matched[1].contains("Serve tunnel error"));
+
+        assert!(filter_log_lines(LOG_SHOW_FIXTURE, "WRN").is_empty());
+    }
+
+    const JSON_LOG_FIXTURE: &str = r#"{"level":"info","ts":"2026-10-16T09:12:01Z","msg":"Starting tunnel","fields":{"tunnelID":"abc"}}
+{"level":"warn","ts":"2026-10-16T09:12:03Z","msg":"Retrying connection","fields":{"connIndex":0,"retry":2}}
+not json at all
+{"level":"error","time":"2026-10-16T09:12:09Z","message":"Serve tunnel error"}"#;
+
+    #[test]
+    fn structured_log_fixture_parses() {
+        let entries: Vec<Option<StructuredLogLine>> = JSON_LOG_FIXTURE
+            .lines()
+            .map(parse_structured_line)
+            .collect();
+        assert_eq!(entries.len(), 4);
+
+        let info = entries[0].as_ref().unwrap();
+        assert_eq!(info.level, "info");
+        assert_eq!(info.msg, "Starting tunnel");
+        assert_eq!(info.fields["tunnelID"], "abc");
+
+        let warn = entries[1].as_ref().unwrap();
+        assert_eq!(warn.level, "warn");
+        assert_eq!(warn.fields["retry"], 2);
+
+        assert!(entries[2].is_none());
+
+        let error = entries[3].as_ref().unwrap();
+        assert_eq!(error.level, "error");
+        assert_eq!(error.ts, "2026-10-16T09:12:09Z");
+        assert_eq!(error.msg, "Serve tunnel error");
+        assert!(error.fields.is_empty());
+    }
+
+    #[test]
+    fn structured_log_line_formatting() {
+        let entry = parse_structured_line(JSON_LOG_FIXTURE.lines().nth(1).unwrap()).unwrap();
+        let line = format_structured_line(&entry);
+        for part in [
+            "2026-10-16T09:12:03Z",
+            "WARN",
+            "Retrying connection",
+            "connIndex",
+            "=0",
+            "retry",
+            "=2",
+        ] {
+            assert!(line.contains(part), "{part} missing from {line}");
+        }
+        assert!(color_level("Err").contains("ERR"));
+        assert_eq!(color_level("debug"), "DEBUG");
+    }
+
+    #[test]
+    fn grep_pattern_is_literal() {
+        assert_eq!(escape_grep_pattern("error"), "error");
+        assert_eq!(
+            escape_grep_pattern("conn[0] (retry 1.5s)?"),
+            r"conn\[0\] \(retry 1\.5s\)\?"
+        );
+        assert_eq!(escape_grep_pattern(r"a\b|c"), r"a\\b\|c");
+    }
+
+    #[test]
+    fn highlight_wraps_every_match() {
+        let line = "ERR dial error=timeout error";
+        assert_eq!(
+            highlight_matches(line, "error", |m| format!("[{m}]")),
+            "ERR dial [error]=timeout [error]"
+        );
+        assert_eq!(highlight_matches(line, "", |m| format!("[{m}]")), line);
+    }
+
+    #[test]
+    fn limits_dropin_contents() {
+        assert_eq!(
+            limits_dropin(Some("512M"), Some(50)),
+            "[Service]\nMemoryMax=512M\nCPUQuota=50%\n"
+        );
+        assert_eq!(limits_dropin(Some("2G"), None), "[Service]\nMemoryMax=2G\n");
+        assert_eq!(
+            parse_limits_dropin(&limits_dropin(Some("100M"), Some(25))),
+            (Some("100M".to_string()), Some(25))
+        );
+    }
+
+    #[test]
+    fn limits_validation() {
+        for ok in ["100M", "512M", "2G", "64K"] {
+            assert!(validate_memory_max(ok).is_ok(), "{ok}");
+        }
+        for bad in ["", "M", "0M", "512", "1.5G", "512MB", "-1G"] {
+            assert!(validate_memory_max(bad).is_err(), "{bad}");
+        }
+        assert_eq!(parse_cpu_quota("50").unwrap(), 50);
+        assert_eq!(parse_cpu_quota("100%").unwrap(), 100);
+        assert!(parse_cpu_quota("0").is_err());
+        assert!(parse_cpu_quota("101").is_err());
+        assert!(parse_cpu_quota("abc").is_err());
+    }
+
+    fn only(available: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |cmd| available.contains(&cmd)
+    }
+
+    #[test]
+    fn brew_search_output_detects_cloudflared() {
+        assert!(search_lists_cloudflared("==> Formulae\ncloudflared\n"));
+        assert!(search_lists_cloudflared(
+            "==> Formulae\ncloudflare/cloudflare/cloudflared ✔\n"
+        ));
+        assert!(search_lists_cloudflared(
+            "cloudflare-wrangler  cloudflared\n"
+        ));
+        assert!(!search_lists_cloudflared(
+            "==> Formulae\ncloudflare-wrangler\nflarectl\n"
+        ));
+        assert!(!search_lists_cloudflared(
+            "Error: No formulae or casks found for cloudflared.\n"
+        ));
+        assert!(!search_lists_cloudflared(""));
+    }
+
+    #[test]
+    fn detect_prefers_apt() {
+        assert_eq!(
+            detect_linux_package_manager(only(&["apt-get", "dnf", "yum"])),
+            Some(InstallMethod::Apt)
+        );
+    }
+
+    #[test]
+    fn detect_dnf_before_yum() {
+        assert_eq!(
+            detect_linux_package_manager(only(&["dnf", "yum"])),
+            Some(InstallMethod::Dnf)
+        );
+        assert_eq!(
+            detect_linux_package_manager(only(&["yum"])),
+            Some(InstallMethod::Yum)
+        );
+    }
+
+    #[test]
+    fn detect_zypper() {
+        assert_eq!(
+            detect_linux_package_manager(only(&["zypper"])),
+            Some(InstallMethod::Zypper)
+        );
+    }
+
+    #[test]
+    fn detect_pacman_requires_aur_helper() {
+        assert_eq!(detect_linux_package_manager(only(&["pacman"])), None);
+        assert_eq!(
+            detect_linux_package_manager(only(&["pacman", "paru"])),
+            Some(InstallMethod::Aur("paru"))
+        );
+        assert_eq!(
+            detect_linux_package_manager(only(&["pacman", "yay", "paru"])),
+            Some(InstallMethod::Aur("yay"))
+        );
+    }
+
+    #[test]
+    fn detect_none_falls_back() {
+        assert_eq!(detect_linux_package_manager(only(&[])), None);
+    }
+
+    #[test]
+    fn install_method_round_trips_through_string() {
+        for m in [
+            InstallMethod::Apt,
+            InstallMethod::Dnf,
+            InstallMethod::Yum,
+            InstallMethod::Zypper,
+            InstallMethod::Aur("yay"),
+            InstallMethod::Aur("paru"),
+            InstallMethod::Homebrew,
+            InstallMethod::Winget,
+            InstallMethod::Binary,
+        ] {
+            assert_eq!(InstallMethod::parse(m.as_str()), Some(m));
+        }
+        assert_eq!(InstallMethod::parse("snap"), None);
+    }
+}
@@ -1,10 +1,25 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
 use comfy_table::{presets::UTF8_FULL, Table};
+use serde::{Deserialize, Serialize};
+use tokio_rustls::rustls::{self, pki_types::ServerName};
 
-use crate::config;
+use crate::client::{
+    CloudflareClient, DnsRecord, IngressRule, NetworkReachability, TokenVerifyStatus, Tunnel,
+    TunnelConfigInner, TunnelConfiguration,
+};
+use crate::config::{self, ApiConfig, ApiConfigStatus, TunnelConfigStatus};
 use crate::error::Result;
 use crate::i18n::lang;
-use crate::t;
+use crate::service::{self, CloudflaredVersion, CredentialStatus};
+use crate::{dns, prompt, scan, t, tunnel};
 
 // ---------------------------------------------------------------------------
 // System status (API-only, no local cloudflared dependency)
@@ -12,6 +27,7 @@ use crate::t;
 
 /// Aggregated system health.
 pub struct SystemStatus {
+    pub api_status: ApiConfigStatus,
     pub api_configured: bool,
     pub account_configured: bool,
     pub zone_configured: bool,
@@ -22,13 +38,10 @@ pub struct SystemStatus {
 pub fn get_system_status() -> SystemStatus {
     let l = lang();
 
-    let api_configured = config::is_api_configured();
-    let account_configured = config::is_account_configured();
-    let zone_configured = config::load_api_config()
-        .ok()
-        .flatten()
-        .map(|c| c.zone_id.is_some())
-        .unwrap_or(false);
+    let api_status = config::check_api_config_status();
+    let api_configured = api_status.has_token();
+    let account_configured = api_status.has_account();
+    let zone_configured = api_status.has_zone_id();
 
     let mut warnings = Vec::new();
 
@@ -64,6 +77,7 @@ pub fn get_system_status() -> SystemStatus {
     }
 
     SystemStatus {
+        api_status,
         api_configured,
         account_configured,
         zone_configured,
@@ -71,6 +85,36 @@ pub fn get_system_status() -> SystemStatus {
     }
 }
 
+/// One-line summary of what the current configuration allows.
+fn api_status_summary(status: ApiConfigStatus) -> &'static str {
+    let l = lang();
+    match status {
+        ApiConfigStatus::NotConfigured => t!(
+            l,
+            "Nothing available until an API token is set.",
+            "设置 API Token 后才能使用各项功能。"
+        ),
+        ApiConfigStatus::TokenOnly => t!(
+            l,
+            "Token saved; select an account to enable tunnel operations.",
+            "Token 已保存；选择账户后可使用隧道功能。"
+        ),
+        ApiConfigStatus::TokenAndAccount => t!(
+            l,
+            "Tunnel operations available, DNS operations require zone configuration.",
+            "隧道功能可用，DNS 功能需要配置域名。"
+        ),
+        ApiConfigStatus::FullyConfigured { has_zone: true } => {
+            t!(l, "All operations available.", "所有功能可用。")
+        }
+        ApiConfigStatus::FullyConfigured { has_zone: false } => t!(
+            l,
+            "All operations available; zone name unknown, re-run `tunnel config set` to store it.",
+            "所有功能可用；域名名称未知，重新运行 `tunnel config set` 可保存。"
+        ),
+    }
+}
+
 /// Pretty-print the system status block.
 pub fn print_status(status: &SystemStatus) {
     let l = lang();
@@ -100,6 +144,7 @@ pub fn print_status(status: &SystemStatus) {
         t!(l, "Zone (DNS)", "域名 (DNS)"),
         ok(status.zone_configured)
     );
+    println!("   {}", api_status_summary(status.api_status).dimmed());
 
     if !status.warnings.is_empty() {
         println!("\n⚠️  {}", t!(l, "Warnings:", "提示:").yellow().bold());
@@ -130,7 +175,8 @@ pub async fn health_check() -> Result<()> {
     ]);
 
     // 1. API configured?
-    let api_ok = config::is_api_configured();
+    let api_status = config::check_api_config_status();
+    let api_ok = api_status.has_token();
     table.add_row(vec![
         t!(l, "API config", "API 配置"),
         if api_ok { "✅" } else { "❌" },
@@ -146,7 +192,7 @@ pub async fn health_check() -> Result<()> {
     ]);
 
     // 2. Account configured?
-    let account_ok = config::is_account_configured();
+    let account_ok = api_status.has_account();
     table.add_row(vec![
         t!(l, "Account", "账户"),
         if account_ok { "✅" } else { "❌" },
@@ -157,78 +203,1717 @@ pub async fn health_check() -> Result<()> {
         },
     ]);
 
-    // 3. Token valid?
+    // 3. Cloudflare API reachable without authentication?
+    let reach = CloudflareClient::check_reachability().await;
+    let latency = reach
+        .latency_ms
+        .map(|ms| format!("{ms} ms"))
+        .unwrap_or_else(|| t!(l, "no response", "无响应").to_string());
+    table.add_row(vec![
+        t!(l, "Network", "网络"),
+        if reach.reachable { "✅" } else { "❌" },
+        &latency,
+    ]);
+
+    // 4. Token valid? Only meaningful once the API is reachable.
     if api_ok {
-        let cfg = config::load_api_config()?.unwrap_or_default();
-        let token = cfg.api_token.as_deref().unwrap_or("");
-        let verify =
-            crate::client::CloudflareClient::verify_token(token, cfg.account_id.as_deref()).await;
-        let (status, detail) = match verify {
-            Ok(crate::client::TokenVerifyStatus::Valid) => ("✅", t!(l, "valid", "有效")),
-            Ok(crate::client::TokenVerifyStatus::Invalid(_)) => {
-                ("❌", t!(l, "invalid or expired", "无效或已过期"))
-            }
-            _ => ("⚠️", t!(l, "inconclusive", "不确定")),
+        let verify = if reach.reachable {
+            let cfg = config::load_effective_api_config()?.unwrap_or_default();
+            let token = cfg.effective_token().unwrap_or("");
+            CloudflareClient::verify_token(token, cfg.account_id.as_deref())
+                .await
+                .unwrap_or(TokenVerifyStatus::Unknown)
+        } else {
+            TokenVerifyStatus::Unknown
         };
+        let (status, detail) = api_check_result(&reach, &verify);
         table.add_row(vec![t!(l, "API Token", "API Token"), status, detail]);
     }
 
+    // 5. Credentials file named by the local cloudflared config.
+    let credentials = service::validate_credentials_file().ok();
+    if let Some(status) = &credentials {
+        let (icon, detail) = match status {
+            CredentialStatus::Valid { tunnel_id } => ("✅", mask_id(tunnel_id)),
+            CredentialStatus::Missing { path } => {
+                ("❌", format!("{} {path}", t!(l, "missing:", "缺失:")))
+            }
+            CredentialStatus::Corrupt { path, error } => ("❌", format!("{path}: {error}")),
+        };
+        table.add_row(vec![
+            t!(l, "Credentials", "凭证文件").to_string(),
+            icon.to_string(),
+            detail,
+        ]);
+    }
+
+    // 6. Local cloudflared config: missing, valid, or unparseable.
+    let tunnel_config = config::tunnel_config_status();
+    let (icon, detail) = match &tunnel_config {
+        TunnelConfigStatus::Valid(path) => ("✅", path.display().to_string()),
+        TunnelConfigStatus::Missing => (
+            "➖",
+            t!(l, "not found (optional)", "未找到（可选）").to_string(),
+        ),
+        TunnelConfigStatus::Invalid { path, .. } => (
+            "❌",
+            format!("{} {}", t!(l, "parse error:", "解析失败:"), path.display()),
+        ),
+    };
+    table.add_row(vec![
+        t!(l, "Tunnel config", "隧道配置").to_string(),
+        icon.to_string(),
+        detail,
+    ]);
+
+    // 7. TLS certificates of the hostnames in the local tunnel config.
+    let hostnames = config::load_tunnel_config()
+        .ok()
+        .flatten()
+        .map(|cfg| cfg.ingress_hostnames())
+        .unwrap_or_default();
+    for hostname in hostnames.iter().filter(|h| !h.starts_with('*')) {
+        let (status, detail) = match check_tls_cert(hostname).await {
+            Ok(info) => tls_check_result(&info),
+            Err(e) => ("❌", e.to_string().red().to_string()),
+        };
+        table.add_row(vec![format!("TLS {hostname}"), status.to_string(), detail]);
+    }
+
+    // 8. Installed cloudflared version.
+    let (status, detail) = cloudflared_version_result(service::get_cloudflared_version().as_ref());
+    table.add_row(vec!["cloudflared".to_string(), status.to_string(), detail]);
+
     println!("{table}");
+
+    if let Some(CredentialStatus::Missing { path }) = &credentials {
+        offer_credentials_refetch(path);
+    }
+    if let TunnelConfigStatus::Invalid { path, error } = &tunnel_config {
+        auto_fix_tunnel_config(path, error)?;
+    }
     Ok(())
 }
 
-/// Print debug information.
-pub fn debug_mode() -> Result<()> {
+/// Status icon and detail for the cloudflared row of the health check.
+fn cloudflared_version_result(version: Option<&CloudflaredVersion>) -> (&'static str, String) {
     let l = lang();
-    println!("\n{}", t!(l, "🐛 Debug Information", "🐛 调试信息").bold());
+    match version {
+        Some(v @ CloudflaredVersion::Release(ver)) if v.is_outdated() => (
+            "⚠️",
+            format!(
+                "{ver} — {}",
+                t!(l, "Outdated, recommend upgrade", "版本过旧，建议升级")
+            )
+            .yellow()
+            .to_string(),
+        ),
+        Some(CloudflaredVersion::Release(ver)) => (
+            "✅",
+            format!("{ver} ({})", t!(l, "release", "正式版"))
+                .green()
+                .to_string(),
+        ),
+        Some(CloudflaredVersion::Dev(ver)) => (
+            "⚠️",
+            format!("{ver} ({})", t!(l, "dev build", "开发版"))
+                .yellow()
+                .to_string(),
+        ),
+        Some(CloudflaredVersion::Unknown) => (
+            "❌",
+            t!(l, "unrecognised version output", "无法识别版本输出")
+                .red()
+                .to_string(),
+        ),
+        None => ("❌", t!(l, "not installed", "未安装").red().to_string()),
+    }
+}
 
+/// The `tunnel:` value of a config file, read line by line so it works even
+/// when the YAML as a whole does not parse.
+fn extract_tunnel_name(raw: &str) -> Option<String> {
+    raw.lines().find_map(|line| {
+        let value = line.strip_prefix("tunnel:")?;
+        let value = value.split(" #").next().unwrap_or(value).trim();
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Smallest config cloudflared accepts: the tunnel (when known) and a
+/// catch-all ingress rule.
+fn minimal_tunnel_config(tunnel: Option<&str>) -> String {
+    let mut out = String::new();
+    if let Some(tunnel) = tunnel {
+        out.push_str(&format!("tunnel: {tunnel}\n"));
+    }
+    out.push_str("ingress:\n  - service: http_status:404\n");
+    out
+}
+
+/// Show why `path` failed to parse and offer to replace it with a minimal
+/// config, backing up the broken file first.
+fn auto_fix_tunnel_config(path: &Path, error: &str) -> Result<()> {
+    let l = lang();
     println!(
+        "\n{} {} {}\n  {}",
+        "❌".red(),
+        t!(l, "Cannot parse", "无法解析"),
+        path.display(),
+        error
+    );
+    let reset = prompt::confirm_opt(
+        t!(l, "Reset to minimal valid config?", "重置为最小可用配置？"),
+        false,
+    );
+    if reset != Some(true) {
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(path).unwrap_or_default();
+    let tunnel = extract_tunnel_name(&raw);
+    let backup = path.with_file_name(format!(
+        "{}.{}.bak",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::copy(path, &backup).with_context(|| format!("failed to back up {}", path.display()))?;
+    config::write_atomic(path, minimal_tunnel_config(tunnel.as_deref()).as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    println!(
+        "{} {} {}",
+        "✅".green(),
+        t!(l, "Config reset. Backup saved to", "配置已重置，备份保存在"),
+        backup.display()
+    );
+    if tunnel.is_none() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "No tunnel name found; add `tunnel: <id>` and `credentials-file:` before running cloudflared.",
+                "未找到隧道名称；运行 cloudflared 前请添加 `tunnel: <id>` 和 `credentials-file:`。"
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Offer to recreate a missing credentials file with cloudflared.
+fn offer_credentials_refetch(path: &str) {
+    let l = lang();
+    let Some(tunnel) = config::load_tunnel_config()
+        .ok()
+        .flatten()
+        .and_then(|cfg| cfg.tunnel().map(str::to_string))
+    else {
+        return;
+    };
+    let fetch = prompt::confirm_opt(
+        t!(
+            l,
+            "Re-fetch the credentials file with cloudflared?",
+            "使用 cloudflared 重新获取凭证文件？"
+        ),
+        true,
+    );
+    if fetch != Some(true) {
+        return;
+    }
+    match service::refetch_credentials(&tunnel, path) {
+        Ok(()) => println!(
+            "{} {} {path}",
+            "✅".green(),
+            t!(l, "Credentials written to", "凭证已写入")
+        ),
+        Err(e) => println!("{} {e}", "❌".red()),
+    }
+}
+
+/// How long a TLS certificate check may take before giving up.
+const TLS_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Days before expiry at which a certificate is reported as a warning.
+const TLS_WARN_DAYS: i64 = 30;
+
+/// Days before expiry at which a certificate is reported as an error.
+const TLS_ERROR_DAYS: i64 = 7;
+
+/// Certificate details of a hostname's TLS endpoint.
+#[derive(Debug, Clone)]
+pub struct CertInfo {
+    pub valid: bool,
+    pub expiry: Option<DateTime<Utc>>,
+    pub days_until_expiry: Option<i64>,
+    pub issuer: Option<String>,
+}
+
+/// Connect to `hostname:443` and inspect the certificate it serves.
+///
+/// A certificate that fails verification yields `valid: false`; failing to
+/// connect at all is an error.
+pub async fn check_tls_cert(hostname: &str) -> Result<CertInfo> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let tls_config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(tls_config));
+    let server_name = ServerName::try_from(hostname.to_string())
+        .with_context(|| format!("invalid hostname: {hostname}"))?;
+
+    let tcp = tokio::time::timeout(
+        TLS_CHECK_TIMEOUT,
+        tokio::net::TcpStream::connect((hostname, 443)),
+    )
+    .await
+    .with_context(|| format!("timed out connecting to {hostname}:443"))?
+    .with_context(|| format!("cannot connect to {hostname}:443"))?;
+
+    let stream =
+        match tokio::time::timeout(TLS_CHECK_TIMEOUT, connector.connect(server_name, tcp)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(_)) => {
+                return Ok(CertInfo {
+                    valid: false,
+                    expiry: None,
+                    days_until_expiry: None,
+                    issuer: None,
+                })
+            }
+            Err(_) => bail!("timed out during TLS handshake with {hostname}"),
+        };
+    let der = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .context("server sent no certificate")?;
+    cert_info_from_der(der, Utc::now())
+}
+
+/// Extract expiry and issuer from a verified DER-encoded leaf certificate.
+fn cert_info_from_der(der: &[u8], now: DateTime<Utc>) -> Result<CertInfo> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| anyhow::anyhow!("cannot parse certificate: {e}"))?;
+    let expiry = DateTime::from_timestamp(cert.validity().not_after.timestamp(), 0);
+    let issuer = cert
+        .issuer()
+        .iter_organization()
+        .next()
+        .and_then(|o| o.as_str().ok())
+        .map(str::to_string)
+        .or_else(|| Some(cert.issuer().to_string()));
+    Ok(CertInfo {
+        valid: expiry.is_some_and(|e| e > now),
+        expiry,
+        days_until_expiry: expiry.map(|e| (e - now).num_days()),
+        issuer,
+    })
+}
+
+/// Status and colored detail for a TLS certificate row.
+fn tls_check_result(info: &CertInfo) -> (&'static str, String) {
+    let l = lang();
+    let days = match (info.valid, info.days_until_expiry) {
+        (true, Some(days)) => days,
+        _ => {
+            return (
+                "❌",
+                t!(l, "invalid certificate", "证书无效").red().to_string(),
+            )
+        }
+    };
+    let expiry = info
+        .expiry
+        .map(|e| e.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let issuer = info.issuer.as_deref().unwrap_or("?");
+    let detail = format!(
+        "{} {days} {} ({expiry}, {issuer})",
+        t!(l, "expires in", "剩余"),
+        t!(l, "days", "天"),
+    );
+    if days < TLS_ERROR_DAYS {
+        ("❌", detail.red().to_string())
+    } else if days < TLS_WARN_DAYS {
+        ("⚠️", detail.yellow().to_string())
+    } else {
+        ("✅", detail)
+    }
+}
+
+/// Status and detail for the token check: a network failure is reported
+/// before (and instead of) an authentication failure.
+fn api_check_result(
+    reach: &NetworkReachability,
+    verify: &TokenVerifyStatus,
+) -> (&'static str, &'static str) {
+    let l = lang();
+    if !reach.reachable {
+        return (
+            "❌",
+            t!(
+                l,
+                "Cannot reach Cloudflare API (network/DNS issue)",
+                "无法连接 Cloudflare API（网络/DNS 问题）"
+            ),
+        );
+    }
+    match verify {
+        TokenVerifyStatus::Valid => ("✅", t!(l, "valid", "有效")),
+        TokenVerifyStatus::Invalid(_) => (
+            "❌",
+            t!(l, "Auth failure (check token)", "认证失败（请检查 Token）"),
+        ),
+        TokenVerifyStatus::Unknown => ("⚠️", t!(l, "inconclusive", "不确定")),
+    }
+}
+
+/// Shorten an account or zone ID to its first 8 characters.
+fn mask_id(id: &str) -> String {
+    let prefix: String = id.chars().take(8).collect();
+    format!("{prefix}...")
+}
+
+/// Hide the directory of a credentials file, keeping only the
+/// `<tunnel-uuid>.json` file name.
+fn mask_credentials_path(path: &str) -> String {
+    match path.rsplit(['/', '\\']).next() {
+        Some(name) if name.len() < path.len() => format!(".../{name}"),
+        _ => path.to_string(),
+    }
+}
+
+/// The YAML of `cfg` with `credentials-file` masked unless `full`.
+fn tunnel_config_yaml(cfg: &config::LocalTunnelConfig, full: bool) -> String {
+    let mut yaml = cfg.yaml.clone();
+    if !full {
+        if let Some(creds) = yaml.get_mut("credentials-file") {
+            if let Some(path) = creds.as_str() {
+                *creds = serde_yaml::Value::String(mask_credentials_path(path));
+            }
+        }
+    }
+    serde_yaml::to_string(&yaml).unwrap_or_default()
+}
+
+/// Plain-text debug report suitable for pasting into an issue.
+fn debug_report(full_config: bool) -> String {
+    let l = lang();
+    let mut out = Vec::new();
+
+    out.push(format!(
+        "{}: {}",
+        t!(l, "Version", "版本"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    out.push(format!(
         "{}: {}",
         t!(l, "Config path", "配置路径"),
         config::api_config_path()
             .map(|p| p.display().to_string())
             .unwrap_or_else(|_| "unknown".to_string())
-    );
-    println!("{}: {}", t!(l, "Platform", "平台"), std::env::consts::OS);
-    println!("{}: {}", t!(l, "Arch", "架构"), std::env::consts::ARCH);
+    ));
+    out.push(format!(
+        "{}: {}",
+        t!(l, "Platform", "平台"),
+        std::env::consts::OS
+    ));
+    out.push(format!(
+        "{}: {}",
+        t!(l, "Arch", "架构"),
+        std::env::consts::ARCH
+    ));
 
-    if let Ok(Some(cfg)) = config::load_api_config() {
-        println!("API Token: {}", cfg.masked_token());
-        println!(
-            "Account ID: {}",
-            cfg.account_id.as_deref().unwrap_or("not set")
-        );
-        println!("Zone: {}", cfg.zone_name.as_deref().unwrap_or("not set"));
+    if let Ok(Some(cfg)) = config::load_effective_api_config() {
+        let id = |v: Option<&str>| match v {
+            Some(v) if full_config => v.to_string(),
+            Some(v) => mask_id(v),
+            None => "not set".to_string(),
+        };
+        out.push(format!("API Token: {}", cfg.masked_token()));
+        out.push(format!("Account ID: {}", id(cfg.account_id.as_deref())));
+        out.push(format!("Zone ID: {}", id(cfg.zone_id.as_deref())));
+        out.push(format!(
+            "Zone: {}",
+            cfg.zone_name.as_deref().unwrap_or("not set")
+        ));
+        // Shows IDs unmasked; the token is always redacted
+        if full_config {
+            if let Ok(client) = CloudflareClient::from_config(&cfg) {
+                out.push(format!("Client: {client:?}"));
+            }
+        }
+    }
+
+    out.push(String::new());
+    match config::load_tunnel_config() {
+        Ok(Some(cfg)) => {
+            let path = cfg.path.display().to_string();
+            out.push(format!(
+                "{}: {}",
+                t!(l, "Tunnel config", "隧道配置"),
+                if full_config {
+                    path
+                } else {
+                    mask_credentials_path(&path)
+                }
+            ));
+            out.push(tunnel_config_yaml(&cfg, full_config).trim_end().to_string());
+            out.push(format!(
+                "{}: {}",
+                t!(l, "Ingress rules", "入口规则数"),
+                cfg.ingress_count()
+            ));
+            for hostname in cfg.ingress_hostnames() {
+                out.push(format!("  - {hostname}"));
+            }
+        }
+        Ok(None) => out.push(format!(
+            "{}: {}",
+            t!(l, "Tunnel config", "隧道配置"),
+            t!(l, "not found", "未找到")
+        )),
+        Err(e) => out.push(format!("{}: {e:#}", t!(l, "Tunnel config", "隧道配置"))),
     }
 
+    out.join("\n")
+}
+
+/// Print debug information, or write it to `output` (a temp file when the
+/// flag is given without a path). `full_config` shows unmasked IDs and paths.
+pub fn debug_mode(full_config: bool, output: Option<Option<PathBuf>>) -> Result<()> {
+    let l = lang();
+    let report = debug_report(full_config);
+
+    let Some(path) = output else {
+        println!("\n{}", t!(l, "🐛 Debug Information", "🐛 调试信息").bold());
+        println!("{report}");
+        return Ok(());
+    };
+    let path = path.unwrap_or_else(|| {
+        std::env::temp_dir().join(format!(
+            "opentunnel-debug-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    fs::write(&path, report + "\n")
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    println!(
+        "{} {} {}",
+        "✅".green(),
+        t!(l, "Debug information written to", "调试信息已写入"),
+        path.display()
+    );
     Ok(())
 }
 
-/// Export the current configuration to stdout as JSON.
-pub fn export_config() -> Result<()> {
+// ---------------------------------------------------------------------------
+// Export / import configuration
+// ---------------------------------------------------------------------------
+
+/// Serialization format for config export/import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+/// Portable configuration snapshot. The API token is only included when
+/// explicitly requested (`--include-token`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigExport {
+    pub api_config: ExportedApiConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tunnels: Vec<ExportedTunnel>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportedApiConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+    pub account_id: Option<String>,
+    pub zone_id: Option<String>,
+    pub zone_name: Option<String>,
+    pub language: Option<String>,
+}
+
+/// A tunnel and its remotely-managed ingress rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTunnel {
+    pub id: String,
+    pub name: String,
+    pub ingress: Vec<IngressRule>,
+}
+
+impl ConfigExport {
+    /// Snapshot the non-secret parts of an `ApiConfig`.
+    pub fn from_api_config(cfg: &ApiConfig) -> Self {
+        Self {
+            api_config: ExportedApiConfig {
+                api_token: None,
+                account_id: cfg.account_id.clone(),
+                zone_id: cfg.zone_id.clone(),
+                zone_name: cfg.zone_name.clone(),
+                language: cfg.language.clone(),
+            },
+            tunnels: Vec::new(),
+        }
+    }
+
+    /// Apply the snapshot and a separately entered token to `current`, the
+    /// config being replaced. Labelled tokens and local settings (response
+    /// limit, audit log) are kept; the imported token becomes the active one.
+    pub fn merge_into(&self, current: ApiConfig, api_token: String) -> ApiConfig {
+        let exported = &self.api_config;
+        let account_name = if current.account_id == exported.account_id {
+            current.account_name.clone()
+        } else {
+            None
+        };
+        ApiConfig {
+            api_token: Some(api_token),
+            active_token: None,
+            account_id: exported.account_id.clone(),
+            account_name,
+            zone_id: exported.zone_id.clone(),
+            zone_name: exported.zone_name.clone(),
+            language: exported.language.clone().or(current.language.clone()),
+            ..current
+        }
+    }
+
+    /// YAML output starts with a `# Generated by openTunnel ...` comment;
+    /// JSON has no comment syntax and is written as-is.
+    pub fn serialize(&self, format: ConfigFormat) -> Result<String> {
+        Ok(match format {
+            ConfigFormat::Json => serde_json::to_string_pretty(self)?,
+            ConfigFormat::Yaml => format!(
+                "# Generated by openTunnel v{} on {}\n{}",
+                env!("CARGO_PKG_VERSION"),
+                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+                serde_yaml::to_string(self)?
+            ),
+        })
+    }
+
+    pub fn parse(content: &str, format: ConfigFormat) -> Result<Self> {
+        Ok(match format {
+            ConfigFormat::Json => {
+                serde_json::from_str(content).context("failed to parse JSON export")?
+            }
+            ConfigFormat::Yaml => {
+                serde_yaml::from_str(content).context("failed to parse YAML export")?
+            }
+        })
+    }
+}
+
+/// Export the current configuration (and tunnel ingress rules, when the API
+/// is reachable) to stdout or to `output`. With `include_token`, the API
+/// token is written too.
+pub async fn export_config(
+    format: ConfigFormat,
+    output: Option<PathBuf>,
+    include_token: bool,
+) -> Result<()> {
     let l = lang();
 
     let api_cfg = config::load_api_config()?.unwrap_or_default();
+    let mut export = ConfigExport::from_api_config(&api_cfg);
+    if include_token {
+        export.api_config.api_token = api_cfg.effective_token().map(str::to_string);
+    }
+
+    if let Ok(client) = CloudflareClient::from_effective_config() {
+        if let Ok(tunnels) = client.list_tunnels().await {
+            for t_info in tunnels {
+                if let Ok(Some(cfg)) = client.get_tunnel_config(&t_info.id).await {
+                    export.tunnels.push(ExportedTunnel {
+                        id: t_info.id,
+                        name: t_info.name,
+                        ingress: cfg.config.ingress,
+                    });
+                }
+            }
+        }
+    }
 
-    let export = serde_json::json!({
-        "api_config": {
-            "account_id": api_cfg.account_id,
-            "zone_id": api_cfg.zone_id,
-            "zone_name": api_cfg.zone_name,
-            "language": api_cfg.language,
-            // Intentionally omit api_token for security
+    let content = export.serialize(format)?;
+    match output {
+        Some(path) => {
+            // Owner-only (0600) like config.json: the export may hold the token
+            config::write_atomic(&path, content.as_bytes())
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!(
+                "{} {} {}",
+                "✅".green(),
+                t!(l, "Configuration exported to", "配置已导出到"),
+                path.display()
+            );
+        }
+        None => println!("{content}"),
+    }
+    // Notes go to stderr so `tunnel config export > file` stays parseable
+    if include_token {
+        eprintln!(
+            "\n{}",
+            t!(
+                l,
+                "🚨 This export contains your API token in plain text. Anyone with the file can control your Cloudflare account — store it securely and never commit it.",
+                "🚨 该导出文件包含明文 API Token。任何拿到该文件的人都能操作你的 Cloudflare 账户——请妥善保管，切勿提交到代码仓库。"
+            )
+            .red()
+            .bold()
+        );
+    } else {
+        eprintln!(
+            "\n{}",
+            t!(
+                l,
+                "⚠️  API token omitted for security. Re-configure with `tunnel config set`.",
+                "⚠️  出于安全考虑，API Token 已省略。请通过 `tunnel config set` 重新配置。"
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Import a configuration previously written by `export_config`.
+/// The API token is prompted for separately and verified before saving.
+pub async fn import_config(path: PathBuf, format: ConfigFormat) -> Result<()> {
+    let l = lang();
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let export = ConfigExport::parse(&content, format)?;
+
+    let token = match &export.api_config.api_token {
+        Some(token) => token.clone(),
+        None => match prompt::input_opt("API Token", false, None) {
+            Some(v) => v.trim().to_string(),
+            None => return Ok(()),
         },
-    });
+    };
+    if token.is_empty() {
+        return Ok(());
+    }
 
-    println!("{}", serde_json::to_string_pretty(&export)?);
+    let previous = config::load_api_config().ok().flatten();
+    let cfg = export.merge_into(previous.clone().unwrap_or_default(), token);
+    let token = cfg.effective_token().unwrap_or_default();
+    match CloudflareClient::verify_token(token, cfg.account_id.as_deref()).await? {
+        TokenVerifyStatus::Valid => {
+            println!("  {} {}", "✅".green(), t!(l, "Token valid", "Token 有效"))
+        }
+        TokenVerifyStatus::Invalid(reason) => {
+            bail!(
+                "{} — {reason}",
+                t!(l, "Token invalid or expired", "Token 无效或已过期")
+            );
+        }
+        TokenVerifyStatus::Unknown => println!(
+            "  {} {}",
+            "⚠️".yellow(),
+            t!(l, "Token status unknown", "Token 状态未知")
+        ),
+    }
+
+    config::save_api_config_if_changed(&cfg, previous.as_ref())?;
     println!(
-        "\n{}",
+        "{} {}",
+        "✅".green(),
+        t!(l, "Configuration imported.", "配置已导入。")
+    );
+
+    if export.tunnels.is_empty() || cfg.account_id.is_none() {
+        return Ok(());
+    }
+
+    let restore = prompt::confirm_opt(
+        &format!(
+            "{} ({})",
+            t!(
+                l,
+                "Restore ingress rules for exported tunnels?",
+                "是否恢复导出隧道的入口规则？"
+            ),
+            export.tunnels.len()
+        ),
+        false,
+    )
+    .unwrap_or(false);
+    if !restore {
+        return Ok(());
+    }
+
+    let client = CloudflareClient::from_config(&cfg)?;
+    for tunnel in &export.tunnels {
+        let body = TunnelConfiguration {
+            config: TunnelConfigInner {
+                ingress: tunnel.ingress.clone(),
+            },
+        };
+        match client.put_tunnel_config(&tunnel.id, &body).await {
+            Ok(_) => println!("  {} {}", "✅".green(), tunnel.name),
+            Err(e) => println!("  {} {} — {:#}", "❌".red(), tunnel.name, e),
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Terraform export
+// ---------------------------------------------------------------------------
+
+/// Quote a string as an HCL literal, escaping interpolation sequences.
+fn hcl_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace("${", "$${")
+        .replace("%{", "%%{");
+    format!("\"{escaped}\"")
+}
+
+/// Turn `name` into a Terraform resource label unique within `used`.
+fn hcl_label(name: &str, used: &mut HashSet<String>) -> String {
+    let mut base: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    base = base.trim_matches('_').to_string();
+    if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) {
+        base = format!("r_{base}");
+    }
+
+    let mut label = base.clone();
+    let mut n = 2;
+    while !used.insert(label.clone()) {
+        label = format!("{base}_{n}");
+        n += 1;
+    }
+    label
+}
+
+/// Render tunnels, their ingress rules and tunnel CNAME records as Terraform
+/// (Cloudflare provider v4). Deleted tunnels are skipped; secrets are left
+/// as commented placeholders.
+fn render_terraform(
+    account_id: &str,
+    zone_id: Option<&str>,
+    tunnels: &[(Tunnel, Option<TunnelConfiguration>)],
+    records: &[DnsRecord],
+) -> String {
+    let mut out = String::new();
+    out.push_str(
+        "# Generated by openTunnel. Review before running `terraform import`/`apply`.\n\n",
+    );
+    out.push_str(
+        "terraform {\n  required_providers {\n    cloudflare = {\n      source  = \"cloudflare/cloudflare\"\n      version = \"~> 4.0\"\n    }\n  }\n}\n\n",
+    );
+    out.push_str("provider \"cloudflare\" {\n");
+    out.push_str("  # Sensitive: prefer the CLOUDFLARE_API_TOKEN environment variable.\n");
+    out.push_str("  # api_token = \"<your API token>\"\n}\n");
+
+    let mut used = HashSet::new();
+    let mut tunnel_labels: Vec<(&str, String)> = Vec::new();
+
+    for (tunnel, config) in tunnels.iter().filter(|(t, _)| t.deleted_at.is_none()) {
+        let label = hcl_label(&tunnel.name, &mut used);
+        out.push_str(&format!(
+            "\nresource \"cloudflare_tunnel\" \"{label}\" {{\n  account_id = {}\n  name       = {}\n  # Sensitive: the tunnel secret is not exported.\n  # secret   = \"<base64 tunnel secret>\"\n}}\n",
+            hcl_string(account_id),
+            hcl_string(&tunnel.name),
+        ));
+
+        if let Some(config) = config {
+            out.push_str(&format!(
+                "\nresource \"cloudflare_tunnel_config\" \"{label}\" {{\n  account_id = {}\n  tunnel_id  = cloudflare_tunnel.{label}.id\n\n  config {{\n",
+                hcl_string(account_id),
+            ));
+            for rule in &config.config.ingress {
+                out.push_str("    ingress_rule {\n");
+                if let Some(hostname) = &rule.hostname {
+                    out.push_str(&format!("      hostname = {}\n", hcl_string(hostname)));
+                }
+                out.push_str(&format!("      service  = {}\n", hcl_string(&rule.service)));
+                out.push_str("    }\n");
+            }
+            out.push_str("  }\n}\n");
+        }
+        tunnel_labels.push((tunnel.id.as_str(), label));
+    }
+
+    if let Some(zone_id) = zone_id {
+        for record in records {
+            if record.record_type != "CNAME" {
+                continue;
+            }
+            let Some(tunnel_id) = record.content.strip_suffix(".cfargotunnel.com") else {
+                continue;
+            };
+            let content = match tunnel_labels.iter().find(|(id, _)| *id == tunnel_id) {
+                Some((_, label)) => {
+                    format!("\"${{cloudflare_tunnel.{label}.id}}.cfargotunnel.com\"")
+                }
+                None => hcl_string(&record.content),
+            };
+            let label = hcl_label(&record.name, &mut used);
+            out.push_str(&format!(
+                "\nresource \"cloudflare_record\" \"{label}\" {{\n  zone_id = {}\n  name    = {}\n  type    = \"CNAME\"\n  content = {content}\n  proxied = {}\n}}\n",
+                hcl_string(zone_id),
+                hcl_string(&record.name),
+                record.proxied.unwrap_or(true),
+            ));
+        }
+    }
+
+    out
+}
+
+/// Write the account's tunnels, ingress rules and tunnel DNS records to a
+/// Terraform file at `path`.
+pub async fn export_terraform(client: &CloudflareClient, path: &Path) -> Result<()> {
+    let l = lang();
+
+    // Deleted tunnels are excluded by the query and again here, so none can
+    // end up as a resource Terraform would try to recreate
+    let mut tunnels = Vec::new();
+    let listed = client.list_tunnels_filtered(false, None).await?;
+    for tunnel in listed.into_iter().filter(|t| t.deleted_at.is_none()) {
+        let config = client.get_tunnel_config(&tunnel.id).await?;
+        tunnels.push((tunnel, config));
+    }
+    let records = if client.zone_id.is_some() {
+        client.list_dns_records().await?
+    } else {
+        Vec::new()
+    };
+
+    let hcl = render_terraform(
+        &client.account_id,
+        client.zone_id.as_deref(),
+        &tunnels,
+        &records,
+    );
+    fs::write(path, hcl).with_context(|| format!("failed to write {}", path.display()))?;
+
+    println!(
+        "{} {} {} ({} {})",
+        "✅".green(),
+        t!(
+            l,
+            "Terraform configuration written to",
+            "Terraform 配置已写入"
+        ),
+        path.display(),
+        tunnels.len(),
+        t!(l, "tunnels", "个隧道")
+    );
+    println!(
+        "{}",
         t!(
             l,
-            "⚠️  API token omitted for security. Re-configure with `tunnel config set`.",
-            "⚠️  出于安全考虑，API Token 已省略。请通过 `tunnel config set` 重新配置。"
+            "⚠️  Secrets are commented out; fill them in or use environment variables.",
+            "⚠️  敏感值已注释，请自行填写或使用环境变量。"
         )
         .yellow()
     );
     Ok(())
 }
+
+// ---------------------------------------------------------------------------
+// API benchmark
+// ---------------------------------------------------------------------------
+
+/// Operations slower than this are shown in yellow.
+const BENCHMARK_SLOW: Duration = Duration::from_secs(2);
+/// Operations slower than this are shown in red.
+const BENCHMARK_VERY_SLOW: Duration = Duration::from_secs(5);
+
+/// Timing of one benchmarked API operation.
+#[derive(Debug)]
+struct BenchmarkTiming {
+    operation: &'static str,
+    elapsed: Duration,
+    /// The error message when the operation failed.
+    error: Option<String>,
+}
+
+/// Run `op` and record how long it took and whether it failed.
+async fn time_operation<T, F>(operation: &'static str, op: F) -> (BenchmarkTiming, Option<T>)
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    let start = Instant::now();
+    let result = op.await;
+    let elapsed = start.elapsed();
+    match result {
+        Ok(value) => (
+            BenchmarkTiming {
+                operation,
+                elapsed,
+                error: None,
+            },
+            Some(value),
+        ),
+        Err(e) => (
+            BenchmarkTiming {
+                operation,
+                elapsed,
+                error: Some(format!("{e:#}")),
+            },
+            None,
+        ),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum BenchmarkSpeed {
+    Normal,
+    Slow,
+    VerySlow,
+}
+
+fn benchmark_speed(elapsed: Duration) -> BenchmarkSpeed {
+    if elapsed > BENCHMARK_VERY_SLOW {
+        BenchmarkSpeed::VerySlow
+    } else if elapsed > BENCHMARK_SLOW {
+        BenchmarkSpeed::Slow
+    } else {
+        BenchmarkSpeed::Normal
+    }
+}
+
+/// Milliseconds, colored yellow above 2s and red above 5s.
+fn format_benchmark_ms(elapsed: Duration) -> String {
+    let ms = elapsed.as_millis().to_string();
+    match benchmark_speed(elapsed) {
+        BenchmarkSpeed::Normal => ms,
+        BenchmarkSpeed::Slow => ms.yellow().to_string(),
+        BenchmarkSpeed::VerySlow => ms.red().to_string(),
+    }
+}
+
+/// Time the API calls the tool relies on most, to spot regional latency or
+/// rate limiting.
+pub async fn benchmark(client: &CloudflareClient) -> Result<()> {
+    let l = lang();
+    println!(
+        "\n{}",
+        t!(l, "⏱ Benchmarking API...", "⏱ 正在测试 API 性能...").bold()
+    );
+
+    let start = Instant::now();
+    let mut timings = Vec::new();
+
+    let (timing, tunnels) = time_operation("list tunnels", client.list_tunnels()).await;
+    timings.push(timing);
+    if let Some(tunnel) = tunnels.and_then(|t| t.into_iter().next()) {
+        let (timing, _) =
+            time_operation("get tunnel config", client.get_tunnel_config(&tunnel.id)).await;
+        timings.push(timing);
+    }
+    if client.zone_id.is_some() {
+        let (timing, _) = time_operation("list DNS records", client.list_dns_records()).await;
+        timings.push(timing);
+    }
+    let (timing, _) = time_operation("list Access apps", client.list_access_apps(None, None)).await;
+    timings.push(timing);
+    let (timing, _) = time_operation("verify token", client.verify_current_token()).await;
+    timings.push(timing);
+
+    let total = start.elapsed();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        t!(l, "Operation", "操作"),
+        t!(l, "Time (ms)", "耗时 (ms)"),
+        t!(l, "Status", "状态"),
+    ]);
+    for timing in &timings {
+        table.add_row(vec![
+            timing.operation.to_string(),
+            format_benchmark_ms(timing.elapsed),
+            match &timing.error {
+                None => "✅".to_string(),
+                Some(e) => format!("❌ {e}"),
+            },
+        ]);
+    }
+    table.add_row(vec![
+        t!(l, "Total", "总计").bold().to_string(),
+        format_benchmark_ms(total),
+        String::new(),
+    ]);
+    println!("{table}");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Raw API access (expert use)
+// ---------------------------------------------------------------------------
+
+/// Send an arbitrary request to the Cloudflare API and print the response,
+/// pretty-printed when it is JSON.
+pub async fn raw_api(
+    client: &CloudflareClient,
+    method: &str,
+    path: &str,
+    body: Option<String>,
+) -> Result<()> {
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .with_context(|| format!("invalid HTTP method '{method}'"))?;
+    let body = body
+        .map(|b| serde_json::from_str::<serde_json::Value>(&b).context("body is not valid JSON"))
+        .transpose()?;
+
+    let response = client.raw_request(method, path, body).await?;
+    match serde_json::from_str::<serde_json::Value>(&response) {
+        Ok(json) => println!("{}", serde_json::to_string_pretty(&json)?),
+        Err(_) => println!("{response}"),
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Mapping suggestions from local scan
+// ---------------------------------------------------------------------------
+
+/// Suggest a hostname prefix for a local service, by well-known port first
+/// and then by keywords in its description.
+pub fn suggest_hostname_prefix(port: u16, description: &str) -> String {
+    let by_port = match port {
+        80 | 443 | 8080 | 8443 => Some("www"),
+        3000 | 4200 => Some("app"),
+        3001 | 5173 => Some("dev"),
+        4000 => Some("graphql"),
+        5000 | 8000 => Some("api"),
+        5432 => Some("postgres"),
+        6379 => Some("redis"),
+        8888 => Some("notebook"),
+        9000 => Some("tools"),
+        9090 => Some("metrics"),
+        27017 => Some("mongo"),
+        _ => None,
+    };
+    if let Some(prefix) = by_port {
+        return prefix.to_string();
+    }
+
+    let desc = description.to_lowercase();
+    const KEYWORDS: &[(&str, &str)] = &[
+        ("jupyter", "notebook"),
+        ("prometheus", "metrics"),
+        ("grafana", "grafana"),
+        ("graphql", "graphql"),
+        ("postgres", "postgres"),
+        ("redis", "redis"),
+        ("mongo", "mongo"),
+        ("django", "api"),
+        ("flask", "api"),
+        ("react", "app"),
+        ("vite", "dev"),
+    ];
+    KEYWORDS
+        .iter()
+        .find(|(keyword, _)| desc.contains(keyword))
+        .map(|(_, prefix)| prefix.to_string())
+        .unwrap_or_else(|| format!("svc-{port}"))
+}
+
+/// Suggested full hostnames for `services`; repeated prefixes get the port appended.
+fn suggest_hostnames(services: &[(u16, String)], zone_name: &str) -> Vec<String> {
+    let mut used = std::collections::HashSet::new();
+    services
+        .iter()
+        .map(|(port, desc)| {
+            let mut prefix = suggest_hostname_prefix(*port, desc);
+            if !used.insert(prefix.clone()) {
+                prefix = format!("{prefix}-{port}");
+                used.insert(prefix.clone());
+            }
+            format!("{prefix}.{zone_name}")
+        })
+        .collect()
+}
+
+/// Check that `hostname` looks like a DNS name inside `zone_name`.
+fn validate_hostname(hostname: &str, zone_name: &str) -> std::result::Result<(), String> {
+    let l = lang();
+    let hostname = hostname.trim();
+    if hostname.is_empty() {
+        return Err(t!(l, "hostname cannot be empty", "域名不能为空").to_string());
+    }
+    if !hostname
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        return Err(t!(
+            l,
+            "hostname may only contain letters, digits, '-' and '.'",
+            "域名只能包含字母、数字、'-' 和 '.'"
+        )
+        .to_string());
+    }
+    if hostname != zone_name && !hostname.ends_with(&format!(".{zone_name}")) {
+        return Err(format!(
+            "{} {zone_name}",
+            t!(l, "hostname must be inside", "域名必须位于")
+        ));
+    }
+    Ok(())
+}
+
+/// Scan local services, suggest hostnames for them and map the accepted ones
+/// onto a tunnel, then sync DNS records for the tunnel.
+pub async fn suggest_config(client: &CloudflareClient, tunnel_id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let zone_name = dns::zone_name(client).await?;
+
+    println!(
+        "\n{}",
+        t!(l, "🔍 Scanning local services...", "🔍 扫描本地服务...").bold()
+    );
+    let services = scan::discover_services(500, scan::DEFAULT_CONCURRENCY).await;
+    if services.is_empty() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "No running services detected on common ports.",
+                "未在常见端口上发现运行中的服务。"
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    let suggestions = suggest_hostnames(&services, &zone_name);
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        t!(l, "Port", "端口"),
+        t!(l, "Service", "服务"),
+        t!(l, "Suggested hostname", "建议域名"),
+    ]);
+    for ((port, desc), hostname) in services.iter().zip(&suggestions) {
+        table.add_row(vec![port.to_string(), desc.clone(), hostname.clone()]);
+    }
+    println!("{table}");
+
+    let tunnel_id = match tunnel_id {
+        Some(id) => id,
+        None => match tunnel::select_tunnel(client).await? {
+            Some(t) => t.id,
+            None => return Ok(()),
+        },
+    };
+
+    let mut mapped = 0usize;
+    for ((port, desc), suggested) in services.iter().zip(&suggestions) {
+        let accept = prompt::confirm_opt(
+            &format!(
+                "{} {port} ({desc})?",
+                t!(l, "Map service on port", "映射端口上的服务")
+            ),
+            true,
+        );
+        match accept {
+            Some(true) => {}
+            Some(false) => continue,
+            None => break,
+        }
+
+        let hostname =
+            match prompt::input_validated(t!(l, "Hostname", "域名"), Some(suggested), |v| {
+                validate_hostname(v, &zone_name)
+            }) {
+                Some(v) => v.trim().to_string(),
+                None => break,
+            };
+
+        let service = scan::local_service_url(*port);
+        match tunnel::put_mapping(client, &tunnel_id, &hostname, &service).await {
+            Ok(()) => mapped += 1,
+            Err(e) => println!("  {} {} — {:#}", "❌".red(), hostname, e),
+        }
+    }
+
+    if mapped > 0 {
+        dns::sync_tunnel_routes(client, Some(tunnel_id), true).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tunnel_name_extracted_from_broken_yaml() {
+        let broken = "tunnel: 6ff42ae2-765d-4adf-8112-31c55c1551ef # home\ncredentials-file: /root/.cloudflared/6ff42ae2.json\ningress:\n  - hostname: app.example.com\n   service: http://localhost:3000\n\t- service: [unterminated\n";
+        assert!(serde_yaml::from_str::<serde_yaml::Value>(broken).is_err());
+        assert_eq!(
+            extract_tunnel_name(broken).as_deref(),
+            Some("6ff42ae2-765d-4adf-8112-31c55c1551ef")
+        );
+        assert_eq!(
+            extract_tunnel_name("ingress: [\ntunnel: \"home\"\n").as_deref(),
+            Some("home")
+        );
+        // Indented keys belong to some other mapping
+        assert_eq!(extract_tunnel_name("origin:\n  tunnel: nested\n"), None);
+        assert_eq!(extract_tunnel_name("tunnel:\n"), None);
+    }
+
+    #[test]
+    fn minimal_config_parses() {
+        let cfg = minimal_tunnel_config(Some("home"));
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&cfg).unwrap();
+        assert_eq!(yaml["tunnel"].as_str(), Some("home"));
+        assert_eq!(
+            yaml["ingress"][0]["service"].as_str(),
+            Some("http_status:404")
+        );
+        assert!(!minimal_tunnel_config(None).contains("tunnel:"));
+    }
+
+    #[tokio::test]
+    async fn time_operation_records_duration_and_outcome() {
+        let (timing, value) = time_operation("sleep", async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(7)
+        })
+        .await;
+        assert_eq!(timing.operation, "sleep");
+        assert!(timing.elapsed >= Duration::from_millis(20));
+        assert!(timing.error.is_none());
+        assert_eq!(value, Some(7));
+
+        let (timing, value) = time_operation("fail", async {
+            Err::<(), _>(anyhow::anyhow!("rate limited"))
+        })
+        .await;
+        assert_eq!(timing.error.as_deref(), Some("rate limited"));
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn benchmark_speed_thresholds() {
+        assert_eq!(
+            benchmark_speed(Duration::from_millis(1500)),
+            BenchmarkSpeed::Normal
+        );
+        assert_eq!(
+            benchmark_speed(Duration::from_secs(2)),
+            BenchmarkSpeed::Normal
+        );
+        assert_eq!(
+            benchmark_speed(Duration::from_millis(2500)),
+            BenchmarkSpeed::Slow
+        );
+        assert_eq!(
+            benchmark_speed(Duration::from_secs(5)),
+            BenchmarkSpeed::Slow
+        );
+        assert_eq!(
+            benchmark_speed(Duration::from_millis(6000)),
+            BenchmarkSpeed::VerySlow
+        );
+    }
+
+    /// Braces balance outside string literals and never close early.
+    fn braces_balanced(hcl: &str) -> bool {
+        let mut depth = 0i32;
+        let mut in_string = false;
+        let mut escaped = false;
+        for line in hcl.lines() {
+            if !in_string && line.trim_start().starts_with('#') {
+                continue;
+            }
+            for c in line.chars() {
+                if in_string {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => in_string = false,
+                        _ => {}
+                    }
+                    continue;
+                }
+                match c {
+                    '"' => in_string = true,
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth < 0 {
+                            return false;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        depth == 0 && !in_string
+    }
+
+    #[test]
+    fn api_check_reports_network_before_auth() {
+        let down = NetworkReachability {
+            reachable: false,
+            latency_ms: None,
+        };
+        let up = NetworkReachability {
+            reachable: true,
+            latency_ms: Some(42),
+        };
+        let invalid = TokenVerifyStatus::Invalid("Invalid API Token".to_string());
+
+        let (status, detail) = api_check_result(&down, &invalid);
+        assert_eq!(status, "❌");
+        assert!(detail.contains("network") || detail.contains("网络"));
+
+        let (status, detail) = api_check_result(&up, &invalid);
+        assert_eq!(status, "❌");
+        assert!(detail.contains("Auth failure") || detail.contains("认证失败"));
+
+        assert_eq!(api_check_result(&up, &TokenVerifyStatus::Valid).0, "✅");
+        assert_eq!(api_check_result(&up, &TokenVerifyStatus::Unknown).0, "⚠️");
+        assert_eq!(api_check_result(&down, &TokenVerifyStatus::Valid).0, "❌");
+    }
+
+    fn cert_expiring_in(days: i64) -> CertInfo {
+        let expiry = Utc::now() + chrono::Duration::days(days);
+        CertInfo {
+            valid: true,
+            expiry: Some(expiry),
+            days_until_expiry: Some(days),
+            issuer: Some("Let's Encrypt".to_string()),
+        }
+    }
+
+    #[test]
+    fn tls_status_follows_expiry_thresholds() {
+        assert_eq!(tls_check_result(&cert_expiring_in(90)).0, "✅");
+        assert_eq!(tls_check_result(&cert_expiring_in(29)).0, "⚠️");
+        assert_eq!(tls_check_result(&cert_expiring_in(6)).0, "❌");
+
+        let invalid = CertInfo {
+            valid: false,
+            ..cert_expiring_in(90)
+        };
+        assert_eq!(tls_check_result(&invalid).0, "❌");
+
+        let (_, detail) = tls_check_result(&cert_expiring_in(90));
+        assert!(detail.contains("90") && detail.contains("Let's Encrypt"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires network access"]
+    async fn tls_cert_of_public_hostname() {
+        let info = check_tls_cert("www.cloudflare.com").await.unwrap();
+        assert!(info.valid);
+        assert!(info.days_until_expiry.unwrap() > 0);
+        assert!(info.issuer.is_some());
+    }
+
+    #[test]
+    fn terraform_export_is_balanced() {
+        let tunnel = Tunnel {
+            id: "tid-1".to_string(),
+            name: "home lab".to_string(),
+            created_at: None,
+            status: Some("healthy".to_string()),
+            deleted_at: None,
+        };
+        let deleted = Tunnel {
+            id: "tid-old".to_string(),
+            name: "retired".to_string(),
+            created_at: None,
+            status: Some("deleted".to_string()),
+            deleted_at: Some("2025-01-01T00:00:00Z".to_string()),
+        };
+        let config = TunnelConfiguration {
+            config: TunnelConfigInner {
+                ingress: vec![
+                    IngressRule {
+                        hostname: Some("app.example.com".to_string()),
+                        service: "http://localhost:3000".to_string(),
+                        origin_request: None,
+                    },
+                    IngressRule {
+                        hostname: None,
+                        service: "http_status:404".to_string(),
+                        origin_request: None,
+                    },
+                ],
+            },
+        };
+        let records = vec![
+            DnsRecord {
+                id: "r1".to_string(),
+                name: "app.example.com".to_string(),
+                record_type: "CNAME".to_string(),
+                content: "tid-1.cfargotunnel.com".to_string(),
+                proxied: Some(true),
+                ttl: Some(1),
+                priority: None,
+            },
+            DnsRecord {
+                id: "r2".to_string(),
+                name: "example.com".to_string(),
+                record_type: "A".to_string(),
+                content: "192.0.2.1".to_string(),
+                proxied: Some(false),
+                ttl: Some(300),
+                priority: None,
+            },
+        ];
+
+        let hcl = render_terraform(
+            "acc",
+            Some("zone"),
+            &[(tunnel, Some(config)), (deleted, None)],
+            &records,
+        );
+        assert!(braces_balanced(&hcl), "{hcl}");
+        assert!(hcl.contains("resource \"cloudflare_tunnel\" \"home_lab\""));
+        assert!(hcl.contains("resource \"cloudflare_tunnel_config\" \"home_lab\""));
+        assert!(hcl.contains("hostname = \"app.example.com\""));
+        assert!(hcl.contains("content = \"${cloudflare_tunnel.home_lab.id}.cfargotunnel.com\""));
+        assert!(hcl.contains("# secret"));
+        assert!(!hcl.contains("192.0.2.1"));
+        assert!(!hcl.contains("retired"));
+    }
+
+    #[test]
+    fn hcl_helpers_escape_and_dedupe() {
+        assert_eq!(hcl_string(r#"a "b" ${c}"#), r#""a \"b\" $${c}""#);
+        let mut used = HashSet::new();
+        assert_eq!(hcl_label("app.example.com", &mut used), "app_example_com");
+        assert_eq!(hcl_label("app-example.com", &mut used), "app_example_com_2");
+        assert_eq!(hcl_label("3d", &mut used), "r_3d");
+    }
+
+    #[test]
+    fn credentials_path_keeps_only_file_name() {
+        assert_eq!(
+            mask_credentials_path(
+                "/home/alice/.cloudflared/6ff42ae2-765d-4adf-8112-31c55c1551ef.json"
+            ),
+            ".../6ff42ae2-765d-4adf-8112-31c55c1551ef.json"
+        );
+        assert_eq!(
+            mask_credentials_path(r"C:\Users\bob\.cloudflared\tid.json"),
+            ".../tid.json"
+        );
+        assert_eq!(mask_credentials_path("tid.json"), "tid.json");
+        assert_eq!(mask_id("0123456789abcdef"), "01234567...");
+    }
+
+    #[test]
+    fn tunnel_config_yaml_masks_credentials_unless_full() {
+        let cfg = config::LocalTunnelConfig {
+            path: PathBuf::from("/etc/cloudflared/config.yml"),
+            yaml: serde_yaml::from_str(
+                "tunnel: tid\ncredentials-file: /root/.cloudflared/tid.json\ningress:\n  - hostname: app.example.com\n    service: http://localhost:3000\n  - service: http_status:404\n",
+            )
+            .unwrap(),
+        };
+        let masked = tunnel_config_yaml(&cfg, false);
+        assert!(masked.contains(".../tid.json"));
+        assert!(!masked.contains("/root/"));
+        assert!(tunnel_config_yaml(&cfg, true).contains("/root/.cloudflared/tid.json"));
+        assert_eq!(cfg.ingress_count(), 2);
+        assert_eq!(cfg.ingress_hostnames(), vec!["app.example.com"]);
+    }
+
+    #[test]
+    fn hostname_prefix_for_every_default_port() {
+        for &(port, desc, _) in scan::DEFAULT_PORTS {
+            let prefix = suggest_hostname_prefix(port, desc);
+            assert!(
+                !prefix.starts_with("svc-"),
+                "no specific suggestion for {port} ({desc})"
+            );
+            assert!(validate_hostname(&format!("{prefix}.example.com"), "example.com").is_ok());
+        }
+        assert_eq!(suggest_hostname_prefix(3000, "React / Node.js"), "app");
+        assert_eq!(suggest_hostname_prefix(8888, "Jupyter"), "notebook");
+        assert_eq!(suggest_hostname_prefix(9090, "Prometheus"), "metrics");
+    }
+
+    #[test]
+    fn hostname_prefix_falls_back_to_description_and_port() {
+        assert_eq!(suggest_hostname_prefix(3100, "Grafana"), "grafana");
+        assert_eq!(suggest_hostname_prefix(12345, "custom"), "svc-12345");
+    }
+
+    #[test]
+    fn suggested_hostnames_are_unique() {
+        let services = vec![
+            (80, "HTTP".to_string()),
+            (8080, "HTTP Alternate".to_string()),
+            (3000, "React / Node.js".to_string()),
+        ];
+        assert_eq!(
+            suggest_hostnames(&services, "example.com"),
+            vec!["www.example.com", "www-8080.example.com", "app.example.com"]
+        );
+    }
+
+    #[test]
+    fn validate_hostname_requires_zone() {
+        assert!(validate_hostname("app.example.com", "example.com").is_ok());
+        assert!(validate_hostname("app.other.com", "example.com").is_err());
+        assert!(validate_hostname("bad host.example.com", "example.com").is_err());
+        assert!(validate_hostname("", "example.com").is_err());
+    }
+
+    fn sample_config() -> ApiConfig {
+        ApiConfig {
+            api_token: Some("secret-token".to_string()),
+            account_id: Some("acc".to_string()),
+            zone_id: Some("zone".to_string()),
+            zone_name: Some("example.com".to_string()),
+            language: Some("zh".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn round_trip(format: ConfigFormat) {
+        let original = sample_config();
+        let mut export = ConfigExport::from_api_config(&original);
+        export.tunnels.push(ExportedTunnel {
+            id: "tid".to_string(),
+            name: "home".to_string(),
+            ingress: vec![IngressRule {
+                hostname: None,
+                service: "http_status:404".to_string(),
+                origin_request: None,
+            }],
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export");
+        fs::write(&path, export.serialize(format).unwrap()).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+
+        assert!(!content.contains("secret-token"));
+        let parsed = ConfigExport::parse(&content, format).unwrap();
+        let imported = parsed.merge_into(ApiConfig::default(), "secret-token".to_string());
+        assert_eq!(imported.api_token, original.api_token);
+        assert_eq!(imported.account_id, original.account_id);
+        assert_eq!(imported.zone_id, original.zone_id);
+        assert_eq!(imported.zone_name, original.zone_name);
+        assert_eq!(imported.language, original.language);
+        assert_eq!(parsed.tunnels.len(), 1);
+        assert_eq!(parsed.tunnels[0].ingress[0].service, "http_status:404");
+    }
+
+    #[test]
+    fn export_import_round_trip_json() {
+        round_trip(ConfigFormat::Json);
+    }
+
+    #[test]
+    fn export_import_round_trip_yaml() {
+        round_trip(ConfigFormat::Yaml);
+    }
+
+    #[test]
+    fn import_keeps_local_tokens_and_settings() {
+        let current = ApiConfig {
+            api_token: Some("old-token".to_string()),
+            account_id: Some("acc".to_string()),
+            account_name: Some("Home".to_string()),
+            tokens: vec![config::NamedToken {
+                label: "ro".to_string(),
+                token: "ro-token".to_string(),
+                scopes: Vec::new(),
+            }],
+            active_token: Some("ro".to_string()),
+            response_size_limit_mb: Some(20),
+            audit_log: Some(PathBuf::from("/tmp/audit.jsonl")),
+            ..Default::default()
+        };
+        let export = ConfigExport::from_api_config(&sample_config());
+
+        let merged = export.merge_into(current.clone(), "new-token".to_string());
+        assert_eq!(merged.effective_token(), Some("new-token"));
+        assert_eq!(merged.tokens, current.tokens);
+        assert_eq!(merged.account_name.as_deref(), Some("Home"));
+        assert_eq!(merged.response_size_limit_mb, Some(20));
+        assert_eq!(merged.audit_log, current.audit_log);
+        assert_eq!(merged.zone_name.as_deref(), Some("example.com"));
+
+        // A different account's cached name does not carry over
+        let other = ConfigExport {
+            api_config: ExportedApiConfig {
+                account_id: Some("other".to_string()),
+                ..Default::default()
+            },
+            tunnels: Vec::new(),
+        };
+        assert_eq!(
+            other.merge_into(current, "t".to_string()).account_name,
+            None
+        );
+    }
+
+    #[test]
+    fn yaml_and_json_exports_are_equivalent() {
+        let mut export = ConfigExport::from_api_config(&sample_config());
+        export.api_config.api_token = Some("secret-token".to_string());
+        export.tunnels.push(ExportedTunnel {
+            id: "tid".to_string(),
+            name: "home".to_string(),
+            ingress: vec![IngressRule {
+                hostname: Some("app.example.com".to_string()),
+                service: "http://localhost:8080".to_string(),
+                origin_request: Some(serde_json::json!({ "noTLSVerify": true })),
+            }],
+        });
+
+        let yaml = export.serialize(ConfigFormat::Yaml).unwrap();
+        let json = export.serialize(ConfigFormat::Json).unwrap();
+        assert!(yaml.starts_with(&format!(
+            "# Generated by openTunnel v{} on ",
+            env!("CARGO_PKG_VERSION")
+        )));
+
+        let from_yaml = ConfigExport::parse(&yaml, ConfigFormat::Yaml).unwrap();
+        let from_json = ConfigExport::parse(&json, ConfigFormat::Json).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_yaml).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+        assert_eq!(
+            from_yaml.api_config.api_token.as_deref(),
+            Some("secret-token")
+        );
+    }
+
+    #[test]
+    fn parse_legacy_export_without_tunnels() {
+        let json = r#"{"api_config": {"account_id": "acc", "zone_id": null}}"#;
+        let parsed = ConfigExport::parse(json, ConfigFormat::Json).unwrap();
+        assert_eq!(parsed.api_config.account_id.as_deref(), Some("acc"));
+        assert!(parsed.tunnels.is_empty());
+    }
+}
@@ -1,17 +1,45 @@
-use anyhow::bail;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
 use base64::Engine;
+use chrono::{DateTime, Utc};
 use colored::Colorize;
-use comfy_table::{presets::UTF8_FULL, Table};
-
-use crate::client::{CloudflareClient, IngressRule, TunnelConfigInner, TunnelConfiguration};
-use crate::error::Result;
+use comfy_table::{presets::UTF8_FULL, Cell, Color, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{
+    CloudflareClient, IngressRule, Tunnel, TunnelConfigInner, TunnelConfiguration, TunnelConnector,
+};
+use crate::error::{CftError, Result};
+use crate::event::{self, EventAction};
 use crate::i18n::lang;
-use crate::{dns, prompt, service, t};
+use crate::{access, config, dns, prompt, service, t, util};
 
 fn short_id(id: &str) -> String {
     id.chars().take(8).collect()
 }
 
+impl prompt::TableRow for IngressRule {
+    fn header() -> Vec<String> {
+        let l = lang();
+        vec![
+            t!(l, "Hostname", "域名").to_string(),
+            t!(l, "Service", "服务").to_string(),
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.hostname.clone().unwrap_or_else(|| "*".to_string()),
+            self.service.clone(),
+        ]
+    }
+}
+
 /// Format an ISO-8601 timestamp to "YYYY-MM-DD HH:MM".
 fn format_time(ts: Option<&str>) -> String {
     match ts {
@@ -34,6 +62,7 @@ fn normalize_service_input(input: &str) -> String {
         || trimmed.starts_with("https://")
         || trimmed.starts_with("http_status:")
         || trimmed.starts_with("unix:")
+        || trimmed.starts_with("unix+tls:")
         || trimmed.starts_with("ssh://")
         || trimmed.starts_with("rdp://")
         || trimmed.starts_with("tcp://")
@@ -58,6 +87,116 @@ fn normalize_service_input(input: &str) -> String {
     trimmed.to_string()
 }
 
+/// A Unix socket target must be an absolute path to an existing file.
+fn validate_socket_path(path: &str) -> std::result::Result<(), String> {
+    let l = lang();
+    if !path.starts_with('/') {
+        return Err(t!(l, "Path must start with /", "路径必须以 / 开头").to_string());
+    }
+    if !Path::new(path).exists() {
+        return Err(t!(l, "File does not exist", "文件不存在").to_string());
+    }
+    Ok(())
+}
+
+/// Ask for the service target: a URL, or a Unix socket (optionally TLS-wrapped).
+fn prompt_service_target() -> Option<String> {
+    let l = lang();
+    let kinds = [
+        t!(
+            l,
+            "URL (http, https, tcp, ssh, ...)",
+            "URL (http、https、tcp、ssh 等)"
+        ),
+        t!(l, "Unix socket", "Unix 套接字"),
+        t!(l, "Unix socket (TLS)", "Unix 套接字 (TLS)"),
+    ];
+    let scheme = match prompt::select_opt(t!(l, "Service type", "服务类型"), &kinds, Some(0))? {
+        0 => {
+            return prompt::input_opt(
+                t!(
+                    l,
+                    "Service URL (e.g. http://localhost:3000)",
+                    "服务地址 (如 http://localhost:3000)"
+                ),
+                false,
+                None,
+            )
+        }
+        1 => "unix",
+        _ => "unix+tls",
+    };
+    let path = prompt::input_validated(
+        t!(l, "Socket path", "套接字路径"),
+        None,
+        validate_socket_path,
+    )?;
+    Some(format!("{scheme}:{path}"))
+}
+
+/// `host:port` a URL-style service target connects to. IPv6 hosts come back
+/// without brackets (`http://[::1]:8080` gives `("::1", 8080)`).
+fn service_socket_addr(service: &str) -> Option<(String, u16)> {
+    let url = reqwest::Url::parse(service).ok()?;
+    let host = url.host_str()?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    let port = match url.port_or_known_default() {
+        Some(port) => port,
+        None => match url.scheme() {
+            "ssh" => 22,
+            "rdp" => 3389,
+            _ => return None,
+        },
+    };
+    Some((host.to_string(), port))
+}
+
+/// Whether the local service accepts connections; `None` when the target
+/// cannot be checked (e.g. `http_status:` or a non-Unix platform for sockets).
+/// Every address the host resolves to is tried at once, so a dead IPv6
+/// `localhost` entry does not hold up the IPv4 one.
+async fn service_reachable(service: &str) -> Option<bool> {
+    const TIMEOUT: Duration = Duration::from_secs(2);
+    if let Some(path) = service
+        .strip_prefix("unix+tls:")
+        .or_else(|| service.strip_prefix("unix:"))
+    {
+        #[cfg(unix)]
+        {
+            let connect = tokio::net::UnixStream::connect(path);
+            return Some(matches!(
+                tokio::time::timeout(TIMEOUT, connect).await,
+                Ok(Ok(_))
+            ));
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            return None;
+        }
+    }
+    let (host, port) = service_socket_addr(service)?;
+    let probe = async {
+        let Ok(addrs) = tokio::net::lookup_host((host.as_str(), port)).await else {
+            return false;
+        };
+        let mut attempts = tokio::task::JoinSet::new();
+        for addr in addrs {
+            attempts.spawn(tokio::net::TcpStream::connect(addr));
+        }
+        while let Some(attempt) = attempts.join_next().await {
+            if matches!(attempt, Ok(Ok(_))) {
+                return true;
+            }
+        }
+        false
+    };
+    Some(tokio::time::timeout(TIMEOUT, probe).await.unwrap_or(false))
+}
+
 // ---------------------------------------------------------------------------
 // Tunnel selection helper
 // ---------------------------------------------------------------------------
@@ -84,7 +223,12 @@ pub async fn select_tunnel(client: &CloudflareClient) -> Result<Option<crate::cl
         })
         .collect();
 
-    let sel = prompt::select_opt(t!(l, "Select tunnel", "选择隧道"), &items, None);
+    let sel = prompt::paged_select(
+        t!(l, "Select tunnel", "选择隧道"),
+        &items,
+        prompt::DEFAULT_PAGE_SIZE,
+        None,
+    );
 
     Ok(sel.and_then(|i| tunnels.into_iter().nth(i)))
 }
@@ -104,27 +248,288 @@ async fn resolve_tunnel_id(
 // List tunnels
 // ---------------------------------------------------------------------------
 
+/// Connector lookups issued at once when enriching the tunnel list.
+const CONNECTION_FETCH_CONCURRENCY: usize = 5;
+
+/// How long connector summaries are reused before asking the API again.
+const CONNECTION_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Connector count and most recent connector start for one tunnel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ConnectorSummary {
+    count: usize,
+    /// Latest `run_at` among the connectors (never later than now);
+    /// `None` when the tunnel has no connector with a start time.
+    last_active: Option<DateTime<Utc>>,
+}
+
+impl ConnectorSummary {
+    fn from_connectors(connectors: &[TunnelConnector], now: DateTime<Utc>) -> Self {
+        let last_active = connectors
+            .iter()
+            .filter_map(|c| c.run_at.as_deref())
+            .filter_map(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc).min(now))
+            .max();
+        Self {
+            count: connectors.len(),
+            last_active,
+        }
+    }
+}
+
+/// Output format of `tunnel show`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MappingsFormat {
+    #[default]
+    Table,
+    /// The ingress rules as a JSON array, for `dns sync-from-file`
+    Json,
+}
+
+/// Ordering for `tunnel list --sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TunnelSort {
+    Name,
+    /// Most recently active first; never-connected tunnels last.
+    LastActive,
+}
+
+fn connection_cache() -> &'static Mutex<HashMap<String, (Instant, ConnectorSummary)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, ConnectorSummary)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Look up per-tunnel info (connectors, configs), at most five lookups in flight.
+/// Failed lookups are left out of the result.
+async fn fetch_connector_info<T, F, Fut>(ids: Vec<String>, fetch: F) -> HashMap<String, T>
+where
+    T: Send + 'static,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    let handles = util::spawn_bounded(ids, CONNECTION_FETCH_CONCURRENCY, |id| {
+        let fut = fetch(id.clone());
+        async move { (id, fut.await.ok()) }
+    });
+
+    let mut info = HashMap::new();
+    for handle in handles {
+        if let Ok((id, Some(value))) = handle.await {
+            info.insert(id, value);
+        }
+    }
+    info
+}
+
+/// Connector summaries for `ids`, served from a 10-second cache where possible.
+async fn connector_summaries(
+    client: &CloudflareClient,
+    ids: &[String],
+) -> HashMap<String, ConnectorSummary> {
+    let mut summaries = HashMap::new();
+    let mut stale = Vec::new();
+    if let Ok(cache) = connection_cache().lock() {
+        for id in ids {
+            match cache.get(id) {
+                Some((at, summary)) if at.elapsed() < CONNECTION_CACHE_TTL => {
+                    summaries.insert(id.clone(), *summary);
+                }
+                _ => stale.push(id.clone()),
+            }
+        }
+    } else {
+        stale = ids.to_vec();
+    }
+
+    let fresh = fetch_connector_info(stale, |id| {
+        let client = client.clone();
+        async move {
+            let connectors = client.list_tunnel_connections(&id).await?;
+            Ok(ConnectorSummary::from_connectors(&connectors, Utc::now()))
+        }
+    })
+    .await;
+
+    if let Ok(mut cache) = connection_cache().lock() {
+        let now = Instant::now();
+        for (id, summary) in &fresh {
+            cache.insert(id.clone(), (now, *summary));
+        }
+    }
+    summaries.extend(fresh);
+    summaries
+}
+
+/// Table cell for a connector count; zero connectors on a tunnel the API
+/// reports as healthy or degraded is suspicious.
+fn connection_cell(status: Option<&str>, count: Option<usize>) -> String {
+    match count {
+        Some(0) if matches!(status, Some("healthy" | "degraded")) => "0 ⚠️".to_string(),
+        Some(n) => n.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Number of routed hostnames per tunnel; a tunnel without a remote config
+/// counts as 0 and failed lookups are left out.
+async fn hostname_counts(client: &CloudflareClient, ids: &[String]) -> HashMap<String, usize> {
+    fetch_connector_info(ids.to_vec(), |id| {
+        let client = client.clone();
+        async move {
+            let config = client.get_tunnel_config(&id).await?;
+            Ok(config.map_or(0, |c| c.hostname_count()))
+        }
+    })
+    .await
+}
+
+/// Table cell for a hostname count; 0 is highlighted as a likely
+/// misconfiguration.
+fn hostname_cell(count: Option<usize>) -> Cell {
+    match count {
+        Some(0) => Cell::new("0").fg(Color::Yellow),
+        Some(n) => Cell::new(n),
+        None => Cell::new("-"),
+    }
+}
+
+/// Table cell for the last connector activity, e.g. "3d ago" or "never".
+fn last_active_cell(summary: Option<&ConnectorSummary>, now: DateTime<Utc>) -> String {
+    match summary {
+        Some(ConnectorSummary {
+            last_active: Some(at),
+            ..
+        }) => util::human_duration(now.signed_duration_since(*at).num_seconds().max(0) as u64),
+        Some(_) => t!(lang(), "never", "从未").to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Sort tunnels by last activity, newest first. Tunnels that never
+/// connected (or whose lookup failed) come last; ties are ordered by name.
+fn sort_by_last_active(tunnels: &mut [Tunnel], summaries: &HashMap<String, ConnectorSummary>) {
+    let last = |t: &Tunnel| summaries.get(&t.id).and_then(|s| s.last_active);
+    tunnels.sort_by(|a, b| last(b).cmp(&last(a)).then_with(|| a.name.cmp(&b.name)));
+}
+
+/// Whether a tunnel had no connector activity in the last `days` days.
+/// Tunnels whose lookup failed are not reported as inactive, and neither is
+/// anything when `days` reaches past the earliest representable time.
+fn is_inactive(summary: Option<&ConnectorSummary>, days: u64, now: DateTime<Utc>) -> bool {
+    let cutoff = i64::try_from(days)
+        .ok()
+        .and_then(chrono::Duration::try_days)
+        .and_then(|span| now.checked_sub_signed(span));
+    let Some(cutoff) = cutoff else {
+        return false;
+    };
+    match summary {
+        Some(s) => s.last_active.is_none_or(|at| at < cutoff),
+        None => false,
+    }
+}
+
 /// List all tunnels via the Cloudflare API.
-pub async fn list_tunnels(client: &CloudflareClient) -> Result<()> {
+/// With `verbose`, also show connector counts and last activity; sorting by
+/// activity or filtering with `inactive_days` implies `verbose`.
+pub async fn list_tunnels(
+    client: &CloudflareClient,
+    verbose: bool,
+    sort: Option<TunnelSort>,
+    inactive_days: Option<u64>,
+    count_hostnames: bool,
+) -> Result<()> {
     let l = lang();
     println!(
         "{}",
         t!(l, "Fetching tunnel list...", "获取隧道列表...").bold()
     );
 
-    let tunnels = client.list_tunnels().await?;
+    let mut tunnels = client.list_tunnels().await?;
 
     if tunnels.is_empty() {
         println!("{}", t!(l, "No tunnels found.", "未找到隧道。"));
         return Ok(());
     }
 
+    let detailed = verbose || sort == Some(TunnelSort::LastActive) || inactive_days.is_some();
+    let summaries = if detailed {
+        let ids: Vec<String> = tunnels.iter().map(|t_info| t_info.id.clone()).collect();
+        connector_summaries(client, &ids).await
+    } else {
+        HashMap::new()
+    };
+
+    let now = Utc::now();
+    if let Some(days) = inactive_days {
+        tunnels.retain(|t_info| is_inactive(summaries.get(&t_info.id), days, now));
+        if tunnels.is_empty() {
+            println!(
+                "{}",
+                t!(
+                    l,
+                    "No tunnels inactive for that long.",
+                    "没有闲置这么久的隧道。"
+                )
+            );
+            return Ok(());
+        }
+    }
+    let hostnames = if count_hostnames {
+        let ids: Vec<String> = tunnels.iter().map(|t_info| t_info.id.clone()).collect();
+        hostname_counts(client, &ids).await
+    } else {
+        HashMap::new()
+    };
+    match sort {
+        Some(TunnelSort::Name) => tunnels.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some(TunnelSort::LastActive) => sort_by_last_active(&mut tunnels, &summaries),
+        None => {}
+    }
+
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
-    table.set_header(vec![t!(l, "Name", "名称"), t!(l, "Status", "状态")]);
+    let mut header = vec![
+        t!(l, "Name", "名称"),
+        t!(l, "Status", "状态"),
+        t!(l, "Created", "创建时间"),
+        t!(l, "Age", "已创建"),
+    ];
+    if detailed {
+        header.push(t!(l, "Connections", "连接数"));
+        header.push(t!(l, "Last active", "最近活跃"));
+    }
+    if count_hostnames {
+        header.push(t!(l, "Hostnames", "域名数"));
+    }
+    table.set_header(header);
 
     for t_info in tunnels.iter() {
-        table.add_row(vec![&t_info.name, t_info.status.as_deref().unwrap_or("-")]);
+        let created = format_time(t_info.created_at.as_deref());
+        let age = t_info
+            .created_at
+            .as_deref()
+            .and_then(util::age_since)
+            .unwrap_or_else(|| "-".to_string());
+        let mut row = vec![
+            Cell::new(&t_info.name),
+            Cell::new(t_info.status.as_deref().unwrap_or("-")),
+            Cell::new(created),
+            Cell::new(age),
+        ];
+        if detailed {
+            let summary = summaries.get(&t_info.id);
+            row.push(Cell::new(connection_cell(
+                t_info.status.as_deref(),
+                summary.map(|s| s.count),
+            )));
+            row.push(Cell::new(last_active_cell(summary, now)));
+        }
+        if count_hostnames {
+            row.push(hostname_cell(hostnames.get(&t_info.id).copied()));
+        }
+        table.add_row(row);
     }
 
     println!("{table}");
@@ -140,30 +545,170 @@ pub async fn list_tunnels(client: &CloudflareClient) -> Result<()> {
 // Create tunnel
 // ---------------------------------------------------------------------------
 
-/// Create a new tunnel.
-pub async fn create_tunnel(client: &CloudflareClient, name: Option<String>) -> Result<()> {
+/// Generate a random tunnel secret (32 bytes, base64).
+fn generate_tunnel_secret() -> String {
+    let secret_bytes: Vec<u8> = (0..32).map(|_| rand::random::<u8>()).collect();
+    base64::engine::general_purpose::STANDARD.encode(&secret_bytes)
+}
+
+/// Check a user-supplied tunnel secret: standard base64 that decodes to
+/// exactly 32 bytes. Returns the trimmed secret.
+fn validate_tunnel_secret(secret: &str) -> Result<String> {
+    let secret = secret.trim();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(secret)
+        .map_err(|e| anyhow::anyhow!("tunnel secret is not valid base64: {e}"))?;
+    if bytes.len() != 32 {
+        bail!(
+            "tunnel secret must decode to exactly 32 bytes, got {}",
+            bytes.len()
+        );
+    }
+    Ok(secret.to_string())
+}
+
+/// Read a tunnel secret from the environment variable `var` via `lookup`.
+fn secret_from_env(var: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    match lookup(var) {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        _ => bail!("environment variable {var} is not set or empty"),
+    }
+}
+
+/// The validated secret given by `--secret` or `--secret-env`, if any.
+pub fn provided_secret(
+    secret: Option<String>,
+    secret_env: Option<String>,
+) -> Result<Option<String>> {
+    let secret = match (secret, secret_env) {
+        (Some(secret), _) => secret,
+        (None, Some(var)) => secret_from_env(&var, |k| std::env::var(k).ok())?,
+        (None, None) => return Ok(None),
+    };
+    validate_tunnel_secret(&secret).map(Some)
+}
+
+/// What to do when the chosen tunnel name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NameConflictChoice {
+    UseExisting,
+    Rename,
+    Cancel,
+}
+
+/// Outcome of picking a name for a new tunnel.
+#[derive(Debug, Clone)]
+enum TunnelNameResolution {
+    Create(String),
+    UseExisting(Tunnel),
+    Cancel,
+}
+
+/// The tunnel whose name matches `name`, ignoring case.
+fn find_tunnel_by_name<'a>(tunnels: &'a [Tunnel], name: &str) -> Option<&'a Tunnel> {
+    let name = name.trim();
+    tunnels.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// Keep asking until `name` does not clash with one of `existing`, or the user
+/// settles on an existing tunnel or cancels. `existing` is fetched once by the
+/// caller so renaming does not hit the API again.
+fn resolve_tunnel_name(
+    existing: &[Tunnel],
+    mut name: String,
+    mut on_conflict: impl FnMut(&Tunnel) -> NameConflictChoice,
+    mut ask_name: impl FnMut() -> Option<String>,
+) -> TunnelNameResolution {
+    loop {
+        let Some(found) = find_tunnel_by_name(existing, &name) else {
+            return TunnelNameResolution::Create(name);
+        };
+        match on_conflict(found) {
+            NameConflictChoice::UseExisting => {
+                return TunnelNameResolution::UseExisting(found.clone())
+            }
+            NameConflictChoice::Cancel => return TunnelNameResolution::Cancel,
+            NameConflictChoice::Rename => match ask_name() {
+                Some(next) => name = next,
+                None => return TunnelNameResolution::Cancel,
+            },
+        }
+    }
+}
+
+/// Warn about a name clash and ask how to continue.
+fn prompt_name_conflict(found: &Tunnel) -> NameConflictChoice {
+    let l = lang();
+    println!(
+        "{} {} '{}' {} (ID: {}...)",
+        "⚠️".yellow(),
+        t!(l, "Tunnel", "隧道"),
+        found.name,
+        t!(l, "already exists", "已存在"),
+        short_id(&found.id)
+    );
+    let choices = [
+        t!(l, "Use existing tunnel", "使用已有隧道"),
+        t!(l, "Choose a different name", "换一个名称"),
+        t!(l, "Cancel", "取消"),
+    ];
+    match prompt::select_opt(t!(l, "What next?", "接下来?"), &choices, Some(1)) {
+        Some(0) => NameConflictChoice::UseExisting,
+        Some(1) => NameConflictChoice::Rename,
+        _ => NameConflictChoice::Cancel,
+    }
+}
+
+/// Create a tunnel. `secret` (already validated) replaces the generated
+/// one; together with `name` it makes creation non-interactive for CI/CD.
+pub async fn create_tunnel(
+    client: &CloudflareClient,
+    name: Option<String>,
+    secret: Option<String>,
+) -> Result<()> {
     let l = lang();
+    if let (Some(name), Some(secret)) = (&name, &secret) {
+        return create_tunnel_unattended(client, name, secret).await;
+    }
+
+    let ask_name = || prompt::input_opt(t!(l, "Tunnel name", "隧道名称"), false, None);
     let name = match name {
         Some(n) => n,
-        None => match prompt::input_opt(t!(l, "Tunnel name", "隧道名称"), false, None) {
+        None => match ask_name() {
             Some(v) => v,
             None => return Ok(()),
         },
     };
 
-    // Generate a random tunnel secret (32 bytes, base64)
-    let secret_bytes: Vec<u8> = (0..32).map(|_| rand::random::<u8>()).collect();
-    let secret = base64::engine::general_purpose::STANDARD.encode(&secret_bytes);
+    // A failed listing should not block creation; the API still rejects duplicates.
+    let existing = client.list_tunnels().await.unwrap_or_default();
+    let tunnel = match resolve_tunnel_name(&existing, name, prompt_name_conflict, ask_name) {
+        TunnelNameResolution::Create(name) => {
+            let secret = secret.unwrap_or_else(generate_tunnel_secret);
 
-    println!("{}", t!(l, "Creating tunnel...", "正在创建隧道...").bold());
-    let tunnel = client.create_tunnel(&name, &secret).await?;
+            println!("{}", t!(l, "Creating tunnel...", "正在创建隧道...").bold());
+            let tunnel = client.create_tunnel(&name, &secret).await?;
 
-    println!(
-        "{} {} (ID: {})",
-        "✅".green(),
-        t!(l, "Tunnel created:", "隧道已创建:"),
-        tunnel.id
-    );
+            println!(
+                "{} {} (ID: {})",
+                "✅".green(),
+                t!(l, "Tunnel created:", "隧道已创建:"),
+                tunnel.id
+            );
+            tunnel
+        }
+        TunnelNameResolution::UseExisting(tunnel) => {
+            println!(
+                "{} {} '{}' (ID: {})",
+                "✅".green(),
+                t!(l, "Using existing tunnel", "使用已有隧道"),
+                tunnel.name,
+                tunnel.id
+            );
+            tunnel
+        }
+        TunnelNameResolution::Cancel => return Ok(()),
+    };
 
     let takeover = prompt::confirm_opt(
         t!(
@@ -186,7 +731,7 @@ pub async fn create_tunnel(client: &CloudflareClient, name: Option<String>) -> R
             .bold()
         );
 
-        match service::install(client, Some(tunnel.id.clone())).await {
+        match service::install(client, Some(tunnel.id.clone()), None).await {
             Ok(_) => match service::start() {
                 Ok(_) => {
                     println!(
@@ -229,30 +774,68 @@ pub async fn create_tunnel(client: &CloudflareClient, name: Option<String>) -> R
             }
         }
     } else {
-        println!(
-            "\n{}",
-            t!(
-                l,
-                "To run this tunnel in background via program, use:",
-                "若要由程序后台托管运行，请执行："
-            )
-            .bold()
-        );
-        println!("  tunnel service install --tunnel {}", tunnel.id);
-        println!("  tunnel service start");
-        println!(
-            "  {}",
+        print_service_instructions(&tunnel.id);
+    }
+
+    Ok(())
+}
+
+/// Create tunnel `name` with `secret` without any prompts; an existing
+/// tunnel of the same name is an error rather than a question.
+async fn create_tunnel_unattended(
+    client: &CloudflareClient,
+    name: &str,
+    secret: &str,
+) -> Result<()> {
+    let l = lang();
+    let existing = client.list_tunnels().await?;
+    if let Some(tunnel) = find_tunnel_by_name(&existing, name) {
+        bail!(
+            "{} '{}' (ID: {})",
             t!(
                 l,
-                "Or fetch token manually only when needed: `tunnel token <id>`",
-                "或仅在需要时手动取 token：`tunnel token <id>`"
-            )
+                "A tunnel with this name already exists:",
+                "同名隧道已存在:"
+            ),
+            tunnel.name,
+            tunnel.id
         );
     }
 
+    let tunnel = client.create_tunnel(name.trim(), secret).await?;
+    println!(
+        "{} {} (ID: {})",
+        "✅".green(),
+        t!(l, "Tunnel created:", "隧道已创建:"),
+        tunnel.id
+    );
+    print_service_instructions(&tunnel.id);
     Ok(())
 }
 
+fn print_service_instructions(tunnel_id: &str) {
+    let l = lang();
+    println!(
+        "\n{}",
+        t!(
+            l,
+            "To run this tunnel in background via program, use:",
+            "若要由程序后台托管运行，请执行："
+        )
+        .bold()
+    );
+    println!("  tunnel service install --tunnel {tunnel_id}");
+    println!("  tunnel service start");
+    println!(
+        "  {}",
+        t!(
+            l,
+            "Or fetch token manually only when needed: `tunnel token <id>`",
+            "或仅在需要时手动取 token：`tunnel token <id>`"
+        )
+    );
+}
+
 // ---------------------------------------------------------------------------
 // Delete tunnel
 // ---------------------------------------------------------------------------
@@ -294,12 +877,25 @@ pub async fn delete_tunnel(client: &CloudflareClient) -> Result<()> {
         None => return Ok(()),
     };
 
-    let confirmed = prompt::confirm_opt(
+    // Mappings that stop working once the tunnel is gone
+    let orphaned: Vec<IngressRule> = client
+        .get_tunnel_config(&target.id)
+        .await
+        .ok()
+        .flatten()
+        .map(|c| c.config.ingress)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.hostname.is_some())
+        .collect();
+
+    let confirmed = prompt::confirm_with_table(
         &format!(
             "{} '{}' ?",
             t!(l, "Delete tunnel", "确认删除隧道"),
             target.name
         ),
+        &orphaned,
         false,
     )
     .unwrap_or(false);
@@ -336,15 +932,71 @@ pub async fn get_token(client: &CloudflareClient, id: Option<String>) -> Result<
         t!(l, "Run this tunnel with:", "使用以下命令运行隧道:").bold()
     );
     println!("  cloudflared tunnel run --token {}", token);
+
+    warn_token_health(client, &tunnel_id).await;
     Ok(())
 }
 
+/// Tunnel statuses under which a fresh token may not connect.
+fn is_unhealthy_status(status: Option<&str>) -> bool {
+    matches!(status, Some("degraded") | Some("inactive"))
+}
+
+/// Warn after `get_token` when the tunnel is unhealthy or the credentials file
+/// named by the local cloudflared config is missing or broken.
+async fn warn_token_health(client: &CloudflareClient, tunnel_id: &str) {
+    let l = lang();
+    let tunnel = match client.get_tunnel(tunnel_id).await {
+        Ok(t) => t,
+        Err(_) => return,
+    };
+
+    if is_unhealthy_status(tunnel.status.as_deref()) {
+        println!(
+            "\n{}",
+            format!(
+                "⚠️ {}",
+                t!(
+                    l,
+                    "Tunnel is not healthy; the generated token may not establish connections until the underlying issue is resolved",
+                    "隧道状态异常；在问题解决之前，生成的 Token 可能无法建立连接"
+                )
+            )
+            .yellow()
+        );
+    }
+
+    let Some(path) = config::load_tunnel_config()
+        .ok()
+        .flatten()
+        .and_then(|cfg| cfg.credentials_file_for(&tunnel.id, &tunnel.name))
+    else {
+        return;
+    };
+    let msg = match service::credential_status(&path) {
+        service::CredentialStatus::Valid { .. } => return,
+        service::CredentialStatus::Missing { path } => {
+            format!(
+                "{} {path}",
+                t!(l, "Credentials file not found at", "未找到凭证文件:")
+            )
+        }
+        service::CredentialStatus::Corrupt { path, error } => {
+            format!(
+                "{} {path}: {error}",
+                t!(l, "Credentials file is invalid:", "凭证文件无效:")
+            )
+        }
+    };
+    println!("{}", format!("⚠️ {msg}").yellow());
+}
+
 // ---------------------------------------------------------------------------
-// Show mappings (remotely-managed tunnel config via API)
+// Force disconnect
 // ---------------------------------------------------------------------------
 
-/// Show current ingress mappings for a tunnel via the API.
-pub async fn show_mappings(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+/// Drop all active connections of a tunnel after typed confirmation.
+pub async fn force_disconnect(client: &CloudflareClient, id: Option<String>) -> Result<()> {
     let l = lang();
 
     let tunnel_id = match resolve_tunnel_id(client, id).await? {
@@ -352,39 +1004,187 @@ pub async fn show_mappings(client: &CloudflareClient, id: Option<String>) -> Res
         None => return Ok(()),
     };
 
-    let config = client.get_tunnel_config(&tunnel_id).await?;
-    let rules = &config.config.ingress;
+    println!(
+        "{}",
+        t!(
+            l,
+            "⚠️  This will drop all active connections. Running cloudflared processes must reconnect.",
+            "⚠️  这将断开所有活跃连接，运行中的 cloudflared 进程需要重新连接。"
+        )
+        .yellow()
+    );
 
-    if rules.is_empty() {
-        println!("\n{}", t!(l, "No mappings configured.", "未配置映射。"));
+    let phrase = short_id(&tunnel_id);
+    if !prompt::confirm_by_typing(t!(l, "Force disconnect", "强制断开"), &phrase) {
+        println!("{}", t!(l, "Aborted.", "已中止。"));
         return Ok(());
     }
 
-    // Fetch connector info for origin IP and running time
-    let conns = client.list_tunnel_connections(&tunnel_id).await.ok();
-    let (origin_ip, run_at) = conns
-        .as_ref()
-        .and_then(|c| c.first())
-        .map(|conn| {
-            let ip = conn
-                .conns
-                .first()
-                .and_then(|c| c.origin_ip.clone())
-                .unwrap_or_else(|| "-".to_string());
-            let run = format_time(conn.run_at.as_deref());
-            (ip, run)
-        })
-        .unwrap_or_else(|| ("-".to_string(), "-".to_string()));
-
+    client.force_disconnect_tunnel(&tunnel_id).await?;
     println!(
-        "\n{} {}  {} {}  {} {}",
-        t!(l, "Tunnel:", "隧道:").bold(),
-        short_id(&tunnel_id).cyan(),
-        t!(l, "Origin IP:", "来源 IP:").bold(),
-        origin_ip,
-        t!(l, "Running since:", "运行时间:").bold(),
-        run_at,
-    );
+        "{} {}",
+        "✅".green(),
+        t!(
+            l,
+            "All tunnel connections dropped.",
+            "已断开隧道的所有连接。"
+        )
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Rotate tunnel secret
+// ---------------------------------------------------------------------------
+
+/// Replace the tunnel secret with a freshly generated one. The local service,
+/// if running, is reinstalled with the new token and restarted.
+pub async fn rotate_secret(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let tunnel_id = match resolve_tunnel_id(client, id).await? {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    println!(
+        "{}",
+        t!(
+            l,
+            "⚠️  All cloudflared processes using the old token will disconnect and must be restarted with the new token.",
+            "⚠️  所有使用旧 Token 的 cloudflared 进程都将断开，需要使用新 Token 重新启动。"
+        )
+        .yellow()
+    );
+
+    let phrase = short_id(&tunnel_id);
+    if !prompt::confirm_by_typing(t!(l, "Rotate secret", "轮换密钥"), &phrase) {
+        println!("{}", t!(l, "Aborted.", "已中止。"));
+        return Ok(());
+    }
+
+    let secret = generate_tunnel_secret();
+    client.rotate_tunnel_secret(&tunnel_id, &secret).await?;
+    println!(
+        "{} {}",
+        "✅".green(),
+        t!(l, "Tunnel secret rotated.", "隧道密钥已轮换。")
+    );
+
+    if service::is_running() {
+        println!(
+            "{}",
+            t!(
+                l,
+                "🔄 Reinstalling the local service with the new token...",
+                "🔄 正在使用新 Token 重新安装本地服务..."
+            )
+            .bold()
+        );
+        service::install(client, Some(tunnel_id), None).await?;
+        service::restart(true).await?;
+    } else {
+        println!(
+            "💡 {}",
+            t!(
+                l,
+                "Get the new token with `tunnel token` and restart your connectors.",
+                "使用 `tunnel token` 获取新 Token，并重启连接器。"
+            )
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Show mappings (remotely-managed tunnel config via API)
+// ---------------------------------------------------------------------------
+
+/// Show current ingress mappings for a tunnel via the API.
+pub async fn show_mappings(
+    client: &CloudflareClient,
+    id: Option<String>,
+    format: MappingsFormat,
+) -> Result<()> {
+    let l = lang();
+
+    // JSON goes to stdout for scripts, so never mix a tunnel picker into it.
+    if format == MappingsFormat::Json && id.is_none() {
+        return Err(CftError::InvalidInput(
+            t!(
+                l,
+                "--format json needs a tunnel ID",
+                "--format json 需要指定隧道 ID"
+            )
+            .to_string(),
+        )
+        .into());
+    }
+
+    let tunnel_id = match resolve_tunnel_id(client, id).await? {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    if format == MappingsFormat::Json {
+        let rules = client
+            .get_tunnel_config(&tunnel_id)
+            .await?
+            .map(|c| c.config.ingress)
+            .unwrap_or_default();
+        println!("{}", serde_json::to_string_pretty(&rules)?);
+        return Ok(());
+    }
+
+    let config = match client.get_tunnel_config(&tunnel_id).await? {
+        Some(config) => config,
+        None => {
+            println!(
+                "\n{}",
+                t!(
+                    l,
+                    "No configuration set for this tunnel yet.",
+                    "该隧道尚未设置配置。"
+                )
+            );
+            return Ok(());
+        }
+    };
+    let rules = &config.config.ingress;
+
+    if rules.is_empty() {
+        println!("\n{}", t!(l, "No mappings configured.", "未配置映射。"));
+        return Ok(());
+    }
+
+    // Fetch connector info for origin IP and running time
+    let conns = client.list_tunnel_connections(&tunnel_id).await.ok();
+    let (origin_ip, run_at) = conns
+        .as_ref()
+        .and_then(|c| c.first())
+        .map(|conn| {
+            let ip = conn
+                .conns
+                .first()
+                .and_then(|c| c.origin_ip.clone())
+                .unwrap_or_else(|| "-".to_string());
+            let run = match conn.run_at.as_deref().and_then(util::age_since) {
+                Some(age) => format!("{} ({age})", format_time(conn.run_at.as_deref())),
+                None => format_time(conn.run_at.as_deref()),
+            };
+            (ip, run)
+        })
+        .unwrap_or_else(|| ("-".to_string(), "-".to_string()));
+
+    println!(
+        "\n{} {}  {} {}  {} {}",
+        t!(l, "Tunnel:", "隧道:").bold(),
+        short_id(&tunnel_id).cyan(),
+        t!(l, "Origin IP:", "来源 IP:").bold(),
+        origin_ip,
+        t!(l, "Running since:", "运行时间:").bold(),
+        run_at,
+    );
 
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
@@ -406,16 +1206,300 @@ pub async fn show_mappings(client: &CloudflareClient, id: Option<String>) -> Res
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Mapping snapshots (<config dir>/snapshots/<tunnel_id>.json)
+// ---------------------------------------------------------------------------
+
+/// Ingress rules of a tunnel as captured at a point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MappingsSnapshot {
+    captured_at: String,
+    tunnel_id: String,
+    ingress: Vec<IngressRule>,
+}
+
+/// One line of a mapping diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiffLine {
+    Added(String),
+    Removed(String),
+    Unchanged(String),
+}
+
+fn snapshot_path(tunnel_id: &str) -> Result<PathBuf> {
+    Ok(config::config_dir()?
+        .join("snapshots")
+        .join(format!("{tunnel_id}.json")))
+}
+
+fn load_mappings_snapshot(tunnel_id: &str) -> Result<Option<MappingsSnapshot>> {
+    let path = snapshot_path(tunnel_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let snapshot = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(snapshot))
+}
+
+/// Save the ingress rules of `config` as the reference for [`diff_mappings`].
+pub fn capture_mappings_snapshot(tunnel_id: &str, config: &TunnelConfiguration) -> Result<()> {
+    let path = snapshot_path(tunnel_id)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let snapshot = MappingsSnapshot {
+        captured_at: Utc::now().to_rfc3339(),
+        tunnel_id: tunnel_id.to_string(),
+        ingress: config.config.ingress.clone(),
+    };
+    config::write_atomic(&path, serde_json::to_string_pretty(&snapshot)?.as_bytes())
+}
+
+/// One comparable line per ingress rule, in evaluation order.
+fn mapping_lines(rules: &[IngressRule]) -> Vec<String> {
+    rules
+        .iter()
+        .map(|r| {
+            let mut line = format!("{} → {}", r.hostname.as_deref().unwrap_or("*"), r.service);
+            if let Some(origin) = &r.origin_request {
+                line.push_str(&format!(" originRequest={origin}"));
+            }
+            line
+        })
+        .collect()
+}
+
+/// Line diff of `before` and `after` based on their longest common subsequence.
+fn diff_lines(before: &[String], after: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (before.len(), after.len());
+    // lcs[i][j] = LCS length of before[i..] and after[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if before[i] == after[j] {
+            out.push(DiffLine::Unchanged(before[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine::Removed(before[i].clone()));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(after[j].clone()));
+            j += 1;
+        }
+    }
+    out.extend(before[i..].iter().cloned().map(DiffLine::Removed));
+    out.extend(after[j..].iter().cloned().map(DiffLine::Added));
+    out
+}
+
+/// Compare a tunnel's current mappings with its saved snapshot. Without a
+/// snapshot, the current mappings are captured as the reference.
+pub async fn diff_mappings(client: &CloudflareClient, id: Option<String>) -> Result<()> {
+    let l = lang();
+
+    let tunnel_id = match resolve_tunnel_id(client, id).await? {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let config = client
+        .get_tunnel_config(&tunnel_id)
+        .await?
+        .unwrap_or_else(|| TunnelConfiguration {
+            config: TunnelConfigInner {
+                ingress: Vec::new(),
+            },
+        });
+
+    let Some(snapshot) = load_mappings_snapshot(&tunnel_id)? else {
+        capture_mappings_snapshot(&tunnel_id, &config)?;
+        println!(
+            "{} {}",
+            "📸".cyan(),
+            t!(
+                l,
+                "No snapshot yet; saved the current mappings as the reference.",
+                "尚无快照；已将当前映射保存为基准。"
+            )
+        );
+        return Ok(());
+    };
+
+    println!(
+        "\n{} {}  {} {}",
+        t!(l, "Tunnel:", "隧道:").bold(),
+        short_id(&tunnel_id).cyan(),
+        t!(l, "Snapshot:", "快照:").bold(),
+        format_time(Some(&snapshot.captured_at))
+    );
+    let diff = diff_lines(
+        &mapping_lines(&snapshot.ingress),
+        &mapping_lines(&config.config.ingress),
+    );
+    for line in &diff {
+        match line {
+            DiffLine::Added(text) => println!("{}", format!("+ {text}").green()),
+            DiffLine::Removed(text) => println!("{}", format!("- {text}").red()),
+            DiffLine::Unchanged(text) => println!("{}", format!("  {text}").dimmed()),
+        }
+    }
+
+    if diff.iter().all(|d| matches!(d, DiffLine::Unchanged(_))) {
+        println!(
+            "\n{} {}",
+            "✅".green(),
+            t!(l, "No changes since the snapshot.", "自快照以来没有变化。")
+        );
+        return Ok(());
+    }
+    if prompt::confirm_opt(
+        t!(
+            l,
+            "Accept these changes as the new snapshot?",
+            "将这些变更保存为新快照？"
+        ),
+        false,
+    ) == Some(true)
+    {
+        capture_mappings_snapshot(&tunnel_id, &config)?;
+        println!(
+            "{} {}",
+            "✅".green(),
+            t!(l, "Snapshot updated.", "快照已更新。")
+        );
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Add mapping (remotely-managed via API)
 // ---------------------------------------------------------------------------
 
+/// Insert a hostname→service rule before the catch-all rule and upload the config.
+/// Fails if the hostname is already mapped.
+pub async fn put_mapping(
+    client: &CloudflareClient,
+    tunnel_id: &str,
+    hostname: &str,
+    service: &str,
+) -> Result<()> {
+    let l = lang();
+
+    // Fetch current config
+    let mut config = client
+        .get_tunnel_config(tunnel_id)
+        .await?
+        .unwrap_or_else(|| TunnelConfiguration {
+            config: TunnelConfigInner {
+                ingress: vec![IngressRule {
+                    hostname: None,
+                    service: "http_status:404".to_string(),
+                    origin_request: None,
+                }],
+            },
+        });
+
+    // Check for duplicates
+    if config
+        .config
+        .ingress
+        .iter()
+        .any(|r| r.hostname.as_deref() == Some(hostname))
+    {
+        bail!(
+            "{}",
+            t!(l, "Hostname already mapped.", "该域名已存在映射。")
+        );
+    }
+
+    // Insert before the catch-all rule (last entry)
+    let insert_pos = if config.config.ingress.is_empty() {
+        0
+    } else {
+        config.config.ingress.len() - 1
+    };
+
+    config.config.ingress.insert(
+        insert_pos,
+        IngressRule {
+            hostname: Some(hostname.to_string()),
+            service: service.to_string(),
+            origin_request: None,
+        },
+    );
+
+    client.put_tunnel_config(tunnel_id, &config).await?;
+    println!("{} {} → {}", "✅".green(), hostname.cyan(), service);
+    log_mapping_event(EventAction::MappingAdded, tunnel_id, hostname, service);
+    Ok(())
+}
+
+/// Record a mapping change in the event log; a failed write only warns.
+fn log_mapping_event(action: EventAction, tunnel_id: &str, hostname: &str, service: &str) {
+    let details = serde_json::json!({
+        "tunnel_id": tunnel_id,
+        "hostname": hostname,
+        "service": service,
+    });
+    if let Err(e) = event::log(action, details) {
+        eprintln!("{} {e:#}", "⚠️".yellow());
+    }
+}
+
+/// Hostnames other than `hostname` whose rules already route to `service`.
+fn hostnames_for_service<'a>(
+    ingress: &'a [IngressRule],
+    hostname: &str,
+    service: &str,
+) -> Vec<&'a str> {
+    let service = service.trim_end_matches('/');
+    ingress
+        .iter()
+        .filter(|r| r.service.trim_end_matches('/') == service)
+        .filter_map(|r| r.hostname.as_deref())
+        .filter(|h| *h != hostname)
+        .collect()
+}
+
+/// Whether `local` is a config.yml for `tunnel_id` that defines its own
+/// ingress rules.
+fn local_config_conflicts(local: &config::LocalTunnelConfig, tunnel_id: &str) -> bool {
+    local.tunnel() == Some(tunnel_id) && local.ingress_count() > 0
+}
+
+/// Whether a local config.yml also routes traffic for `tunnel_id`, so a
+/// cloudflared started with `--config` may ignore the API configuration.
+fn detect_local_remote_conflict(tunnel_id: &str) -> bool {
+    matches!(
+        config::load_tunnel_config(),
+        Ok(Some(local)) if local_config_conflicts(&local, tunnel_id)
+    )
+}
+
 /// Add a hostname→service mapping via the tunnel configuration API.
+/// Unless `allow_duplicate_service` is set, asks before mapping a service
+/// that another hostname already points to.
 pub async fn add_mapping(
     client: &CloudflareClient,
     tunnel_id: Option<String>,
     hostname: Option<String>,
     service: Option<String>,
+    allow_duplicate_service: bool,
 ) -> Result<()> {
     let l = lang();
 
@@ -423,6 +1507,17 @@ pub async fn add_mapping(
         Some(id) => id,
         None => return Ok(()),
     };
+    if detect_local_remote_conflict(&tunnel_id) {
+        println!(
+            "{} {}",
+            "⚠️".yellow(),
+            t!(
+                l,
+                "A local config.yml also exists for this tunnel. The API config takes precedence, but running cloudflared with `--config` may override it.",
+                "该隧道同时存在本地 config.yml。API 配置优先，但使用 `--config` 运行 cloudflared 时可能会覆盖它。"
+            )
+        );
+    }
 
     let hostname = match hostname {
         Some(h) => h,
@@ -442,15 +1537,7 @@ pub async fn add_mapping(
 
     let raw_service = match service {
         Some(s) => s,
-        None => match prompt::input_opt(
-            t!(
-                l,
-                "Service URL (e.g. http://localhost:3000)",
-                "服务地址 (如 http://localhost:3000)"
-            ),
-            false,
-            None,
-        ) {
+        None => match prompt_service_target() {
             Some(v) => v,
             None => return Ok(()),
         },
@@ -468,52 +1555,45 @@ pub async fn add_mapping(
             service
         );
     }
-
-    // Fetch current config
-    let mut config = client
-        .get_tunnel_config(&tunnel_id)
-        .await
-        .unwrap_or_else(|_| TunnelConfiguration {
-            config: TunnelConfigInner {
-                ingress: vec![IngressRule {
-                    hostname: None,
-                    service: "http_status:404".to_string(),
-                    origin_request: None,
-                }],
-            },
-        });
-
-    // Check for duplicates
-    if config
-        .config
-        .ingress
-        .iter()
-        .any(|r| r.hostname.as_deref() == Some(hostname.as_str()))
-    {
-        bail!(
-            "{}",
-            t!(l, "Hostname already mapped.", "该域名已存在映射。")
+    if service_reachable(&service).await == Some(false) {
+        println!(
+            "{} {} {}",
+            "⚠️".yellow(),
+            t!(
+                l,
+                "Service is not reachable right now:",
+                "服务当前无法连接:"
+            ),
+            service
         );
     }
 
-    // Insert before the catch-all rule (last entry)
-    let insert_pos = if config.config.ingress.is_empty() {
-        0
-    } else {
-        config.config.ingress.len() - 1
-    };
-
-    config.config.ingress.insert(
-        insert_pos,
-        IngressRule {
-            hostname: Some(hostname.clone()),
-            service: service.clone(),
-            origin_request: None,
-        },
-    );
+    if !allow_duplicate_service {
+        if let Some(config) = client.get_tunnel_config(&tunnel_id).await? {
+            let others = hostnames_for_service(&config.config.ingress, &hostname, &service);
+            if !others.is_empty() {
+                println!(
+                    "{}",
+                    format!(
+                        "⚠️ {} '{}' {}",
+                        t!(l, "hostname", "域名"),
+                        others.join("', '"),
+                        t!(l, "already points to this service.", "已指向该服务。")
+                    )
+                    .yellow()
+                );
+                let proceed = prompt::confirm_opt(
+                    t!(l, "Add another mapping anyway?", "仍要添加新的映射吗？"),
+                    false,
+                );
+                if proceed != Some(true) {
+                    return Ok(());
+                }
+            }
+        }
+    }
 
-    client.put_tunnel_config(&tunnel_id, &config).await?;
-    println!("{} {} → {}", "✅".green(), hostname.cyan(), service);
+    put_mapping(client, &tunnel_id, &hostname, &service).await?;
 
     // Offer to create DNS record for this specific hostname (only if zone is configured)
     if client.zone_id.is_some() {
@@ -552,6 +1632,20 @@ pub async fn add_mapping(
         );
     }
 
+    // Scripted runs skip the question; `tunnel access protect` covers them.
+    let protect = prompt::is_interactive()
+        && prompt::confirm_opt(
+            t!(
+                l,
+                "Protect with Cloudflare Access?",
+                "是否使用 Cloudflare Access 保护？"
+            ),
+            false,
+        ) == Some(true);
+    if protect {
+        access::protect_mapping(client, Some(hostname), Some(tunnel_id)).await?;
+    }
+
     Ok(())
 }
 
@@ -572,7 +1666,13 @@ pub async fn remove_mapping(
         None => return Ok(()),
     };
 
-    let mut config = client.get_tunnel_config(&tunnel_id).await?;
+    let mut config = match client.get_tunnel_config(&tunnel_id).await? {
+        Some(config) => config,
+        None => {
+            println!("{}", t!(l, "No mappings to remove.", "没有可移除的映射。"));
+            return Ok(());
+        }
+    };
 
     let hostnames: Vec<String> = config
         .config
@@ -604,16 +1704,20 @@ pub async fn remove_mapping(
         }
     };
 
-    let before = config.config.ingress.len();
+    let Some(removed_service) = config
+        .config
+        .ingress
+        .iter()
+        .find(|r| r.hostname.as_deref() == Some(&target))
+        .map(|r| r.service.clone())
+    else {
+        bail!("{}", t!(l, "Mapping not found.", "未找到该映射。"));
+    };
     config
         .config
         .ingress
         .retain(|r| r.hostname.as_deref() != Some(&target));
 
-    if config.config.ingress.len() == before {
-        bail!("{}", t!(l, "Mapping not found.", "未找到该映射。"));
-    }
-
     client.put_tunnel_config(&tunnel_id, &config).await?;
     println!(
         "{} {} {}",
@@ -621,5 +1725,497 @@ pub async fn remove_mapping(
         target.cyan(),
         t!(l, "removed.", "已移除。")
     );
+    log_mapping_event(
+        EventAction::MappingRemoved,
+        &tunnel_id,
+        &target,
+        &removed_service,
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(hostname: Option<&str>, service: &str) -> IngressRule {
+        IngressRule {
+            hostname: hostname.map(str::to_string),
+            service: service.to_string(),
+            origin_request: None,
+        }
+    }
+
+    #[test]
+    fn generated_secrets_are_unique() {
+        let secrets: std::collections::HashSet<String> =
+            (0..100).map(|_| generate_tunnel_secret()).collect();
+        assert_eq!(secrets.len(), 100);
+
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(secrets.iter().next().unwrap())
+            .unwrap();
+        assert_eq!(decoded.len(), 32);
+    }
+
+    #[test]
+    fn tunnel_secret_validation() {
+        let valid = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+        assert_eq!(
+            validate_tunnel_secret(&format!(" {valid}\n")).unwrap(),
+            valid
+        );
+
+        let short = base64::engine::general_purpose::STANDARD.encode([7u8; 16]);
+        let err = validate_tunnel_secret(&short).unwrap_err().to_string();
+        assert!(err.contains("exactly 32 bytes, got 16"), "{err}");
+
+        let err = validate_tunnel_secret("not base64!")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("not valid base64"), "{err}");
+
+        // URL-safe alphabet is not accepted
+        let url_safe = base64::engine::general_purpose::URL_SAFE.encode([0xfbu8; 32]);
+        assert!(validate_tunnel_secret(&url_safe).is_err());
+    }
+
+    #[test]
+    fn tunnel_secret_read_from_env() {
+        let env = |key: &str| match key {
+            "TUNNEL_SECRET" => Some("c2VjcmV0".to_string()),
+            "EMPTY" => Some("  ".to_string()),
+            _ => None,
+        };
+        assert_eq!(secret_from_env("TUNNEL_SECRET", env).unwrap(), "c2VjcmV0");
+        assert!(secret_from_env("EMPTY", env).is_err());
+        let err = secret_from_env("MISSING", env).unwrap_err().to_string();
+        assert!(err.contains("MISSING"));
+
+        assert_eq!(provided_secret(None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn duplicate_service_detection() {
+        let ingress = vec![
+            rule(Some("app.example.com"), "http://localhost:3000"),
+            rule(Some("api.example.com"), "http://localhost:8000/"),
+            rule(None, "http_status:404"),
+        ];
+
+        assert_eq!(
+            hostnames_for_service(&ingress, "new.example.com", "http://localhost:3000"),
+            vec!["app.example.com"]
+        );
+        assert_eq!(
+            hostnames_for_service(&ingress, "new.example.com", "http://localhost:8000"),
+            vec!["api.example.com"]
+        );
+        assert!(
+            hostnames_for_service(&ingress, "app.example.com", "http://localhost:3000").is_empty()
+        );
+        assert!(
+            hostnames_for_service(&ingress, "new.example.com", "http://localhost:5000").is_empty()
+        );
+        assert!(hostnames_for_service(&ingress, "new.example.com", "http_status:404").is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetch_connector_counts_uses_mock_results() {
+        let ids = vec!["idle".to_string(), "busy".to_string(), "broken".to_string()];
+        let counts = fetch_connector_info(ids, |id| async move {
+            match id.as_str() {
+                "idle" => Ok(0),
+                "busy" => Ok(2),
+                _ => Err(anyhow::anyhow!("boom")),
+            }
+        })
+        .await;
+
+        assert_eq!(counts.get("idle"), Some(&0));
+        assert_eq!(counts.get("busy"), Some(&2));
+        assert_eq!(counts.get("broken"), None);
+    }
+
+    #[tokio::test]
+    async fn fetch_hostname_counts_per_tunnel() {
+        let config = |hosts: &[&str]| {
+            let mut ingress: Vec<IngressRule> = hosts
+                .iter()
+                .map(|h| rule(Some(h), "http://localhost:3000"))
+                .collect();
+            ingress.push(rule(None, "http_status:404"));
+            TunnelConfiguration {
+                config: TunnelConfigInner { ingress },
+            }
+        };
+        let configs: HashMap<String, Option<TunnelConfiguration>> = HashMap::from([
+            ("empty".to_string(), Some(config(&[]))),
+            ("one".to_string(), Some(config(&["app.example.com"]))),
+            (
+                "three".to_string(),
+                Some(config(&["a.example.com", "b.example.com", "c.example.com"])),
+            ),
+            ("local".to_string(), None),
+        ]);
+
+        let ids = vec![
+            "empty".to_string(),
+            "one".to_string(),
+            "three".to_string(),
+            "local".to_string(),
+            "missing".to_string(),
+        ];
+        let counts = fetch_connector_info(ids, |id| {
+            let config = configs.get(&id).cloned();
+            async move {
+                let config = config.ok_or_else(|| anyhow::anyhow!("not found"))?;
+                Ok(config.map_or(0, |c| c.hostname_count()))
+            }
+        })
+        .await;
+
+        assert_eq!(counts.get("empty"), Some(&0));
+        assert_eq!(counts.get("one"), Some(&1));
+        assert_eq!(counts.get("three"), Some(&3));
+        assert_eq!(counts.get("local"), Some(&0));
+        assert_eq!(counts.get("missing"), None);
+    }
+
+    fn tunnel(id: &str, name: &str) -> Tunnel {
+        Tunnel {
+            id: id.to_string(),
+            name: name.to_string(),
+            created_at: None,
+            status: Some("healthy".to_string()),
+            deleted_at: None,
+        }
+    }
+
+    fn connector(run_at: Option<&str>) -> TunnelConnector {
+        TunnelConnector {
+            id: "conn".to_string(),
+            version: None,
+            arch: None,
+            run_at: run_at.map(str::to_string),
+            conns: Vec::new(),
+        }
+    }
+
+    fn ts(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn connector_summary_picks_latest_run_at() {
+        let now = ts("2026-03-10T12:00:00Z");
+        let summary = ConnectorSummary::from_connectors(
+            &[
+                connector(Some("2026-03-01T00:00:00Z")),
+                connector(None),
+                connector(Some("not a date")),
+                connector(Some("2026-03-05T00:00:00Z")),
+            ],
+            now,
+        );
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.last_active, Some(ts("2026-03-05T00:00:00Z")));
+
+        // Clock skew: a run_at in the future counts as "now"
+        let future =
+            ConnectorSummary::from_connectors(&[connector(Some("2027-01-01T00:00:00Z"))], now);
+        assert_eq!(future.last_active, Some(now));
+
+        let nulls = ConnectorSummary::from_connectors(&[connector(None)], now);
+        assert_eq!(nulls.last_active, None);
+        assert_eq!(
+            ConnectorSummary::from_connectors(&[], now),
+            ConnectorSummary::default()
+        );
+    }
+
+    #[test]
+    fn sort_by_last_active_handles_edge_cases() {
+        let now = ts("2026-03-10T12:00:00Z");
+        let mut tunnels = vec![
+            tunnel("never", "b-never"),
+            tunnel("old", "old"),
+            tunnel("future", "future"),
+            tunnel("nulls", "a-nulls"),
+            tunnel("unknown", "unknown"),
+            tunnel("recent", "recent"),
+        ];
+        let summaries: HashMap<String, ConnectorSummary> = [
+            ("never", vec![]),
+            ("old", vec![connector(Some("2026-01-01T00:00:00Z"))]),
+            ("future", vec![connector(Some("2030-01-01T00:00:00Z"))]),
+            ("nulls", vec![connector(None)]),
+            ("recent", vec![connector(Some("2026-03-09T12:00:00Z"))]),
+        ]
+        .into_iter()
+        .map(|(id, conns)| {
+            (
+                id.to_string(),
+                ConnectorSummary::from_connectors(&conns, now),
+            )
+        })
+        .collect();
+
+        sort_by_last_active(&mut tunnels, &summaries);
+        let order: Vec<&str> = tunnels.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["future", "recent", "old", "nulls", "never", "unknown"]
+        );
+
+        assert!(is_inactive(summaries.get("never"), 7, now));
+        assert!(is_inactive(summaries.get("nulls"), 7, now));
+        assert!(is_inactive(summaries.get("old"), 7, now));
+        assert!(!is_inactive(summaries.get("old"), 90, now));
+        assert!(!is_inactive(summaries.get("recent"), 7, now));
+        assert!(!is_inactive(summaries.get("future"), 0, now));
+        assert!(!is_inactive(summaries.get("unknown"), 7, now));
+        assert!(!is_inactive(summaries.get("never"), u64::MAX, now));
+        assert!(!is_inactive(summaries.get("old"), 1 << 40, now));
+
+        let never = last_active_cell(summaries.get("never"), now);
+        assert!(never == "never" || never == "从未");
+        assert_eq!(last_active_cell(summaries.get("recent"), now), "1d ago");
+        assert_eq!(last_active_cell(None, now), "-");
+    }
+
+    #[test]
+    fn connection_cell_flags_idle_active_tunnel() {
+        assert_eq!(connection_cell(Some("healthy"), Some(0)), "0 ⚠️");
+        assert_eq!(connection_cell(Some("degraded"), Some(0)), "0 ⚠️");
+        assert_eq!(connection_cell(Some("inactive"), Some(0)), "0");
+        assert_eq!(connection_cell(Some("down"), Some(0)), "0");
+        assert_eq!(connection_cell(Some("healthy"), Some(2)), "2");
+        assert_eq!(connection_cell(Some("healthy"), None), "-");
+    }
+
+    #[test]
+    fn unhealthy_statuses_warn() {
+        assert!(is_unhealthy_status(Some("degraded")));
+        assert!(is_unhealthy_status(Some("inactive")));
+        assert!(!is_unhealthy_status(Some("healthy")));
+        assert!(!is_unhealthy_status(Some("down")));
+        assert!(!is_unhealthy_status(None));
+    }
+
+    #[test]
+    fn mapping_diff_marks_added_removed_and_unchanged() {
+        let before = mapping_lines(&[
+            rule(Some("app.example.com"), "http://localhost:3000"),
+            rule(Some("api.example.com"), "http://localhost:8080"),
+            rule(None, "http_status:404"),
+        ]);
+        let after = mapping_lines(&[
+            rule(Some("app.example.com"), "http://localhost:3000"),
+            rule(Some("api.example.com"), "http://localhost:9090"),
+            rule(Some("admin.example.com"), "http://localhost:8000"),
+            rule(None, "http_status:404"),
+        ]);
+
+        assert_eq!(
+            diff_lines(&before, &after),
+            vec![
+                DiffLine::Unchanged("app.example.com → http://localhost:3000".to_string()),
+                DiffLine::Removed("api.example.com → http://localhost:8080".to_string()),
+                DiffLine::Added("api.example.com → http://localhost:9090".to_string()),
+                DiffLine::Added("admin.example.com → http://localhost:8000".to_string()),
+                DiffLine::Unchanged("* → http_status:404".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mapping_diff_edge_cases() {
+        let lines = mapping_lines(&[rule(Some("a.example.com"), "http://localhost:1")]);
+        assert!(diff_lines(&lines, &lines)
+            .iter()
+            .all(|d| matches!(d, DiffLine::Unchanged(_))));
+        assert_eq!(
+            diff_lines(&[], &lines),
+            vec![DiffLine::Added(lines[0].clone())]
+        );
+        assert_eq!(
+            diff_lines(&lines, &[]),
+            vec![DiffLine::Removed(lines[0].clone())]
+        );
+
+        let mut with_origin = rule(Some("a.example.com"), "http://localhost:1");
+        with_origin.origin_request = Some(serde_json::json!({"noTLSVerify": true}));
+        assert_ne!(mapping_lines(&[with_origin])[0], lines[0]);
+    }
+
+    #[test]
+    fn socket_path_validation() {
+        let dir = std::env::temp_dir().join(format!("opentunnel-sock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("app.sock");
+        std::fs::write(&socket, "").unwrap();
+
+        assert!(validate_socket_path(socket.to_str().unwrap()).is_ok());
+        assert!(validate_socket_path("run/app.sock").is_err());
+        assert!(validate_socket_path("").is_err());
+        assert!(validate_socket_path(dir.join("missing.sock").to_str().unwrap()).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unix_targets_are_kept_as_is() {
+        assert_eq!(
+            normalize_service_input("unix:/run/app.sock"),
+            "unix:/run/app.sock"
+        );
+        assert_eq!(
+            normalize_service_input("unix+tls:/run/app.sock"),
+            "unix+tls:/run/app.sock"
+        );
+    }
+
+    #[test]
+    fn service_socket_addr_uses_scheme_defaults() {
+        assert_eq!(
+            service_socket_addr("http://localhost:3000"),
+            Some(("localhost".to_string(), 3000))
+        );
+        assert_eq!(
+            service_socket_addr("https://example.com/path"),
+            Some(("example.com".to_string(), 443))
+        );
+        assert_eq!(service_socket_addr("http_status:404"), None);
+        assert_eq!(service_socket_addr("unix:/run/app.sock"), None);
+        assert_eq!(
+            service_socket_addr("http://[::1]:8080"),
+            Some(("::1".to_string(), 8080))
+        );
+        assert_eq!(
+            service_socket_addr("ssh://[fd00::2]"),
+            Some(("fd00::2".to_string(), 22))
+        );
+    }
+
+    #[tokio::test]
+    async fn tcp_reachability_over_ipv6() {
+        let Ok(listener) = tokio::net::TcpListener::bind("[::1]:0").await else {
+            return; // no IPv6 loopback in this environment
+        };
+        let port = listener.local_addr().unwrap().port();
+        let target = format!("http://[::1]:{port}");
+        assert_eq!(service_reachable(&target).await, Some(true));
+
+        drop(listener);
+        assert_eq!(service_reachable(&target).await, Some(false));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_reachability() {
+        let dir = std::env::temp_dir().join(format!("opentunnel-uds-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("svc.sock");
+        let _ = std::fs::remove_file(&path);
+        let _listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let target = format!("unix:{}", path.display());
+        assert_eq!(service_reachable(&target).await, Some(true));
+
+        let missing = format!("unix+tls:{}", dir.join("none.sock").display());
+        assert_eq!(service_reachable(&missing).await, Some(false));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn credentials_file_only_for_matching_tunnel() {
+        let cfg = config::LocalTunnelConfig {
+            path: "config.yml".into(),
+            yaml: serde_yaml::from_str(
+                "tunnel: home\ncredentials-file: /root/.cloudflared/tid.json\n",
+            )
+            .unwrap(),
+        };
+        assert_eq!(
+            cfg.credentials_file_for("tid", "home"),
+            Some("/root/.cloudflared/tid.json".into())
+        );
+        assert_eq!(cfg.credentials_file_for("other", "office"), None);
+    }
+
+    #[test]
+    fn local_config_conflict_needs_same_tunnel_and_ingress() {
+        let local = |yaml: &str| config::LocalTunnelConfig {
+            path: "config.yml".into(),
+            yaml: serde_yaml::from_str(yaml).unwrap(),
+        };
+        let with_ingress = local(
+            "tunnel: tid\ncredentials-file: /root/.cloudflared/tid.json\ningress:\n  - hostname: app.example.com\n    service: http://localhost:3000\n  - service: http_status:404\n",
+        );
+        assert!(local_config_conflicts(&with_ingress, "tid"));
+        assert!(!local_config_conflicts(&with_ingress, "other"));
+        assert!(!local_config_conflicts(&local("tunnel: tid\n"), "tid"));
+    }
+
+    #[test]
+    fn tunnel_name_match_ignores_case() {
+        let existing = vec![tunnel("abc123", "My-Tunnel"), tunnel("def456", "web")];
+        assert_eq!(
+            find_tunnel_by_name(&existing, "my-tunnel").map(|t| t.id.as_str()),
+            Some("abc123")
+        );
+        assert_eq!(
+            find_tunnel_by_name(&existing, " WEB ").map(|t| t.id.as_str()),
+            Some("def456")
+        );
+        assert!(find_tunnel_by_name(&existing, "my-tunnel-2").is_none());
+    }
+
+    #[test]
+    fn tunnel_name_resolution_loops_until_unique() {
+        let existing = vec![tunnel("abc123", "prod"), tunnel("def456", "prod-2")];
+
+        // Two clashing renames, then a free name.
+        let mut names = vec!["prod-3".to_string(), "PROD-2".to_string()];
+        let mut conflicts = 0;
+        let resolved = resolve_tunnel_name(
+            &existing,
+            "Prod".to_string(),
+            |_| {
+                conflicts += 1;
+                NameConflictChoice::Rename
+            },
+            || names.pop(),
+        );
+        assert!(matches!(resolved, TunnelNameResolution::Create(ref n) if n == "prod-3"));
+        assert_eq!(conflicts, 2);
+
+        let resolved = resolve_tunnel_name(
+            &existing,
+            "prod".to_string(),
+            |_| NameConflictChoice::UseExisting,
+            || None,
+        );
+        assert!(matches!(resolved, TunnelNameResolution::UseExisting(ref t) if t.id == "abc123"));
+
+        let resolved = resolve_tunnel_name(
+            &existing,
+            "prod".to_string(),
+            |_| NameConflictChoice::Cancel,
+            || panic!("should not ask for a name"),
+        );
+        assert!(matches!(resolved, TunnelNameResolution::Cancel));
+
+        // Escaping the rename prompt cancels too.
+        let resolved = resolve_tunnel_name(
+            &existing,
+            "prod".to_string(),
+            |_| NameConflictChoice::Rename,
+            || None,
+        );
+        assert!(matches!(resolved, TunnelNameResolution::Cancel));
+
+        let resolved =
+            resolve_tunnel_name(&existing, "new".to_string(), |_| unreachable!(), || None);
+        assert!(matches!(resolved, TunnelNameResolution::Create(ref n) if n == "new"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+/// Format an elapsed duration as a short relative age, e.g. `2h ago`, `3d ago`.
+/// The value is counted in whole units of the largest unit that fits, rounding
+/// down, so 3599s is `59m ago`, never `60m ago`.
+pub fn human_duration(secs: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+
+    let (unit, suffix) = match secs {
+        s if s < MINUTE => return format!("{s}s ago"),
+        s if s < HOUR => (MINUTE, "m"),
+        s if s < DAY => (HOUR, "h"),
+        s if s < WEEK => (DAY, "d"),
+        s if s < MONTH => (WEEK, "w"),
+        _ => (MONTH, "mo"),
+    };
+    let value = secs / unit;
+    format!("{value}{suffix} ago")
+}
+
+/// Format a byte count with binary units, e.g. `512 B`, `12.5 MB`, `1.2 GB`.
+pub fn format_bytes(n: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = n.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Parse a short duration such as `30m`, `1h`, `2d` or `1w` into seconds.
+pub fn parse_duration(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = input.split_at(split);
+    let value: u64 = value.parse().ok()?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => return None,
+    };
+    value.checked_mul(unit_secs).filter(|s| *s > 0)
+}
+
+/// Age of an RFC 3339 timestamp relative to now, or `None` if it cannot be parsed.
+pub fn age_since(ts: &str) -> Option<String> {
+    let then = chrono::DateTime::parse_from_rfc3339(ts).ok()?;
+    let elapsed = chrono::Utc::now().signed_duration_since(then);
+    Some(human_duration(elapsed.num_seconds().max(0) as u64))
+}
+
+/// Spawn one task per item, allowing at most `limit` of them to run `f` at once.
+pub fn spawn_bounded<T, F, Fut>(items: Vec<T>, limit: usize, f: F) -> Vec<JoinHandle<Fut::Output>>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(limit.max(1)));
+    items
+        .into_iter()
+        .map(|item| {
+            let semaphore = semaphore.clone();
+            let fut = f(item);
+            tokio::spawn(async move {
+                // The semaphore is never closed, so acquire cannot fail
+                let _permit = semaphore.acquire_owned().await.ok();
+                fut.await
+            })
+        })
+        .collect()
+}
+
+/// Run `check` every `every` until it returns `true` or `limit` elapses.
+/// Returns whether the condition was met in time.
+pub async fn wait_until<F, Fut>(limit: Duration, every: Duration, mut check: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut interval = tokio::time::interval(every);
+    tokio::time::timeout(limit, async {
+        loop {
+            interval.tick().await;
+            if check().await {
+                break;
+            }
+        }
+    })
+    .await
+    .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bytes_units() {
+        assert_eq!(format_bytes(0.0), "0 B");
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_bytes(1536.0), "1.5 KB");
+        assert_eq!(format_bytes(12.5 * 1024.0 * 1024.0), "12.5 MB");
+        assert_eq!(format_bytes(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GB");
+        assert_eq!(format_bytes(2048.0 * 1024f64.powi(4)), "2048.0 TB");
+    }
+
+    #[tokio::test]
+    async fn wait_until_stops_once_reachable() {
+        let mut attempts = 0;
+        let ok = wait_until(Duration::from_secs(5), Duration::from_millis(10), || {
+            attempts += 1;
+            let reachable = attempts >= 3;
+            async move { reachable }
+        })
+        .await;
+        assert!(ok);
+        assert_eq!(attempts, 3);
+
+        let ok = wait_until(
+            Duration::from_millis(50),
+            Duration::from_millis(10),
+            || async { false },
+        )
+        .await;
+        assert!(!ok);
+    }
+
+    #[test]
+    fn human_duration_seconds() {
+        assert_eq!(human_duration(0), "0s ago");
+        assert_eq!(human_duration(59), "59s ago");
+    }
+
+    #[test]
+    fn human_duration_minutes() {
+        assert_eq!(human_duration(60), "1m ago");
+        assert_eq!(human_duration(150), "2m ago");
+        assert_eq!(human_duration(3599), "59m ago");
+    }
+
+    #[test]
+    fn human_duration_hours() {
+        assert_eq!(human_duration(3600), "1h ago");
+        assert_eq!(human_duration(2 * 3600 + 10 * 60), "2h ago");
+        assert_eq!(human_duration(86399), "23h ago");
+    }
+
+    #[test]
+    fn human_duration_days() {
+        assert_eq!(human_duration(86400), "1d ago");
+        assert_eq!(human_duration(3 * 86400), "3d ago");
+    }
+
+    #[test]
+    fn human_duration_weeks() {
+        assert_eq!(human_duration(7 * 86400), "1w ago");
+        assert_eq!(human_duration(4 * 7 * 86400), "4w ago");
+    }
+
+    #[test]
+    fn human_duration_months() {
+        assert_eq!(human_duration(30 * 86400), "1mo ago");
+        assert_eq!(human_duration(365 * 86400), "12mo ago");
+    }
+
+    #[tokio::test]
+    async fn spawn_bounded_limits_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+
+        let handles = spawn_bounded((0..50).collect::<Vec<_>>(), 5, |_| {
+            let in_flight = in_flight.clone();
+            let max_seen = max_seen.clone();
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_seen.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        for h in handles {
+            h.await.unwrap();
+        }
+
+        let max = max_seen.load(Ordering::SeqCst);
+        assert!(max <= 5, "at most 5 tasks may run at once, saw {max}");
+        assert!(max > 0);
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("45s"), Some(45));
+        assert_eq!(parse_duration("30m"), Some(1800));
+        assert_eq!(parse_duration("1h"), Some(3600));
+        assert_eq!(parse_duration("2d"), Some(172800));
+        assert_eq!(parse_duration("1w"), Some(604800));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("3y"), None);
+    }
+
+    #[test]
+    fn age_since_rejects_garbage() {
+        assert_eq!(age_since("not a timestamp"), None);
+        assert!(age_since("2020-01-01T00:00:00Z").is_some());
+    }
+}